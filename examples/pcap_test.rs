@@ -10,6 +10,7 @@ use ethercat_master::slave::PdoEntry;
 use ethercat_master::slave::PdoMapping;
 use ethercat_master::slave::SlaveConfig;
 use ethercat_master::slave::SyncMode;
+use ethercat_master::task::SystemClock;
 use ethercat_master::EtherCatMaster;
 use pcap::Device;
 use std::env;
@@ -49,7 +50,8 @@ fn pdu_test(name: &str) {
     println!("\npdu_test");
     let dev = new_device(name);
     let mut buf = [0; 1500];
-    let iface = PduInterface::new(dev, &mut buf);
+    let clock = SystemClock::new();
+    let iface = PduInterface::new(dev, &mut buf, &clock);
     let mut s_buf = [0; 256];
     let mut sif: SocketInterface<_, 1> = SocketInterface::new(iface);
     let handle = sif.add_socket(PduSocket::new(&mut s_buf)).unwrap();
@@ -78,7 +80,8 @@ fn read_eeprom_test(name: &str) {
     println!("\nread_eeprom_test");
     let dev = new_device(name);
     let mut buf = [0; 1500];
-    let iface = PduInterface::new(dev, &mut buf);
+    let clock = SystemClock::new();
+    let iface = PduInterface::new(dev, &mut buf, &clock);
     let mut s_buf = [0; 256];
     let mut sif: SocketInterface<_, 1> = SocketInterface::new(iface);
     let handle = sif.add_socket(PduSocket::new(&mut s_buf)).unwrap();
@@ -97,7 +100,8 @@ fn sdo_test(name: &str) {
     println!("\nsdo_test");
     let dev = new_device(name);
     let mut buf = [0; 1500];
-    let iface = PduInterface::new(dev, &mut buf);
+    let clock = SystemClock::new();
+    let iface = PduInterface::new(dev, &mut buf, &clock);
 
     let mut slaves: [_; 10] = Default::default();
     let mut pdu_buffer = vec![0; 1500];
@@ -116,26 +120,26 @@ fn sdo_test(name: &str) {
 
     println!("reading vender id");
     let vender_id = master
-        .read_sdo_as_u32(SlaveAddress::SlavePosition(0), 0x1018, 0x01)
+        .read_sdo::<u32>(SlaveAddress::SlavePosition(0), 0x1018, 0x01)
         .unwrap();
     println!("vender id: {:x}", vender_id);
 
     println!("reading gain");
     let gain = master
-        .read_sdo_as_u16(SlaveAddress::SlavePosition(0), 0x2005, 0x01)
+        .read_sdo::<u16>(SlaveAddress::SlavePosition(0), 0x2005, 0x01)
         .unwrap();
     println!("gain: {:x}", gain);
 
     println!("writing gain");
     master
-        .write_sdo_as_u16(SlaveAddress::SlavePosition(0), 0x2005, 0x01, gain + 1)
+        .write_sdo::<u16>(SlaveAddress::SlavePosition(0), 0x2005, 0x01, gain + 1)
         .unwrap();
     let gain = master
-        .read_sdo_as_u16(SlaveAddress::SlavePosition(0), 0x2005, 0x01)
+        .read_sdo::<u16>(SlaveAddress::SlavePosition(0), 0x2005, 0x01)
         .unwrap();
     println!("gain: {:x}", gain);
     master
-        .write_sdo_as_u16(SlaveAddress::SlavePosition(0), 0x2005, 0x01, gain - 1)
+        .write_sdo::<u16>(SlaveAddress::SlavePosition(0), 0x2005, 0x01, gain - 1)
         .unwrap();
 
     println!("sdo_test done");
@@ -145,7 +149,8 @@ fn dc_test(name: &str) {
     println!("\ndc_test");
     let dev = new_device(name);
     let mut buf = [0; 1500];
-    let iface = PduInterface::new(dev, &mut buf);
+    let clock = SystemClock::new();
+    let iface = PduInterface::new(dev, &mut buf, &clock);
 
     let mut slaves: [_; 10] = Default::default();
     let mut pdu_buffer = vec![0; 1500];
@@ -197,7 +202,8 @@ fn pdo_test(name: &str) {
     println!("\npdo_test");
     let dev = new_device(name);
     let mut buf = [0; 1500];
-    let iface = PduInterface::new(dev, &mut buf);
+    let clock = SystemClock::new();
+    let iface = PduInterface::new(dev, &mut buf, &clock);
 
     let mut output_pdo_map0 = [PdoMapping {
         is_fixed: false,
@@ -319,7 +325,7 @@ fn pdo_test(name: &str) {
 
     let c_word = ControlWord::new_switch_on_and_enable_operation();
     master
-        .write_pdo_as_u16(
+        .write_pdo::<u16>(
             SlaveAddress::SlavePosition(0),
             0,
             0,
@@ -345,7 +351,7 @@ fn pdo_test(name: &str) {
         std::thread::sleep(std::time::Duration::from_micros(16000));
 
         let err_count = master
-            .read_sdo_as_u16(SlaveAddress::SlavePosition(0), 0x1C32, 0x0C)
+            .read_sdo::<u16>(SlaveAddress::SlavePosition(0), 0x1C32, 0x0C)
             .unwrap();
         println!("err_count: {:x}", err_count);
         if let (Some(AlState::Operational), _) = master.al_state() {
@@ -366,19 +372,19 @@ fn pdo_test(name: &str) {
 
         if let (Some(AlState::Operational), _) = master.al_state() {
             let error_code = master
-                .read_pdo_as_u16(SlaveAddress::SlavePosition(0), 0, 0)
+                .read_pdo::<u16>(SlaveAddress::SlavePosition(0), 0, 0)
                 .unwrap();
             let status_word = master
-                .read_pdo_as_u16(SlaveAddress::SlavePosition(0), 0, 1)
+                .read_pdo::<u16>(SlaveAddress::SlavePosition(0), 0, 1)
                 .unwrap();
             let actual_position = master
-                .read_pdo_as_u32(SlaveAddress::SlavePosition(0), 0, 2)
+                .read_pdo::<u32>(SlaveAddress::SlavePosition(0), 0, 2)
                 .unwrap();
             let actual_torque = master
-                .read_pdo_as_u16(SlaveAddress::SlavePosition(0), 0, 3)
+                .read_pdo::<u16>(SlaveAddress::SlavePosition(0), 0, 3)
                 .unwrap();
             let position_error = master
-                .read_pdo_as_u32(SlaveAddress::SlavePosition(0), 0, 4)
+                .read_pdo::<u32>(SlaveAddress::SlavePosition(0), 0, 4)
                 .unwrap();
 
             let status_word = StatusWord(status_word.to_le_bytes());
@@ -398,7 +404,7 @@ fn pdo_test(name: &str) {
                     c_word.0[0] = 0b0000_0110;
                 }
                 master
-                    .write_pdo_as_u16(
+                    .write_pdo::<u16>(
                         SlaveAddress::SlavePosition(0),
                         0,
                         0,
@@ -412,12 +418,12 @@ fn pdo_test(name: &str) {
                 //dbg!(actual_torque);
                 //dbg!(position_error);
                 master
-                    .write_pdo_as_u32(SlaveAddress::SlavePosition(0), 0, 1, count2 * 500)
+                    .write_pdo::<u32>(SlaveAddress::SlavePosition(0), 0, 1, count2 * 500)
                     .unwrap();
                 count2 += 1;
                 dbg!(count2);
                 let err_count = master
-                .read_sdo_as_u16(SlaveAddress::SlavePosition(0), 0x1C32, 0x0C)
+                .read_sdo::<u16>(SlaveAddress::SlavePosition(0), 0x1C32, 0x0C)
                 .unwrap();
                 println!("err_count: {:x}", err_count);
             }
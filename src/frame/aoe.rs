@@ -0,0 +1,202 @@
+use bitfield::*;
+use num_enum::FromPrimitive;
+
+bitfield! {
+    #[derive(Debug, Clone)]
+    pub struct AmsFrame([u8]);
+    pub u16, target_port, set_target_port: 63, 48;
+    pub u16, source_port, set_source_port: 127, 112;
+    pub u16, command_id, set_command_id: 143, 128;
+    /// A combination of `AmsStateFlags`.
+    pub u16, state_flags, set_state_flags: 159, 144;
+    /// Size of the ADS data which follow the header.
+    pub u32, data_length, set_data_length: 191, 160;
+    pub u32, error_code, set_error_code: 223, 192;
+    pub u32, invoke_id, set_invoke_id: 255, 224;
+}
+
+impl AmsFrame<[u8; 32]> {
+    pub const HEADER_SIZE: usize = 32;
+    pub fn new() -> Self {
+        Self([0; Self::HEADER_SIZE])
+    }
+}
+
+impl<B: AsRef<[u8]>> AmsFrame<B> {
+    pub fn target_net_id(&self) -> [u8; 6] {
+        let mut net_id = [0; 6];
+        net_id.copy_from_slice(&self.0.as_ref()[0..6]);
+        net_id
+    }
+
+    pub fn source_net_id(&self) -> [u8; 6] {
+        let mut net_id = [0; 6];
+        net_id.copy_from_slice(&self.0.as_ref()[8..14]);
+        net_id
+    }
+
+    pub fn ads_command(&self) -> AdsCommand {
+        self.command_id().into()
+    }
+}
+
+impl<B: AsMut<[u8]>> AmsFrame<B> {
+    pub fn set_target_net_id(&mut self, net_id: [u8; 6]) {
+        self.0.as_mut()[0..6].copy_from_slice(&net_id);
+    }
+
+    pub fn set_source_net_id(&mut self, net_id: [u8; 6]) {
+        self.0.as_mut()[8..14].copy_from_slice(&net_id);
+    }
+
+    pub fn set_ads_command(&mut self, command: AdsCommand) {
+        self.set_command_id(command as u16)
+    }
+}
+
+impl<'a> AmsFrame<&'a [u8]> {
+    pub fn without_header(&self) -> &'a [u8] {
+        &self.0[AmsFrame::HEADER_SIZE..]
+    }
+}
+
+/// Bits of the state flags of the AMS header.
+pub struct AmsStateFlags;
+impl AmsStateFlags {
+    pub const RESPONSE: u16 = 0x0001;
+    pub const NO_RETURN: u16 = 0x0002;
+    pub const ADS_COMMAND: u16 = 0x0004;
+}
+
+/// NetId and port of an AMS endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AmsAddress {
+    pub net_id: [u8; 6],
+    pub port: u16,
+}
+
+impl AmsAddress {
+    pub fn new(net_id: [u8; 6], port: u16) -> Self {
+        Self { net_id, port }
+    }
+}
+
+/// AMS header without the command, the state flags and the data length,
+/// which follow from the request or the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmsHeader {
+    pub target: AmsAddress,
+    pub source: AmsAddress,
+    pub error_code: AdsErrorCode,
+    pub invoke_id: u32,
+}
+
+/// Index group and index offset of ADS data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AdsIndex {
+    pub group: u32,
+    pub offset: u32,
+}
+
+impl AdsIndex {
+    pub fn new(group: u32, offset: u32) -> Self {
+        Self { group, offset }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u16)]
+pub enum AdsCommand {
+    ReadDeviceInfo = 1,
+    Read,
+    Write,
+    ReadState,
+    WriteControl,
+    AddDeviceNotification,
+    DeleteDeviceNotification,
+    DeviceNotification,
+    ReadWrite,
+    #[num_enum(default)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u16)]
+pub enum AdsState {
+    Invalid = 0,
+    Idle,
+    Reset,
+    Init,
+    Start,
+    Run,
+    Stop,
+    SaveConfig,
+    LoadConfig,
+    PowerFailure,
+    PowerGood,
+    Error,
+    Shutdown,
+    Suspend,
+    Resume,
+    Config,
+    Reconfig,
+    #[num_enum(default)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u32)]
+pub enum AdsErrorCode {
+    NoError = 0x0000,
+    InternalError = 0x0001,
+    NoRealTime = 0x0002,
+    AllocationLockedMemory = 0x0003,
+    MailboxFull = 0x0004,
+    WrongHmsg = 0x0005,
+    TargetPortNotFound = 0x0006,
+    TargetMachineNotFound = 0x0007,
+    UnknownCommandId = 0x0008,
+    BadTaskId = 0x0009,
+    NoIo = 0x000A,
+    UnknownAmsCommand = 0x000B,
+    Win32Error = 0x000C,
+    PortNotConnected = 0x000D,
+    InvalidAmsLength = 0x000E,
+    InvalidAmsNetId = 0x000F,
+    PortDisabled = 0x0012,
+    PortAlreadyConnected = 0x0013,
+    DeviceError = 0x0700,
+    ServiceNotSupported = 0x0701,
+    InvalidIndexGroup = 0x0702,
+    InvalidIndexOffset = 0x0703,
+    InvalidAccess = 0x0704,
+    InvalidSize = 0x0705,
+    InvalidData = 0x0706,
+    NotReady = 0x0707,
+    Busy = 0x0708,
+    InvalidContext = 0x0709,
+    NoMemory = 0x070A,
+    InvalidParameter = 0x070B,
+    NotFound = 0x070C,
+    Syntax = 0x070D,
+    Incompatible = 0x070E,
+    AlreadyExists = 0x070F,
+    SymbolNotFound = 0x0710,
+    SymbolVersionInvalid = 0x0711,
+    InvalidState = 0x0712,
+    TransModeNotSupported = 0x0713,
+    InvalidNotificationHandle = 0x0714,
+    ClientUnknown = 0x0715,
+    NoMoreHandles = 0x0716,
+    InvalidWatchSize = 0x0717,
+    NotInitialized = 0x0718,
+    DeviceTimeout = 0x0719,
+    ClientError = 0x0740,
+    ClientInvalidParameter = 0x0741,
+    ClientListEmpty = 0x0742,
+    ClientVariableInUse = 0x0743,
+    ClientInvokeIdInUse = 0x0744,
+    ClientTimeout = 0x0745,
+    #[num_enum(default)]
+    UnknownErrorCode = 0xFFFF_FFFF,
+}
@@ -88,6 +88,197 @@ impl SdoDownloadNormalRequestFrame<[u8; 4]> {
     }
 }
 
+bitfield! {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SdoSegmentFrame([u8]);
+    pub last_segment, set_last_segment: 0;
+    pub u8, segment_data_size, set_segment_data_size: 3, 1;
+    pub toggle, set_toggle: 4;
+    pub u8, command_specifier, set_command_specifier: 7, 5;
+}
+
+impl SdoSegmentFrame<[u8; 1]> {
+    pub const HEADER_SIZE: usize = 1;
+    /// Segment data shorter than this is padded, and its size is given by `segment_data_size`.
+    pub const MIN_DATA_SIZE: usize = 7;
+    pub fn new() -> Self {
+        Self([0; Self::HEADER_SIZE])
+    }
+}
+
+impl<'a> SdoSegmentFrame<&'a [u8]> {
+    pub fn without_header(&self) -> &'a [u8] {
+        &self.0[SdoSegmentFrame::HEADER_SIZE..]
+    }
+}
+
+bitfield! {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SdoInfoFrame([u8]);
+    pub u8, op_code, set_op_code: 6, 0;
+    /// More fragments follow.
+    pub incomplete, set_incomplete: 7;
+    pub u16, fragments_left, set_fragments_left: 31, 16;
+}
+
+impl SdoInfoFrame<[u8; 4]> {
+    pub const HEADER_SIZE: usize = 4;
+    pub fn new() -> Self {
+        Self([0; Self::HEADER_SIZE])
+    }
+}
+
+impl<B: AsRef<[u8]>> SdoInfoFrame<B> {
+    pub fn sdo_info_op_code(&self) -> SdoInfoOpCode {
+        self.op_code().into()
+    }
+}
+
+impl<B: AsMut<[u8]>> SdoInfoFrame<B> {
+    pub fn set_sdo_info_op_code(&mut self, op_code: SdoInfoOpCode) {
+        self.set_op_code(op_code as u8)
+    }
+}
+
+impl<'a> SdoInfoFrame<&'a [u8]> {
+    pub fn without_header(&self) -> &'a [u8] {
+        &self.0[SdoInfoFrame::HEADER_SIZE..]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Copy, FromPrimitive)]
+#[repr(u8)]
+pub enum SdoInfoOpCode {
+    GetOdListReq = 1,
+    GetOdListRes,
+    GetObjectDescriptionReq,
+    GetObjectDescriptionRes,
+    GetEntryDescriptionReq,
+    GetEntryDescriptionRes,
+    Error,
+    #[num_enum(default)]
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Copy, FromPrimitive)]
+#[repr(u16)]
+pub enum OdListType {
+    /// Number of objects in each of the other lists.
+    Length = 0,
+    All,
+    RxPdoMappable,
+    TxPdoMappable,
+    Backup,
+    Settings,
+    #[num_enum(default)]
+    Other,
+}
+
+/// Response of Get OD List, after all fragments are joined.
+#[derive(Debug, Clone)]
+pub struct OdListFrame<B>(pub B);
+
+impl OdListFrame<[u8; 2]> {
+    pub const HEADER_SIZE: usize = 2;
+}
+
+impl<B: AsRef<[u8]>> OdListFrame<B> {
+    pub fn list_type(&self) -> OdListType {
+        u16::from_le_bytes([self.0.as_ref()[0], self.0.as_ref()[1]]).into()
+    }
+
+    /// Indexes of the objects, or the lengths of the lists for `OdListType::Length`.
+    pub fn indexes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.0.as_ref()[OdListFrame::HEADER_SIZE..]
+            .chunks_exact(2)
+            .map(|index| u16::from_le_bytes([index[0], index[1]]))
+    }
+}
+
+bitfield! {
+    /// Response of Get Object Description. The name follows.
+    #[derive(Debug, Clone)]
+    pub struct ObjectDescriptionFrame([u8]);
+    pub u16, index, set_index: 15, 0;
+    pub u16, data_type, set_data_type: 31, 16;
+    pub u8, max_sub_index, set_max_sub_index: 39, 32;
+    pub u8, object_code, set_object_code: 47, 40;
+}
+
+impl ObjectDescriptionFrame<[u8; 6]> {
+    pub const HEADER_SIZE: usize = 6;
+    pub fn new() -> Self {
+        Self([0; Self::HEADER_SIZE])
+    }
+}
+
+impl<'a> ObjectDescriptionFrame<&'a [u8]> {
+    pub fn name(&self) -> &'a [u8] {
+        &self.0[ObjectDescriptionFrame::HEADER_SIZE..]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Copy, FromPrimitive)]
+#[repr(u8)]
+pub enum ObjectCode {
+    Variable = 7,
+    Array = 8,
+    Record = 9,
+    #[num_enum(default)]
+    Other,
+}
+
+bitfield! {
+    /// Response of Get Entry Description.
+    /// The values requested by `value_info` and the description follow.
+    #[derive(Debug, Clone)]
+    pub struct EntryDescriptionFrame([u8]);
+    pub u16, index, set_index: 15, 0;
+    pub u8, sub_index, set_sub_index: 23, 16;
+    pub u8, value_info, set_value_info: 31, 24;
+    pub u16, data_type, set_data_type: 47, 32;
+    pub u16, bit_length, set_bit_length: 63, 48;
+    /// See `ObjectAccess`.
+    pub u16, object_access, set_object_access: 79, 64;
+}
+
+impl EntryDescriptionFrame<[u8; 10]> {
+    pub const HEADER_SIZE: usize = 10;
+    pub fn new() -> Self {
+        Self([0; Self::HEADER_SIZE])
+    }
+}
+
+impl<'a> EntryDescriptionFrame<&'a [u8]> {
+    pub fn without_header(&self) -> &'a [u8] {
+        &self.0[EntryDescriptionFrame::HEADER_SIZE..]
+    }
+}
+
+/// Values requested in addition to an entry description.
+pub struct ValueInfo;
+impl ValueInfo {
+    pub const UNIT_TYPE: u8 = 0x08;
+    pub const DEFAULT_VALUE: u8 = 0x10;
+    pub const MINIMUM_VALUE: u8 = 0x20;
+    pub const MAXIMUM_VALUE: u8 = 0x40;
+}
+
+/// Bits of the object access of an entry description.
+pub struct ObjectAccess;
+impl ObjectAccess {
+    pub const READ_PRE_OP: u16 = 0x0001;
+    pub const READ_SAFE_OP: u16 = 0x0002;
+    pub const READ_OP: u16 = 0x0004;
+    pub const WRITE_PRE_OP: u16 = 0x0008;
+    pub const WRITE_SAFE_OP: u16 = 0x0010;
+    pub const WRITE_OP: u16 = 0x0020;
+    pub const RX_PDO_MAPPABLE: u16 = 0x0040;
+    pub const TX_PDO_MAPPABLE: u16 = 0x0080;
+    pub const BACKUP: u16 = 0x0100;
+    pub const SETTINGS: u16 = 0x0200;
+}
+
 #[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq)]
 #[repr(u32)]
 pub enum AbortCode {
@@ -129,9 +320,10 @@ pub enum AbortCode {
 bitfield! {
     #[derive(Debug, Clone)]
     pub struct EmmergencyFrame([u8]);
-    u16, error_code, _: 15, 0;
-    u8, error_register, _: 23, 16;
-    u64, data, _: 63, 24;
+    pub u16, error_code, set_error_code: 15, 0;
+    pub u8, error_register, set_error_register: 23, 16;
+    /// Manufacturer specific data. 5 bytes.
+    pub u64, data, set_data: 63, 24;
 }
 
 impl EmmergencyFrame<[u8; 8]> {
@@ -0,0 +1,144 @@
+use bitfield::*;
+use num_enum::FromPrimitive;
+
+bitfield! {
+    #[derive(Debug, Clone)]
+    pub struct EoeFrame([u8]);
+    pub u8, frame_type, set_frame_type: 3, 0;
+    pub u8, port, set_port: 7, 4;
+    pub last_fragment, set_last_fragment: 8;
+    pub time_appended, set_time_appended: 9;
+    pub time_request, set_time_request: 10;
+    pub u8, fragment_number, set_fragment_number: 21, 16;
+    /// Complete size of the frame in the first fragment, and offset of the fragment in the others.
+    /// Both are in units of 32 octets.
+    pub u8, offset_buffer, set_offset_buffer: 27, 22;
+    pub u8, frame_number, set_frame_number: 31, 28;
+    /// Result of a response, instead of the fragment fields.
+    pub u16, result, set_result: 31, 16;
+}
+
+impl EoeFrame<[u8; 4]> {
+    pub const HEADER_SIZE: usize = 4;
+    /// Fragments except the last one are a multiple of this size.
+    pub const FRAGMENT_UNIT: usize = 32;
+    pub fn new() -> Self {
+        Self([0; Self::HEADER_SIZE])
+    }
+}
+
+impl<B: AsRef<[u8]>> EoeFrame<B> {
+    pub fn eoe_frame_type(&self) -> EoeFrameType {
+        self.frame_type().into()
+    }
+}
+
+impl<B: AsMut<[u8]>> EoeFrame<B> {
+    pub fn set_eoe_frame_type(&mut self, frame_type: EoeFrameType) {
+        self.set_frame_type(frame_type as u8)
+    }
+}
+
+impl<'a> EoeFrame<&'a [u8]> {
+    pub fn without_header(&self) -> &'a [u8] {
+        &self.0[EoeFrame::HEADER_SIZE..]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum EoeFrameType {
+    Fragment = 0,
+    InitResponseTimestamp = 1,
+    SetIpReq = 2,
+    SetIpRes = 3,
+    SetAddressFilterReq = 4,
+    SetAddressFilterRes = 5,
+    GetIpReq = 6,
+    GetIpRes = 7,
+    GetAddressFilterReq = 8,
+    GetAddressFilterRes = 9,
+    #[num_enum(default)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u16)]
+pub enum EoeResult {
+    Success = 0x0000,
+    #[num_enum(default)]
+    UnspecifiedError = 0x0001,
+    UnsupportedFrameType = 0x0002,
+    NoIpSupport = 0x0201,
+    NoDhcpSupport = 0x0202,
+    NoFilterSupport = 0x0401,
+}
+
+/// Parameters of a Set IP Parameter request. `None` leaves a parameter unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EoeIpParameter {
+    pub mac_address: Option<[u8; 6]>,
+    pub ip_address: Option<[u8; 4]>,
+    pub subnet_mask: Option<[u8; 4]>,
+    pub default_gateway: Option<[u8; 4]>,
+    pub dns_server: Option<[u8; 4]>,
+    /// Padded with zeros.
+    pub dns_name: Option<[u8; 32]>,
+}
+
+impl EoeIpParameter {
+    /// Flags followed by all parameters, also the ones not included.
+    pub const SIZE: usize = 4 + 6 + 4 * 4 + 32;
+
+    /// Addresses are UINT32, so their octets are in reverse order.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let data = data.get(..Self::SIZE)?;
+        let flags = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let included = |bit: u32| flags & (1 << bit) != 0;
+        let address = |offset: usize| {
+            let mut address = [0; 4];
+            address.copy_from_slice(&data[offset..offset + 4]);
+            address.reverse();
+            address
+        };
+        let mut mac_address = [0; 6];
+        mac_address.copy_from_slice(&data[4..10]);
+        let mut dns_name = [0; 32];
+        dns_name.copy_from_slice(&data[26..58]);
+        Some(Self {
+            mac_address: included(0).then_some(mac_address),
+            ip_address: included(1).then(|| address(10)),
+            subnet_mask: included(2).then(|| address(14)),
+            default_gateway: included(3).then(|| address(18)),
+            dns_server: included(4).then(|| address(22)),
+            dns_name: included(5).then_some(dns_name),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut data = [0; Self::SIZE];
+        let mut flags = 0_u32;
+        if let Some(mac_address) = self.mac_address {
+            flags |= 1 << 0;
+            data[4..10].copy_from_slice(&mac_address);
+        }
+        for (bit, offset, address) in [
+            (1, 10, self.ip_address),
+            (2, 14, self.subnet_mask),
+            (3, 18, self.default_gateway),
+            (4, 22, self.dns_server),
+        ] {
+            if let Some(mut address) = address {
+                flags |= 1 << bit;
+                address.reverse();
+                data[offset..offset + 4].copy_from_slice(&address);
+            }
+        }
+        if let Some(dns_name) = self.dns_name {
+            flags |= 1 << 5;
+            data[26..58].copy_from_slice(&dns_name);
+        }
+        data[..4].copy_from_slice(&flags.to_le_bytes());
+        data
+    }
+}
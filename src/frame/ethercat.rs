@@ -2,7 +2,11 @@ use bitfield::*;
 use num_enum::FromPrimitive;
 
 use super::{
-    AbortCode, CoeFrame, CoeServiceType, EmmergencyFrame, SdoDownloadNormalRequestFrame, SdoFrame,
+    AbortCode, AdsCommand, AdsErrorCode, AdsIndex, AdsState, AmsAddress, AmsFrame, AmsHeader,
+    AmsStateFlags, CoeFrame, CoeServiceType, EmmergencyFrame, EoeFrame, EoeFrameType,
+    EoeIpParameter, EoeResult, FoeErrorCode, FoeFrame, FoeOpCode, OdListType,
+    SdoDownloadNormalRequestFrame, SdoFrame, SdoInfoFrame, SdoInfoOpCode, SdoSegmentFrame,
+    SoeErrorCode, SoeFrame, SoeOpCode,
 };
 
 const DST_MAC: u64 = 0xFF_FF_FF_FF_FF_FF;
@@ -151,8 +155,8 @@ bitfield! {
     #[derive(Debug, Clone, PartialEq, Eq)]
     pub struct MailboxErrorFrame([u8]);
     u16;
-    pub service_type, _: 15, 0;
-    pub detail, _: 31, 16;
+    pub service_type, set_service_type: 15, 0;
+    pub detail, set_detail: 31, 16;
 }
 
 impl MailboxErrorFrame<[u8; 4]> {
@@ -249,27 +253,60 @@ impl<'a> MailboxFrame<&'a [u8]> {
                     .ok_or(LengthError)?;
                 Ok(Mailbox::Error(detail.error_detail()))
             }
-            MailboxType::AoE => Ok(Mailbox::UnsupportedProtocol(MailboxType::AoE)),
-            MailboxType::EoE => Ok(Mailbox::UnsupportedProtocol(MailboxType::EoE)),
+            MailboxType::AoE => {
+                let ams_frame = coe_frame
+                    .get(..(self.length() as usize).min(coe_frame.len()))
+                    .filter(|frame| AmsFrame::HEADER_SIZE <= frame.len())
+                    .ok_or(LengthError)?;
+                Ok(Mailbox::AoE(aoe(AmsFrame(ams_frame))?))
+            }
+            MailboxType::EoE => {
+                let eoe_length = (self.length() as usize).min(coe_frame.len());
+                let eoe_frame = &coe_frame[..eoe_length];
+                eoe_frame
+                    .get(EoeFrame::HEADER_SIZE - 1)
+                    .ok_or(LengthError)?;
+                Ok(Mailbox::EoE(eoe(EoeFrame(eoe_frame))?))
+            }
             MailboxType::CoE => {
                 if len < MailboxFrame::HEADER_SIZE + CoeFrame::HEADER_SIZE + SdoFrame::HEADER_SIZE {
                     return Err(LengthError);
                 }
+                // The data of normal and segmented transfers end at the mailbox length.
+                let coe_length = (self.length() as usize).clamp(
+                    CoeFrame::HEADER_SIZE + SdoFrame::HEADER_SIZE,
+                    coe_frame.len(),
+                );
+                let coe_frame = &coe_frame[..coe_length];
                 let sdo_frame = CoeFrame(coe_frame).without_header();
                 let sdo_payload = SdoFrame(sdo_frame).without_header();
-                let index = SdoFrame(sdo_frame).index();
-                let sub_index = SdoFrame(sdo_frame).sub_index();
+                let mut coe_index = CoeIndex {
+                    index: SdoFrame(sdo_frame).index(),
+                    sub_index: SdoFrame(sdo_frame).sub_index(),
+                    complete_access: SdoFrame(sdo_frame).complete_access(),
+                };
+                let segment = SdoSegmentFrame(sdo_frame);
                 let coe = match CoeFrame(coe_frame).coe_service_type() {
                     CoeServiceType::Emmergency => {
-                        sdo_payload
+                        // An emergency has no SDO header.
+                        sdo_frame
                             .get(EmmergencyFrame::SIZE - 1)
                             .ok_or(LengthError)?;
-                        CoE::Emmergency(EmmergencyFrame(&sdo_payload))
+                        CoE::Emmergency(EmmergencyFrame(sdo_frame))
                     }
                     CoeServiceType::SdoReq => {
                         let sdo_header = SdoFrame(sdo_frame);
 
                         match sdo_header.command_specifier() {
+                            // Download Segment Request
+                            0 => {
+                                coe_index = CoeIndex::default();
+                                CoE::SdoReq(SdoReq::DownLoadSegment {
+                                    toggle: segment.toggle(),
+                                    last: segment.last_segment(),
+                                    data: segment_data(&segment)?,
+                                })
+                            }
                             // Download Request
                             1 => {
                                 // expedited
@@ -291,15 +328,28 @@ impl<'a> MailboxFrame<&'a [u8]> {
                                     let mut complete_size = [0; 4];
                                     let buf = &sdo_payload[..4];
                                     complete_size.iter_mut().zip(buf).for_each(|(s, b)| *s = *b);
-                                    let size = u32::from_le_bytes(complete_size) as usize;
-
-                                    sdo_payload.get(size + 4 - 1).ok_or(LengthError)?;
+                                    let complete_size = u32::from_le_bytes(complete_size);
 
-                                    CoE::SdoReq(SdoReq::DownLoad(&sdo_payload[4..size + 4]))
+                                    let data = &sdo_payload[4..];
+                                    if let Some(data) = data.get(..complete_size as usize) {
+                                        CoE::SdoReq(SdoReq::DownLoad(data))
+                                    } else {
+                                        CoE::SdoReq(SdoReq::SegmentedDownLoad {
+                                            complete_size,
+                                            data,
+                                        })
+                                    }
                                 }
                             }
                             // Upload Request
                             2 => CoE::SdoReq(SdoReq::Upload),
+                            // Upload Segment Request
+                            3 => {
+                                coe_index = CoeIndex::default();
+                                CoE::SdoReq(SdoReq::UploadSegment {
+                                    toggle: segment.toggle(),
+                                })
+                            }
                             // Abort
                             4 => {
                                 let mut abort_code = [0; 4];
@@ -322,6 +372,22 @@ impl<'a> MailboxFrame<&'a [u8]> {
                     CoeServiceType::SdoRes => {
                         let sdo_header = SdoFrame(sdo_frame);
                         match sdo_header.command_specifier() {
+                            // Upload Segment Response
+                            0 => {
+                                coe_index = CoeIndex::default();
+                                CoE::SdoRes(SdoRes::UploadSegment {
+                                    toggle: segment.toggle(),
+                                    last: segment.last_segment(),
+                                    data: segment_data(&segment)?,
+                                })
+                            }
+                            // Download Segment Response
+                            1 => {
+                                coe_index = CoeIndex::default();
+                                CoE::SdoRes(SdoRes::DownLoadSegment {
+                                    toggle: segment.toggle(),
+                                })
+                            }
                             // Upload Response
                             2 => {
                                 // expedited
@@ -344,11 +410,17 @@ impl<'a> MailboxFrame<&'a [u8]> {
                                     let mut complete_size = [0; 4];
                                     let buf = &sdo_payload[..4];
                                     complete_size.iter_mut().zip(buf).for_each(|(s, b)| *s = *b);
-                                    let size = u32::from_le_bytes(complete_size) as usize;
+                                    let complete_size = u32::from_le_bytes(complete_size);
 
-                                    sdo_payload.get(size + 4 - 1).ok_or(LengthError)?;
-
-                                    CoE::SdoRes(SdoRes::Upload(&sdo_payload[4..size + 4]))
+                                    let data = &sdo_payload[4..];
+                                    if let Some(data) = data.get(..complete_size as usize) {
+                                        CoE::SdoRes(SdoRes::Upload(data))
+                                    } else {
+                                        CoE::SdoRes(SdoRes::SegmentedUpload {
+                                            complete_size,
+                                            data,
+                                        })
+                                    }
                                 }
                             }
                             // Download Response
@@ -366,84 +438,72 @@ impl<'a> MailboxFrame<&'a [u8]> {
                     CoeServiceType::RxPdoRemoteReq => {
                         CoE::UnsupportedType(CoeServiceType::RxPdoRemoteReq)
                     }
-                    CoeServiceType::SdoInfo => CoE::UnsupportedType(CoeServiceType::SdoInfo),
+                    CoeServiceType::SdoInfo => {
+                        coe_index = CoeIndex::default();
+                        CoE::SdoInfo(sdo_info(SdoInfoFrame(sdo_frame))?)
+                    }
                     CoeServiceType::Other => CoE::UnsupportedType(CoeServiceType::Other),
                 };
-                Ok(Mailbox::CoE((CoeIndex { index, sub_index }, coe)))
+                Ok(Mailbox::CoE((coe_index, coe)))
+            }
+            MailboxType::FoE => {
+                // The data of FoE end at the mailbox length.
+                let foe_length = (self.length() as usize).min(coe_frame.len());
+                let foe_frame = &coe_frame[..foe_length];
+                foe_frame
+                    .get(FoeFrame::HEADER_SIZE - 1)
+                    .ok_or(LengthError)?;
+                Ok(Mailbox::FoE(foe(FoeFrame(foe_frame))))
+            }
+            MailboxType::SoE => {
+                // The data of SoE end at the mailbox length.
+                let soe_length = (self.length() as usize).min(coe_frame.len());
+                let soe_frame = &coe_frame[..soe_length];
+                soe_frame
+                    .get(SoeFrame::HEADER_SIZE - 1)
+                    .ok_or(LengthError)?;
+                Ok(Mailbox::SoE(soe(SoeFrame(soe_frame))?))
+            }
+            MailboxType::VoE => {
+                // The vendor header is a part of the data.
+                let voe_length = (self.length() as usize).min(coe_frame.len());
+                Ok(Mailbox::VoE(&coe_frame[..voe_length]))
             }
-            MailboxType::FoE => Ok(Mailbox::UnsupportedProtocol(MailboxType::FoE)),
-            MailboxType::SoE => Ok(Mailbox::UnsupportedProtocol(MailboxType::SoE)),
-            MailboxType::VoE => Ok(Mailbox::UnsupportedProtocol(MailboxType::VoE)),
             MailboxType::Other => Ok(Mailbox::UnsupportedProtocol(MailboxType::Other)),
         }
     }
 }
 
 impl<'a> MailboxFrame<&'a mut [u8]> {
-    pub fn set_mailbox(&mut self, mailbox: &Mailbox) -> Result<(), LengthError> {
-        match mailbox {
-            Mailbox::Error(_) => {
-                unimplemented!()
+    /// Writes `mailbox` after the header, and sets the type and the length in the header.
+    /// The address, the priority and the count are not changed.
+    pub fn set_mailbox(&mut self, mailbox: &Mailbox) -> Result<(), MailboxEncodeError> {
+        let buf = self
+            .0
+            .get_mut(MailboxFrame::HEADER_SIZE..)
+            .ok_or(LengthError)?;
+        let (mb_type, size) = match mailbox {
+            Mailbox::Error(detail) => (MailboxType::Error, set_mailbox_error(buf, *detail)?),
+            Mailbox::AoE(aoe) => (MailboxType::AoE, set_aoe(buf, aoe)?),
+            Mailbox::CoE((coe_index, coe)) => (MailboxType::CoE, set_coe(buf, coe_index, coe)?),
+            Mailbox::EoE(eoe) => (MailboxType::EoE, set_eoe(buf, eoe)?),
+            Mailbox::FoE(foe) => (MailboxType::FoE, set_foe(buf, foe)?),
+            Mailbox::SoE(soe) => (MailboxType::SoE, set_soe(buf, soe)?),
+            Mailbox::VoE(data) => {
+                buf.get_mut(..data.len())
+                    .ok_or(LengthError)?
+                    .copy_from_slice(data);
+                (MailboxType::VoE, data.len())
             }
-            Mailbox::CoE((coe_index, coe)) => {
-                self.set_mb_type(MailboxType::CoE);
-                match coe {
-                    CoE::Emmergency(_) => unimplemented!(),
-                    CoE::SdoReq(sdo_req) => {
-                        let mut coe_frame = CoeFrame(&mut self.0[MailboxFrame::HEADER_SIZE..]);
-                        coe_frame.set_number(0);
-                        coe_frame.set_coe_service_type(CoeServiceType::SdoReq);
-                        let mut sdo_frame = SdoFrame(&mut coe_frame.0[CoeFrame::HEADER_SIZE..]);
-                        let mailbox_payload_length = match sdo_req {
-                            SdoReq::DownLoad(data) => {
-                                // Download normal request
-                                sdo_frame.set_complete_access(false);
-                                sdo_frame.set_data_set_size(0);
-                                sdo_frame.set_command_specifier(1); // download request
-                                sdo_frame.set_transfer_type(false); // normal transfer
-                                sdo_frame.set_size_indicator(true);
-                                sdo_frame.set_index(coe_index.index);
-                                sdo_frame.set_sub_index(coe_index.sub_index);
-                                let mut download_frame = SdoDownloadNormalRequestFrame(
-                                    &mut sdo_frame.0[SdoFrame::HEADER_SIZE..],
-                                );
-                                download_frame.set_complete_size(data.len() as u32);
-                                let payload_length = CoeFrame::HEADER_SIZE
-                                    + SdoFrame::HEADER_SIZE
-                                    + SdoDownloadNormalRequestFrame::HEADER_SIZE
-                                    + data.len();
-                                assert!(payload_length <= u16::MAX as usize);
-                                sdo_frame.0[SdoFrame::HEADER_SIZE
-                                    + SdoDownloadNormalRequestFrame::HEADER_SIZE..]
-                                    .iter_mut()
-                                    .zip(data.iter())
-                                    .for_each(|(frame, d)| *frame = *d);
-                                payload_length as u16
-                            }
-                            SdoReq::Upload => {
-                                // Upload request
-                                sdo_frame.set_complete_access(false);
-                                sdo_frame.set_data_set_size(0);
-                                sdo_frame.set_command_specifier(2); // upload request
-                                sdo_frame.set_transfer_type(false);
-                                sdo_frame.set_size_indicator(false);
-                                sdo_frame.set_index(coe_index.index);
-                                sdo_frame.set_sub_index(coe_index.sub_index);
-                                let payload_length =
-                                    CoeFrame::HEADER_SIZE + SdoFrame::HEADER_SIZE + 4;
-                                payload_length as u16
-                            }
-                            SdoReq::Abort(_) => unimplemented!(),
-                            SdoReq::Other(_) => unimplemented!(),
-                        };
-                        self.set_length(mailbox_payload_length);
-                    }
-                    CoE::SdoRes(_) => unimplemented!(),
-                    CoE::UnsupportedType(_) => unimplemented!("Unsupported CoE service type"),
-                }
+            Mailbox::UnsupportedProtocol(MailboxType::Other) => {
+                return Err(MailboxEncodeError::UnknownCode)
             }
-            Mailbox::UnsupportedProtocol(_) => unimplemented!("Unsupported mailbox protocol"),
-        }
+            // A mailbox without data.
+            Mailbox::UnsupportedProtocol(mb_type) => (*mb_type, 0),
+        };
+        let length = u16::try_from(size).map_err(|_| LengthError)?;
+        self.set_mb_type(mb_type);
+        self.set_length(length);
         Ok(())
     }
 }
@@ -451,19 +511,221 @@ impl<'a> MailboxFrame<&'a mut [u8]> {
 #[derive(Debug)]
 pub enum Mailbox<'a> {
     Error(MailboxErrorDetail),
+    AoE(AoE<'a>),
     CoE((CoeIndex, CoE<'a>)),
+    EoE(EoE<'a>),
+    FoE(FoE<'a>),
+    SoE(SoE<'a>),
+    /// Vendor specific data, which begin with the vendor header.
+    VoE(&'a [u8]),
     UnsupportedProtocol(MailboxType),
 }
 
 impl<'a> Mailbox<'a> {
     pub fn new_sdo_download_request(index: u16, sub_index: u8, data: &'a [u8]) -> Self {
         let sdo_req = SdoReq::DownLoad(data);
-        Self::CoE((CoeIndex { index, sub_index }, CoE::SdoReq(sdo_req)))
+        Self::CoE((CoeIndex::new(index, sub_index), CoE::SdoReq(sdo_req)))
     }
 
     pub fn new_sdo_upload_request(index: u16, sub_index: u8) -> Self {
         let sdo_req = SdoReq::Upload;
-        Self::CoE((CoeIndex { index, sub_index }, CoE::SdoReq(sdo_req)))
+        Self::CoE((CoeIndex::new(index, sub_index), CoE::SdoReq(sdo_req)))
+    }
+
+    /// Downloads all entries of an object from `sub_index` (0 or 1).
+    pub fn new_sdo_complete_download_request(index: u16, sub_index: u8, data: &'a [u8]) -> Self {
+        let sdo_req = SdoReq::DownLoad(data);
+        Self::CoE((CoeIndex::complete(index, sub_index), CoE::SdoReq(sdo_req)))
+    }
+
+    /// Uploads all entries of an object from `sub_index` (0 or 1).
+    pub fn new_sdo_complete_upload_request(index: u16, sub_index: u8) -> Self {
+        let sdo_req = SdoReq::Upload;
+        Self::CoE((CoeIndex::complete(index, sub_index), CoE::SdoReq(sdo_req)))
+    }
+
+    /// The first part of a segmented download. The rest of `complete_size` follows in segments.
+    pub fn new_sdo_segmented_download_request(
+        index: u16,
+        sub_index: u8,
+        complete_size: u32,
+        data: &'a [u8],
+    ) -> Self {
+        let sdo_req = SdoReq::SegmentedDownLoad {
+            complete_size,
+            data,
+        };
+        Self::CoE((CoeIndex::new(index, sub_index), CoE::SdoReq(sdo_req)))
+    }
+
+    pub fn new_sdo_download_segment_request(toggle: bool, last: bool, data: &'a [u8]) -> Self {
+        let sdo_req = SdoReq::DownLoadSegment { toggle, last, data };
+        Self::CoE((CoeIndex::default(), CoE::SdoReq(sdo_req)))
+    }
+
+    pub fn new_sdo_upload_segment_request(toggle: bool) -> Self {
+        let sdo_req = SdoReq::UploadSegment { toggle };
+        Self::CoE((CoeIndex::default(), CoE::SdoReq(sdo_req)))
+    }
+
+    pub fn new_sdo_abort_request(index: u16, sub_index: u8, abort_code: AbortCode) -> Self {
+        let sdo_req = SdoReq::Abort(abort_code);
+        Self::CoE((CoeIndex::new(index, sub_index), CoE::SdoReq(sdo_req)))
+    }
+
+    pub fn new_sdo_info_od_list_request(list_type: OdListType) -> Self {
+        let sdo_info = SdoInfo::GetOdListReq(list_type);
+        Self::CoE((CoeIndex::default(), CoE::SdoInfo(sdo_info)))
+    }
+
+    pub fn new_sdo_info_object_description_request(index: u16) -> Self {
+        let sdo_info = SdoInfo::GetObjectDescriptionReq(index);
+        Self::CoE((CoeIndex::default(), CoE::SdoInfo(sdo_info)))
+    }
+
+    /// `value_info` is a combination of `ValueInfo`.
+    pub fn new_sdo_info_entry_description_request(
+        index: u16,
+        sub_index: u8,
+        value_info: u8,
+    ) -> Self {
+        let sdo_info = SdoInfo::GetEntryDescriptionReq {
+            index,
+            sub_index,
+            value_info,
+        };
+        Self::CoE((CoeIndex::default(), CoE::SdoInfo(sdo_info)))
+    }
+
+    pub fn new_ads_read_request(header: AmsHeader, index: AdsIndex, length: u32) -> Self {
+        Self::AoE(AoE::Request {
+            header,
+            request: AdsRequest::Read { index, length },
+        })
+    }
+
+    pub fn new_ads_write_request(header: AmsHeader, index: AdsIndex, data: &'a [u8]) -> Self {
+        Self::AoE(AoE::Request {
+            header,
+            request: AdsRequest::Write { index, data },
+        })
+    }
+
+    /// Writes `data` and reads up to `read_length` octets in one request.
+    pub fn new_ads_read_write_request(
+        header: AmsHeader,
+        index: AdsIndex,
+        read_length: u32,
+        data: &'a [u8],
+    ) -> Self {
+        Self::AoE(AoE::Request {
+            header,
+            request: AdsRequest::ReadWrite {
+                index,
+                read_length,
+                data,
+            },
+        })
+    }
+
+    pub fn new_ads_read_state_request(header: AmsHeader) -> Self {
+        Self::AoE(AoE::Request {
+            header,
+            request: AdsRequest::ReadState,
+        })
+    }
+
+    /// `offset` is the complete size of the frame in the first fragment.
+    /// See `EoeFrame::offset_buffer`.
+    pub fn new_eoe_fragment(
+        frame_number: u8,
+        fragment_number: u8,
+        offset: u8,
+        last: bool,
+        data: &'a [u8],
+    ) -> Self {
+        Self::EoE(EoE::Fragment {
+            port: 0,
+            frame_number,
+            fragment_number,
+            offset,
+            last,
+            data,
+        })
+    }
+
+    pub fn new_eoe_set_ip_request(parameter: EoeIpParameter) -> Self {
+        Self::EoE(EoE::SetIpReq(parameter))
+    }
+
+    pub fn new_eoe_set_ip_response(result: EoeResult) -> Self {
+        Self::EoE(EoE::SetIpRes(result))
+    }
+
+    pub fn new_foe_read_request(password: u32, file_name: &'a [u8]) -> Self {
+        Self::FoE(FoE::ReadReq {
+            password,
+            file_name,
+        })
+    }
+
+    pub fn new_foe_write_request(password: u32, file_name: &'a [u8]) -> Self {
+        Self::FoE(FoE::WriteReq {
+            password,
+            file_name,
+        })
+    }
+
+    /// Packet numbers start with 1.
+    pub fn new_foe_data(packet_number: u32, data: &'a [u8]) -> Self {
+        Self::FoE(FoE::Data {
+            packet_number,
+            data,
+        })
+    }
+
+    pub fn new_foe_ack(packet_number: u32) -> Self {
+        Self::FoE(FoE::Ack(packet_number))
+    }
+
+    pub fn new_foe_error(code: FoeErrorCode) -> Self {
+        Self::FoE(FoE::Error { code, text: &[] })
+    }
+
+    /// `elements` is a combination of `SoeElement`.
+    pub fn new_soe_read_request(drive_number: u8, idn: u16, elements: u8) -> Self {
+        Self::SoE(SoE::ReadReq {
+            drive_number,
+            elements,
+            idn,
+        })
+    }
+
+    /// The last or only fragment of a write request.
+    pub fn new_soe_write_request(drive_number: u8, idn: u16, elements: u8, data: &'a [u8]) -> Self {
+        Self::SoE(SoE::WriteReq {
+            drive_number,
+            elements,
+            incomplete: false,
+            idn,
+            data,
+        })
+    }
+
+    /// A fragment of a write request which more fragments follow.
+    pub fn new_soe_write_fragment(
+        drive_number: u8,
+        elements: u8,
+        fragments_left: u16,
+        data: &'a [u8],
+    ) -> Self {
+        Self::SoE(SoE::WriteReq {
+            drive_number,
+            elements,
+            incomplete: true,
+            idn: fragments_left,
+            data,
+        })
     }
 
     pub fn sdo_upload_response(&self) -> Option<&[u8]> {
@@ -498,13 +760,190 @@ pub enum CoE<'a> {
     Emmergency(EmmergencyFrame<&'a [u8]>),
     SdoReq(SdoReq<'a>),
     SdoRes(SdoRes<'a>),
+    SdoInfo(SdoInfo<'a>),
     UnsupportedType(CoeServiceType),
 }
 
+/// ADS over EtherCAT. The data length of the AMS header follows from the request or the response.
+#[derive(Debug, Clone, Copy)]
+pub enum AoE<'a> {
+    Request {
+        header: AmsHeader,
+        request: AdsRequest<'a>,
+    },
+    Response {
+        header: AmsHeader,
+        response: AdsResponse<'a>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AdsRequest<'a> {
+    Read {
+        index: AdsIndex,
+        length: u32,
+    },
+    Write {
+        index: AdsIndex,
+        data: &'a [u8],
+    },
+    ReadWrite {
+        index: AdsIndex,
+        read_length: u32,
+        data: &'a [u8],
+    },
+    ReadState,
+    Other(AdsCommand),
+}
+
+/// A response with an error code in the AMS header may have no data. It is `Other` then.
+#[derive(Debug, Clone, Copy)]
+pub enum AdsResponse<'a> {
+    Read {
+        result: AdsErrorCode,
+        data: &'a [u8],
+    },
+    Write {
+        result: AdsErrorCode,
+    },
+    ReadWrite {
+        result: AdsErrorCode,
+        data: &'a [u8],
+    },
+    ReadState {
+        result: AdsErrorCode,
+        ads_state: AdsState,
+        device_state: u16,
+    },
+    Other(AdsCommand),
+}
+
+/// Ethernet over EtherCAT.
+#[derive(Debug, Clone, Copy)]
+pub enum EoE<'a> {
+    /// A fragment of an Ethernet frame without FCS.
+    /// `offset` is the complete size of the frame in the first fragment.
+    /// See `EoeFrame::offset_buffer`.
+    Fragment {
+        port: u8,
+        frame_number: u8,
+        fragment_number: u8,
+        offset: u8,
+        last: bool,
+        data: &'a [u8],
+    },
+    SetIpReq(EoeIpParameter),
+    SetIpRes(EoeResult),
+    Other(EoeFrameType),
+}
+
+/// File access over EtherCAT.
+#[derive(Debug, Clone, Copy)]
+pub enum FoE<'a> {
+    ReadReq {
+        password: u32,
+        file_name: &'a [u8],
+    },
+    WriteReq {
+        password: u32,
+        file_name: &'a [u8],
+    },
+    /// A packet shorter than the mailbox capacity is the last one of the file.
+    Data {
+        packet_number: u32,
+        data: &'a [u8],
+    },
+    Ack(u32),
+    Error {
+        code: FoeErrorCode,
+        text: &'a [u8],
+    },
+    /// The slave is busy. `done` of `entire` is the progress.
+    Busy {
+        done: u16,
+        entire: u16,
+    },
+    Other(FoeOpCode),
+}
+
+/// Servo drive profile over EtherCAT.
+/// While `incomplete` is set, `idn` is the number of fragments left.
+#[derive(Debug, Clone, Copy)]
+pub enum SoE<'a> {
+    ReadReq {
+        drive_number: u8,
+        elements: u8,
+        idn: u16,
+    },
+    ReadRes {
+        drive_number: u8,
+        elements: u8,
+        incomplete: bool,
+        idn: u16,
+        data: &'a [u8],
+    },
+    WriteReq {
+        drive_number: u8,
+        elements: u8,
+        incomplete: bool,
+        idn: u16,
+        data: &'a [u8],
+    },
+    WriteRes {
+        drive_number: u8,
+        elements: u8,
+        idn: u16,
+    },
+    /// Response with the error flag to a request. `op_code` is the one of the response.
+    Error {
+        op_code: SoeOpCode,
+        drive_number: u8,
+        elements: u8,
+        idn: u16,
+        code: SoeErrorCode,
+    },
+    Other(SoeOpCode),
+}
+
+/// SDO information service. It does not use `CoeIndex`.
+#[derive(Debug, Clone, Copy)]
+pub enum SdoInfo<'a> {
+    GetOdListReq(OdListType),
+    GetObjectDescriptionReq(u16),
+    GetEntryDescriptionReq {
+        index: u16,
+        sub_index: u8,
+        value_info: u8,
+    },
+    /// A fragment of a response. The first fragment begins with the header of the response,
+    /// e.g. `ObjectDescriptionFrame`, and the rest continue the data.
+    Response {
+        op_code: SdoInfoOpCode,
+        incomplete: bool,
+        fragments_left: u16,
+        data: &'a [u8],
+    },
+    Error(AbortCode),
+    Other(SdoInfoOpCode),
+}
+
 #[derive(Debug)]
 pub enum SdoRes<'a> {
     DownLoad,
     Upload(&'a [u8]),
+    /// Upload response whose data do not fit in the mailbox. `data` is the first part.
+    SegmentedUpload {
+        complete_size: u32,
+        data: &'a [u8],
+    },
+    DownLoadSegment {
+        toggle: bool,
+    },
+    UploadSegment {
+        toggle: bool,
+        last: bool,
+        data: &'a [u8],
+    },
     Other(CommandSpecifier),
 }
 
@@ -512,6 +951,19 @@ pub enum SdoRes<'a> {
 pub enum SdoReq<'a> {
     DownLoad(&'a [u8]),
     Upload,
+    /// Download request whose data do not fit in the mailbox. `data` is the first part.
+    SegmentedDownLoad {
+        complete_size: u32,
+        data: &'a [u8],
+    },
+    DownLoadSegment {
+        toggle: bool,
+        last: bool,
+        data: &'a [u8],
+    },
+    UploadSegment {
+        toggle: bool,
+    },
     Abort(AbortCode),
     Other(CommandSpecifier),
 }
@@ -519,18 +971,761 @@ pub enum SdoReq<'a> {
 #[derive(Debug, Clone, Copy)]
 pub struct LengthError;
 
+/// Error of `MailboxFrame::set_mailbox`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MailboxEncodeError {
+    /// The mailbox does not fit in the buffer.
+    Length,
+    /// A code whose value is not known, e.g. `AdsCommand::Other`, cannot be written.
+    UnknownCode,
+}
+
+impl From<LengthError> for MailboxEncodeError {
+    fn from(_: LengthError) -> Self {
+        Self::Length
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct CommandSpecifier(pub u8);
 
-#[derive(Debug, Clone, Copy)]
+/// Segments do not have an index. It is zero for them.
+#[derive(Debug, Clone, Copy, Default)]
 pub struct CoeIndex {
     pub index: u16,
     pub sub_index: u8,
+    /// If true, all entries of the object from `sub_index` (0 or 1) are accessed.
+    /// Sub index 0 is 16-bit in the data then.
+    pub complete_access: bool,
+}
+
+impl CoeIndex {
+    pub fn new(index: u16, sub_index: u8) -> Self {
+        Self {
+            index,
+            sub_index,
+            complete_access: false,
+        }
+    }
+
+    pub fn complete(index: u16, sub_index: u8) -> Self {
+        Self {
+            index,
+            sub_index,
+            complete_access: true,
+        }
+    }
+}
+
+fn sdo_info<'a>(frame: SdoInfoFrame<&'a [u8]>) -> Result<SdoInfo<'a>, LengthError> {
+    let data = frame.without_header();
+    let u16_at = |offset: usize| -> Result<u16, LengthError> {
+        let bytes = data.get(offset..offset + 2).ok_or(LengthError)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+    let op_code = frame.sdo_info_op_code();
+    let sdo_info = match op_code {
+        SdoInfoOpCode::GetOdListReq => SdoInfo::GetOdListReq(u16_at(0)?.into()),
+        SdoInfoOpCode::GetObjectDescriptionReq => SdoInfo::GetObjectDescriptionReq(u16_at(0)?),
+        SdoInfoOpCode::GetEntryDescriptionReq => {
+            data.get(4 - 1).ok_or(LengthError)?;
+            SdoInfo::GetEntryDescriptionReq {
+                index: u16_at(0)?,
+                sub_index: data[2],
+                value_info: data[3],
+            }
+        }
+        SdoInfoOpCode::GetOdListRes
+        | SdoInfoOpCode::GetObjectDescriptionRes
+        | SdoInfoOpCode::GetEntryDescriptionRes => SdoInfo::Response {
+            op_code,
+            incomplete: frame.incomplete(),
+            fragments_left: frame.fragments_left(),
+            data,
+        },
+        SdoInfoOpCode::Error => {
+            let code = data.get(..4).ok_or(LengthError)?;
+            let code = u32::from_le_bytes([code[0], code[1], code[2], code[3]]);
+            SdoInfo::Error(AbortCode::from(code))
+        }
+        SdoInfoOpCode::Other => SdoInfo::Other(op_code),
+    };
+    Ok(sdo_info)
+}
+
+/// Returns the size of the SDO information header and data.
+fn set_sdo_info(buf: &mut [u8], sdo_info: &SdoInfo) -> Result<usize, MailboxEncodeError> {
+    let mut req = [0; 4];
+    let (op_code, incomplete, fragments_left, data): (_, _, _, &[u8]) = match sdo_info {
+        SdoInfo::GetOdListReq(list_type) => {
+            req[..2].copy_from_slice(&(*list_type as u16).to_le_bytes());
+            (SdoInfoOpCode::GetOdListReq, false, 0, &req[..2])
+        }
+        SdoInfo::GetObjectDescriptionReq(index) => {
+            req[..2].copy_from_slice(&index.to_le_bytes());
+            (SdoInfoOpCode::GetObjectDescriptionReq, false, 0, &req[..2])
+        }
+        SdoInfo::GetEntryDescriptionReq {
+            index,
+            sub_index,
+            value_info,
+        } => {
+            req[..2].copy_from_slice(&index.to_le_bytes());
+            req[2] = *sub_index;
+            req[3] = *value_info;
+            (SdoInfoOpCode::GetEntryDescriptionReq, false, 0, &req)
+        }
+        SdoInfo::Response {
+            op_code,
+            incomplete,
+            fragments_left,
+            data,
+        } => (*op_code, *incomplete, *fragments_left, data),
+        SdoInfo::Error(abort_code) => {
+            req.copy_from_slice(&(*abort_code as u32).to_le_bytes());
+            (SdoInfoOpCode::Error, false, 0, &req)
+        }
+        SdoInfo::Other(SdoInfoOpCode::Other) => return Err(MailboxEncodeError::UnknownCode),
+        SdoInfo::Other(op_code) => (*op_code, false, 0, &[]),
+    };
+    let size = SdoInfoFrame::HEADER_SIZE + data.len();
+    let buf = buf.get_mut(..size).ok_or(LengthError)?;
+    let mut frame = SdoInfoFrame(&mut buf[..]);
+    frame.set_sdo_info_op_code(op_code);
+    frame.set_incomplete(incomplete);
+    frame.set_fragments_left(fragments_left);
+    buf[1] = 0;
+    buf[SdoInfoFrame::HEADER_SIZE..].copy_from_slice(data);
+    Ok(size)
+}
+
+fn aoe<'a>(frame: AmsFrame<&'a [u8]>) -> Result<AoE<'a>, LengthError> {
+    let data = frame.without_header();
+    let data = &data[..(frame.data_length() as usize).min(data.len())];
+    let u32_at = |offset: usize| -> Result<u32, LengthError> {
+        let bytes = data.get(offset..offset + 4).ok_or(LengthError)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    // Data whose length is given before them.
+    let data_at = |offset: usize| -> Result<&'a [u8], LengthError> {
+        let length = u32_at(offset)? as usize;
+        data.get(offset + 4..offset + 4 + length).ok_or(LengthError)
+    };
+    let index = || -> Result<AdsIndex, LengthError> { Ok(AdsIndex::new(u32_at(0)?, u32_at(4)?)) };
+    let header = AmsHeader {
+        target: AmsAddress::new(frame.target_net_id(), frame.target_port()),
+        source: AmsAddress::new(frame.source_net_id(), frame.source_port()),
+        error_code: AdsErrorCode::from(frame.error_code()),
+        invoke_id: frame.invoke_id(),
+    };
+    let command = frame.ads_command();
+    if frame.state_flags() & AmsStateFlags::RESPONSE == 0 {
+        let request = match command {
+            AdsCommand::Read => AdsRequest::Read {
+                index: index()?,
+                length: u32_at(8)?,
+            },
+            AdsCommand::Write => AdsRequest::Write {
+                index: index()?,
+                data: data_at(8)?,
+            },
+            AdsCommand::ReadWrite => {
+                let length = u32_at(12)? as usize;
+                AdsRequest::ReadWrite {
+                    index: index()?,
+                    read_length: u32_at(8)?,
+                    data: data.get(16..16 + length).ok_or(LengthError)?,
+                }
+            }
+            AdsCommand::ReadState => AdsRequest::ReadState,
+            command => AdsRequest::Other(command),
+        };
+        return Ok(AoE::Request { header, request });
+    }
+    if header.error_code != AdsErrorCode::NoError && data.is_empty() {
+        let response = AdsResponse::Other(command);
+        return Ok(AoE::Response { header, response });
+    }
+    let result = || -> Result<AdsErrorCode, LengthError> { Ok(AdsErrorCode::from(u32_at(0)?)) };
+    let response = match command {
+        AdsCommand::Read => AdsResponse::Read {
+            result: result()?,
+            data: data_at(4)?,
+        },
+        AdsCommand::Write => AdsResponse::Write { result: result()? },
+        AdsCommand::ReadWrite => AdsResponse::ReadWrite {
+            result: result()?,
+            data: data_at(4)?,
+        },
+        AdsCommand::ReadState => {
+            let states = data.get(4..8).ok_or(LengthError)?;
+            AdsResponse::ReadState {
+                result: result()?,
+                ads_state: AdsState::from(u16::from_le_bytes([states[0], states[1]])),
+                device_state: u16::from_le_bytes([states[2], states[3]]),
+            }
+        }
+        command => AdsResponse::Other(command),
+    };
+    Ok(AoE::Response { header, response })
+}
+
+/// Returns the size of the AMS header and ADS data.
+fn set_aoe(buf: &mut [u8], aoe: &AoE) -> Result<usize, MailboxEncodeError> {
+    // Fixed fields of the ADS data, which may be followed by variable data.
+    let mut fields = [0; 16];
+    let (header, command, state_flags, fields_size, data): (_, _, _, _, &[u8]) = match aoe {
+        AoE::Request { header, request } => {
+            let state_flags = AmsStateFlags::ADS_COMMAND;
+            let mut set_index = |index: &AdsIndex| {
+                fields[0..4].copy_from_slice(&index.group.to_le_bytes());
+                fields[4..8].copy_from_slice(&index.offset.to_le_bytes());
+            };
+            match request {
+                AdsRequest::Read { index, length } => {
+                    set_index(index);
+                    fields[8..12].copy_from_slice(&length.to_le_bytes());
+                    (header, AdsCommand::Read, state_flags, 12, &[])
+                }
+                AdsRequest::Write { index, data } => {
+                    set_index(index);
+                    fields[8..12].copy_from_slice(&(data.len() as u32).to_le_bytes());
+                    (header, AdsCommand::Write, state_flags, 12, data)
+                }
+                AdsRequest::ReadWrite {
+                    index,
+                    read_length,
+                    data,
+                } => {
+                    set_index(index);
+                    fields[8..12].copy_from_slice(&read_length.to_le_bytes());
+                    fields[12..16].copy_from_slice(&(data.len() as u32).to_le_bytes());
+                    (header, AdsCommand::ReadWrite, state_flags, 16, data)
+                }
+                AdsRequest::ReadState => (header, AdsCommand::ReadState, state_flags, 0, &[]),
+                AdsRequest::Other(command) => (header, *command, state_flags, 0, &[]),
+            }
+        }
+        AoE::Response { header, response } => {
+            let state_flags = AmsStateFlags::ADS_COMMAND | AmsStateFlags::RESPONSE;
+            let mut set_result = |result: &AdsErrorCode| {
+                fields[0..4].copy_from_slice(&(*result as u32).to_le_bytes());
+            };
+            match response {
+                AdsResponse::Read { result, data } => {
+                    set_result(result);
+                    fields[4..8].copy_from_slice(&(data.len() as u32).to_le_bytes());
+                    (header, AdsCommand::Read, state_flags, 8, data)
+                }
+                AdsResponse::Write { result } => {
+                    set_result(result);
+                    (header, AdsCommand::Write, state_flags, 4, &[])
+                }
+                AdsResponse::ReadWrite { result, data } => {
+                    set_result(result);
+                    fields[4..8].copy_from_slice(&(data.len() as u32).to_le_bytes());
+                    (header, AdsCommand::ReadWrite, state_flags, 8, data)
+                }
+                AdsResponse::ReadState {
+                    result,
+                    ads_state,
+                    device_state,
+                } => {
+                    set_result(result);
+                    fields[4..6].copy_from_slice(&(*ads_state as u16).to_le_bytes());
+                    fields[6..8].copy_from_slice(&device_state.to_le_bytes());
+                    (header, AdsCommand::ReadState, state_flags, 8, &[])
+                }
+                AdsResponse::Other(command) => (header, *command, state_flags, 0, &[]),
+            }
+        }
+    };
+    if command == AdsCommand::Other {
+        return Err(MailboxEncodeError::UnknownCode);
+    }
+    let data_length = fields_size + data.len();
+    let size = AmsFrame::HEADER_SIZE + data_length;
+    let buf = buf.get_mut(..size).ok_or(LengthError)?;
+    let mut frame = AmsFrame(&mut buf[..]);
+    frame.set_target_net_id(header.target.net_id);
+    frame.set_target_port(header.target.port);
+    frame.set_source_net_id(header.source.net_id);
+    frame.set_source_port(header.source.port);
+    frame.set_ads_command(command);
+    frame.set_state_flags(state_flags);
+    frame.set_data_length(data_length as u32);
+    frame.set_error_code(header.error_code as u32);
+    frame.set_invoke_id(header.invoke_id);
+    let data_offset = AmsFrame::HEADER_SIZE + fields_size;
+    buf[AmsFrame::HEADER_SIZE..data_offset].copy_from_slice(&fields[..fields_size]);
+    buf[data_offset..].copy_from_slice(data);
+    Ok(size)
+}
+
+fn eoe<'a>(frame: EoeFrame<&'a [u8]>) -> Result<EoE<'a>, LengthError> {
+    let data = frame.without_header();
+    let eoe = match frame.eoe_frame_type() {
+        EoeFrameType::Fragment => {
+            let last = frame.last_fragment();
+            // A time stamp may follow the last fragment.
+            let data = if last && frame.time_appended() {
+                let size = data.len().checked_sub(4).ok_or(LengthError)?;
+                &data[..size]
+            } else {
+                data
+            };
+            EoE::Fragment {
+                port: frame.port(),
+                frame_number: frame.frame_number(),
+                fragment_number: frame.fragment_number(),
+                offset: frame.offset_buffer(),
+                last,
+                data,
+            }
+        }
+        EoeFrameType::SetIpReq => {
+            EoE::SetIpReq(EoeIpParameter::from_bytes(data).ok_or(LengthError)?)
+        }
+        EoeFrameType::SetIpRes => EoE::SetIpRes(EoeResult::from(frame.result())),
+        frame_type => EoE::Other(frame_type),
+    };
+    Ok(eoe)
+}
+
+/// Returns the size of the EoE header and data.
+fn set_eoe(buf: &mut [u8], eoe: &EoE) -> Result<usize, MailboxEncodeError> {
+    let parameter;
+    let (frame_type, data): (_, &[u8]) = match eoe {
+        EoE::Fragment { data, .. } => (EoeFrameType::Fragment, data),
+        EoE::SetIpReq(ip_parameter) => {
+            parameter = ip_parameter.to_bytes();
+            (EoeFrameType::SetIpReq, &parameter)
+        }
+        EoE::SetIpRes(_) => (EoeFrameType::SetIpRes, &[]),
+        EoE::Other(EoeFrameType::Other) => return Err(MailboxEncodeError::UnknownCode),
+        EoE::Other(frame_type) => (*frame_type, &[]),
+    };
+    let size = EoeFrame::HEADER_SIZE + data.len();
+    let buf = buf.get_mut(..size).ok_or(LengthError)?;
+    buf[..EoeFrame::HEADER_SIZE].fill(0);
+    let mut frame = EoeFrame(&mut buf[..]);
+    frame.set_eoe_frame_type(frame_type);
+    match eoe {
+        EoE::Fragment {
+            port,
+            frame_number,
+            fragment_number,
+            offset,
+            last,
+            ..
+        } => {
+            frame.set_port(*port);
+            frame.set_frame_number(*frame_number);
+            frame.set_fragment_number(*fragment_number);
+            frame.set_offset_buffer(*offset);
+            frame.set_last_fragment(*last);
+        }
+        EoE::SetIpReq(_) => frame.set_last_fragment(true),
+        EoE::SetIpRes(result) => {
+            frame.set_last_fragment(true);
+            frame.set_result(*result as u16);
+        }
+        EoE::Other(_) => {}
+    }
+    buf[EoeFrame::HEADER_SIZE..].copy_from_slice(data);
+    Ok(size)
+}
+
+fn foe<'a>(frame: FoeFrame<&'a [u8]>) -> FoE<'a> {
+    let data = frame.without_header();
+    match frame.foe_op_code() {
+        FoeOpCode::Read => FoE::ReadReq {
+            password: frame.value(),
+            file_name: data,
+        },
+        FoeOpCode::Write => FoE::WriteReq {
+            password: frame.value(),
+            file_name: data,
+        },
+        FoeOpCode::Data => FoE::Data {
+            packet_number: frame.value(),
+            data,
+        },
+        FoeOpCode::Ack => FoE::Ack(frame.value()),
+        FoeOpCode::Error => FoE::Error {
+            code: FoeErrorCode::from(frame.value()),
+            text: data,
+        },
+        FoeOpCode::Busy => FoE::Busy {
+            done: frame.busy_done(),
+            entire: frame.busy_entire(),
+        },
+        op_code => FoE::Other(op_code),
+    }
+}
+
+/// Returns the size of the FoE header and data.
+fn set_foe(buf: &mut [u8], foe: &FoE) -> Result<usize, MailboxEncodeError> {
+    let (op_code, value, data): (_, _, &[u8]) = match foe {
+        FoE::ReadReq {
+            password,
+            file_name,
+        } => (FoeOpCode::Read, *password, file_name),
+        FoE::WriteReq {
+            password,
+            file_name,
+        } => (FoeOpCode::Write, *password, file_name),
+        FoE::Data {
+            packet_number,
+            data,
+        } => (FoeOpCode::Data, *packet_number, data),
+        FoE::Ack(packet_number) => (FoeOpCode::Ack, *packet_number, &[]),
+        FoE::Error { code, text } => (FoeOpCode::Error, *code as u32, text),
+        FoE::Busy { done, entire } => {
+            let value = (*entire as u32) << 16 | *done as u32;
+            (FoeOpCode::Busy, value, &[])
+        }
+        FoE::Other(FoeOpCode::Other) => return Err(MailboxEncodeError::UnknownCode),
+        FoE::Other(op_code) => (*op_code, 0, &[]),
+    };
+    let size = FoeFrame::HEADER_SIZE + data.len();
+    let buf = buf.get_mut(..size).ok_or(LengthError)?;
+    let mut frame = FoeFrame(&mut buf[..]);
+    frame.set_foe_op_code(op_code);
+    frame.set_value(value);
+    buf[1] = 0;
+    buf[FoeFrame::HEADER_SIZE..].copy_from_slice(data);
+    Ok(size)
+}
+
+fn soe<'a>(frame: SoeFrame<&'a [u8]>) -> Result<SoE<'a>, LengthError> {
+    let data = frame.without_header();
+    let op_code = frame.soe_op_code();
+    let drive_number = frame.drive_number();
+    let elements = frame.elements();
+    let idn = frame.idn();
+    if frame.error() {
+        let code = data.get(..2).ok_or(LengthError)?;
+        return Ok(SoE::Error {
+            op_code,
+            drive_number,
+            elements,
+            idn,
+            code: SoeErrorCode::from(u16::from_le_bytes([code[0], code[1]])),
+        });
+    }
+    let soe = match op_code {
+        SoeOpCode::ReadReq => SoE::ReadReq {
+            drive_number,
+            elements,
+            idn,
+        },
+        SoeOpCode::ReadRes => SoE::ReadRes {
+            drive_number,
+            elements,
+            incomplete: frame.incomplete(),
+            idn,
+            data,
+        },
+        SoeOpCode::WriteReq => SoE::WriteReq {
+            drive_number,
+            elements,
+            incomplete: frame.incomplete(),
+            idn,
+            data,
+        },
+        SoeOpCode::WriteRes => SoE::WriteRes {
+            drive_number,
+            elements,
+            idn,
+        },
+        op_code => SoE::Other(op_code),
+    };
+    Ok(soe)
+}
+
+/// Returns the size of the SoE header and data.
+fn set_soe(buf: &mut [u8], soe: &SoE) -> Result<usize, MailboxEncodeError> {
+    let error_code;
+    let (op_code, incomplete, data): (_, _, &[u8]) = match soe {
+        SoE::ReadReq { .. } => (SoeOpCode::ReadReq, false, &[]),
+        SoE::ReadRes {
+            incomplete, data, ..
+        } => (SoeOpCode::ReadRes, *incomplete, data),
+        SoE::WriteReq {
+            incomplete, data, ..
+        } => (SoeOpCode::WriteReq, *incomplete, data),
+        SoE::WriteRes { .. } => (SoeOpCode::WriteRes, false, &[]),
+        SoE::Error { op_code, code, .. } => {
+            error_code = (*code as u16).to_le_bytes();
+            (*op_code, false, &error_code)
+        }
+        SoE::Other(SoeOpCode::Other) => return Err(MailboxEncodeError::UnknownCode),
+        SoE::Other(op_code) => (*op_code, false, &[]),
+    };
+    let size = SoeFrame::HEADER_SIZE + data.len();
+    let buf = buf.get_mut(..size).ok_or(LengthError)?;
+    let mut frame = SoeFrame(&mut buf[..]);
+    frame.set_soe_op_code(op_code);
+    frame.set_incomplete(incomplete);
+    frame.set_error(matches!(soe, SoE::Error { .. }));
+    match soe {
+        SoE::ReadReq {
+            drive_number,
+            elements,
+            idn,
+        }
+        | SoE::ReadRes {
+            drive_number,
+            elements,
+            idn,
+            ..
+        }
+        | SoE::WriteReq {
+            drive_number,
+            elements,
+            idn,
+            ..
+        }
+        | SoE::WriteRes {
+            drive_number,
+            elements,
+            idn,
+        }
+        | SoE::Error {
+            drive_number,
+            elements,
+            idn,
+            ..
+        } => {
+            frame.set_drive_number(*drive_number);
+            frame.set_elements(*elements);
+            frame.set_idn(*idn);
+        }
+        SoE::Other(_) => {}
+    }
+    buf[SoeFrame::HEADER_SIZE..].copy_from_slice(data);
+    Ok(size)
+}
+
+/// Returns the size of the mailbox error.
+fn set_mailbox_error(buf: &mut [u8], detail: MailboxErrorDetail) -> Result<usize, LengthError> {
+    let buf = buf.get_mut(..MailboxErrorFrame::SIZE).ok_or(LengthError)?;
+    let mut frame = MailboxErrorFrame(buf);
+    // The service type of a mailbox error is always 1.
+    frame.set_service_type(1);
+    frame.set_detail(detail as u16);
+    Ok(MailboxErrorFrame::SIZE)
+}
+
+/// Returns the size of the CoE header and data.
+fn set_coe(buf: &mut [u8], coe_index: &CoeIndex, coe: &CoE) -> Result<usize, MailboxEncodeError> {
+    let header = buf.get_mut(..CoeFrame::HEADER_SIZE).ok_or(LengthError)?;
+    header.fill(0);
+    let service_type = match coe {
+        CoE::Emmergency(_) => CoeServiceType::Emmergency,
+        CoE::SdoReq(_) => CoeServiceType::SdoReq,
+        CoE::SdoRes(_) => CoeServiceType::SdoRes,
+        CoE::SdoInfo(_) => CoeServiceType::SdoInfo,
+        CoE::UnsupportedType(CoeServiceType::Other) => return Err(MailboxEncodeError::UnknownCode),
+        CoE::UnsupportedType(service_type) => *service_type,
+    };
+    let mut coe_frame = CoeFrame(header);
+    coe_frame.set_number(0);
+    coe_frame.set_coe_service_type(service_type);
+    let buf = &mut buf[CoeFrame::HEADER_SIZE..];
+    let size = match coe {
+        CoE::Emmergency(emmergency) => {
+            let data = emmergency
+                .0
+                .get(..EmmergencyFrame::SIZE)
+                .ok_or(LengthError)?;
+            buf.get_mut(..EmmergencyFrame::SIZE)
+                .ok_or(LengthError)?
+                .copy_from_slice(data);
+            EmmergencyFrame::SIZE
+        }
+        CoE::SdoReq(sdo_req) => set_sdo_req(buf, coe_index, sdo_req)?,
+        CoE::SdoRes(sdo_res) => set_sdo_res(buf, coe_index, sdo_res)?,
+        CoE::SdoInfo(sdo_info) => set_sdo_info(buf, sdo_info)?,
+        // Only the header.
+        CoE::UnsupportedType(_) => 0,
+    };
+    Ok(CoeFrame::HEADER_SIZE + size)
+}
+
+/// Returns the size of the SDO header and data.
+fn set_sdo_req(
+    buf: &mut [u8],
+    coe_index: &CoeIndex,
+    sdo_req: &SdoReq,
+) -> Result<usize, MailboxEncodeError> {
+    let size = match sdo_req {
+        SdoReq::DownLoad(data) => {
+            // Download normal request
+            set_sdo_normal(buf, coe_index, 1, data.len() as u32, data)?
+        }
+        SdoReq::SegmentedDownLoad {
+            complete_size,
+            data,
+        } => set_sdo_normal(buf, coe_index, 1, *complete_size, data)?,
+        SdoReq::Upload => {
+            // Upload request
+            sdo_frame(buf, SdoFrame::HEADER_SIZE + 4, 2, coe_index)?;
+            SdoFrame::HEADER_SIZE + 4
+        }
+        SdoReq::DownLoadSegment { toggle, last, data } => {
+            // Download segment request
+            set_segment(buf, 0, *toggle, *last, data)?
+        }
+        SdoReq::UploadSegment { toggle } => {
+            // Upload segment request. The size of the segment data is 0.
+            let size = set_segment(buf, 3, *toggle, false, &[])?;
+            SdoSegmentFrame(&mut buf[..]).set_segment_data_size(0);
+            size
+        }
+        SdoReq::Abort(AbortCode::UnknownAbortCode) => return Err(MailboxEncodeError::UnknownCode),
+        SdoReq::Abort(abort_code) => {
+            let index = CoeIndex::new(coe_index.index, coe_index.sub_index);
+            let buf = sdo_frame(buf, SdoFrame::HEADER_SIZE + 4, 4, &index)?;
+            buf[SdoFrame::HEADER_SIZE..].copy_from_slice(&(*abort_code as u32).to_le_bytes());
+            SdoFrame::HEADER_SIZE + 4
+        }
+        SdoReq::Other(CommandSpecifier(command_specifier)) => {
+            sdo_frame(
+                buf,
+                SdoFrame::HEADER_SIZE + 4,
+                *command_specifier,
+                coe_index,
+            )?;
+            SdoFrame::HEADER_SIZE + 4
+        }
+    };
+    Ok(size)
+}
+
+/// Returns the size of the SDO header and data.
+fn set_sdo_res(
+    buf: &mut [u8],
+    coe_index: &CoeIndex,
+    sdo_res: &SdoRes,
+) -> Result<usize, LengthError> {
+    match sdo_res {
+        SdoRes::DownLoad => {
+            // Download response
+            sdo_frame(buf, SdoFrame::HEADER_SIZE + 4, 3, coe_index)?;
+            Ok(SdoFrame::HEADER_SIZE + 4)
+        }
+        SdoRes::Upload(data) if !data.is_empty() && data.len() <= 4 => {
+            // Upload expedited response
+            let buf = sdo_frame(buf, SdoFrame::HEADER_SIZE + 4, 2, coe_index)?;
+            let mut sdo_frame = SdoFrame(&mut buf[..]);
+            sdo_frame.set_transfer_type(true);
+            sdo_frame.set_size_indicator(true);
+            sdo_frame.set_data_set_size((4 - data.len()) as u8);
+            buf[SdoFrame::HEADER_SIZE..SdoFrame::HEADER_SIZE + data.len()].copy_from_slice(data);
+            Ok(SdoFrame::HEADER_SIZE + 4)
+        }
+        SdoRes::Upload(data) => set_sdo_normal(buf, coe_index, 2, data.len() as u32, data),
+        SdoRes::SegmentedUpload {
+            complete_size,
+            data,
+        } => set_sdo_normal(buf, coe_index, 2, *complete_size, data),
+        SdoRes::DownLoadSegment { toggle } => {
+            // Download segment response. It has no segment data.
+            let size = set_segment(buf, 1, *toggle, false, &[])?;
+            SdoSegmentFrame(&mut buf[..]).set_segment_data_size(0);
+            Ok(size)
+        }
+        SdoRes::UploadSegment { toggle, last, data } => {
+            // Upload segment response
+            set_segment(buf, 0, *toggle, *last, data)
+        }
+        SdoRes::Other(CommandSpecifier(command_specifier)) => {
+            sdo_frame(
+                buf,
+                SdoFrame::HEADER_SIZE + 4,
+                *command_specifier,
+                coe_index,
+            )?;
+            Ok(SdoFrame::HEADER_SIZE + 4)
+        }
+    }
+}
+
+/// Clears `size` octets, and writes the SDO header with the command specifier.
+/// Returns the cleared octets.
+fn sdo_frame<'a>(
+    buf: &'a mut [u8],
+    size: usize,
+    command_specifier: u8,
+    coe_index: &CoeIndex,
+) -> Result<&'a mut [u8], LengthError> {
+    let buf = buf.get_mut(..size).ok_or(LengthError)?;
+    buf.fill(0);
+    let mut sdo_frame = SdoFrame(&mut buf[..]);
+    sdo_frame.set_command_specifier(command_specifier);
+    sdo_frame.set_complete_access(coe_index.complete_access);
+    sdo_frame.set_index(coe_index.index);
+    sdo_frame.set_sub_index(coe_index.sub_index);
+    Ok(buf)
+}
+
+/// Writes a normal transfer, whose data follow the complete size.
+fn set_sdo_normal(
+    buf: &mut [u8],
+    coe_index: &CoeIndex,
+    command_specifier: u8,
+    complete_size: u32,
+    data: &[u8],
+) -> Result<usize, LengthError> {
+    let size = SdoFrame::HEADER_SIZE + SdoDownloadNormalRequestFrame::HEADER_SIZE + data.len();
+    let buf = sdo_frame(buf, size, command_specifier, coe_index)?;
+    SdoFrame(&mut buf[..]).set_size_indicator(true);
+    let buf = &mut buf[SdoFrame::HEADER_SIZE..];
+    SdoDownloadNormalRequestFrame(&mut buf[..]).set_complete_size(complete_size);
+    buf[SdoDownloadNormalRequestFrame::HEADER_SIZE..].copy_from_slice(data);
+    Ok(size)
+}
+
+/// Writes a segment. Data shorter than `SdoSegmentFrame::MIN_DATA_SIZE` are padded.
+fn set_segment(
+    buf: &mut [u8],
+    command_specifier: u8,
+    toggle: bool,
+    last: bool,
+    data: &[u8],
+) -> Result<usize, LengthError> {
+    let size = SdoSegmentFrame::HEADER_SIZE + data.len().max(SdoSegmentFrame::MIN_DATA_SIZE);
+    let buf = buf.get_mut(..size).ok_or(LengthError)?;
+    buf.fill(0);
+    let mut segment = SdoSegmentFrame(&mut buf[..]);
+    segment.set_command_specifier(command_specifier);
+    segment.set_toggle(toggle);
+    segment.set_last_segment(last);
+    segment.set_segment_data_size(SdoSegmentFrame::MIN_DATA_SIZE.saturating_sub(data.len()) as u8);
+    buf[SdoSegmentFrame::HEADER_SIZE..SdoSegmentFrame::HEADER_SIZE + data.len()]
+        .copy_from_slice(data);
+    Ok(size)
+}
+
+/// Data of a segment. Data shorter than `SdoSegmentFrame::MIN_DATA_SIZE` are padded.
+fn segment_data<'a>(segment: &SdoSegmentFrame<&'a [u8]>) -> Result<&'a [u8], LengthError> {
+    let data = segment.without_header();
+    let size = if data.len() <= SdoSegmentFrame::MIN_DATA_SIZE {
+        SdoSegmentFrame::MIN_DATA_SIZE.saturating_sub(segment.segment_data_size() as usize)
+    } else {
+        data.len()
+    };
+    data.get(..size).ok_or(LengthError)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Mailbox, MailboxFrame};
+    use super::{
+        AbortCode, CoE, CoeIndex, EmmergencyFrame, Mailbox, MailboxEncodeError, MailboxErrorDetail,
+        MailboxFrame, MailboxType, SdoReq, SdoRes,
+    };
     #[test]
     fn upload_req_test() {
         let mut buf = [0; 256];
@@ -596,4 +1791,170 @@ mod tests {
             assert_eq!(a, b);
         }
     }
+
+    #[test]
+    fn download_segment_req_test() {
+        let mut buf = [0; 256];
+        let mut mb_frame = MailboxFrame(buf.as_mut());
+        mb_frame.set_count(1);
+        mb_frame
+            .set_mailbox(&Mailbox::new_sdo_download_segment_request(
+                true,
+                true,
+                &[0x78, 0x9A, 0xBC],
+            ))
+            .unwrap();
+
+        let mb_head = [0x0A, 0x00, 0x00, 0x00, 0x00, 0x13];
+        let coe_data = [0x00, 0x20, 0x19, 0x78, 0x9A, 0xBC, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(buf[..MailboxFrame::HEADER_SIZE], mb_head);
+        assert_eq!(
+            buf[MailboxFrame::HEADER_SIZE..MailboxFrame::HEADER_SIZE + coe_data.len()],
+            coe_data
+        );
+
+        match MailboxFrame(&buf[..]).mailbox().unwrap() {
+            Mailbox::CoE((_, CoE::SdoReq(SdoReq::DownLoadSegment { toggle, last, data }))) => {
+                assert!(toggle);
+                assert!(last);
+                assert_eq!(data, [0x78, 0x9A, 0xBC]);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn abort_req_test() {
+        let mut buf = [0; 256];
+        let mut mb_frame = MailboxFrame(buf.as_mut());
+        mb_frame.set_count(2);
+        mb_frame
+            .set_mailbox(&Mailbox::new_sdo_abort_request(
+                0x1234,
+                0x56,
+                AbortCode::Timeout,
+            ))
+            .unwrap();
+
+        let mb_head = [0x0A, 0x00, 0x00, 0x00, 0x00, 0x23];
+        let coe_data = [0x00, 0x20, 0x80, 0x34, 0x12, 0x56, 0x00, 0x00, 0x04, 0x05];
+        assert_eq!(buf[..MailboxFrame::HEADER_SIZE], mb_head);
+        assert_eq!(
+            buf[MailboxFrame::HEADER_SIZE..MailboxFrame::HEADER_SIZE + coe_data.len()],
+            coe_data
+        );
+
+        match MailboxFrame(&buf[..]).mailbox().unwrap() {
+            Mailbox::CoE((coe_index, CoE::SdoReq(SdoReq::Abort(code)))) => {
+                assert_eq!((coe_index.index, coe_index.sub_index), (0x1234, 0x56));
+                assert_eq!(code, AbortCode::Timeout);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn upload_res_test() {
+        let mut buf = [0; 256];
+        let message = Mailbox::CoE((
+            CoeIndex::new(0x1234, 0x56),
+            CoE::SdoRes(SdoRes::Upload(&[0x78, 0x9A])),
+        ));
+        MailboxFrame(buf.as_mut()).set_mailbox(&message).unwrap();
+
+        let mb_head = [0x0A, 0x00, 0x00, 0x00, 0x00, 0x03];
+        let coe_data = [0x00, 0x30, 0x4B, 0x34, 0x12, 0x56, 0x78, 0x9A, 0x00, 0x00];
+        assert_eq!(buf[..MailboxFrame::HEADER_SIZE], mb_head);
+        assert_eq!(
+            buf[MailboxFrame::HEADER_SIZE..MailboxFrame::HEADER_SIZE + coe_data.len()],
+            coe_data
+        );
+
+        // A normal transfer for data which do not fit in the expedited one.
+        let data = [0x11, 0x22, 0x33, 0x44, 0x55];
+        let message = Mailbox::CoE((
+            CoeIndex::new(0x1234, 0x56),
+            CoE::SdoRes(SdoRes::Upload(&data)),
+        ));
+        MailboxFrame(buf.as_mut()).set_mailbox(&message).unwrap();
+        match MailboxFrame(&buf[..]).mailbox().unwrap() {
+            Mailbox::CoE((_, CoE::SdoRes(SdoRes::Upload(uploaded)))) => {
+                assert_eq!(uploaded, data)
+            }
+            other => panic!("{:?}", other),
+        }
+
+        let message = Mailbox::CoE((
+            CoeIndex::default(),
+            CoE::SdoRes(SdoRes::UploadSegment {
+                toggle: true,
+                last: true,
+                data: &data,
+            }),
+        ));
+        MailboxFrame(buf.as_mut()).set_mailbox(&message).unwrap();
+        match MailboxFrame(&buf[..]).mailbox().unwrap() {
+            Mailbox::CoE((_, CoE::SdoRes(SdoRes::UploadSegment { toggle, last, data }))) => {
+                assert!(toggle);
+                assert!(last);
+                assert_eq!(data, [0x11, 0x22, 0x33, 0x44, 0x55]);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn error_and_emergency_test() {
+        let mut buf = [0; 256];
+        MailboxFrame(buf.as_mut())
+            .set_mailbox(&Mailbox::Error(MailboxErrorDetail::InvalidHeader))
+            .unwrap();
+        assert_eq!(
+            buf[..10],
+            [0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x05, 0x00]
+        );
+        match MailboxFrame(&buf[..]).mailbox().unwrap() {
+            Mailbox::Error(detail) => assert_eq!(detail, MailboxErrorDetail::InvalidHeader),
+            other => panic!("{:?}", other),
+        }
+
+        let mut emergency = EmmergencyFrame::new();
+        emergency.set_error_code(0x8130);
+        emergency.set_error_register(0x11);
+        let message = Mailbox::CoE((
+            CoeIndex::default(),
+            CoE::Emmergency(EmmergencyFrame(&emergency.0)),
+        ));
+        MailboxFrame(buf.as_mut()).set_mailbox(&message).unwrap();
+        match MailboxFrame(&buf[..]).mailbox().unwrap() {
+            Mailbox::CoE((_, CoE::Emmergency(frame))) => {
+                assert_eq!(frame.error_code(), 0x8130);
+                assert_eq!(frame.error_register(), 0x11);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn encode_error_test() {
+        let mut buf = [0; 256];
+        let mut mb_frame = MailboxFrame(buf.as_mut());
+        let unknown = [
+            Mailbox::UnsupportedProtocol(MailboxType::Other),
+            Mailbox::new_sdo_abort_request(0x1234, 0x56, AbortCode::UnknownAbortCode),
+        ];
+        for message in unknown.iter() {
+            assert_eq!(
+                mb_frame.set_mailbox(message),
+                Err(MailboxEncodeError::UnknownCode)
+            );
+        }
+
+        let mut buf = [0; 12];
+        let message = Mailbox::new_sdo_download_request(0x1234, 0x56, &[0; 8]);
+        assert_eq!(
+            MailboxFrame(buf.as_mut()).set_mailbox(&message),
+            Err(MailboxEncodeError::Length)
+        );
+    }
 }
@@ -0,0 +1,73 @@
+use bitfield::*;
+use num_enum::FromPrimitive;
+
+bitfield! {
+    #[derive(Debug, Clone)]
+    pub struct FoeFrame([u8]);
+    pub u8, op_code, set_op_code: 7, 0;
+    /// Password of a read or write request, packet number of data or an acknowledge,
+    /// or error code.
+    pub u32, value, set_value: 47, 16;
+    pub u16, busy_done, set_busy_done: 31, 16;
+    pub u16, busy_entire, set_busy_entire: 47, 32;
+}
+
+impl FoeFrame<[u8; 6]> {
+    pub const HEADER_SIZE: usize = 6;
+    pub fn new() -> Self {
+        Self([0; Self::HEADER_SIZE])
+    }
+}
+
+impl<B: AsRef<[u8]>> FoeFrame<B> {
+    pub fn foe_op_code(&self) -> FoeOpCode {
+        self.op_code().into()
+    }
+}
+
+impl<B: AsMut<[u8]>> FoeFrame<B> {
+    pub fn set_foe_op_code(&mut self, op_code: FoeOpCode) {
+        self.set_op_code(op_code as u8)
+    }
+}
+
+impl<'a> FoeFrame<&'a [u8]> {
+    /// File name of a request, file data or error text.
+    pub fn without_header(&self) -> &'a [u8] {
+        &self.0[FoeFrame::HEADER_SIZE..]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum FoeOpCode {
+    Read = 1,
+    Write,
+    Data,
+    Ack,
+    Error,
+    Busy,
+    #[num_enum(default)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u32)]
+pub enum FoeErrorCode {
+    #[num_enum(default)]
+    NotDefined = 0x8000,
+    NotFound = 0x8001,
+    AccessDenied = 0x8002,
+    DiskFull = 0x8003,
+    Illegal = 0x8004,
+    PacketNumberWrong = 0x8005,
+    AlreadyExists = 0x8006,
+    NoUser = 0x8007,
+    BootstrapOnly = 0x8008,
+    NotBootstrap = 0x8009,
+    NoRights = 0x800A,
+    ProgramError = 0x800B,
+    InvalidChecksum = 0x800C,
+    InvalidFirmware = 0x800D,
+    NoFile = 0x800F,
+}
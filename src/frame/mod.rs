@@ -1,6 +1,14 @@
+mod aoe;
 mod coe;
+mod eoe;
 mod ethercat;
+mod foe;
 mod frame_util;
+mod soe;
+pub use aoe::*;
 pub use coe::*;
+pub use eoe::*;
 pub use ethercat::*;
+pub use foe::*;
 pub use frame_util::*;
+pub use soe::*;
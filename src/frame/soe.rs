@@ -0,0 +1,124 @@
+use bitfield::*;
+use num_enum::FromPrimitive;
+
+bitfield! {
+    #[derive(Debug, Clone)]
+    pub struct SoeFrame([u8]);
+    pub u8, op_code, set_op_code: 2, 0;
+    /// More fragments follow.
+    pub incomplete, set_incomplete: 3;
+    pub error, set_error: 4;
+    pub u8, drive_number, set_drive_number: 7, 5;
+    /// A combination of `SoeElement`.
+    pub u8, elements, set_elements: 15, 8;
+    /// IDN, or the number of fragments left while `incomplete` is set.
+    pub u16, idn, set_idn: 31, 16;
+}
+
+impl SoeFrame<[u8; 4]> {
+    pub const HEADER_SIZE: usize = 4;
+    pub fn new() -> Self {
+        Self([0; Self::HEADER_SIZE])
+    }
+}
+
+impl<B: AsRef<[u8]>> SoeFrame<B> {
+    pub fn soe_op_code(&self) -> SoeOpCode {
+        self.op_code().into()
+    }
+}
+
+impl<B: AsMut<[u8]>> SoeFrame<B> {
+    pub fn set_soe_op_code(&mut self, op_code: SoeOpCode) {
+        self.set_op_code(op_code as u8)
+    }
+}
+
+impl<'a> SoeFrame<&'a [u8]> {
+    pub fn without_header(&self) -> &'a [u8] {
+        &self.0[SoeFrame::HEADER_SIZE..]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum SoeOpCode {
+    ReadReq = 1,
+    ReadRes,
+    WriteReq,
+    WriteRes,
+    Notification,
+    Emergency,
+    #[num_enum(default)]
+    Other,
+}
+
+/// Bits of the elements of an IDN.
+/// The data of several elements follow in the order of the bits.
+pub struct SoeElement;
+impl SoeElement {
+    pub const DATA_STATE: u8 = 0x01;
+    pub const NAME: u8 = 0x02;
+    pub const ATTRIBUTE: u8 = 0x04;
+    pub const UNIT: u8 = 0x08;
+    pub const MINIMUM: u8 = 0x10;
+    pub const MAXIMUM: u8 = 0x20;
+    pub const VALUE: u8 = 0x40;
+    pub const DEFAULT: u8 = 0x80;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u16)]
+pub enum SoeErrorCode {
+    NoError = 0x0000,
+    NoIdn = 0x1001,
+    InvalidAccessToElement1 = 0x1009,
+    NoName = 0x2001,
+    NameTransmissionTooShort = 0x2002,
+    NameTransmissionTooLong = 0x2003,
+    NameCannotBeChanged = 0x2004,
+    NameWriteProtected = 0x2005,
+    AttributeTransmissionTooShort = 0x3002,
+    AttributeTransmissionTooLong = 0x3003,
+    AttributeCannotBeChanged = 0x3004,
+    AttributeWriteProtected = 0x3005,
+    NoUnit = 0x4001,
+    UnitTransmissionTooShort = 0x4002,
+    UnitTransmissionTooLong = 0x4003,
+    UnitCannotBeChanged = 0x4004,
+    UnitWriteProtected = 0x4005,
+    NoMinimum = 0x5001,
+    MinimumTransmissionTooShort = 0x5002,
+    MinimumTransmissionTooLong = 0x5003,
+    MinimumCannotBeChanged = 0x5004,
+    MinimumWriteProtected = 0x5005,
+    NoMaximum = 0x6001,
+    MaximumTransmissionTooShort = 0x6002,
+    MaximumTransmissionTooLong = 0x6003,
+    MaximumCannotBeChanged = 0x6004,
+    MaximumWriteProtected = 0x6005,
+    DataTransmissionTooShort = 0x7002,
+    DataTransmissionTooLong = 0x7003,
+    DataCannotBeChanged = 0x7004,
+    DataWriteProtected = 0x7005,
+    DataSmallerThanMinimum = 0x7006,
+    DataLargerThanMaximum = 0x7007,
+    InvalidData = 0x7008,
+    DataWriteProtectedByPassword = 0x7009,
+    DataWriteProtectedByConfiguration = 0x700A,
+    InvalidIndirectAddressing = 0x700B,
+    DataWriteProtectedByOtherSetting = 0x700C,
+    ProcedureCommandAlreadyActive = 0x7010,
+    ProcedureCommandNotInterruptible = 0x7011,
+    ProcedureCommandNotExecutableAtThisTime = 0x7012,
+    ProcedureCommandNotExecutable = 0x7013,
+    NoDataState = 0x7014,
+    NoDefaultValue = 0x8001,
+    DefaultValueTransmissionTooLong = 0x8002,
+    DefaultValueCannotBeChanged = 0x8004,
+    InvalidDriveNumber = 0x800A,
+    GeneralError = 0x800B,
+    NoElementAddressed = 0x800C,
+    #[num_enum(default)]
+    UnknownErrorCode,
+}
@@ -0,0 +1,236 @@
+use crate::frame::{EoeFrame, ETHERNET_FRAME_SIZE_WITHOUT_FCS};
+
+use super::SlaveAddress;
+
+/// Ethernet frames tunneled to a slave with EoE.
+/// `EtherCatMaster::poll_eoe` exchanges them with the slave through the mailbox.
+/// Fragments which other mailbox requests read from the slave are kept until the next poll.
+/// With the smoltcp feature, it is a `smoltcp::phy::Device`.
+#[derive(Debug)]
+pub struct EoeDevice<'a> {
+    slave_address: SlaveAddress,
+    /// Frame to be sent. It is empty if `tx_length` is 0.
+    tx_buf: &'a mut [u8],
+    tx_length: usize,
+    frame_number: u8,
+    /// Frame being received.
+    rx_buf: &'a mut [u8],
+    rx_length: usize,
+    rx_state: RxState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RxState {
+    Idle,
+    Receiving { frame_number: u8, next_fragment: u8 },
+    Complete,
+}
+
+/// The fragment does not continue the frame being received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FragmentError {
+    Unmatch,
+    BufferSmall,
+}
+
+impl<'a> EoeDevice<'a> {
+    /// The buffers limit the size of frames, which are up to 1514 octets without FCS.
+    pub fn new(slave_address: SlaveAddress, tx_buf: &'a mut [u8], rx_buf: &'a mut [u8]) -> Self {
+        Self {
+            slave_address,
+            tx_buf,
+            tx_length: 0,
+            frame_number: 0,
+            rx_buf,
+            rx_length: 0,
+            rx_state: RxState::Idle,
+        }
+    }
+
+    pub fn slave_address(&self) -> SlaveAddress {
+        self.slave_address
+    }
+
+    /// Returns false if the last frame has not been sent yet or the frame is too large.
+    pub fn send_frame(&mut self, frame: &[u8]) -> bool {
+        if self.tx_length != 0 || frame.is_empty() || self.max_frame_size() < frame.len() {
+            return false;
+        }
+        self.tx_buf[..frame.len()].copy_from_slice(frame);
+        self.tx_length = frame.len();
+        true
+    }
+
+    /// Passes a received frame to `f`, and then frees the buffer for the next one.
+    pub fn receive_frame<R, F: FnOnce(&[u8]) -> R>(&mut self, f: F) -> Option<R> {
+        if self.rx_state != RxState::Complete {
+            return None;
+        }
+        let output = f(&self.rx_buf[..self.rx_length]);
+        self.rx_state = RxState::Idle;
+        Some(output)
+    }
+
+    pub fn max_frame_size(&self) -> usize {
+        self.tx_buf
+            .len()
+            .min(self.rx_buf.len())
+            .min(ETHERNET_FRAME_SIZE_WITHOUT_FCS)
+    }
+
+    pub(crate) fn pending_frame(&self) -> Option<(u8, &[u8])> {
+        if self.tx_length == 0 {
+            None
+        } else {
+            Some((self.frame_number, &self.tx_buf[..self.tx_length]))
+        }
+    }
+
+    pub(crate) fn frame_sent(&mut self) {
+        self.tx_length = 0;
+        self.frame_number = (self.frame_number + 1) & 0x0F;
+    }
+
+    /// A received frame has to be taken before the next one.
+    pub(crate) fn can_receive(&self) -> bool {
+        self.rx_state != RxState::Complete
+    }
+
+    /// On errors, the frame is dropped.
+    pub(crate) fn receive_fragment(
+        &mut self,
+        frame_number: u8,
+        fragment_number: u8,
+        offset: u8,
+        last: bool,
+        data: &[u8],
+    ) -> Result<(), FragmentError> {
+        let offset = offset as usize * EoeFrame::FRAGMENT_UNIT;
+        let next_fragment = match self.rx_state {
+            _ if fragment_number == 0 => {
+                // `offset` is the complete size rounded up.
+                if self.rx_buf.len() + EoeFrame::FRAGMENT_UNIT <= offset {
+                    self.rx_state = RxState::Idle;
+                    return Err(FragmentError::BufferSmall);
+                }
+                self.rx_length = 0;
+                0
+            }
+            RxState::Receiving {
+                frame_number: number,
+                next_fragment,
+            } if number == frame_number
+                && next_fragment == fragment_number
+                && self.rx_length == offset =>
+            {
+                next_fragment
+            }
+            _ => {
+                self.rx_state = RxState::Idle;
+                return Err(FragmentError::Unmatch);
+            }
+        };
+        let Some(buf) = self
+            .rx_buf
+            .get_mut(self.rx_length..self.rx_length + data.len())
+        else {
+            self.rx_state = RxState::Idle;
+            return Err(FragmentError::BufferSmall);
+        };
+        buf.copy_from_slice(data);
+        self.rx_length += data.len();
+        self.rx_state = if last {
+            RxState::Complete
+        } else {
+            RxState::Receiving {
+                frame_number,
+                next_fragment: next_fragment + 1,
+            }
+        };
+        Ok(())
+    }
+}
+
+#[cfg(feature = "smoltcp")]
+mod smoltcp_device {
+    use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
+    use smoltcp::time::Instant;
+
+    use super::{EoeDevice, RxState};
+
+    pub struct EoeRxToken<'a> {
+        frame: &'a mut [u8],
+        state: &'a mut RxState,
+    }
+
+    impl<'a> RxToken for EoeRxToken<'a> {
+        fn consume<R, F>(self, _timestamp: Instant, f: F) -> smoltcp::Result<R>
+        where
+            F: FnOnce(&mut [u8]) -> smoltcp::Result<R>,
+        {
+            *self.state = RxState::Idle;
+            f(self.frame)
+        }
+    }
+
+    pub struct EoeTxToken<'a> {
+        buf: &'a mut [u8],
+        length: &'a mut usize,
+    }
+
+    impl<'a> TxToken for EoeTxToken<'a> {
+        fn consume<R, F>(self, _timestamp: Instant, len: usize, f: F) -> smoltcp::Result<R>
+        where
+            F: FnOnce(&mut [u8]) -> smoltcp::Result<R>,
+        {
+            let buf = self.buf.get_mut(..len).ok_or(smoltcp::Error::Exhausted)?;
+            let output = f(buf)?;
+            *self.length = len;
+            Ok(output)
+        }
+    }
+
+    /// A frame is sent and received at a time.
+    impl<'a, 'b> Device<'a> for EoeDevice<'b> {
+        type RxToken = EoeRxToken<'a>;
+        type TxToken = EoeTxToken<'a>;
+
+        fn receive(&'a mut self) -> Option<(Self::RxToken, Self::TxToken)> {
+            if self.rx_state != RxState::Complete || self.tx_length != 0 {
+                return None;
+            }
+            let max_frame_size = self.max_frame_size();
+            let rx = EoeRxToken {
+                frame: &mut self.rx_buf[..self.rx_length],
+                state: &mut self.rx_state,
+            };
+            let tx = EoeTxToken {
+                buf: &mut self.tx_buf[..max_frame_size],
+                length: &mut self.tx_length,
+            };
+            Some((rx, tx))
+        }
+
+        fn transmit(&'a mut self) -> Option<Self::TxToken> {
+            if self.tx_length != 0 {
+                return None;
+            }
+            let max_frame_size = self.max_frame_size();
+            Some(EoeTxToken {
+                buf: &mut self.tx_buf[..max_frame_size],
+                length: &mut self.tx_length,
+            })
+        }
+
+        fn capabilities(&self) -> DeviceCapabilities {
+            let mut capabilities = DeviceCapabilities::default();
+            capabilities.medium = Medium::Ethernet;
+            capabilities.max_transmission_unit = self.max_frame_size();
+            capabilities.max_burst_size = Some(1);
+            capabilities
+        }
+    }
+}
+
+#[cfg(feature = "smoltcp")]
+pub use smoltcp_device::{EoeRxToken, EoeTxToken};
@@ -1,3 +1,5 @@
+use core::task::Waker;
+
 /// A smoltcp-like raw network interface.
 pub trait RawEthernetDevice {
     type TxToken<'a>: TxToken
@@ -10,6 +12,12 @@ pub trait RawEthernetDevice {
     fn transmit<'a>(&'a mut self) -> Option<Self::TxToken<'a>>;
 
     fn receive<'a>(&'a mut self) -> Option<Self::RxToken<'a>>;
+
+    /// Registers the waker of an async task, which is woken when a token may be available again.
+    /// Returns false if the device can not wake it. Then the task wakes itself and is polled again.
+    fn register_waker(&mut self, _waker: &Waker) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -30,6 +38,9 @@ pub trait RxToken {
         F: FnOnce(&[u8]) -> Result<(), ()>;
 }
 
+#[cfg(all(feature = "raw-socket", target_os = "linux"))]
+pub mod raw_socket_device;
+
 #[cfg(feature = "smoltcp")]
 pub mod smoltcp_device {
     use super::{DeviceError, RawEthernetDevice, RxToken, TxToken};
@@ -104,8 +115,16 @@ pub mod smoltcp_device {
     }
 }
 
+#[cfg(feature = "std")]
+pub mod pcap_replay_device;
+
+#[cfg(feature = "std")]
+pub mod simulator;
+
+#[cfg(feature = "std")]
+pub mod pcapng_tap_device;
+
 #[cfg(feature = "pcap")]
-#[cfg(windows)]
 pub mod pcap_device {
     use pcap::{Active, Capture, Device, Packet};
 
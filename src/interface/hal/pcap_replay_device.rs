@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use crate::frame::{EthernetFrame, ETHERCAT_TYPE, ETHERNET_FRAME_SIZE_WITHOUT_FCS};
+
+use super::{DeviceError, RawEthernetDevice, RxToken, TxToken};
+
+const MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+const MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+const LINKTYPE_ETHERNET: u32 = 1;
+const GLOBAL_HEADER_SIZE: usize = 24;
+const RECORD_HEADER_SIZE: usize = 16;
+
+/// Offline device which serves the EtherCAT frames of a recorded .pcap file.
+///
+/// Received frames are returned in the recorded order, one per `receive()`.
+/// Transmitted frames are counted and discarded.
+/// This needs no libpcap, so it is available with the std feature only.
+pub struct PcapReplayDevice {
+    frames: VecDeque<Vec<u8>>,
+    tx_buf: Vec<u8>,
+    tx_count: usize,
+}
+
+impl PcapReplayDevice {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a classic pcap stream (microsecond or nanosecond resolution, either byte order).
+    /// Frames whose ether type is not ETHERCAT_TYPE are skipped.
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; GLOBAL_HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let big_endian = if magic == MAGIC_MICROS || magic == MAGIC_NANOS {
+            false
+        } else if magic.swap_bytes() == MAGIC_MICROS || magic.swap_bytes() == MAGIC_NANOS {
+            true
+        } else {
+            return Err(invalid_data("not a pcap file"));
+        };
+        let read_u32 = |bytes: &[u8]| {
+            let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            }
+        };
+        if read_u32(&header[20..24]) != LINKTYPE_ETHERNET {
+            return Err(invalid_data("link type is not ethernet"));
+        }
+
+        let mut frames = VecDeque::new();
+        let mut record = [0; RECORD_HEADER_SIZE];
+        loop {
+            match reader.read_exact(&mut record) {
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+            let captured_len = read_u32(&record[8..12]) as usize;
+            if ETHERNET_FRAME_SIZE_WITHOUT_FCS + 4 < captured_len {
+                return Err(invalid_data("captured frame is too long"));
+            }
+            let mut frame = vec![0; captured_len];
+            reader.read_exact(&mut frame)?;
+            if captured_len < EthernetFrame::HEADER_SIZE
+                || EthernetFrame(&frame).ether_type() != ETHERCAT_TYPE
+            {
+                continue;
+            }
+            frames.push_back(frame);
+        }
+
+        Ok(Self {
+            frames,
+            tx_buf: vec![0; ETHERNET_FRAME_SIZE_WITHOUT_FCS],
+            tx_count: 0,
+        })
+    }
+
+    /// The number of frames which have not been received yet.
+    pub fn remaining(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The number of frames which have been transmitted to this device.
+    pub fn tx_count(&self) -> usize {
+        self.tx_count
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl RawEthernetDevice for PcapReplayDevice {
+    type TxToken<'a> = PcapReplayTxToken<'a>
+    where
+        Self: 'a;
+
+    type RxToken<'a> = PcapReplayRxToken
+    where
+        Self: 'a;
+
+    fn transmit<'a>(&'a mut self) -> Option<Self::TxToken<'a>> {
+        let Self {
+            tx_buf, tx_count, ..
+        } = self;
+        Some(PcapReplayTxToken { buf: tx_buf, tx_count })
+    }
+
+    fn receive<'a>(&'a mut self) -> Option<Self::RxToken<'a>> {
+        self.frames.pop_front().map(PcapReplayRxToken)
+    }
+}
+
+pub struct PcapReplayTxToken<'a> {
+    buf: &'a mut [u8],
+    tx_count: &'a mut usize,
+}
+
+impl<'a> TxToken for PcapReplayTxToken<'a> {
+    fn consume<F>(self, len: usize, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), ()>,
+    {
+        let buf = self.buf.get_mut(..len).ok_or(DeviceError::Device)?;
+        f(buf).map_err(|_| DeviceError::Function)?;
+        *self.tx_count += 1;
+        Ok(())
+    }
+}
+
+pub struct PcapReplayRxToken(Vec<u8>);
+
+impl RxToken for PcapReplayRxToken {
+    fn consume<F>(self, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&[u8]) -> Result<(), ()>,
+    {
+        f(&self.0).map_err(|_| DeviceError::Function)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{CommandType, EtherCatFrameUtil};
+    use crate::interface::{Command, PduInterface};
+    use crate::task::ManualClock;
+
+    fn push_record(file: &mut Vec<u8>, frame: &[u8]) {
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(frame);
+    }
+
+    #[test]
+    fn replay_test() {
+        let mut file = Vec::new();
+        file.extend_from_slice(&MAGIC_MICROS.to_le_bytes());
+        file.extend_from_slice(&[2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0]);
+        file.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+
+        // A frame which is not EtherCAT
+        let mut other = [0; 60];
+        other[12] = 0x08;
+        push_record(&mut file, &other);
+
+        // A returned BRD with WKC = 3
+        let mut frame = [0; 60];
+        let mut ec_frame = EtherCatFrameUtil::new_unchecked(&mut frame[..]);
+        ec_frame.init();
+        assert!(ec_frame.add_command(CommandType::BRD, 3, 0x0130, &[0x08, 0x00], Some(0)));
+        let len = ec_frame.dlpdus().next().unwrap().0.len();
+        let wkc_offset = EthernetFrame::HEADER_SIZE + 2 + len - 2;
+        frame[wkc_offset] = 3;
+        frame[6] = 0x07;
+        push_record(&mut file, &frame);
+
+        let device = PcapReplayDevice::from_reader(&file[..]).unwrap();
+        assert_eq!(device.remaining(), 1);
+
+        let mut buf = [0; 64];
+        let clock = ManualClock::new();
+        let mut pdu_if = PduInterface::new(device, &mut buf, &clock);
+        pdu_if
+            .add_pdu(0, Command::new(CommandType::BRD, 0, 0x0130), 2, |_| {})
+            .unwrap();
+        assert_eq!(pdu_if.transmit_one_frame(), Ok(true));
+        assert_eq!(pdu_if.receive_one_frame(), Ok(true));
+        let pdu = pdu_if.consume_pdus().next().unwrap();
+        assert_eq!(pdu.wkc(), Some(3));
+        assert_eq!(pdu.adp(), 3);
+    }
+}
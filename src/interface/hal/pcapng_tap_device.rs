@@ -0,0 +1,355 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::task::Waker;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{DeviceError, RawEthernetDevice, RxToken, TxToken};
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const LINKTYPE_ETHERNET: u16 = 1;
+const OPTION_END: u16 = 0;
+const OPTION_EPB_FLAGS: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl Direction {
+    /// Direction bits of epb_flags.
+    fn flags(&self) -> u32 {
+        match self {
+            Direction::Inbound => 0b01,
+            Direction::Outbound => 0b10,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    /// Microseconds since the UNIX epoch.
+    pub timestamp_us: u64,
+    pub direction: Direction,
+    pub data: Vec<u8>,
+}
+
+enum Sink {
+    Writer(Box<dyn Write>),
+    Ring {
+        frames: VecDeque<CapturedFrame>,
+        capacity: usize,
+    },
+}
+
+struct Recorder {
+    sink: Sink,
+    error: Option<io::Error>,
+}
+
+impl Recorder {
+    fn record(&mut self, direction: Direction, data: &[u8]) {
+        let frame = CapturedFrame {
+            timestamp_us: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_micros() as u64)
+                .unwrap_or_default(),
+            direction,
+            data: data.to_vec(),
+        };
+        match &mut self.sink {
+            Sink::Writer(writer) => {
+                if let Err(err) = write_enhanced_packet_block(writer, &frame) {
+                    self.error.get_or_insert(err);
+                }
+            }
+            Sink::Ring { frames, capacity } => {
+                if *capacity <= frames.len() {
+                    frames.pop_front();
+                }
+                frames.push_back(frame);
+            }
+        }
+    }
+}
+
+/// Wrapper device which records all transmitted and received frames of the inner device.
+///
+/// In the writer mode, every frame is written to a pcapng stream immediately.
+/// In the ring mode, the last frames are kept in memory and written by `dump`, e.g. after an unexpected WKC.
+/// The inner device can be reached through `EtherCatMaster::device_mut`.
+pub struct PcapngTapDevice<D> {
+    inner: D,
+    recorder: Recorder,
+}
+
+impl<D> PcapngTapDevice<D>
+where
+    D: RawEthernetDevice,
+{
+    /// Records to a new pcapng file.
+    pub fn create<P: AsRef<Path>>(inner: D, path: P) -> io::Result<Self> {
+        Self::with_writer(inner, BufWriter::new(File::create(path)?))
+    }
+
+    /// Records to a pcapng stream. The section header and the interface description are written first.
+    pub fn with_writer<W: Write + 'static>(inner: D, mut writer: W) -> io::Result<Self> {
+        write_header(&mut writer)?;
+        Ok(Self {
+            inner,
+            recorder: Recorder {
+                sink: Sink::Writer(Box::new(writer)),
+                error: None,
+            },
+        })
+    }
+
+    /// Keeps the last `capacity` frames in memory.
+    pub fn with_ring(inner: D, capacity: usize) -> Self {
+        Self {
+            inner,
+            recorder: Recorder {
+                sink: Sink::Ring {
+                    frames: VecDeque::with_capacity(capacity),
+                    capacity,
+                },
+                error: None,
+            },
+        }
+    }
+
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut D {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// Frames in the ring. Empty in the writer mode.
+    pub fn frames(&self) -> impl Iterator<Item = &CapturedFrame> {
+        let frames = match &self.recorder.sink {
+            Sink::Ring { frames, .. } => Some(frames.iter()),
+            Sink::Writer(_) => None,
+        };
+        frames.into_iter().flatten()
+    }
+
+    /// Writes the frames in the ring as a pcapng stream.
+    pub fn dump<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_header(&mut writer)?;
+        for frame in self.frames() {
+            write_enhanced_packet_block(&mut writer, frame)?;
+        }
+        writer.flush()
+    }
+
+    pub fn dump_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.dump(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn clear(&mut self) {
+        if let Sink::Ring { frames, .. } = &mut self.recorder.sink {
+            frames.clear();
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.recorder.sink {
+            Sink::Writer(writer) => writer.flush(),
+            Sink::Ring { .. } => Ok(()),
+        }
+    }
+
+    /// The first error while writing frames.
+    /// Recording does not stop the inner device, so errors are kept here.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.recorder.error.take()
+    }
+}
+
+impl<D> RawEthernetDevice for PcapngTapDevice<D>
+where
+    D: RawEthernetDevice,
+{
+    type TxToken<'a> = PcapngTapTxToken<'a, D::TxToken<'a>>
+    where
+        Self: 'a;
+
+    type RxToken<'a> = PcapngTapRxToken<'a, D::RxToken<'a>>
+    where
+        Self: 'a;
+
+    fn transmit<'a>(&'a mut self) -> Option<Self::TxToken<'a>> {
+        let Self { inner, recorder } = self;
+        inner
+            .transmit()
+            .map(|token| PcapngTapTxToken { token, recorder })
+    }
+
+    fn receive<'a>(&'a mut self) -> Option<Self::RxToken<'a>> {
+        let Self { inner, recorder } = self;
+        inner
+            .receive()
+            .map(|token| PcapngTapRxToken { token, recorder })
+    }
+
+    fn register_waker(&mut self, waker: &Waker) -> bool {
+        self.inner.register_waker(waker)
+    }
+}
+
+pub struct PcapngTapTxToken<'a, T> {
+    token: T,
+    recorder: &'a mut Recorder,
+}
+
+impl<'a, T: TxToken> TxToken for PcapngTapTxToken<'a, T> {
+    fn consume<F>(self, len: usize, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), ()>,
+    {
+        let Self { token, recorder } = self;
+        token.consume(len, |buf| {
+            f(buf)?;
+            recorder.record(Direction::Outbound, buf);
+            Ok(())
+        })
+    }
+}
+
+pub struct PcapngTapRxToken<'a, T> {
+    token: T,
+    recorder: &'a mut Recorder,
+}
+
+impl<'a, T: RxToken> RxToken for PcapngTapRxToken<'a, T> {
+    fn consume<F>(self, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&[u8]) -> Result<(), ()>,
+    {
+        let Self { token, recorder } = self;
+        token.consume(|buf| {
+            recorder.record(Direction::Inbound, buf);
+            f(buf)
+        })
+    }
+}
+
+fn write_header<W: Write>(writer: &mut W) -> io::Result<()> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+    body.extend_from_slice(&1_u16.to_le_bytes()); // major version
+    body.extend_from_slice(&0_u16.to_le_bytes()); // minor version
+    body.extend_from_slice(&(-1_i64).to_le_bytes()); // section length is not specified
+    write_block(writer, SECTION_HEADER_BLOCK, &body)?;
+
+    let mut body = Vec::new();
+    body.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+    body.extend_from_slice(&0_u16.to_le_bytes()); // reserved
+    body.extend_from_slice(&0_u32.to_le_bytes()); // no snap length limit
+    write_block(writer, INTERFACE_DESCRIPTION_BLOCK, &body)
+}
+
+fn write_enhanced_packet_block<W: Write + ?Sized>(
+    writer: &mut W,
+    frame: &CapturedFrame,
+) -> io::Result<()> {
+    let mut body = Vec::with_capacity(frame.data.len() + 32);
+    body.extend_from_slice(&0_u32.to_le_bytes()); // interface id
+    body.extend_from_slice(&((frame.timestamp_us >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(frame.timestamp_us as u32).to_le_bytes());
+    body.extend_from_slice(&(frame.data.len() as u32).to_le_bytes()); // captured length
+    body.extend_from_slice(&(frame.data.len() as u32).to_le_bytes()); // original length
+    body.extend_from_slice(&frame.data);
+    body.resize((body.len() + 3) & !3, 0);
+    body.extend_from_slice(&OPTION_EPB_FLAGS.to_le_bytes());
+    body.extend_from_slice(&4_u16.to_le_bytes());
+    body.extend_from_slice(&frame.direction.flags().to_le_bytes());
+    body.extend_from_slice(&OPTION_END.to_le_bytes());
+    body.extend_from_slice(&0_u16.to_le_bytes());
+    write_block(writer, ENHANCED_PACKET_BLOCK, &body)
+}
+
+fn write_block<W: Write + ?Sized>(writer: &mut W, block_type: u32, body: &[u8]) -> io::Result<()> {
+    let total_length = (body.len() + 12) as u32;
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&total_length.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&total_length.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::CommandType;
+    use crate::interface::simulator::{SimulatedSegment, VirtualEsc};
+    use crate::interface::{Command, PduInterface};
+    use crate::task::ManualClock;
+
+    #[test]
+    fn ring_test() {
+        let mut segment = SimulatedSegment::new();
+        segment.push_slave(VirtualEsc::new(1, 2, 3));
+        let device = PcapngTapDevice::with_ring(segment, 2);
+
+        let mut buf = [0; 64];
+        let clock = ManualClock::new();
+        let mut pdu_if = PduInterface::new(device, &mut buf, &clock);
+        for _ in 0..2 {
+            pdu_if
+                .add_pdu(0, Command::new(CommandType::BRD, 0, 0x0130), 2, |_| {})
+                .unwrap();
+            assert_eq!(pdu_if.transmit_one_frame(), Ok(true));
+            assert_eq!(pdu_if.receive_one_frame(), Ok(true));
+            pdu_if.consume_pdus().for_each(drop);
+        }
+
+        let device = pdu_if.device();
+        let frames: Vec<_> = device.frames().collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].direction, Direction::Outbound);
+        assert_eq!(frames[1].direction, Direction::Inbound);
+        // The returned frame has the locally administered bit.
+        assert_eq!(frames[1].data[6] & 0x02, 0x02);
+
+        let mut file = Vec::new();
+        device.dump(&mut file).unwrap();
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                file[offset],
+                file[offset + 1],
+                file[offset + 2],
+                file[offset + 3],
+            ])
+        };
+        let mut offset = 0;
+        let mut blocks = Vec::new();
+        while offset < file.len() {
+            let block_type = read_u32(offset);
+            let length = read_u32(offset + 4) as usize;
+            assert_eq!(length % 4, 0);
+            assert_eq!(read_u32(offset + length - 4) as usize, length);
+            blocks.push(block_type);
+            offset += length;
+        }
+        assert_eq!(
+            blocks,
+            [
+                SECTION_HEADER_BLOCK,
+                INTERFACE_DESCRIPTION_BLOCK,
+                ENHANCED_PACKET_BLOCK,
+                ENHANCED_PACKET_BLOCK
+            ]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use crate::frame::{ETHERCAT_TYPE, ETHERNET_FRAME_SIZE_WITHOUT_FCS};
+
+use super::{DeviceError, RawEthernetDevice, RxToken, TxToken};
+
+// Not exported by every libc version.
+const PACKET_OUTGOING: u8 = 4;
+const PACKET_IGNORE_OUTGOING: i32 = 23;
+
+/// Raw ethernet device on a Linux AF_PACKET/SOCK_RAW socket.
+///
+/// The socket is bound to a single interface and only receives frames whose ether type is ETHERCAT_TYPE.
+/// Both tokens are non-blocking.
+pub struct RawSocketDevice {
+    fd: RawFd,
+    ifindex: i32,
+    tx_buf: Vec<u8>,
+    rx_buf: Vec<u8>,
+}
+
+impl RawSocketDevice {
+    /// Opens a raw socket on the interface (e.g. "eth0").
+    /// This requires CAP_NET_RAW.
+    pub fn new(interface: &str) -> io::Result<Self> {
+        let name = CString::new(interface)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid interface name"))?;
+        let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if ifindex == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let protocol = ETHERCAT_TYPE.to_be() as i32;
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_PACKET,
+                libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                protocol,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let device = Self {
+            fd,
+            ifindex: ifindex as i32,
+            tx_buf: vec![0; ETHERNET_FRAME_SIZE_WITHOUT_FCS],
+            rx_buf: vec![0; ETHERNET_FRAME_SIZE_WITHOUT_FCS],
+        };
+
+        let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+        addr.sll_family = libc::AF_PACKET as u16;
+        addr.sll_protocol = protocol as u16;
+        addr.sll_ifindex = device.ifindex;
+        let res = unsafe {
+            libc::bind(
+                fd,
+                &addr as *const libc::sockaddr_ll as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Returned frames are addressed to the broadcast address, but some slaves rewrite the destination.
+        let mut mreq: libc::packet_mreq = unsafe { mem::zeroed() };
+        mreq.mr_ifindex = device.ifindex;
+        mreq.mr_type = libc::PACKET_MR_PROMISC as u16;
+        device.set_option(libc::SOL_PACKET, libc::PACKET_ADD_MEMBERSHIP, &mreq)?;
+
+        // Our own transmitted frames are not needed. Older kernels do not know this option,
+        // so outgoing frames are also dropped in `receive`.
+        let _ = device.set_option(libc::SOL_PACKET, PACKET_IGNORE_OUTGOING, &1i32);
+
+        Ok(device)
+    }
+
+    /// Sets SO_PRIORITY of the socket.
+    /// Priorities above 6 require CAP_NET_ADMIN.
+    pub fn set_priority(&mut self, priority: u32) -> io::Result<()> {
+        self.set_option(libc::SOL_SOCKET, libc::SO_PRIORITY, &(priority as i32))
+    }
+
+    pub fn interface_index(&self) -> i32 {
+        self.ifindex
+    }
+
+    fn set_option<T>(&self, level: i32, name: i32, value: &T) -> io::Result<()> {
+        let res = unsafe {
+            libc::setsockopt(
+                self.fd,
+                level,
+                name,
+                value as *const T as *const libc::c_void,
+                mem::size_of::<T>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl AsRawFd for RawSocketDevice {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for RawSocketDevice {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+impl RawEthernetDevice for RawSocketDevice {
+    type TxToken<'a> = RawSocketTxToken<'a>
+    where
+        Self: 'a;
+
+    type RxToken<'a> = RawSocketRxToken<'a>
+    where
+        Self: 'a;
+
+    fn transmit<'a>(&'a mut self) -> Option<Self::TxToken<'a>> {
+        Some(RawSocketTxToken {
+            fd: self.fd,
+            buf: &mut self.tx_buf,
+        })
+    }
+
+    fn receive<'a>(&'a mut self) -> Option<Self::RxToken<'a>> {
+        loop {
+            let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+            let mut addr_len = mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+            let len = unsafe {
+                libc::recvfrom(
+                    self.fd,
+                    self.rx_buf.as_mut_ptr() as *mut libc::c_void,
+                    self.rx_buf.len(),
+                    libc::MSG_DONTWAIT,
+                    &mut addr as *mut libc::sockaddr_ll as *mut libc::sockaddr,
+                    &mut addr_len,
+                )
+            };
+            if len < 0 {
+                return None;
+            }
+            if addr.sll_pkttype == PACKET_OUTGOING {
+                continue;
+            }
+            return Some(RawSocketRxToken(&self.rx_buf[..len as usize]));
+        }
+    }
+}
+
+pub struct RawSocketTxToken<'a> {
+    fd: RawFd,
+    buf: &'a mut [u8],
+}
+
+impl<'a> TxToken for RawSocketTxToken<'a> {
+    fn consume<F>(self, len: usize, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), ()>,
+    {
+        let buf = self.buf.get_mut(..len).ok_or(DeviceError::Device)?;
+        f(buf).map_err(|_| DeviceError::Function)?;
+        let sent = unsafe {
+            libc::send(
+                self.fd,
+                buf.as_ptr() as *const libc::c_void,
+                len,
+                libc::MSG_DONTWAIT,
+            )
+        };
+        if sent < 0 || sent as usize != len {
+            Err(DeviceError::Device)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub struct RawSocketRxToken<'a>(&'a [u8]);
+
+impl<'a> RxToken for RawSocketRxToken<'a> {
+    fn consume<F>(self, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&[u8]) -> Result<(), ()>,
+    {
+        f(self.0).map_err(|_| DeviceError::Function)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::EthernetFrame;
+
+    // Needs root and a veth pair:
+    // ip link add ecat0 type veth peer name ecat1
+    // ip link set ecat0 up && ip link set ecat1 up
+    #[test]
+    #[ignore]
+    fn veth_loopback_test() {
+        let mut tx_dev = RawSocketDevice::new("ecat0").unwrap();
+        let mut rx_dev = RawSocketDevice::new("ecat1").unwrap();
+        tx_dev.set_priority(6).unwrap();
+
+        assert!(rx_dev.receive().is_none());
+
+        let token = tx_dev.transmit().unwrap();
+        token
+            .consume(60, |buf| {
+                EthernetFrame(&mut buf[..]).set_ethercat_default();
+                buf[EthernetFrame::HEADER_SIZE] = 0xAB;
+                Ok(())
+            })
+            .unwrap();
+
+        let mut recieved = None;
+        for _ in 0..1000 {
+            if let Some(token) = rx_dev.receive() {
+                token
+                    .consume(|buf| {
+                        recieved = Some((buf.len(), buf[EthernetFrame::HEADER_SIZE]));
+                        Ok(())
+                    })
+                    .unwrap();
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(recieved, Some((60, 0xAB)));
+    }
+}
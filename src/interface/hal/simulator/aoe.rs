@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use crate::frame::{
+    AdsCommand, AdsErrorCode, AdsIndex, AdsRequest, AdsResponse, AdsState, AmsAddress, AmsHeader,
+    AoE, Mailbox, MailboxFrame, MailboxType,
+};
+
+use super::mailbox::new_mailbox;
+
+/// ADS device behind a virtual ESC which the master reads and writes with AoE.
+/// Variables are addressed by the index group and offset, and have a fixed size.
+#[derive(Debug)]
+pub struct AoeServer {
+    address: AmsAddress,
+    variables: BTreeMap<AdsIndex, Vec<u8>>,
+    ads_state: AdsState,
+    device_state: u16,
+    duplicate_response: bool,
+}
+
+impl Default for AoeServer {
+    fn default() -> Self {
+        Self {
+            address: AmsAddress::new([192, 168, 0, 1, 1, 1], 851),
+            variables: BTreeMap::new(),
+            ads_state: AdsState::Run,
+            device_state: 0,
+            duplicate_response: false,
+        }
+    }
+}
+
+impl AoeServer {
+    /// Requests to another NetId or port are answered with an error in the AMS header.
+    pub fn address(&self) -> AmsAddress {
+        self.address
+    }
+
+    pub fn set_address(&mut self, address: AmsAddress) {
+        self.address = address;
+    }
+
+    pub fn variable(&self, index: AdsIndex) -> Option<&[u8]> {
+        self.variables.get(&index).map(|data| data.as_slice())
+    }
+
+    pub fn insert_variable(&mut self, index: AdsIndex, data: &[u8]) {
+        self.variables.insert(index, data.to_vec());
+    }
+
+    pub fn set_ads_state(&mut self, ads_state: AdsState, device_state: u16) {
+        self.ads_state = ads_state;
+        self.device_state = device_state;
+    }
+
+    /// Each response is sent twice, as if the master has missed the first one.
+    pub fn set_duplicate_response(&mut self, duplicate: bool) {
+        self.duplicate_response = duplicate;
+    }
+
+    pub(super) fn process_request(
+        &mut self,
+        request: &MailboxFrame<&[u8]>,
+        aoe: AoE,
+        mailbox_size: usize,
+    ) -> Vec<Vec<u8>> {
+        let (header, ads_request) = match aoe {
+            AoE::Request { header, request } => (header, request),
+            AoE::Response { .. } => return Vec::new(),
+        };
+        let mut response_header = AmsHeader {
+            target: header.source,
+            source: header.target,
+            error_code: AdsErrorCode::NoError,
+            invoke_id: header.invoke_id,
+        };
+        let mailbox =
+            |header, response| aoe_mailbox(request.count(), header, response, mailbox_size);
+        let response = match ads_request {
+            _ if header.target.net_id != self.address.net_id => {
+                response_header.error_code = AdsErrorCode::TargetMachineNotFound;
+                mailbox(response_header, AdsResponse::Other(command(&ads_request)))
+            }
+            _ if header.target.port != self.address.port => {
+                response_header.error_code = AdsErrorCode::TargetPortNotFound;
+                mailbox(response_header, AdsResponse::Other(command(&ads_request)))
+            }
+            AdsRequest::Read { index, length } => {
+                let (result, data) = result_and_data(self.read(index, length));
+                mailbox(
+                    response_header,
+                    AdsResponse::Read {
+                        result,
+                        data: &data,
+                    },
+                )
+            }
+            AdsRequest::Write { index, data } => {
+                let result = self
+                    .write(index, data)
+                    .err()
+                    .unwrap_or(AdsErrorCode::NoError);
+                mailbox(response_header, AdsResponse::Write { result })
+            }
+            // The data are written, and then read back.
+            AdsRequest::ReadWrite {
+                index,
+                read_length,
+                data,
+            } => {
+                let read = self
+                    .write(index, data)
+                    .and_then(|_| self.read(index, read_length));
+                let (result, data) = result_and_data(read);
+                mailbox(
+                    response_header,
+                    AdsResponse::ReadWrite {
+                        result,
+                        data: &data,
+                    },
+                )
+            }
+            AdsRequest::ReadState => {
+                let response = AdsResponse::ReadState {
+                    result: AdsErrorCode::NoError,
+                    ads_state: self.ads_state,
+                    device_state: self.device_state,
+                };
+                mailbox(response_header, response)
+            }
+            AdsRequest::Other(command) => {
+                response_header.error_code = AdsErrorCode::ServiceNotSupported;
+                mailbox(response_header, AdsResponse::Other(command))
+            }
+        };
+        if self.duplicate_response {
+            vec![response.clone(), response]
+        } else {
+            vec![response]
+        }
+    }
+
+    fn read(&self, index: AdsIndex, length: u32) -> Result<Vec<u8>, AdsErrorCode> {
+        let value = self
+            .variable(index)
+            .ok_or(AdsErrorCode::InvalidIndexOffset)?;
+        if (length as usize) < value.len() {
+            Err(AdsErrorCode::InvalidSize)
+        } else {
+            Ok(value.to_vec())
+        }
+    }
+
+    fn write(&mut self, index: AdsIndex, data: &[u8]) -> Result<(), AdsErrorCode> {
+        let value = self
+            .variables
+            .get_mut(&index)
+            .ok_or(AdsErrorCode::InvalidIndexOffset)?;
+        if value.len() != data.len() {
+            Err(AdsErrorCode::InvalidSize)
+        } else {
+            value.copy_from_slice(data);
+            Ok(())
+        }
+    }
+}
+
+fn result_and_data(read: Result<Vec<u8>, AdsErrorCode>) -> (AdsErrorCode, Vec<u8>) {
+    match read {
+        Ok(data) => (AdsErrorCode::NoError, data),
+        Err(result) => (result, Vec::new()),
+    }
+}
+
+fn command(request: &AdsRequest) -> AdsCommand {
+    match request {
+        AdsRequest::Read { .. } => AdsCommand::Read,
+        AdsRequest::Write { .. } => AdsCommand::Write,
+        AdsRequest::ReadWrite { .. } => AdsCommand::ReadWrite,
+        AdsRequest::ReadState => AdsCommand::ReadState,
+        AdsRequest::Other(command) => *command,
+    }
+}
+
+fn aoe_mailbox(
+    count: u8,
+    header: AmsHeader,
+    response: AdsResponse,
+    mailbox_size: usize,
+) -> Vec<u8> {
+    let mut mailbox = new_mailbox(
+        MailboxType::AoE,
+        count,
+        mailbox_size - MailboxFrame::HEADER_SIZE,
+    );
+    let mut frame = MailboxFrame(&mut mailbox[..]);
+    frame
+        .set_mailbox(&Mailbox::AoE(AoE::Response { header, response }))
+        .unwrap();
+    let length = frame.length() as usize;
+    mailbox.truncate(MailboxFrame::HEADER_SIZE + length);
+    mailbox
+}
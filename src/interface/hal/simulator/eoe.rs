@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+
+use crate::frame::{EoE, EoeFrame, EoeIpParameter, EoeResult, Mailbox, MailboxFrame, MailboxType};
+
+use super::mailbox::new_mailbox;
+
+/// Ethernet frames of a virtual ESC which the master tunnels with EoE.
+/// The test plays the IP stack of the slave with `pop_frame` and `VirtualEsc::send_eoe_frame`.
+#[derive(Debug, Default)]
+pub struct EoeServer {
+    ip_parameter: EoeIpParameter,
+    received_frames: VecDeque<Vec<u8>>,
+    /// Frame being received.
+    frame: Vec<u8>,
+    next_fragment: u8,
+    frame_number: u8,
+}
+
+impl EoeServer {
+    /// Parameters set by the master.
+    pub fn ip_parameter(&self) -> &EoeIpParameter {
+        &self.ip_parameter
+    }
+
+    /// Pops the oldest frame received from the master.
+    pub fn pop_frame(&mut self) -> Option<Vec<u8>> {
+        self.received_frames.pop_front()
+    }
+
+    /// Returns no response to fragments.
+    pub(super) fn process_request(
+        &mut self,
+        request: &MailboxFrame<&[u8]>,
+        eoe: EoE,
+        mailbox_size: usize,
+    ) -> Option<Vec<u8>> {
+        match eoe {
+            EoE::Fragment {
+                fragment_number,
+                last,
+                data,
+                ..
+            } => {
+                if fragment_number == 0 {
+                    self.frame.clear();
+                } else if fragment_number != self.next_fragment {
+                    // The rest of a broken frame is dropped.
+                    return None;
+                }
+                self.frame.extend_from_slice(data);
+                self.next_fragment = fragment_number + 1;
+                if last {
+                    self.received_frames
+                        .push_back(core::mem::take(&mut self.frame));
+                }
+                None
+            }
+            EoE::SetIpReq(parameter) => {
+                let ip_parameter = &mut self.ip_parameter;
+                ip_parameter.mac_address = parameter.mac_address.or(ip_parameter.mac_address);
+                ip_parameter.ip_address = parameter.ip_address.or(ip_parameter.ip_address);
+                ip_parameter.subnet_mask = parameter.subnet_mask.or(ip_parameter.subnet_mask);
+                ip_parameter.default_gateway =
+                    parameter.default_gateway.or(ip_parameter.default_gateway);
+                ip_parameter.dns_server = parameter.dns_server.or(ip_parameter.dns_server);
+                ip_parameter.dns_name = parameter.dns_name.or(ip_parameter.dns_name);
+                let response = Mailbox::new_eoe_set_ip_response(EoeResult::Success);
+                Some(eoe_mailbox(request.count(), &response, mailbox_size))
+            }
+            _ => {
+                let response = Mailbox::new_eoe_set_ip_response(EoeResult::UnsupportedFrameType);
+                Some(eoe_mailbox(request.count(), &response, mailbox_size))
+            }
+        }
+    }
+
+    /// Splits a frame into fragment mailboxes.
+    pub(super) fn fragments(&mut self, frame: &[u8], mailbox_size: usize) -> Vec<Vec<u8>> {
+        let capacity = (mailbox_size - MailboxFrame::HEADER_SIZE - EoeFrame::HEADER_SIZE)
+            / EoeFrame::FRAGMENT_UNIT
+            * EoeFrame::FRAGMENT_UNIT;
+        let frame_number = self.frame_number;
+        self.frame_number = (self.frame_number + 1) & 0x0F;
+        let num_fragments = frame.len().div_ceil(capacity);
+        frame
+            .chunks(capacity)
+            .enumerate()
+            .map(|(i, data)| {
+                let offset = if i == 0 {
+                    frame.len().div_ceil(EoeFrame::FRAGMENT_UNIT)
+                } else {
+                    i * capacity / EoeFrame::FRAGMENT_UNIT
+                };
+                let fragment = Mailbox::new_eoe_fragment(
+                    frame_number,
+                    i as u8,
+                    offset as u8,
+                    i + 1 == num_fragments,
+                    data,
+                );
+                eoe_mailbox(0, &fragment, mailbox_size)
+            })
+            .collect()
+    }
+}
+
+fn eoe_mailbox(count: u8, eoe: &Mailbox, mailbox_size: usize) -> Vec<u8> {
+    let mut mailbox = new_mailbox(
+        MailboxType::EoE,
+        count,
+        mailbox_size - MailboxFrame::HEADER_SIZE,
+    );
+    let mut frame = MailboxFrame(&mut mailbox[..]);
+    frame.set_mailbox(eoe).unwrap();
+    let length = frame.length() as usize;
+    mailbox.truncate(MailboxFrame::HEADER_SIZE + length);
+    mailbox
+}
@@ -0,0 +1,964 @@
+use bit_field::BitField;
+use std::collections::VecDeque;
+
+use crate::frame::{
+    AbortCode, CoE, CommandType, EmmergencyFrame, EtherCatPdu, Mailbox, MailboxFrame, SdoReq,
+    WKC_LENGTH,
+};
+use crate::register::{
+    sii, AlControl, AlStatus, DcRecieveTime, DcSystemTime, DcSystemTimeDelta, DcSystemTimeOffset,
+    DcSystemTimeTransmissionDelay, DlControl, DlInformation, DlStatus, FixedStationAddress,
+    FmmuRegister, PdiControl, SiiAccess, SiiAddress, SiiControl, SiiData, SyncManagerActivation,
+    SyncManagerControl, SyncManagerPdiControl, SyncManagerStatus,
+};
+use crate::slave::AlState;
+
+use super::mailbox::{self, ProtocolServers, SegmentedTransfer};
+use super::{copy_bits, AoeServer, EoeServer, FoeServer, ObjectDictionary, SoeServer};
+
+const RAM_SIZE: usize = 0x2000;
+const MEMORY_SIZE: usize = 0x1000 + RAM_SIZE;
+const NUM_FMMU: usize = 8;
+const NUM_SM: usize = 8;
+const SII_SIZE_WORDS: usize = 0x80;
+const GENERAL_CATEGORY_SIZE_WORDS: u16 = 0x10;
+
+const STATION_ALIAS_ADDRESS: u16 = FixedStationAddress::ADDRESS + 2;
+const AL_STATUS_CODE_ADDRESS: u16 = AlStatus::ADDRESS + 4;
+
+const SM_BUFFER_TYPE_MAILBOX: u8 = 0b10;
+const SM_DIRECTION_READ: u8 = 0b00;
+const SM_DIRECTION_WRITE: u8 = 0b01;
+
+const MAILBOX_RX_OFFSET: u16 = 0x1000;
+const MAILBOX_TX_OFFSET: u16 = 0x1080;
+const MAILBOX_SIZE: u16 = 0x80;
+const BOOTSTRAP_RX_OFFSET: u16 = 0x1000;
+const BOOTSTRAP_TX_OFFSET: u16 = 0x1100;
+const BOOTSTRAP_SIZE: u16 = 0x100;
+
+// AL status codes
+const INVALID_REQUESTED_STATE_CHANGE: u16 = 0x0011;
+const UNKNOWN_REQUESTED_STATE: u16 = 0x0012;
+const BOOTSTRAP_NOT_SUPPORTED: u16 = 0x0013;
+const INVALID_MAILBOX_CONFIGURATION: u16 = 0x0015;
+const INVALID_OUTPUT_CONFIGURATION: u16 = 0x001D;
+const INVALID_INPUT_CONFIGURATION: u16 = 0x001E;
+
+type Application = Box<dyn FnMut(AlState, &mut ObjectDictionary)>;
+pub(super) type VoeHandler = Box<dyn FnMut(&[u8]) -> Option<Vec<u8>>>;
+
+/// Software model of an ESC and its slave application.
+///
+/// The model covers what the master uses: registers, SII, FMMUs, sync managers (mailbox and buffered),
+/// the AL state machine, DC, a CoE SDO server, an EoE endpoint, FoE, SoE and AoE servers.
+/// Process data are exchanged with the object dictionary through the assigned PDOs (0x1C12, 0x1C13).
+pub struct VirtualEsc {
+    mem: Vec<u8>,
+    sii: Vec<u16>,
+    od: ObjectDictionary,
+    aoe: AoeServer,
+    eoe: EoeServer,
+    foe: FoeServer,
+    soe: SoeServer,
+    al_state: AlState,
+    linked_ports: [bool; 4],
+    clock_offset_ns: u64,
+    local_time_ns: u64,
+    drift_correction_ns: u64,
+    port_receive_times: [u32; 4],
+    mailbox_responses: VecDeque<Vec<u8>>,
+    last_mailbox_response: Option<Vec<u8>>,
+    /// Count of the last mailbox written by the master.
+    last_request_count: u8,
+    /// Count of the last mailbox put in the tx mailbox. A repeated mailbox keeps its count.
+    response_count: u8,
+    sdo_transfer: SegmentedTransfer,
+    /// Index, sub index and code of the SDO aborts written by the master.
+    sdo_aborts: Vec<(u16, u8, AbortCode)>,
+    application: Option<Application>,
+    voe_handler: Option<VoeHandler>,
+}
+
+impl VirtualEsc {
+    /// ESC with the default SII (standard mailbox 0x1000/0x1080, 128 bytes each,
+    /// AoE, EoE, CoE, FoE and SoE) and `ObjectDictionary::with_default_objects`.
+    pub fn new(vender_id: u32, product_code: u32, revision_number: u32) -> Self {
+        let mut sii = vec![0; SII_SIZE_WORDS];
+        sii[sii::PdiControl::ADDRESS as usize] = 0x0005;
+        for (address, value) in [
+            (sii::VenderId::ADDRESS, vender_id),
+            (sii::ProductCode::ADDRESS, product_code),
+            (sii::RevisionNumber::ADDRESS, revision_number),
+        ] {
+            sii[address as usize] = value as u16;
+            sii[address as usize + 1] = (value >> 16) as u16;
+        }
+        sii[sii::BootstrapRxMailboxOffset::ADDRESS as usize] = BOOTSTRAP_RX_OFFSET;
+        sii[sii::BootstrapRxMailboxSize::ADDRESS as usize] = BOOTSTRAP_SIZE;
+        sii[sii::BootstrapTxMailboxOffset::ADDRESS as usize] = BOOTSTRAP_TX_OFFSET;
+        sii[sii::BootstrapTxMailboxSize::ADDRESS as usize] = BOOTSTRAP_SIZE;
+        sii[sii::StandardRxMailboxOffset::ADDRESS as usize] = MAILBOX_RX_OFFSET;
+        sii[sii::StandardRxMailboxSize::ADDRESS as usize] = MAILBOX_SIZE;
+        sii[sii::StandardTxMailboxOffset::ADDRESS as usize] = MAILBOX_TX_OFFSET;
+        sii[sii::StandardTxMailboxSize::ADDRESS as usize] = MAILBOX_SIZE;
+        // AoE, EoE, CoE, FoE and SoE
+        sii[sii::MailboxProtocol::ADDRESS as usize] = 0x001F;
+        // size in KiBit - 1, version
+        sii[0x3E] = (SII_SIZE_WORDS * 16 / 1024 - 1) as u16;
+        sii[0x3F] = 1;
+        // general category with SDO and SDO complete access
+        let general = sii::Categories::ADDRESS as usize;
+        sii[general] = sii::CategoryType::General as u16;
+        sii[general + 1] = GENERAL_CATEGORY_SIZE_WORDS;
+        let coe_details = sii::CoeDetails::ENABLE_SDO | sii::CoeDetails::ENABLE_SDO_COMPLETE_ACCESS;
+        sii[general + 2 + sii::CoeDetails::OFFSET as usize] = (coe_details as u16) << 8;
+        // end of categories
+        sii[general + 2 + GENERAL_CATEGORY_SIZE_WORDS as usize] = sii::CategoryType::End as u16;
+        sii[sii::Checksum::ADDRESS as usize] = sii_checksum(&sii) as u16;
+
+        let mut esc = Self {
+            mem: vec![0; MEMORY_SIZE],
+            sii,
+            od: ObjectDictionary::with_default_objects(vender_id, product_code, revision_number),
+            aoe: AoeServer::default(),
+            eoe: EoeServer::default(),
+            foe: FoeServer::default(),
+            soe: SoeServer::default(),
+            al_state: AlState::Init,
+            linked_ports: [true, false, false, false],
+            clock_offset_ns: 0,
+            local_time_ns: 0,
+            drift_correction_ns: 0,
+            port_receive_times: [0; 4],
+            mailbox_responses: VecDeque::new(),
+            last_mailbox_response: None,
+            last_request_count: 0,
+            response_count: 0,
+            sdo_transfer: SegmentedTransfer::None,
+            sdo_aborts: Vec::new(),
+            application: None,
+            voe_handler: None,
+        };
+
+        let dl_info = esc.reg_mut(DlInformation::ADDRESS, DlInformation::SIZE);
+        dl_info[0] = 0x11; // type
+        dl_info[4] = NUM_FMMU as u8;
+        dl_info[5] = NUM_SM as u8;
+        dl_info[6] = (RAM_SIZE / 1024) as u8;
+        dl_info[7] = 0b0000_1111; // port 0 and 1: MII
+        dl_info[8] = 0b0000_1100; // DC supported, 64 bit DC
+                                  // read size: 8 bytes, address: 2 bytes
+        esc.mem[SiiControl::ADDRESS as usize] = 0b1100_0000;
+        esc.set_al_status(AlState::Init, None);
+        esc.reload_sii();
+        esc
+    }
+
+    pub fn od(&self) -> &ObjectDictionary {
+        &self.od
+    }
+
+    pub fn od_mut(&mut self) -> &mut ObjectDictionary {
+        &mut self.od
+    }
+
+    pub fn aoe(&self) -> &AoeServer {
+        &self.aoe
+    }
+
+    pub fn aoe_mut(&mut self) -> &mut AoeServer {
+        &mut self.aoe
+    }
+
+    pub fn eoe(&self) -> &EoeServer {
+        &self.eoe
+    }
+
+    pub fn eoe_mut(&mut self) -> &mut EoeServer {
+        &mut self.eoe
+    }
+
+    pub fn foe(&self) -> &FoeServer {
+        &self.foe
+    }
+
+    pub fn foe_mut(&mut self) -> &mut FoeServer {
+        &mut self.foe
+    }
+
+    pub fn soe(&self) -> &SoeServer {
+        &self.soe
+    }
+
+    pub fn soe_mut(&mut self) -> &mut SoeServer {
+        &mut self.soe
+    }
+
+    pub fn sii(&self) -> &[u16] {
+        &self.sii
+    }
+
+    /// Changes are applied to the registers by `reload_sii`.
+    pub fn sii_mut(&mut self) -> &mut [u16] {
+        &mut self.sii
+    }
+
+    /// Writes the station alias to the SII and updates the checksum.
+    pub fn set_station_alias(&mut self, alias: u16) {
+        self.sii[sii::StationAlias::ADDRESS as usize] = alias;
+        self.sii[sii::Checksum::ADDRESS as usize] = sii_checksum(&self.sii) as u16;
+        self.reload_sii();
+    }
+
+    /// Loads the PDI control and the station alias from the SII.
+    pub fn reload_sii(&mut self) {
+        let pdi_control = self.sii[sii::PdiControl::ADDRESS as usize];
+        self.reg_mut(PdiControl::ADDRESS, PdiControl::SIZE)
+            .copy_from_slice(&pdi_control.to_le_bytes());
+        let alias = self.sii[sii::StationAlias::ADDRESS as usize];
+        self.reg_mut(STATION_ALIAS_ADDRESS, 2)
+            .copy_from_slice(&alias.to_le_bytes());
+        let checksum_error =
+            self.sii[sii::Checksum::ADDRESS as usize] as u8 != sii_checksum(&self.sii);
+        self.mem[SiiControl::ADDRESS as usize + 1].set_bit(3, checksum_error);
+    }
+
+    pub fn linked_ports(&self) -> [bool; 4] {
+        self.linked_ports
+    }
+
+    pub fn al_state(&self) -> AlState {
+        self.al_state
+    }
+
+    /// Deviation of the local clock from the simulated time.
+    pub fn set_clock_offset_ns(&mut self, offset_ns: u64) {
+        self.clock_offset_ns = offset_ns;
+    }
+
+    /// System time when the last frame passed.
+    pub fn system_time_ns(&self) -> u64 {
+        self.system_time()
+    }
+
+    pub fn register(&self, address: u16, length: usize) -> &[u8] {
+        &self.mem[address as usize..address as usize + length]
+    }
+
+    /// Index, sub index and code of the SDO aborts written by the master.
+    pub fn sdo_aborts(&self) -> &[(u16, u8, AbortCode)] {
+        &self.sdo_aborts
+    }
+
+    /// Queues an emergency which is sent before the pending mailbox responses.
+    pub fn push_emergency(&mut self, emergency: &EmmergencyFrame<[u8; EmmergencyFrame::SIZE]>) {
+        self.mailbox_responses
+            .push_front(mailbox::emergency_mailbox(emergency));
+    }
+
+    /// Queues an Ethernet frame which is sent to the master in fragments.
+    pub fn send_eoe_frame(&mut self, frame: &[u8]) {
+        let mailbox_size = self.sii[sii::StandardTxMailboxSize::ADDRESS as usize] as usize;
+        let fragments = self.eoe.fragments(frame, mailbox_size);
+        self.mailbox_responses.extend(fragments);
+    }
+
+    /// Sets the slave application which is called once per frame after the outputs are updated.
+    pub fn set_application<F>(&mut self, application: F)
+    where
+        F: FnMut(AlState, &mut ObjectDictionary) + 'static,
+    {
+        self.application = Some(Box::new(application));
+    }
+
+    /// Sets the handler of VoE mailboxes, which returns the data of the response if any.
+    /// Without a handler, VoE is answered with an error mailbox.
+    pub fn set_voe_handler<F>(&mut self, handler: F)
+    where
+        F: FnMut(&[u8]) -> Option<Vec<u8>> + 'static,
+    {
+        self.voe_handler = Some(Box::new(handler));
+    }
+
+    pub(super) fn set_linked_ports(&mut self, linked_ports: [bool; 4]) {
+        self.linked_ports = linked_ports;
+        let mut status = 0_u16;
+        status.set_bit(0, true); // PDI operational
+        for (port, linked) in linked_ports.iter().enumerate() {
+            status.set_bit(4 + port, *linked); // physical link
+            status.set_bit(8 + port * 2, !*linked); // loop closed
+            status.set_bit(9 + port * 2, *linked); // signal detection
+        }
+        self.reg_mut(DlStatus::ADDRESS, DlStatus::SIZE)
+            .copy_from_slice(&status.to_le_bytes());
+    }
+
+    /// Processes all datagrams of a frame.
+    /// `return_ns` is the time when the frame comes back to port 1, or None if port 1 is open.
+    pub(super) fn process_datagrams(
+        &mut self,
+        datagrams: &mut [u8],
+        arrival_ns: u64,
+        return_ns: Option<u64>,
+    ) {
+        self.local_time_ns = arrival_ns.wrapping_add(self.clock_offset_ns);
+        self.port_receive_times = [0; 4];
+        self.port_receive_times[0] = self.local_time_ns as u32;
+        if let Some(return_ns) = return_ns {
+            self.port_receive_times[1] = return_ns.wrapping_add(self.clock_offset_ns) as u32;
+        }
+        self.update_system_time();
+
+        if self.al_state == AlState::SafeOperational || self.al_state == AlState::Operational {
+            self.update_inputs();
+        }
+
+        let mut offset = 0;
+        while offset + EtherCatPdu::HEADER_SIZE <= datagrams.len() {
+            let header = EtherCatPdu(&datagrams[offset..offset + EtherCatPdu::HEADER_SIZE]);
+            let command = CommandType::from(header.command_type());
+            let adp = header.adp();
+            let ado = header.ado();
+            let length = header.length() as usize;
+            let has_next = header.has_next();
+            let data_offset = offset + EtherCatPdu::HEADER_SIZE;
+            let wkc_offset = data_offset + length;
+            if datagrams.len() < wkc_offset + WKC_LENGTH {
+                break;
+            }
+            let (header, rest) = datagrams[offset..].split_at_mut(EtherCatPdu::HEADER_SIZE);
+            let (data, wkc) = rest.split_at_mut(length);
+            let wkc = &mut wkc[..WKC_LENGTH];
+            let mut wkc_value = u16::from_le_bytes([wkc[0], wkc[1]]);
+            let adp = self.process_datagram(command, adp, ado, data, &mut wkc_value);
+            EtherCatPdu(header).set_adp(adp);
+            wkc.copy_from_slice(&wkc_value.to_le_bytes());
+
+            offset = wkc_offset + WKC_LENGTH;
+            if !has_next {
+                break;
+            }
+        }
+
+        if self.al_state == AlState::Operational {
+            self.update_outputs();
+        }
+        if let Some(application) = &mut self.application {
+            application(self.al_state, &mut self.od);
+        }
+        self.service_mailbox();
+    }
+
+    /// Returns the new ADP.
+    fn process_datagram(
+        &mut self,
+        command: CommandType,
+        adp: u16,
+        ado: u16,
+        data: &mut [u8],
+        wkc: &mut u16,
+    ) -> u16 {
+        use CommandType::*;
+        match command {
+            APRD | APWR | APRW | ARMW => {
+                self.physical_access(command, adp == 0, ado, data, wkc);
+                adp.wrapping_add(1)
+            }
+            FPRD | FPWR | FPRW | FRMW => {
+                let hit = adp == self.station_address()
+                    || (self.alias_enabled() && adp == self.station_alias());
+                self.physical_access(command, hit, ado, data, wkc);
+                adp
+            }
+            BRD | BWR | BRW => {
+                self.physical_access(command, true, ado, data, wkc);
+                adp.wrapping_add(1)
+            }
+            LRD | LWR | LRW => {
+                let address = adp as u32 | (ado as u32) << 16;
+                self.logical_access(command, address, data, wkc);
+                adp
+            }
+            NOP | Invalid => adp,
+        }
+    }
+
+    fn physical_access(
+        &mut self,
+        command: CommandType,
+        hit: bool,
+        ado: u16,
+        data: &mut [u8],
+        wkc: &mut u16,
+    ) {
+        use CommandType::*;
+        match command {
+            APRD | FPRD if hit && self.read(ado, data, false) => *wkc += 1,
+            BRD if self.read(ado, data, true) => *wkc += 1,
+            APWR | FPWR | BWR if hit && self.write(ado, data) => *wkc += 1,
+            APRW | FPRW | BRW if hit => {
+                let write_data = data.to_vec();
+                if self.read(ado, data, command == BRW) {
+                    *wkc += 1;
+                }
+                if self.write(ado, &write_data) {
+                    *wkc += 2;
+                }
+            }
+            // read at the addressed slave, write at the others
+            ARMW | FRMW if hit && self.read(ado, data, false) => *wkc += 1,
+            ARMW | FRMW if !hit && self.write(ado, data) => *wkc += 1,
+            _ => {}
+        }
+    }
+
+    fn logical_access(
+        &mut self,
+        command: CommandType,
+        address: u32,
+        data: &mut [u8],
+        wkc: &mut u16,
+    ) {
+        let write_data = data.to_vec();
+        let datagram_start_bit = address as u64 * 8;
+        let datagram_end_bit = datagram_start_bit + data.len() as u64 * 8;
+        let mut read_hit = false;
+        let mut write_hit = false;
+        for i in 0..NUM_FMMU {
+            let fmmu = FmmuRegister(self.reg_array::<16>(FmmuRegister::ADDRESS + (i * 16) as u16));
+            if !fmmu.enable() || fmmu.length() == 0 {
+                continue;
+            }
+            let logical_start_bit =
+                fmmu.logical_start_address() as u64 * 8 + fmmu.logical_start_bit() as u64;
+            let logical_end_bit = (fmmu.logical_start_address() as u64 + fmmu.length() as u64 - 1)
+                * 8
+                + fmmu.logical_end_bit() as u64
+                + 1;
+            let start_bit = logical_start_bit.max(datagram_start_bit);
+            let end_bit = logical_end_bit.min(datagram_end_bit);
+            if end_bit <= start_bit {
+                continue;
+            }
+            let bit_length = (end_bit - start_bit) as usize;
+            let physical_bit = fmmu.physical_start_address() as usize * 8
+                + fmmu.physical_start_bit() as usize
+                + (start_bit - logical_start_bit) as usize;
+            let data_bit = (start_bit - datagram_start_bit) as usize;
+            let physical_address = (physical_bit / 8) as u16;
+            let mut buf = vec![0; (physical_bit % 8 + bit_length).div_ceil(8)];
+
+            let is_read = command != CommandType::LWR && fmmu.read_enable();
+            let is_write = command != CommandType::LRD && fmmu.write_enable();
+            if is_read && self.read(physical_address, &mut buf, false) {
+                copy_bits(&buf, physical_bit % 8, data, data_bit, bit_length);
+                read_hit = true;
+            }
+            if is_write {
+                self.read_raw(physical_address, &mut buf);
+                copy_bits(
+                    &write_data,
+                    data_bit,
+                    &mut buf,
+                    physical_bit % 8,
+                    bit_length,
+                );
+                if self.write(physical_address, &buf) {
+                    write_hit = true;
+                }
+            }
+        }
+        if read_hit {
+            *wkc += 1;
+        }
+        if write_hit {
+            *wkc += if command == CommandType::LRW { 2 } else { 1 };
+        }
+    }
+
+    /// Returns false if the area is not readable now (e.g. an empty mailbox).
+    fn read(&mut self, address: u16, data: &mut [u8], or: bool) -> bool {
+        let start = address as usize;
+        let end = start + data.len();
+        let mut read_last_byte_of = None;
+        for sm in 0..NUM_SM {
+            if let Some((sm_start, sm_end)) = self.mailbox_area(sm, SM_DIRECTION_READ) {
+                if start < sm_end && sm_start < end {
+                    if !self.is_mailbox_full(sm) {
+                        return false;
+                    }
+                    if sm_end <= end {
+                        read_last_byte_of = Some(sm);
+                    }
+                }
+            }
+        }
+        for (i, byte) in data.iter_mut().enumerate() {
+            let value = self.mem.get(start + i).copied().unwrap_or_default();
+            if or {
+                *byte |= value;
+            } else {
+                *byte = value;
+            }
+        }
+        if let Some(sm) = read_last_byte_of {
+            self.set_mailbox_full(sm, false);
+        }
+        true
+    }
+
+    fn read_raw(&self, address: u16, data: &mut [u8]) {
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = self
+                .mem
+                .get(address as usize + i)
+                .copied()
+                .unwrap_or_default();
+        }
+    }
+
+    /// Returns false if the area is not writable now (e.g. a full mailbox).
+    fn write(&mut self, address: u16, data: &[u8]) -> bool {
+        let start = address as usize;
+        let end = start + data.len();
+        let mut wrote_last_byte_of = None;
+        for sm in 0..NUM_SM {
+            if let Some((sm_start, sm_end)) = self.mailbox_area(sm, SM_DIRECTION_READ) {
+                if start < sm_end && sm_start < end {
+                    return false;
+                }
+            }
+            if let Some((sm_start, sm_end)) = self.mailbox_area(sm, SM_DIRECTION_WRITE) {
+                if start < sm_end && sm_start < end {
+                    if self.is_mailbox_full(sm) {
+                        return false;
+                    }
+                    if sm_end <= end {
+                        wrote_last_byte_of = Some(sm);
+                    }
+                }
+            }
+        }
+        let old = self.mem[start.min(MEMORY_SIZE)..end.min(MEMORY_SIZE)].to_vec();
+        for (i, byte) in data.iter().enumerate() {
+            let address = start + i;
+            if address < MEMORY_SIZE && !is_read_only(address as u16) {
+                self.mem[address] = *byte;
+            }
+        }
+        if let Some(sm) = wrote_last_byte_of {
+            self.set_mailbox_full(sm, true);
+        }
+        self.on_write(address, data, &old);
+        true
+    }
+
+    fn on_write(&mut self, address: u16, data: &[u8], old: &[u8]) {
+        let covers = |register: u16| {
+            address <= register && (register as usize) < address as usize + data.len()
+        };
+        let byte_at = |register: u16| data[(register - address) as usize];
+
+        if covers(AlControl::ADDRESS) {
+            self.request_al_state(byte_at(AlControl::ADDRESS));
+        }
+        if covers(SiiControl::ADDRESS) {
+            let write_enable = byte_at(SiiControl::ADDRESS).get_bit(0);
+            self.mem[SiiControl::ADDRESS as usize].set_bit(0, write_enable);
+        }
+        if covers(SiiControl::ADDRESS + 1) {
+            self.sii_command(byte_at(SiiControl::ADDRESS + 1));
+        }
+        if covers(DcRecieveTime::ADDRESS) {
+            for (port, time) in self.port_receive_times.into_iter().enumerate() {
+                let address = DcRecieveTime::ADDRESS + port as u16 * 4;
+                self.reg_mut(address, 4)
+                    .copy_from_slice(&time.to_le_bytes());
+            }
+        }
+        if covers(DcSystemTime::ADDRESS) {
+            let offset = (DcSystemTime::ADDRESS - address) as usize;
+            let length = (data.len() - offset).min(8);
+            if 4 <= length {
+                let mut received = [0; 8];
+                received[..length].copy_from_slice(&data[offset..offset + length]);
+                self.compare_system_time(u64::from_le_bytes(received), length == 8);
+            }
+        }
+        for sm in 0..NUM_SM {
+            let activation = SyncManagerActivation::ADDRESS + (sm * 8) as u16;
+            if covers(activation) {
+                let index = (activation - address) as usize;
+                let (old, new) = (old.get(index).copied().unwrap_or_default(), data[index]);
+                self.on_sm_activation(sm, old, new);
+            }
+        }
+    }
+
+    fn on_sm_activation(&mut self, sm: usize, old: u8, new: u8) {
+        let status = SyncManagerStatus::ADDRESS as usize + sm * 8;
+        if !new.get_bit(0) {
+            self.mem[status] = 0;
+            return;
+        }
+        // The master requests to repeat the last mailbox response.
+        if old.get_bit(1) != new.get_bit(1) {
+            self.repeat_mailbox_response(sm);
+            let pdi_control = SyncManagerPdiControl::ADDRESS as usize + sm * 8;
+            self.mem[pdi_control].set_bit(1, new.get_bit(1));
+        }
+    }
+
+    /// Puts the last mailbox response in the tx mailbox again.
+    fn repeat_mailbox_response(&mut self, sm: usize) {
+        if let (Some((start, end)), Some(response)) = (
+            self.mailbox_area(sm, SM_DIRECTION_READ),
+            self.last_mailbox_response.clone(),
+        ) {
+            let length = response.len().min(end - start);
+            self.mem[start..start + length].copy_from_slice(&response[..length]);
+            self.set_mailbox_full(sm, true);
+        }
+    }
+
+    fn request_al_state(&mut self, control: u8) {
+        let control = AlControl([control, 0]);
+        let has_error = self.mem[AlStatus::ADDRESS as usize].get_bit(4);
+        if has_error {
+            if !control.acknowledge() {
+                return;
+            }
+            self.set_al_status(self.al_state, None);
+        }
+        let requested = AlState::from(control.state());
+        match self.check_al_state_transition(requested) {
+            Ok(()) => {
+                if requested == AlState::Init {
+                    self.mailbox_responses.clear();
+                    self.last_mailbox_response = None;
+                    self.last_request_count = 0;
+                    self.response_count = 0;
+                    self.sdo_transfer = SegmentedTransfer::None;
+                }
+                self.al_state = requested;
+                self.set_al_status(requested, None);
+            }
+            Err(code) => self.set_al_status(self.al_state, Some(code)),
+        }
+    }
+
+    fn check_al_state_transition(&self, requested: AlState) -> Result<(), u16> {
+        use AlState::*;
+        match (self.al_state, requested) {
+            (_, Init) => Ok(()),
+            (Init, PreOperational) => self.check_mailbox_config(
+                sii::StandardRxMailboxOffset::ADDRESS,
+                sii::StandardTxMailboxOffset::ADDRESS,
+            ),
+            (Init, Bootstrap) => {
+                if self.sii[sii::BootstrapRxMailboxSize::ADDRESS as usize] == 0 {
+                    Err(BOOTSTRAP_NOT_SUPPORTED)
+                } else {
+                    self.check_mailbox_config(
+                        sii::BootstrapRxMailboxOffset::ADDRESS,
+                        sii::BootstrapTxMailboxOffset::ADDRESS,
+                    )
+                }
+            }
+            (Bootstrap, Bootstrap) => Ok(()),
+            (PreOperational | SafeOperational | Operational, PreOperational) => Ok(()),
+            (PreOperational, SafeOperational) => {
+                self.check_process_data_config(0x1C12, SM_DIRECTION_WRITE)
+                    .map_err(|_| INVALID_OUTPUT_CONFIGURATION)?;
+                self.check_process_data_config(0x1C13, SM_DIRECTION_READ)
+                    .map_err(|_| INVALID_INPUT_CONFIGURATION)
+            }
+            (SafeOperational | Operational, SafeOperational) => Ok(()),
+            (SafeOperational | Operational, Operational) => Ok(()),
+            (_, InvalidOrMixed) => Err(UNKNOWN_REQUESTED_STATE),
+            _ => Err(INVALID_REQUESTED_STATE_CHANGE),
+        }
+    }
+
+    /// SM0 and SM1 have to match the mailbox in the SII. An ESC without mailbox, e.g. a coupler, is not checked.
+    fn check_mailbox_config(&self, rx_offset_word: u16, tx_offset_word: u16) -> Result<(), u16> {
+        let rx_size = self.sii[rx_offset_word as usize + 1];
+        let tx_size = self.sii[tx_offset_word as usize + 1];
+        if rx_size == 0 && tx_size == 0 {
+            return Ok(());
+        }
+        for (sm, offset_word, direction) in [
+            (0, rx_offset_word, SM_DIRECTION_WRITE),
+            (1, tx_offset_word, SM_DIRECTION_READ),
+        ] {
+            let offset = self.sii[offset_word as usize] as usize;
+            let size = self.sii[offset_word as usize + 1] as usize;
+            if self.mailbox_area(sm, direction) != Some((offset, offset + size)) {
+                return Err(INVALID_MAILBOX_CONFIGURATION);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_process_data_config(&self, assign_index: u16, direction: u8) -> Result<(), ()> {
+        let bit_length = self
+            .od
+            .assigned_bit_length(assign_index)
+            .unwrap_or_default();
+        let size = bit_length.div_ceil(8);
+        match self.process_data_area(direction) {
+            Some((start, end)) if end - start == size => Ok(()),
+            None if size == 0 => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    fn set_al_status(&mut self, state: AlState, error_code: Option<u16>) {
+        let mut status = state as u8;
+        status.set_bit(4, error_code.is_some());
+        self.mem[AlStatus::ADDRESS as usize] = status;
+        self.reg_mut(AL_STATUS_CODE_ADDRESS, 2)
+            .copy_from_slice(&error_code.unwrap_or_default().to_le_bytes());
+    }
+
+    fn sii_command(&mut self, command: u8) {
+        let control = SiiControl::ADDRESS as usize + 1;
+        self.mem[control] &= 0b0000_1000;
+        if self.mem[SiiAccess::ADDRESS as usize].get_bit(0) {
+            // The PDI owns the SII.
+            self.mem[control].set_bit(5, true);
+            return;
+        }
+        let mut address = [0; 4];
+        self.read_raw(SiiAddress::ADDRESS, &mut address);
+        let address = u32::from_le_bytes(address) as usize;
+        if command.get_bit(0) {
+            // read 4 words
+            for i in 0..4 {
+                let word = self.sii.get(address + i).copied().unwrap_or(0xFFFF);
+                self.reg_mut(SiiData::ADDRESS + i as u16 * 2, 2)
+                    .copy_from_slice(&word.to_le_bytes());
+            }
+        } else if command.get_bit(1) {
+            let write_enabled = self.mem[SiiControl::ADDRESS as usize].get_bit(0);
+            if let (true, Some(word)) = (write_enabled, self.sii.get_mut(address)) {
+                let data = &self.mem[SiiData::ADDRESS as usize..SiiData::ADDRESS as usize + 2];
+                *word = u16::from_le_bytes([data[0], data[1]]);
+            } else {
+                self.mem[control].set_bit(5, true);
+            }
+        } else if command.get_bit(2) {
+            self.reload_sii();
+        }
+    }
+
+    fn system_time(&self) -> u64 {
+        let offset = u64::from_le_bytes(self.reg_array(DcSystemTimeOffset::ADDRESS));
+        self.local_time_ns
+            .wrapping_add(offset)
+            .wrapping_add(self.drift_correction_ns)
+    }
+
+    fn update_system_time(&mut self) {
+        let system_time = self.system_time();
+        self.reg_mut(DcSystemTime::ADDRESS, 8)
+            .copy_from_slice(&system_time.to_le_bytes());
+    }
+
+    /// The written system time is the reference clock. The local clock follows it immediately.
+    fn compare_system_time(&mut self, received: u64, is_64bit: bool) {
+        let delay = u32::from_le_bytes(self.reg_array(DcSystemTimeTransmissionDelay::ADDRESS));
+        let received = received.wrapping_add(delay as u64);
+        let own = self.system_time();
+        let diff = if is_64bit {
+            received.wrapping_sub(own) as i64
+        } else {
+            (received as u32).wrapping_sub(own as u32) as i32 as i64
+        };
+        self.drift_correction_ns = self.drift_correction_ns.wrapping_add(diff as u64);
+        let mut delta = (diff.unsigned_abs() as u32).min(0x7FFF_FFFF);
+        delta.set_bit(31, diff < 0);
+        self.reg_mut(DcSystemTimeDelta::ADDRESS, 4)
+            .copy_from_slice(&delta.to_le_bytes());
+        self.update_system_time();
+    }
+
+    fn station_address(&self) -> u16 {
+        u16::from_le_bytes(self.reg_array(FixedStationAddress::ADDRESS))
+    }
+
+    fn station_alias(&self) -> u16 {
+        u16::from_le_bytes(self.reg_array(STATION_ALIAS_ADDRESS))
+    }
+
+    fn alias_enabled(&self) -> bool {
+        self.mem[DlControl::ADDRESS as usize + 3].get_bit(0)
+    }
+
+    /// (start, end) of an enabled sync manager.
+    fn sm_area(&self, sm: usize) -> Option<(usize, usize, SyncManagerControl<[u8; 5]>)> {
+        let address = SyncManagerControl::ADDRESS as usize + sm * 8;
+        let activation = self.mem[SyncManagerActivation::ADDRESS as usize + sm * 8];
+        let control = SyncManagerControl(self.reg_array(address as u16));
+        let start = control.physical_start_address() as usize;
+        let length = control.length() as usize;
+        if activation.get_bit(0) && length != 0 {
+            Some((start, start + length, control))
+        } else {
+            None
+        }
+    }
+
+    fn mailbox_area(&self, sm: usize, direction: u8) -> Option<(usize, usize)> {
+        match self.sm_area(sm) {
+            Some((start, end, control))
+                if control.buffer_type() == SM_BUFFER_TYPE_MAILBOX
+                    && control.direction() == direction =>
+            {
+                Some((start, end))
+            }
+            _ => None,
+        }
+    }
+
+    fn process_data_area(&self, direction: u8) -> Option<(usize, usize)> {
+        (0..NUM_SM).find_map(|sm| match self.sm_area(sm) {
+            Some((start, end, control))
+                if control.buffer_type() != SM_BUFFER_TYPE_MAILBOX
+                    && control.direction() == direction =>
+            {
+                Some((start, end))
+            }
+            _ => None,
+        })
+    }
+
+    fn is_mailbox_full(&self, sm: usize) -> bool {
+        self.mem[SyncManagerStatus::ADDRESS as usize + sm * 8].get_bit(3)
+    }
+
+    fn set_mailbox_full(&mut self, sm: usize, is_full: bool) {
+        self.mem[SyncManagerStatus::ADDRESS as usize + sm * 8].set_bit(3, is_full);
+    }
+
+    fn update_inputs(&mut self) {
+        if let Some((start, end)) = self.process_data_area(SM_DIRECTION_READ) {
+            let mut buf = vec![0; end - start];
+            self.od.pack_process_data(0x1C13, &mut buf);
+            self.mem[start..end].copy_from_slice(&buf);
+        }
+    }
+
+    fn update_outputs(&mut self) {
+        if let Some((start, end)) = self.process_data_area(SM_DIRECTION_WRITE) {
+            self.od.unpack_process_data(0x1C12, &self.mem[start..end]);
+        }
+    }
+
+    fn service_mailbox(&mut self) {
+        if self.al_state == AlState::Init || self.al_state == AlState::InvalidOrMixed {
+            return;
+        }
+        let tx_area = (0..NUM_SM).find_map(|sm| {
+            self.mailbox_area(sm, SM_DIRECTION_READ)
+                .map(|area| (sm, area))
+        });
+        let rx_area = (0..NUM_SM).find_map(|sm| {
+            self.mailbox_area(sm, SM_DIRECTION_WRITE)
+                .map(|area| (sm, area))
+        });
+        if let (Some((rx_sm, (start, end))), Some((_, (tx_start, tx_end)))) = (rx_area, tx_area) {
+            if self.is_mailbox_full(rx_sm) {
+                let request = self.mem[start..end].to_vec();
+                self.set_mailbox_full(rx_sm, false);
+                // A request which the master has sent again has the same count, and is discarded.
+                let count = MailboxFrame(&request).count();
+                let is_repeated = count != 0 && count == self.last_request_count;
+                self.last_request_count = count;
+                if !is_repeated {
+                    if let Ok(Mailbox::CoE((coe_index, CoE::SdoReq(SdoReq::Abort(abort_code))))) =
+                        MailboxFrame(&request[..]).mailbox()
+                    {
+                        self.sdo_aborts
+                            .push((coe_index.index, coe_index.sub_index, abort_code));
+                    }
+                    let responses = mailbox::process_request(
+                        &mut self.od,
+                        ProtocolServers {
+                            aoe: &mut self.aoe,
+                            eoe: &mut self.eoe,
+                            foe: &mut self.foe,
+                            soe: &mut self.soe,
+                            voe: self.voe_handler.as_mut(),
+                        },
+                        self.al_state,
+                        &request,
+                        tx_end - tx_start,
+                        &mut self.sdo_transfer,
+                    );
+                    self.mailbox_responses.extend(responses);
+                }
+            }
+        }
+        if let Some((tx_sm, (start, end))) = tx_area {
+            if !self.is_mailbox_full(tx_sm) {
+                if let Some(mut response) = self.mailbox_responses.pop_front() {
+                    self.response_count = self.response_count % 7 + 1;
+                    MailboxFrame(&mut response[..]).set_count(self.response_count);
+                    let length = response.len().min(end - start);
+                    self.mem[start..end].fill(0);
+                    self.mem[start..start + length].copy_from_slice(&response[..length]);
+                    self.set_mailbox_full(tx_sm, true);
+                    self.last_mailbox_response = Some(response);
+                }
+            }
+        }
+    }
+
+    fn reg_mut(&mut self, address: u16, length: usize) -> &mut [u8] {
+        &mut self.mem[address as usize..address as usize + length]
+    }
+
+    fn reg_array<const N: usize>(&self, address: u16) -> [u8; N] {
+        let mut buf = [0; N];
+        self.read_raw(address, &mut buf);
+        buf
+    }
+}
+
+/// Registers which can not be written through EtherCAT.
+fn is_read_only(address: u16) -> bool {
+    let sm_end = SyncManagerControl::ADDRESS + (NUM_SM * 8) as u16;
+    if (SyncManagerControl::ADDRESS..sm_end).contains(&address) {
+        let offset = (address - SyncManagerControl::ADDRESS) % 8;
+        return offset == 5 || offset == 7;
+    }
+    matches!(
+        address,
+        0x0000..=0x000F // DL information
+        | 0x0110..=0x0111 // DL status
+        | 0x0130..=0x0135 // AL status
+        | 0x0140..=0x0141 // PDI control
+        | 0x0501 // SII access (PDI)
+        | 0x0502..=0x0503 // SII control
+        | 0x0900..=0x0917 // receive time, system time
+        | 0x092C..=0x092F // system time difference
+    )
+}
+
+/// CRC-8 (x^8 + x^2 + x + 1, initial value 0xFF) of the first 7 words.
+fn sii_checksum(sii: &[u16]) -> u8 {
+    let mut crc = 0xFF_u8;
+    for byte in sii[..7].iter().flat_map(|word| word.to_le_bytes()) {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
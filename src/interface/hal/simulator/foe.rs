@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use crate::frame::{FoE, FoeErrorCode, FoeFrame, Mailbox, MailboxFrame, MailboxType};
+
+use super::mailbox::new_mailbox;
+
+/// FoE transfer in progress.
+#[derive(Debug, Clone, Default)]
+enum FoeTransfer {
+    #[default]
+    None,
+    /// `data` is the whole file.
+    Read { data: Vec<u8> },
+    /// `data` is what has been written.
+    Write {
+        file_name: Vec<u8>,
+        packet_number: u32,
+        data: Vec<u8>,
+    },
+}
+
+/// Files of a virtual ESC which the master reads and writes with FoE.
+#[derive(Debug, Default)]
+pub struct FoeServer {
+    files: BTreeMap<Vec<u8>, Vec<u8>>,
+    password: u32,
+    busy_count: usize,
+    transfer: FoeTransfer,
+}
+
+impl FoeServer {
+    pub fn file(&self, file_name: &[u8]) -> Option<&[u8]> {
+        self.files.get(file_name).map(|data| data.as_slice())
+    }
+
+    pub fn insert_file(&mut self, file_name: &[u8], data: &[u8]) {
+        self.files.insert(file_name.to_vec(), data.to_vec());
+    }
+
+    /// Requests with another password are denied. The default is 0.
+    pub fn set_password(&mut self, password: u32) {
+        self.password = password;
+    }
+
+    /// The next `count` requests are answered with busy.
+    pub fn set_busy(&mut self, count: usize) {
+        self.busy_count = count;
+    }
+
+    /// `rx_capacity` is the data size of a packet which fills the rx mailbox.
+    /// Returns no response to the last acknowledge of a read and to an error request.
+    pub(super) fn process_request(
+        &mut self,
+        request: &MailboxFrame<&[u8]>,
+        foe: FoE,
+        mailbox_size: usize,
+        rx_capacity: usize,
+    ) -> Option<Vec<u8>> {
+        if let FoE::Error { .. } = foe {
+            self.transfer = FoeTransfer::None;
+            return None;
+        }
+        if 0 < self.busy_count {
+            self.busy_count -= 1;
+            let busy = Mailbox::FoE(FoE::Busy {
+                done: 0,
+                entire: 100,
+            });
+            return Some(foe_mailbox(request, &busy, mailbox_size));
+        }
+        let tx_capacity = mailbox_size - MailboxFrame::HEADER_SIZE - FoeFrame::HEADER_SIZE;
+        let acknowledged = match foe {
+            FoE::ReadReq {
+                password,
+                file_name,
+            } => match self.check_request(password, file_name) {
+                Ok(data) => {
+                    self.transfer = FoeTransfer::Read {
+                        data: data.to_vec(),
+                    };
+                    0
+                }
+                Err(code) => return Some(foe_mailbox(request, &self.error(code), mailbox_size)),
+            },
+            FoE::WriteReq {
+                password,
+                file_name,
+            } => {
+                let response = if password != self.password {
+                    self.error(FoeErrorCode::NoRights)
+                } else {
+                    self.transfer = FoeTransfer::Write {
+                        file_name: file_name.to_vec(),
+                        packet_number: 0,
+                        data: Vec::new(),
+                    };
+                    Mailbox::new_foe_ack(0)
+                };
+                return Some(foe_mailbox(request, &response, mailbox_size));
+            }
+            FoE::Data {
+                packet_number,
+                data,
+            } => {
+                let response = self.write_packet(packet_number, data, rx_capacity);
+                return Some(foe_mailbox(request, &response, mailbox_size));
+            }
+            FoE::Ack(packet_number) => packet_number,
+            _ => {
+                return Some(foe_mailbox(
+                    request,
+                    &self.error(FoeErrorCode::Illegal),
+                    mailbox_size,
+                ))
+            }
+        };
+        // The packet after the acknowledged one is sent.
+        // No packet follows a packet shorter than the capacity.
+        let offset = acknowledged as usize * tx_capacity;
+        let response = match &self.transfer {
+            FoeTransfer::Read { data } if data.len() < offset => {
+                self.transfer = FoeTransfer::None;
+                return None;
+            }
+            FoeTransfer::Read { data } => {
+                let packet = &data[offset..data.len().min(offset + tx_capacity)];
+                Mailbox::new_foe_data(acknowledged + 1, packet)
+            }
+            _ => self.error(FoeErrorCode::Illegal),
+        };
+        Some(foe_mailbox(request, &response, mailbox_size))
+    }
+
+    fn check_request(&self, password: u32, file_name: &[u8]) -> Result<&[u8], FoeErrorCode> {
+        if password != self.password {
+            return Err(FoeErrorCode::NoRights);
+        }
+        self.file(file_name).ok_or(FoeErrorCode::NotFound)
+    }
+
+    fn write_packet(&mut self, number: u32, packet: &[u8], capacity: usize) -> Mailbox<'static> {
+        let FoeTransfer::Write {
+            file_name,
+            packet_number,
+            data,
+        } = &mut self.transfer
+        else {
+            return self.error(FoeErrorCode::Illegal);
+        };
+        if number != *packet_number + 1 {
+            return self.error(FoeErrorCode::PacketNumberWrong);
+        }
+        *packet_number = number;
+        data.extend_from_slice(packet);
+        if packet.len() < capacity {
+            let file_name = core::mem::take(file_name);
+            let data = core::mem::take(data);
+            self.files.insert(file_name, data);
+            self.transfer = FoeTransfer::None;
+        }
+        Mailbox::new_foe_ack(number)
+    }
+
+    /// Ends the transfer.
+    fn error(&mut self, code: FoeErrorCode) -> Mailbox<'static> {
+        self.transfer = FoeTransfer::None;
+        Mailbox::new_foe_error(code)
+    }
+}
+
+fn foe_mailbox(request: &MailboxFrame<&[u8]>, foe: &Mailbox, mailbox_size: usize) -> Vec<u8> {
+    let mut response = new_mailbox(
+        MailboxType::FoE,
+        request.count(),
+        mailbox_size - MailboxFrame::HEADER_SIZE,
+    );
+    let mut frame = MailboxFrame(&mut response[..]);
+    frame.set_mailbox(foe).unwrap();
+    let length = frame.length() as usize;
+    response.truncate(MailboxFrame::HEADER_SIZE + length);
+    response
+}
@@ -0,0 +1,520 @@
+use crate::frame::{
+    AbortCode, CoE, CoeFrame, CoeIndex, CoeServiceType, EmmergencyFrame, FoeFrame, Mailbox,
+    MailboxErrorDetail, MailboxErrorFrame, MailboxFrame, MailboxType, OdListType, SdoFrame,
+    SdoInfo, SdoInfoFrame, SdoInfoOpCode, SdoReq, SdoSegmentFrame,
+};
+use crate::slave::AlState;
+
+use super::{AoeServer, EoeServer, FoeServer, ObjectDictionary, SoeServer, VoeHandler};
+
+const SDO_RESPONSE_LENGTH: usize = CoeFrame::HEADER_SIZE + SdoFrame::HEADER_SIZE + 4;
+
+/// Segmented SDO transfer in progress.
+#[derive(Debug, Clone, Default)]
+pub(super) enum SegmentedTransfer {
+    #[default]
+    None,
+    /// `data` is the rest to be uploaded.
+    Upload {
+        index: u16,
+        sub_index: u8,
+        data: Vec<u8>,
+        toggle: bool,
+    },
+    /// `data` is what has been downloaded.
+    Download {
+        index: u16,
+        sub_index: u8,
+        complete_access: bool,
+        complete_size: usize,
+        data: Vec<u8>,
+        toggle: bool,
+    },
+}
+
+impl SegmentedTransfer {
+    fn index(&self) -> (u16, u8) {
+        match self {
+            Self::None => (0, 0),
+            Self::Upload {
+                index, sub_index, ..
+            }
+            | Self::Download {
+                index, sub_index, ..
+            } => (*index, *sub_index),
+        }
+    }
+}
+
+/// Servers of the mailbox protocols other than CoE.
+pub(super) struct ProtocolServers<'a> {
+    pub aoe: &'a mut AoeServer,
+    pub eoe: &'a mut EoeServer,
+    pub foe: &'a mut FoeServer,
+    pub soe: &'a mut SoeServer,
+    pub voe: Option<&'a mut VoeHandler>,
+}
+
+/// Serves a mailbox written by the master.
+/// Returns the whole response mailboxes (header and data). Most requests have one response.
+pub(super) fn process_request(
+    od: &mut ObjectDictionary,
+    servers: ProtocolServers,
+    al_state: AlState,
+    request: &[u8],
+    mailbox_size: usize,
+    transfer: &mut SegmentedTransfer,
+) -> Vec<Vec<u8>> {
+    let foe_rx_capacity = request.len() - MailboxFrame::HEADER_SIZE - FoeFrame::HEADER_SIZE;
+    let request_header = MailboxFrame(request);
+    let length = request_header.length() as usize;
+    let request = request.get(..MailboxFrame::HEADER_SIZE + length);
+    let request = if let Some(request) = request {
+        MailboxFrame(request)
+    } else {
+        return vec![error_response(
+            &request_header,
+            MailboxErrorDetail::InvalidSize,
+        )];
+    };
+
+    match (request.mb_type(), request.mailbox()) {
+        (MailboxType::CoE, Ok(Mailbox::CoE((_, CoE::SdoInfo(sdo_info))))) => {
+            sdo_info_response(od, &request, sdo_info, mailbox_size)
+        }
+        (MailboxType::CoE, _) => coe_response(od, al_state, &request, mailbox_size, transfer)
+            .into_iter()
+            .collect(),
+        (MailboxType::AoE, Ok(Mailbox::AoE(aoe_request))) => {
+            servers
+                .aoe
+                .process_request(&request, aoe_request, mailbox_size)
+        }
+        (MailboxType::EoE, Ok(Mailbox::EoE(eoe_request))) => servers
+            .eoe
+            .process_request(&request, eoe_request, mailbox_size)
+            .into_iter()
+            .collect(),
+        (MailboxType::FoE, Ok(Mailbox::FoE(foe_request))) => servers
+            .foe
+            .process_request(&request, foe_request, mailbox_size, foe_rx_capacity)
+            .into_iter()
+            .collect(),
+        (MailboxType::SoE, Ok(Mailbox::SoE(soe_request))) => {
+            servers
+                .soe
+                .process_request(&request, soe_request, mailbox_size)
+        }
+        (MailboxType::VoE, Ok(Mailbox::VoE(data))) if servers.voe.is_some() => {
+            let handler = servers.voe.unwrap();
+            handler(data)
+                .map(|response| voe_mailbox(request.count(), &response))
+                .into_iter()
+                .collect()
+        }
+        _ => vec![error_response(
+            &request,
+            MailboxErrorDetail::UnsupportedProtocol,
+        )],
+    }
+}
+
+fn voe_mailbox(count: u8, data: &[u8]) -> Vec<u8> {
+    let mut mailbox = new_mailbox(MailboxType::VoE, count, data.len());
+    mailbox[MailboxFrame::HEADER_SIZE..].copy_from_slice(data);
+    mailbox
+}
+
+/// The response is split into fragments if it does not fit in the mailbox.
+fn sdo_info_response(
+    od: &ObjectDictionary,
+    request: &MailboxFrame<&[u8]>,
+    sdo_info: SdoInfo,
+    mailbox_size: usize,
+) -> Vec<Vec<u8>> {
+    let result = match sdo_info {
+        SdoInfo::GetOdListReq(list_type) => {
+            od_list(od, list_type).map(|data| (SdoInfoOpCode::GetOdListRes, data))
+        }
+        SdoInfo::GetObjectDescriptionReq(index) => od
+            .object_description(index)
+            .map(|data| (SdoInfoOpCode::GetObjectDescriptionRes, data)),
+        SdoInfo::GetEntryDescriptionReq {
+            index, sub_index, ..
+        } => od
+            .entry_description(index, sub_index)
+            .map(|data| (SdoInfoOpCode::GetEntryDescriptionRes, data)),
+        _ => Err(AbortCode::UnknownClient),
+    };
+    let (op_code, data) = match result {
+        Ok(response) => response,
+        Err(abort_code) => {
+            let sdo_info = SdoInfo::Error(abort_code);
+            return vec![sdo_info_mailbox(request, &sdo_info, mailbox_size)];
+        }
+    };
+    let capacity = mailbox_size
+        - MailboxFrame::HEADER_SIZE
+        - CoeFrame::HEADER_SIZE
+        - SdoInfoFrame::HEADER_SIZE;
+    let num_fragments = data.len().div_ceil(capacity).max(1);
+    (0..num_fragments)
+        .map(|i| {
+            let fragment = &data[i * capacity..data.len().min((i + 1) * capacity)];
+            let sdo_info = SdoInfo::Response {
+                op_code,
+                incomplete: i + 1 < num_fragments,
+                fragments_left: (num_fragments - i - 1) as u16,
+                data: fragment,
+            };
+            sdo_info_mailbox(request, &sdo_info, mailbox_size)
+        })
+        .collect()
+}
+
+fn od_list(od: &ObjectDictionary, list_type: OdListType) -> Result<Vec<u8>, AbortCode> {
+    let mut data = (list_type as u16).to_le_bytes().to_vec();
+    let num_objects = od.indexes().count() as u16;
+    match list_type {
+        // The other lists are empty.
+        OdListType::Length => {
+            for length in [num_objects, 0, 0, 0, 0] {
+                data.extend_from_slice(&length.to_le_bytes());
+            }
+        }
+        OdListType::All => {
+            for index in od.indexes() {
+                data.extend_from_slice(&index.to_le_bytes());
+            }
+        }
+        OdListType::Other => return Err(AbortCode::GeneralError),
+        _ => {}
+    }
+    Ok(data)
+}
+
+fn sdo_info_mailbox(
+    request: &MailboxFrame<&[u8]>,
+    sdo_info: &SdoInfo,
+    mailbox_size: usize,
+) -> Vec<u8> {
+    let message = Mailbox::CoE((CoeIndex::default(), CoE::SdoInfo(*sdo_info)));
+    encode_mailbox(request.count(), &message, mailbox_size)
+}
+
+fn coe_response(
+    od: &mut ObjectDictionary,
+    al_state: AlState,
+    request: &MailboxFrame<&[u8]>,
+    mailbox_size: usize,
+    transfer: &mut SegmentedTransfer,
+) -> Option<Vec<u8>> {
+    let mailbox = if let Ok(mailbox) = request.mailbox() {
+        mailbox
+    } else {
+        return Some(error_response(request, MailboxErrorDetail::SizeTooShort));
+    };
+    let (coe_index, coe) = if let Mailbox::CoE(coe) = mailbox {
+        coe
+    } else {
+        return None;
+    };
+    let sdo_req = if let CoE::SdoReq(sdo_req) = coe {
+        sdo_req
+    } else {
+        return Some(error_response(
+            request,
+            MailboxErrorDetail::ServiceNotSupported,
+        ));
+    };
+    // Segments do not have an index.
+    let (index, sub_index) = match sdo_req {
+        SdoReq::DownLoadSegment { .. } | SdoReq::UploadSegment { .. } => transfer.index(),
+        _ => (coe_index.index, coe_index.sub_index),
+    };
+    let complete_access = coe_index.complete_access;
+
+    let mut response = new_mailbox(MailboxType::CoE, request.count(), SDO_RESPONSE_LENGTH);
+    let result = match sdo_req {
+        SdoReq::DownLoad(data) => {
+            *transfer = SegmentedTransfer::None;
+            write_object(od, index, sub_index, complete_access, data, al_state)
+                .map(|_| set_sdo_header(&mut response, CoeServiceType::SdoRes, 3, index, sub_index))
+        }
+        SdoReq::Upload if complete_access => {
+            *transfer = SegmentedTransfer::None;
+            od.sdo_read_complete(index, sub_index).and_then(|data| {
+                upload_response(
+                    &mut response,
+                    index,
+                    sub_index,
+                    &data,
+                    mailbox_size,
+                    transfer,
+                )
+            })
+        }
+        SdoReq::Upload => {
+            *transfer = SegmentedTransfer::None;
+            od.sdo_read(index, sub_index).and_then(|data| {
+                upload_response(
+                    &mut response,
+                    index,
+                    sub_index,
+                    data,
+                    mailbox_size,
+                    transfer,
+                )
+            })
+        }
+        SdoReq::SegmentedDownLoad {
+            complete_size,
+            data,
+        } => {
+            *transfer = SegmentedTransfer::Download {
+                index,
+                sub_index,
+                complete_access,
+                complete_size: complete_size as usize,
+                data: data.to_vec(),
+                toggle: false,
+            };
+            set_sdo_header(&mut response, CoeServiceType::SdoRes, 3, index, sub_index);
+            Ok(())
+        }
+        SdoReq::DownLoadSegment { toggle, last, data } => {
+            download_segment(od, al_state, &mut response, transfer, toggle, last, data)
+        }
+        SdoReq::UploadSegment { toggle } => {
+            upload_segment(&mut response, transfer, toggle, mailbox_size)
+        }
+        // The master aborted the transfer. No response.
+        SdoReq::Abort(_) => {
+            *transfer = SegmentedTransfer::None;
+            return None;
+        }
+        SdoReq::Other(_) => Err(AbortCode::UnknownClient),
+    };
+
+    if let Err(abort_code) = result {
+        *transfer = SegmentedTransfer::None;
+        let message = Mailbox::new_sdo_abort_request(index, sub_index, abort_code);
+        response = encode_mailbox(request.count(), &message, mailbox_size);
+    }
+    Some(response)
+}
+
+fn upload_response(
+    response: &mut Vec<u8>,
+    index: u16,
+    sub_index: u8,
+    data: &[u8],
+    mailbox_size: usize,
+    transfer: &mut SegmentedTransfer,
+) -> Result<(), AbortCode> {
+    set_sdo_header(response, CoeServiceType::SdoRes, 2, index, sub_index);
+    let sdo_offset = MailboxFrame::HEADER_SIZE + CoeFrame::HEADER_SIZE;
+    let data_offset = sdo_offset + SdoFrame::HEADER_SIZE;
+    let mut sdo = SdoFrame(&mut response[sdo_offset..]);
+    sdo.set_size_indicator(true);
+    if data.len() <= 4 {
+        // expedited
+        sdo.set_transfer_type(true);
+        sdo.set_data_set_size(4 - data.len() as u8);
+        response[data_offset..data_offset + data.len()].copy_from_slice(data);
+    } else {
+        // normal, and segments follow if the data do not fit in the mailbox.
+        let size = data.len().min(mailbox_size.saturating_sub(data_offset + 4));
+        if size < data.len() {
+            *transfer = SegmentedTransfer::Upload {
+                index,
+                sub_index,
+                data: data[size..].to_vec(),
+                toggle: false,
+            };
+        }
+        sdo.set_transfer_type(false);
+        response[data_offset..].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        response.extend_from_slice(&data[..size]);
+        let length = response.len() - MailboxFrame::HEADER_SIZE;
+        MailboxFrame(&mut response[..]).set_length(length as u16);
+    }
+    Ok(())
+}
+
+fn upload_segment(
+    response: &mut Vec<u8>,
+    transfer: &mut SegmentedTransfer,
+    toggle: bool,
+    mailbox_size: usize,
+) -> Result<(), AbortCode> {
+    let (data, expected_toggle) = match transfer {
+        SegmentedTransfer::Upload { data, toggle, .. } => (data, toggle),
+        _ => return Err(AbortCode::UnknownClient),
+    };
+    if toggle != *expected_toggle {
+        return Err(AbortCode::NoToggleBitChange);
+    }
+    let capacity = mailbox_size
+        - MailboxFrame::HEADER_SIZE
+        - CoeFrame::HEADER_SIZE
+        - SdoSegmentFrame::HEADER_SIZE;
+    let segment: Vec<u8> = data.drain(..data.len().min(capacity)).collect();
+    let last = data.is_empty();
+    *expected_toggle = !toggle;
+    if last {
+        *transfer = SegmentedTransfer::None;
+    }
+    // upload segment response
+    set_segment(response, 0, toggle, last, &segment);
+    Ok(())
+}
+
+fn download_segment(
+    od: &mut ObjectDictionary,
+    al_state: AlState,
+    response: &mut Vec<u8>,
+    transfer: &mut SegmentedTransfer,
+    toggle: bool,
+    last: bool,
+    segment: &[u8],
+) -> Result<(), AbortCode> {
+    let (index, sub_index, complete_access, complete_size, data, expected_toggle) = match transfer {
+        SegmentedTransfer::Download {
+            index,
+            sub_index,
+            complete_access,
+            complete_size,
+            data,
+            toggle,
+        } => (
+            *index,
+            *sub_index,
+            *complete_access,
+            *complete_size,
+            data,
+            toggle,
+        ),
+        _ => return Err(AbortCode::UnknownClient),
+    };
+    if toggle != *expected_toggle {
+        return Err(AbortCode::NoToggleBitChange);
+    }
+    data.extend_from_slice(segment);
+    *expected_toggle = !toggle;
+    if last {
+        let data = core::mem::take(data);
+        *transfer = SegmentedTransfer::None;
+        if data.len() != complete_size {
+            return Err(AbortCode::ParameterLengthMismatch);
+        }
+        write_object(od, index, sub_index, complete_access, &data, al_state)?;
+    }
+    // download segment response
+    set_segment(response, 1, toggle, false, &[]);
+    Ok(())
+}
+
+fn write_object(
+    od: &mut ObjectDictionary,
+    index: u16,
+    sub_index: u8,
+    complete_access: bool,
+    data: &[u8],
+    al_state: AlState,
+) -> Result<(), AbortCode> {
+    if complete_access {
+        od.sdo_write_complete(index, sub_index, data, al_state)
+    } else {
+        od.sdo_write(index, sub_index, data, al_state)
+    }
+}
+
+fn set_segment(
+    response: &mut Vec<u8>,
+    command_specifier: u8,
+    toggle: bool,
+    last: bool,
+    data: &[u8],
+) {
+    let sdo_offset = MailboxFrame::HEADER_SIZE + CoeFrame::HEADER_SIZE;
+    let data_offset = sdo_offset + SdoSegmentFrame::HEADER_SIZE;
+    response.resize(
+        data_offset + data.len().max(SdoSegmentFrame::MIN_DATA_SIZE),
+        0,
+    );
+    let mut coe = CoeFrame(&mut response[MailboxFrame::HEADER_SIZE..]);
+    coe.set_number(0);
+    coe.set_coe_service_type(CoeServiceType::SdoRes);
+    let mut segment = SdoSegmentFrame(&mut response[sdo_offset..]);
+    segment.set_command_specifier(command_specifier);
+    segment.set_toggle(toggle);
+    segment.set_last_segment(last);
+    segment.set_segment_data_size(SdoSegmentFrame::MIN_DATA_SIZE.saturating_sub(data.len()) as u8);
+    response[data_offset..data_offset + data.len()].copy_from_slice(data);
+    let length = response.len() - MailboxFrame::HEADER_SIZE;
+    MailboxFrame(&mut response[..]).set_length(length as u16);
+}
+
+fn set_sdo_header(
+    response: &mut [u8],
+    service_type: CoeServiceType,
+    command_specifier: u8,
+    index: u16,
+    sub_index: u8,
+) {
+    let mut coe = CoeFrame(&mut response[MailboxFrame::HEADER_SIZE..]);
+    coe.set_number(0);
+    coe.set_coe_service_type(service_type);
+    let mut sdo = SdoFrame(&mut response[MailboxFrame::HEADER_SIZE + CoeFrame::HEADER_SIZE..]);
+    sdo.set_command_specifier(command_specifier);
+    sdo.set_index(index);
+    sdo.set_sub_index(sub_index);
+}
+
+fn error_response<B: AsRef<[u8]>>(
+    request: &MailboxFrame<B>,
+    detail: MailboxErrorDetail,
+) -> Vec<u8> {
+    encode_mailbox(
+        request.count(),
+        &Mailbox::Error(detail),
+        MailboxFrame::HEADER_SIZE + MailboxErrorFrame::SIZE,
+    )
+}
+
+pub(super) fn emergency_mailbox(
+    emergency: &EmmergencyFrame<[u8; EmmergencyFrame::SIZE]>,
+) -> Vec<u8> {
+    let message = Mailbox::CoE((
+        CoeIndex::default(),
+        CoE::Emmergency(EmmergencyFrame(&emergency.0)),
+    ));
+    encode_mailbox(
+        0,
+        &message,
+        MailboxFrame::HEADER_SIZE + CoeFrame::HEADER_SIZE + EmmergencyFrame::SIZE,
+    )
+}
+
+/// Encodes `message` in a mailbox of up to `mailbox_size`, which is truncated to its length.
+fn encode_mailbox(count: u8, message: &Mailbox, mailbox_size: usize) -> Vec<u8> {
+    let mut mailbox = vec![0; mailbox_size];
+    let mut frame = MailboxFrame(&mut mailbox[..]);
+    frame.set_count(count);
+    frame.set_mailbox(message).unwrap();
+    let length = frame.length() as usize;
+    mailbox.truncate(MailboxFrame::HEADER_SIZE + length);
+    mailbox
+}
+
+pub(super) fn new_mailbox(mb_type: MailboxType, count: u8, length: usize) -> Vec<u8> {
+    let mut mailbox = vec![0; MailboxFrame::HEADER_SIZE + length];
+    let mut header = MailboxFrame(&mut mailbox[..]);
+    header.set_length(length as u16);
+    header.set_address(0);
+    header.set_prioriry(0);
+    header.set_mb_type(mb_type);
+    header.set_count(count);
+    mailbox
+}
@@ -0,0 +1,438 @@
+//! Software model of an EtherCAT segment.
+//!
+//! `SimulatedSegment` implements `RawEthernetDevice`, so the master and all tasks run against it unmodified.
+//! The segment is a line of `VirtualEsc`s. Each frame passes every ESC in order and is returned by the last one.
+//! `SimulatedSegment::into_ring` connects both ends of the line to the master for cable redundancy.
+
+mod aoe;
+mod eoe;
+mod esc;
+mod foe;
+mod mailbox;
+mod od;
+mod soe;
+
+pub use aoe::*;
+pub use eoe::*;
+pub use esc::*;
+pub use foe::*;
+pub use od::*;
+pub use soe::*;
+
+use std::cell::{RefCell, RefMut};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::frame::{EtherCatFrame, EtherCatPdu, EthernetFrame, ETHERCAT_TYPE, WKC_LENGTH};
+
+use super::{DeviceError, RawEthernetDevice, RxToken, TxToken};
+
+const DEFAULT_FRAME_INTERVAL_NS: u64 = 10_000;
+const DEFAULT_HOP_DELAY_NS: u64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SegmentPort {
+    Primary,
+    Secondary,
+}
+
+/// A line topology of virtual ESCs.
+///
+/// The simulated time advances by the frame interval per transmitted frame.
+/// Frames transmitted to an empty segment are lost.
+pub struct SimulatedSegment {
+    slaves: Vec<VirtualEsc>,
+    returned_frames: VecDeque<Vec<u8>>,
+    secondary_returned_frames: VecDeque<Vec<u8>>,
+    is_ring: bool,
+    broken_link: Option<usize>,
+    time_ns: u64,
+    frame_interval_ns: u64,
+    hop_delay_ns: u64,
+    /// Offset address and the number of frames accessing it which are lost.
+    lost_accesses: Option<(u16, usize)>,
+}
+
+impl SimulatedSegment {
+    pub fn new() -> Self {
+        Self {
+            slaves: Vec::new(),
+            returned_frames: VecDeque::new(),
+            secondary_returned_frames: VecDeque::new(),
+            is_ring: false,
+            broken_link: None,
+            time_ns: 0,
+            frame_interval_ns: DEFAULT_FRAME_INTERVAL_NS,
+            hop_delay_ns: DEFAULT_HOP_DELAY_NS,
+            lost_accesses: None,
+        }
+    }
+
+    /// Connects the ESC to the last port of the segment.
+    pub fn push_slave(&mut self, slave: VirtualEsc) {
+        self.slaves.push(slave);
+        self.update_links();
+    }
+
+    /// Connects the last port of the segment to the secondary port of the master.
+    pub fn into_ring(mut self) -> (SimulatedPort, SimulatedPort) {
+        self.is_ring = true;
+        self.update_links();
+        let segment = Rc::new(RefCell::new(self));
+        (
+            SimulatedPort {
+                segment: segment.clone(),
+                port: SegmentPort::Primary,
+            },
+            SimulatedPort {
+                segment,
+                port: SegmentPort::Secondary,
+            },
+        )
+    }
+
+    /// Breaks the link in front of the slave at `position`, or restores the links with None.
+    /// The position `slaves().len()` is the link to the secondary port.
+    pub fn set_broken_link(&mut self, position: Option<usize>) {
+        self.broken_link = position;
+        self.update_links();
+    }
+
+    pub fn slaves(&self) -> &[VirtualEsc] {
+        &self.slaves
+    }
+
+    pub fn slaves_mut(&mut self) -> &mut [VirtualEsc] {
+        &mut self.slaves
+    }
+
+    /// Simulated time in nanoseconds.
+    pub fn time_ns(&self) -> u64 {
+        self.time_ns
+    }
+
+    pub fn set_frame_interval_ns(&mut self, interval_ns: u64) {
+        self.frame_interval_ns = interval_ns;
+    }
+
+    /// Propagation and forwarding delay between two neighboring ESCs.
+    pub fn set_hop_delay_ns(&mut self, delay_ns: u64) {
+        self.hop_delay_ns = delay_ns;
+    }
+
+    /// The next `count` frames which have a datagram at the offset `address` are processed
+    /// by the slaves, but do not return to the master.
+    pub fn lose_frames_accessing(&mut self, address: u16, count: usize) {
+        self.lost_accesses = Some((address, count));
+    }
+
+    fn update_links(&mut self) {
+        let num_slaves = self.slaves.len();
+        let broken_link = self.broken_link;
+        let is_ring = self.is_ring;
+        for (i, slave) in self.slaves.iter_mut().enumerate() {
+            let port1 = if i + 1 < num_slaves { true } else { is_ring };
+            slave.set_linked_ports([
+                broken_link != Some(i),
+                port1 && broken_link != Some(i + 1),
+                false,
+                false,
+            ]);
+        }
+    }
+
+    fn process_frame(&mut self, mut frame: Vec<u8>, port: SegmentPort) {
+        let time_ns = self.time_ns;
+        self.time_ns += self.frame_interval_ns;
+
+        let header_size = EthernetFrame::HEADER_SIZE + EtherCatFrame::HEADER_SIZE;
+        if frame.len() < header_size || EthernetFrame(&frame).ether_type() != ETHERCAT_TYPE {
+            return;
+        }
+        let num_slaves = self.slaves.len();
+        // Slaves which process the frame, and the port the frame returns to.
+        let (first, last, return_port) = match (port, self.broken_link) {
+            (SegmentPort::Primary, None) if self.is_ring => (0, num_slaves, SegmentPort::Secondary),
+            (SegmentPort::Primary, None) => (0, num_slaves, SegmentPort::Primary),
+            (SegmentPort::Primary, Some(position)) => {
+                (0, position.min(num_slaves), SegmentPort::Primary)
+            }
+            // The closed ring passes the frame through port 1 to port 0 without processing.
+            (SegmentPort::Secondary, None) => (0, 0, SegmentPort::Primary),
+            (SegmentPort::Secondary, Some(position)) => {
+                (position.min(num_slaves), num_slaves, SegmentPort::Secondary)
+            }
+        };
+        let num_processing_slaves = (last - first) as u64;
+        let is_lost = match port {
+            SegmentPort::Primary => num_processing_slaves == 0,
+            SegmentPort::Secondary => {
+                !self.is_ring || (self.broken_link.is_some() && num_processing_slaves == 0)
+            }
+        };
+        if is_lost {
+            return;
+        }
+        let length = EtherCatFrame(&frame[EthernetFrame::HEADER_SIZE..]).length() as usize;
+        let end = (header_size + length).min(frame.len());
+
+        for (i, slave) in self.slaves[first..last].iter_mut().enumerate() {
+            let i = i as u64;
+            // The frame goes down through port 0 and comes back through port 1.
+            let arrival_ns = time_ns + i * self.hop_delay_ns;
+            let return_ns = if i + 1 < num_processing_slaves {
+                Some(time_ns + (2 * (num_processing_slaves - 1) - i) * self.hop_delay_ns)
+            } else {
+                None
+            };
+            slave.process_datagrams(&mut frame[header_size..end], arrival_ns, return_ns);
+        }
+
+        if let Some((address, count)) = self.lost_accesses {
+            if 0 < count && accesses(&frame[header_size..end], address) {
+                self.lost_accesses = Some((address, count - 1));
+                return;
+            }
+        }
+
+        // The first ESC sets the locally administered bit of the source address.
+        frame[6] |= 0x02;
+        match return_port {
+            SegmentPort::Primary => self.returned_frames.push_back(frame),
+            SegmentPort::Secondary => self.secondary_returned_frames.push_back(frame),
+        }
+    }
+}
+
+impl Default for SimulatedSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RawEthernetDevice for SimulatedSegment {
+    type TxToken<'a> = SimulatedTxToken<'a>
+    where
+        Self: 'a;
+
+    type RxToken<'a> = SimulatedRxToken
+    where
+        Self: 'a;
+
+    fn transmit<'a>(&'a mut self) -> Option<Self::TxToken<'a>> {
+        Some(SimulatedTxToken { segment: self })
+    }
+
+    fn receive<'a>(&'a mut self) -> Option<Self::RxToken<'a>> {
+        self.returned_frames.pop_front().map(SimulatedRxToken)
+    }
+}
+
+pub struct SimulatedTxToken<'a> {
+    segment: &'a mut SimulatedSegment,
+}
+
+impl<'a> TxToken for SimulatedTxToken<'a> {
+    fn consume<F>(self, len: usize, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), ()>,
+    {
+        let mut frame = vec![0; len];
+        f(&mut frame).map_err(|_| DeviceError::Function)?;
+        self.segment.process_frame(frame, SegmentPort::Primary);
+        Ok(())
+    }
+}
+
+/// One of the two ports of a ring. See `SimulatedSegment::into_ring`.
+pub struct SimulatedPort {
+    segment: Rc<RefCell<SimulatedSegment>>,
+    port: SegmentPort,
+}
+
+impl SimulatedPort {
+    pub fn segment(&self) -> RefMut<'_, SimulatedSegment> {
+        self.segment.borrow_mut()
+    }
+}
+
+impl RawEthernetDevice for SimulatedPort {
+    type TxToken<'a> = SimulatedPortTxToken<'a>
+    where
+        Self: 'a;
+
+    type RxToken<'a> = SimulatedRxToken
+    where
+        Self: 'a;
+
+    fn transmit<'a>(&'a mut self) -> Option<Self::TxToken<'a>> {
+        Some(SimulatedPortTxToken { port: self })
+    }
+
+    fn receive<'a>(&'a mut self) -> Option<Self::RxToken<'a>> {
+        let mut segment = self.segment.borrow_mut();
+        let frames = match self.port {
+            SegmentPort::Primary => &mut segment.returned_frames,
+            SegmentPort::Secondary => &mut segment.secondary_returned_frames,
+        };
+        frames.pop_front().map(SimulatedRxToken)
+    }
+}
+
+pub struct SimulatedPortTxToken<'a> {
+    port: &'a SimulatedPort,
+}
+
+impl<'a> TxToken for SimulatedPortTxToken<'a> {
+    fn consume<F>(self, len: usize, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), ()>,
+    {
+        let mut frame = vec![0; len];
+        f(&mut frame).map_err(|_| DeviceError::Function)?;
+        self.port
+            .segment
+            .borrow_mut()
+            .process_frame(frame, self.port.port);
+        Ok(())
+    }
+}
+
+pub struct SimulatedRxToken(Vec<u8>);
+
+impl RxToken for SimulatedRxToken {
+    fn consume<F>(self, f: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&[u8]) -> Result<(), ()>,
+    {
+        f(&self.0).map_err(|_| DeviceError::Function)
+    }
+}
+
+/// Whether one of the datagrams has the offset `address`.
+fn accesses(datagrams: &[u8], address: u16) -> bool {
+    let mut offset = 0;
+    while offset + EtherCatPdu::HEADER_SIZE <= datagrams.len() {
+        let header = EtherCatPdu(&datagrams[offset..offset + EtherCatPdu::HEADER_SIZE]);
+        if header.ado() == address {
+            return true;
+        }
+        if !header.has_next() {
+            break;
+        }
+        offset += EtherCatPdu::HEADER_SIZE + header.length() as usize + WKC_LENGTH;
+    }
+    false
+}
+
+/// Copies `bit_length` bits. Bit 0 is the LSB of the first byte.
+fn copy_bits(src: &[u8], src_bit: usize, dst: &mut [u8], dst_bit: usize, bit_length: usize) {
+    for i in 0..bit_length {
+        let (s, d) = (src_bit + i, dst_bit + i);
+        if let (Some(s_byte), Some(d_byte)) = (src.get(s / 8), dst.get_mut(d / 8)) {
+            if s_byte & (1 << (s % 8)) == 0 {
+                *d_byte &= !(1 << (d % 8));
+            } else {
+                *d_byte |= 1 << (d % 8);
+            }
+        } else {
+            return;
+        }
+    }
+}
+
+/// Setup shared by the tests which run the master against a simulated segment.
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::*;
+    use crate::interface::{PduInterface, TargetSlave};
+    use crate::slave::{AlState, Slave, SlaveConfig};
+    use crate::task::ManualClock;
+    use crate::EtherCatMaster;
+    use core::time::Duration;
+
+    pub(crate) type SimulatedMaster<'a, D = SimulatedSegment> =
+        EtherCatMaster<'a, 'a, 'a, 'static, 'static, D>;
+
+    /// Segment of ESCs whose clocks have different offsets.
+    pub(crate) fn new_segment(num_slaves: usize) -> SimulatedSegment {
+        let mut segment = SimulatedSegment::new();
+        for i in 0..num_slaves {
+            let mut slave = VirtualEsc::new(0x0000_0ABC, 0x1234 + i as u32, 1);
+            slave.set_clock_offset_ns(1_000_000 * i as u64 + 123);
+            segment.push_slave(slave);
+        }
+        segment
+    }
+
+    /// Buffers and clock which a master of a simulated segment borrows.
+    pub(crate) struct MasterStorage {
+        buf: [u8; 1500],
+        clock: ManualClock,
+        slaves: Vec<(Option<Slave>, SlaveConfig<'static, 'static>)>,
+        socket_buffer: Vec<u8>,
+    }
+
+    impl MasterStorage {
+        pub fn new() -> Self {
+            Self {
+                buf: [0; 1500],
+                clock: ManualClock::with_tick(Duration::from_micros(1)),
+                slaves: Vec::new(),
+                socket_buffer: vec![0; 1500],
+            }
+        }
+
+        /// Master of `segment`, whose slaves are initialized.
+        pub fn master(&mut self, segment: SimulatedSegment) -> SimulatedMaster<'_> {
+            let num_slaves = segment.slaves().len();
+            let Self {
+                buf,
+                clock,
+                slaves,
+                socket_buffer,
+            } = self;
+            let iface = PduInterface::new(segment, buf, clock);
+            init_master(iface, num_slaves, slaves, socket_buffer)
+        }
+
+        /// Master of `segment`, whose slaves are in PreOp.
+        pub fn pre_op_master(&mut self, segment: SimulatedSegment) -> SimulatedMaster<'_> {
+            let num_slaves = segment.slaves().len();
+            let mut master = self.master(segment);
+            master
+                .change_al_state(TargetSlave::All(num_slaves as u16), AlState::PreOperational)
+                .unwrap();
+            master
+        }
+
+        /// Master of `segment` closed to a ring, whose slaves are initialized.
+        pub fn redundant_master(
+            &mut self,
+            segment: SimulatedSegment,
+        ) -> SimulatedMaster<'_, SimulatedPort> {
+            let num_slaves = segment.slaves().len();
+            let (primary, secondary) = segment.into_ring();
+            let Self {
+                buf,
+                clock,
+                slaves,
+                socket_buffer,
+            } = self;
+            let iface = PduInterface::new_redundant(primary, secondary, buf, clock);
+            init_master(iface, num_slaves, slaves, socket_buffer)
+        }
+    }
+
+    fn init_master<'a, D: RawEthernetDevice>(
+        iface: PduInterface<'a, D>,
+        num_slaves: usize,
+        slaves: &'a mut Vec<(Option<Slave>, SlaveConfig<'static, 'static>)>,
+        socket_buffer: &'a mut [u8],
+    ) -> SimulatedMaster<'a, D> {
+        slaves.resize_with(num_slaves, Default::default);
+        let mut master = EtherCatMaster::new(slaves, socket_buffer, iface);
+        master.init().unwrap();
+        master
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+
+use crate::frame::{
+    AbortCode, EntryDescriptionFrame, ObjectAccess, ObjectCode, ObjectDescriptionFrame,
+};
+use crate::register::od::OdPdoEntry;
+use crate::slave::AlState;
+
+use super::copy_bits;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SdoAccess {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+    /// Writable in PreOperational only. e.g. PDO mappings and PDO assignments.
+    ReadWritePreOp,
+}
+
+#[derive(Debug, Clone)]
+pub struct SdoEntry {
+    pub access: SdoAccess,
+    pub data: Vec<u8>,
+}
+
+/// CoE object dictionary of a virtual slave.
+///
+/// Sub index 0 of a record is an ordinary u8 entry. Writing it checks the highest sub index.
+/// Names are for SDO information only. Data types are not modelled.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectDictionary {
+    objects: BTreeMap<u16, BTreeMap<u8, SdoEntry>>,
+    names: BTreeMap<u16, String>,
+}
+
+impl ObjectDictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dictionary with the objects which the master uses for configuration.
+    ///
+    /// Output 0x7000:01 (u16) is mapped by RxPDO 0x1600 and input 0x6000:01 (u16) by TxPDO 0x1A00.
+    pub fn with_default_objects(vender_id: u32, product_code: u32, revision_number: u32) -> Self {
+        use SdoAccess::*;
+        let mut od = Self::new();
+        od.insert(0x1000, 0, ReadOnly, &0x0000_0000_u32.to_le_bytes());
+        od.set_name(0x1000, "Device type");
+        od.insert(0x1008, 0, ReadOnly, b"Virtual ESC");
+        od.set_name(0x1008, "Device name");
+        od.insert_record(
+            0x1018,
+            ReadOnly,
+            &[
+                &vender_id.to_le_bytes(),
+                &product_code.to_le_bytes(),
+                &revision_number.to_le_bytes(),
+                &0_u32.to_le_bytes(),
+            ],
+        );
+        od.set_name(0x1018, "Identity");
+        od.insert_record(
+            0x10F1,
+            ReadWrite,
+            &[&0_u32.to_le_bytes(), &4_u16.to_le_bytes()],
+        );
+
+        let mut rx_entry = OdPdoEntry::new();
+        rx_entry.set_index(0x7000);
+        rx_entry.set_sub_index(1);
+        rx_entry.set_bit_length(16);
+        od.insert_record(
+            0x1600,
+            ReadWritePreOp,
+            &[&rx_entry.0, &[0; 4], &[0; 4], &[0; 4]],
+        );
+        od.insert(0x1600, 0, ReadWritePreOp, &[1]);
+        let mut tx_entry = OdPdoEntry::new();
+        tx_entry.set_index(0x6000);
+        tx_entry.set_sub_index(1);
+        tx_entry.set_bit_length(16);
+        od.insert_record(
+            0x1A00,
+            ReadWritePreOp,
+            &[&tx_entry.0, &[0; 4], &[0; 4], &[0; 4]],
+        );
+        od.insert(0x1A00, 0, ReadWritePreOp, &[1]);
+
+        // SM0: mailbox out, SM1: mailbox in, SM2: outputs, SM3: inputs
+        od.insert_record(0x1C00, ReadOnly, &[&[1], &[2], &[3], &[4]]);
+        od.insert_record(
+            0x1C12,
+            ReadWritePreOp,
+            &[&0x1600_u16.to_le_bytes(), &[0; 2]],
+        );
+        od.insert(0x1C12, 0, ReadWritePreOp, &[1]);
+        od.insert_record(
+            0x1C13,
+            ReadWritePreOp,
+            &[&0x1A00_u16.to_le_bytes(), &[0; 2]],
+        );
+        od.insert(0x1C13, 0, ReadWritePreOp, &[1]);
+        for index in [0x1C32, 0x1C33] {
+            od.insert_record(
+                index,
+                ReadWrite,
+                &[
+                    &0_u16.to_le_bytes(),         // sync type
+                    &1_000_000_u32.to_le_bytes(), // cycle time
+                    &0_u32.to_le_bytes(),         // shift time
+                    &0x000F_u16.to_le_bytes(),    // supported sync types
+                    &100_000_u32.to_le_bytes(),   // minimum cycle time
+                    &0_u32.to_le_bytes(),         // calc and copy time
+                    &0_u32.to_le_bytes(),         // minimum delay time
+                    &0_u16.to_le_bytes(),         // get cycle time
+                    &0_u32.to_le_bytes(),         // delay time
+                    &0_u32.to_le_bytes(),         // sync0 cycle time
+                    &0_u16.to_le_bytes(),         // SM event missed
+                    &0_u16.to_le_bytes(),         // cycle time too small
+                ],
+            );
+        }
+
+        od.insert_record(0x6000, ReadOnly, &[&[0; 2]]);
+        od.insert_record(0x7000, ReadWrite, &[&[0; 2]]);
+        od
+    }
+
+    pub fn insert(&mut self, index: u16, sub_index: u8, access: SdoAccess, data: &[u8]) {
+        let entry = SdoEntry {
+            access,
+            data: data.to_vec(),
+        };
+        self.objects
+            .entry(index)
+            .or_default()
+            .insert(sub_index, entry);
+    }
+
+    /// Inserts sub index 1.. and a read only sub index 0 which holds the number of entries.
+    pub fn insert_record(&mut self, index: u16, access: SdoAccess, entries: &[&[u8]]) {
+        self.insert(index, 0, SdoAccess::ReadOnly, &[entries.len() as u8]);
+        for (i, data) in entries.iter().enumerate() {
+            self.insert(index, i as u8 + 1, access, data);
+        }
+    }
+
+    pub fn remove(&mut self, index: u16) {
+        self.objects.remove(&index);
+        self.names.remove(&index);
+    }
+
+    pub fn set_name(&mut self, index: u16, name: &str) {
+        self.names.insert(index, name.into());
+    }
+
+    pub fn indexes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.objects.keys().copied()
+    }
+
+    /// Response of Get Object Description.
+    pub(super) fn object_description(&self, index: u16) -> Result<Vec<u8>, AbortCode> {
+        let object = self
+            .objects
+            .get(&index)
+            .ok_or(AbortCode::DoesNotExistInDict)?;
+        let mut description = ObjectDescriptionFrame::new();
+        description.set_index(index);
+        description.set_max_sub_index(object.keys().next_back().copied().unwrap_or_default());
+        let object_code = if object.len() < 2 {
+            ObjectCode::Variable
+        } else {
+            ObjectCode::Record
+        };
+        description.set_object_code(object_code as u8);
+        let mut data = description.0.to_vec();
+        data.extend_from_slice(self.names.get(&index).map_or(&[], |name| name.as_bytes()));
+        Ok(data)
+    }
+
+    /// Response of Get Entry Description. No values are included.
+    pub(super) fn entry_description(
+        &self,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<Vec<u8>, AbortCode> {
+        let entry = self.entry(index, sub_index)?;
+        let mut description = EntryDescriptionFrame::new();
+        description.set_index(index);
+        description.set_sub_index(sub_index);
+        description.set_bit_length(entry.data.len() as u16 * 8);
+        let read = ObjectAccess::READ_PRE_OP | ObjectAccess::READ_SAFE_OP | ObjectAccess::READ_OP;
+        let write =
+            ObjectAccess::WRITE_PRE_OP | ObjectAccess::WRITE_SAFE_OP | ObjectAccess::WRITE_OP;
+        let object_access = match entry.access {
+            SdoAccess::ReadOnly => read,
+            SdoAccess::WriteOnly => write,
+            SdoAccess::ReadWrite => read | write,
+            SdoAccess::ReadWritePreOp => read | ObjectAccess::WRITE_PRE_OP,
+        };
+        description.set_object_access(object_access);
+        Ok(description.0.to_vec())
+    }
+
+    /// Local access without SDO checks.
+    pub fn get(&self, index: u16, sub_index: u8) -> Option<&[u8]> {
+        self.objects
+            .get(&index)
+            .and_then(|object| object.get(&sub_index))
+            .map(|entry| entry.data.as_slice())
+    }
+
+    /// Local access without SDO checks.
+    pub fn get_mut(&mut self, index: u16, sub_index: u8) -> Option<&mut Vec<u8>> {
+        self.objects
+            .get_mut(&index)
+            .and_then(|object| object.get_mut(&sub_index))
+            .map(|entry| &mut entry.data)
+    }
+
+    pub fn sdo_read(&self, index: u16, sub_index: u8) -> Result<&[u8], AbortCode> {
+        let entry = self.entry(index, sub_index)?;
+        if entry.access == SdoAccess::WriteOnly {
+            return Err(AbortCode::WriteOnly);
+        }
+        Ok(&entry.data)
+    }
+
+    pub fn sdo_write(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+        al_state: AlState,
+    ) -> Result<(), AbortCode> {
+        let max_sub_index = self
+            .objects
+            .get(&index)
+            .and_then(|object| object.keys().next_back().copied())
+            .unwrap_or_default();
+        let entry = self.entry(index, sub_index)?;
+        match entry.access {
+            SdoAccess::ReadOnly => return Err(AbortCode::ReadOnly),
+            SdoAccess::ReadWritePreOp if al_state != AlState::PreOperational => {
+                return Err(AbortCode::CannotTransferInCurrentState)
+            }
+            _ => {}
+        }
+        if entry.data.len() < data.len() {
+            return Err(AbortCode::ParameterLengthTooLong);
+        }
+        if data.len() < entry.data.len() {
+            return Err(AbortCode::ParameterLengthTooShort);
+        }
+        if sub_index == 0 && 0 < max_sub_index && max_sub_index < data[0] {
+            return Err(AbortCode::ValueRangeExceeded);
+        }
+        self.get_mut(index, sub_index)
+            .unwrap()
+            .copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Complete access from `sub_index` 0 or 1. Sub index 0 is 16-bit in the data.
+    pub fn sdo_read_complete(&self, index: u16, sub_index: u8) -> Result<Vec<u8>, AbortCode> {
+        let num_entries = self.num_entries(index, sub_index)?;
+        let mut data = Vec::new();
+        if sub_index == 0 {
+            data.extend_from_slice(&[num_entries, 0]);
+        }
+        for i in 1..=num_entries {
+            data.extend_from_slice(self.sdo_read(index, i)?);
+        }
+        Ok(data)
+    }
+
+    /// Complete access from `sub_index` 0 or 1. Nothing is written if an entry is rejected.
+    pub fn sdo_write_complete(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+        al_state: AlState,
+    ) -> Result<(), AbortCode> {
+        self.num_entries(index, sub_index)?;
+        let backup = self.objects.get(&index).cloned().unwrap();
+        let result = self.write_entries(index, sub_index, data, al_state);
+        if result.is_err() {
+            self.objects.insert(index, backup);
+        }
+        result
+    }
+
+    fn write_entries(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+        al_state: AlState,
+    ) -> Result<(), AbortCode> {
+        // From sub index 0, the entries up to its new value. From sub index 1, as many as the data.
+        let (num_entries, mut rest) = if sub_index == 0 {
+            let rest = data.get(2..).ok_or(AbortCode::ParameterLengthTooShort)?;
+            (Some(data[0]), rest)
+        } else {
+            (None, data)
+        };
+        let mut i = 1;
+        while num_entries.map_or(!rest.is_empty(), |num_entries| i <= num_entries) {
+            let size = self.entry(index, i)?.data.len();
+            let entry = rest.get(..size).ok_or(AbortCode::ParameterLengthTooShort)?;
+            self.sdo_write(index, i, entry, al_state)?;
+            rest = &rest[size..];
+            i += 1;
+        }
+        if !rest.is_empty() {
+            return Err(AbortCode::ParameterLengthTooLong);
+        }
+        if sub_index == 0 {
+            self.sdo_write(index, 0, &data[..1], al_state)?;
+        }
+        Ok(())
+    }
+
+    /// Number of entries in sub index 0. Complete access is supported for records only.
+    fn num_entries(&self, index: u16, sub_index: u8) -> Result<u8, AbortCode> {
+        let object = self
+            .objects
+            .get(&index)
+            .ok_or(AbortCode::DoesNotExistInDict)?;
+        if 1 < sub_index || object.len() < 2 {
+            return Err(AbortCode::NotSupportedAccess);
+        }
+        Ok(object.get(&0).map_or(0, |entry| entry.data[0]))
+    }
+
+    fn entry(&self, index: u16, sub_index: u8) -> Result<&SdoEntry, AbortCode> {
+        self.objects
+            .get(&index)
+            .ok_or(AbortCode::DoesNotExistInDict)?
+            .get(&sub_index)
+            .ok_or(AbortCode::SubIndexDoesNotExist)
+    }
+
+    /// Entries (index, sub index, bit length) mapped by the PDOs assigned to a sync manager.
+    fn assigned_entries(&self, assign_index: u16) -> Option<Vec<(u16, u8, usize)>> {
+        let mut entries = Vec::new();
+        let num_pdos = *self.get(assign_index, 0)?.first()?;
+        for i in 1..=num_pdos {
+            let pdo = self.get(assign_index, i)?;
+            let pdo_index = u16::from_le_bytes([*pdo.first()?, *pdo.get(1)?]);
+            let num_entries = *self.get(pdo_index, 0)?.first()?;
+            for j in 1..=num_entries {
+                let entry = OdPdoEntry(self.get(pdo_index, j)?);
+                entries.push((
+                    entry.index(),
+                    entry.sub_index(),
+                    entry.bit_length() as usize,
+                ));
+            }
+        }
+        Some(entries)
+    }
+
+    /// Process data size of the PDOs assigned by 0x1C12 or 0x1C13.
+    pub(super) fn assigned_bit_length(&self, assign_index: u16) -> Option<usize> {
+        self.assigned_entries(assign_index)
+            .map(|entries| entries.iter().map(|(_, _, bits)| bits).sum())
+    }
+
+    pub(super) fn pack_process_data(&self, assign_index: u16, buf: &mut [u8]) {
+        let mut bit_offset = 0;
+        for (index, sub_index, bit_length) in
+            self.assigned_entries(assign_index).unwrap_or_default()
+        {
+            // index 0 is a gap
+            if let Some(data) = self.get(index, sub_index) {
+                copy_bits(data, 0, buf, bit_offset, bit_length);
+            }
+            bit_offset += bit_length;
+        }
+    }
+
+    pub(super) fn unpack_process_data(&mut self, assign_index: u16, buf: &[u8]) {
+        let mut bit_offset = 0;
+        for (index, sub_index, bit_length) in
+            self.assigned_entries(assign_index).unwrap_or_default()
+        {
+            if let Some(data) = self.get_mut(index, sub_index) {
+                copy_bits(buf, bit_offset, data, 0, bit_length);
+            }
+            bit_offset += bit_length;
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use crate::frame::{
+    Mailbox, MailboxFrame, MailboxType, SoE, SoeElement, SoeErrorCode, SoeFrame, SoeOpCode,
+};
+
+use super::mailbox::new_mailbox;
+
+/// IDNs of the drives of a virtual ESC which the master reads and writes with SoE.
+/// Each element of an IDN is stored separately. Only the value can be written.
+#[derive(Debug, Default)]
+pub struct SoeServer {
+    elements: BTreeMap<(u8, u16, u8), Vec<u8>>,
+    /// Fragments of a write request received so far.
+    write_data: Vec<u8>,
+}
+
+impl SoeServer {
+    /// `element` is one of `SoeElement`.
+    pub fn element(&self, drive_number: u8, idn: u16, element: u8) -> Option<&[u8]> {
+        self.elements
+            .get(&(drive_number, idn, element))
+            .map(|data| data.as_slice())
+    }
+
+    pub fn insert_element(&mut self, drive_number: u8, idn: u16, element: u8, data: &[u8]) {
+        self.elements
+            .insert((drive_number, idn, element), data.to_vec());
+    }
+
+    /// Returns no response to write fragments which more fragments follow.
+    pub(super) fn process_request(
+        &mut self,
+        request: &MailboxFrame<&[u8]>,
+        soe: SoE,
+        mailbox_size: usize,
+    ) -> Vec<Vec<u8>> {
+        match soe {
+            SoE::ReadReq {
+                drive_number,
+                elements,
+                idn,
+            } => match self.read(drive_number, idn, elements) {
+                Ok(data) => {
+                    read_response(request, drive_number, idn, elements, &data, mailbox_size)
+                }
+                Err(code) => {
+                    let error = SoE::Error {
+                        op_code: SoeOpCode::ReadRes,
+                        drive_number,
+                        elements,
+                        idn,
+                        code,
+                    };
+                    vec![soe_mailbox(request.count(), &error, mailbox_size)]
+                }
+            },
+            SoE::WriteReq {
+                drive_number,
+                elements,
+                incomplete,
+                idn,
+                data,
+            } => {
+                self.write_data.extend_from_slice(data);
+                if incomplete {
+                    return Vec::new();
+                }
+                let data = core::mem::take(&mut self.write_data);
+                let response = match self.write(drive_number, idn, elements, data) {
+                    Ok(()) => SoE::WriteRes {
+                        drive_number,
+                        elements,
+                        idn,
+                    },
+                    Err(code) => SoE::Error {
+                        op_code: SoeOpCode::WriteRes,
+                        drive_number,
+                        elements,
+                        idn,
+                        code,
+                    },
+                };
+                vec![soe_mailbox(request.count(), &response, mailbox_size)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The data of the elements follow in the order of the bits.
+    fn read(&self, drive_number: u8, idn: u16, elements: u8) -> Result<Vec<u8>, SoeErrorCode> {
+        if elements == 0 {
+            return Err(SoeErrorCode::NoElementAddressed);
+        }
+        if !self
+            .elements
+            .keys()
+            .any(|(drive, number, _)| *drive == drive_number && *number == idn)
+        {
+            return Err(SoeErrorCode::NoIdn);
+        }
+        let mut data = Vec::new();
+        for bit in 0..8 {
+            let element = 1 << bit;
+            if elements & element == 0 {
+                continue;
+            }
+            match self.element(drive_number, idn, element) {
+                Some(value) => data.extend_from_slice(value),
+                None => return Err(missing_element(element)),
+            }
+        }
+        Ok(data)
+    }
+
+    fn write(
+        &mut self,
+        drive_number: u8,
+        idn: u16,
+        elements: u8,
+        data: Vec<u8>,
+    ) -> Result<(), SoeErrorCode> {
+        match elements {
+            SoeElement::VALUE => {}
+            SoeElement::NAME => return Err(SoeErrorCode::NameCannotBeChanged),
+            SoeElement::ATTRIBUTE => return Err(SoeErrorCode::AttributeCannotBeChanged),
+            SoeElement::UNIT => return Err(SoeErrorCode::UnitCannotBeChanged),
+            SoeElement::MINIMUM => return Err(SoeErrorCode::MinimumCannotBeChanged),
+            SoeElement::MAXIMUM => return Err(SoeErrorCode::MaximumCannotBeChanged),
+            _ => return Err(SoeErrorCode::GeneralError),
+        }
+        let value = self
+            .elements
+            .get_mut(&(drive_number, idn, SoeElement::VALUE))
+            .ok_or(SoeErrorCode::NoIdn)?;
+        if data.len() < value.len() {
+            Err(SoeErrorCode::DataTransmissionTooShort)
+        } else if value.len() < data.len() {
+            Err(SoeErrorCode::DataTransmissionTooLong)
+        } else {
+            *value = data;
+            Ok(())
+        }
+    }
+}
+
+/// The response is split into fragments if it does not fit in the mailbox.
+fn read_response(
+    request: &MailboxFrame<&[u8]>,
+    drive_number: u8,
+    idn: u16,
+    elements: u8,
+    data: &[u8],
+    mailbox_size: usize,
+) -> Vec<Vec<u8>> {
+    let capacity = mailbox_size - MailboxFrame::HEADER_SIZE - SoeFrame::HEADER_SIZE;
+    let num_fragments = data.len().div_ceil(capacity).max(1);
+    (0..num_fragments)
+        .map(|i| {
+            let fragments_left = num_fragments - i - 1;
+            let response = SoE::ReadRes {
+                drive_number,
+                elements,
+                incomplete: 0 < fragments_left,
+                idn: if 0 < fragments_left {
+                    fragments_left as u16
+                } else {
+                    idn
+                },
+                data: &data[i * capacity..data.len().min((i + 1) * capacity)],
+            };
+            soe_mailbox(request.count(), &response, mailbox_size)
+        })
+        .collect()
+}
+
+fn missing_element(element: u8) -> SoeErrorCode {
+    match element {
+        SoeElement::NAME => SoeErrorCode::NoName,
+        SoeElement::UNIT => SoeErrorCode::NoUnit,
+        SoeElement::MINIMUM => SoeErrorCode::NoMinimum,
+        SoeElement::MAXIMUM => SoeErrorCode::NoMaximum,
+        SoeElement::DEFAULT => SoeErrorCode::NoDefaultValue,
+        SoeElement::DATA_STATE => SoeErrorCode::NoDataState,
+        _ => SoeErrorCode::GeneralError,
+    }
+}
+
+fn soe_mailbox(count: u8, soe: &SoE, mailbox_size: usize) -> Vec<u8> {
+    let mut mailbox = new_mailbox(
+        MailboxType::SoE,
+        count,
+        mailbox_size - MailboxFrame::HEADER_SIZE,
+    );
+    let mut frame = MailboxFrame(&mut mailbox[..]);
+    frame.set_mailbox(&Mailbox::SoE(*soe)).unwrap();
+    let length = frame.length() as usize;
+    mailbox.truncate(MailboxFrame::HEADER_SIZE + length);
+    mailbox
+}
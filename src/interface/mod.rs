@@ -1,6 +1,8 @@
+mod eoe_device;
 mod hal;
 mod pdu_if;
 mod socket_if;
+pub use eoe_device::*;
 pub use hal::*;
 pub use pdu_if::*;
 pub use socket_if::*;
@@ -1,5 +1,8 @@
 use super::hal::{RawEthernetDevice, RxToken, TxToken};
 use crate::frame::*;
+use crate::task::{Clock, EtherCatSystemTime};
+use core::task::Waker;
+use core::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PhyError {
@@ -7,6 +10,8 @@ pub enum PhyError {
     RxError,
     TxNotAvailable,
     RxNotAvailable,
+    /// The response of the frame did not arrive within the receive timeout.
+    LostFrame,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -60,41 +65,211 @@ impl Command {
     }
 }
 
+/// Maximum number of frames which are transmitted before their responses are received.
+pub const MAX_FRAMES_IN_FLIGHT: usize = 8;
+
+/// Default time without the response before a frame is regarded as lost.
+pub const DEFAULT_RECEIVE_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Source address of the frames transmitted through the secondary port.
+const SECONDARY_SRC_MAC: u64 = 0x04_04_04_04_04_04;
+/// The first slave sets this bit of the source address.
+const LOCALLY_ADMINISTERED_BIT: u64 = 0x02_00_00_00_00_00;
+/// Index of the BRD which is transmitted through the secondary port with each frame.
+/// The lower bits are the number of the frame.
+const DUMMY_PDU_INDEX: u8 = 0x80;
+
+/// State of the ring with cable redundancy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedundancyState {
+    /// Frames from the primary port pass all slaves and return to the secondary port.
+    Closed,
+    /// Frames return to the port they were transmitted from.
+    /// The ring is broken after the first `num_slaves - secondary_slaves` slaves.
+    Broken { secondary_slaves: u16 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Port {
+    Primary,
+    Secondary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameState {
+    Queued,
+    Sent,
+    Received,
+    Lost,
+}
+
+/// Region of the PDU buffer which is transmitted as one Ethernet frame.
+#[derive(Debug, Clone, Copy)]
+struct FrameSlot {
+    offset: usize,
+    length: usize,
+    state: FrameState,
+    /// True if all PDUs in the frame are safe to send again.
+    retransmittable: bool,
+    sent_at: EtherCatSystemTime,
+    retransmissions: usize,
+    /// The frame is transmitted through the secondary port as it is in the buffer,
+    /// so that the slaves behind a broken ring process it.
+    forward: bool,
+    /// The frame returned to the primary port. The ring is broken.
+    primary_returned: bool,
+    /// The dummy BRD returned to the secondary port. The ring is broken.
+    secondary_reachable: bool,
+}
+
+impl Default for FrameSlot {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            length: 0,
+            state: FrameState::Queued,
+            retransmittable: true,
+            sent_at: EtherCatSystemTime(0),
+            retransmissions: 0,
+            forward: false,
+            primary_returned: false,
+            secondary_reachable: false,
+        }
+    }
+}
+
+/// PDUs are queued in the buffer and split into several frames.
+/// A response frame is matched to a transmitted frame by the indexes and lengths of its PDUs.
+///
+/// A frame whose response does not arrive within the receive timeout is lost.
+/// If retransmission is enabled, a lost frame which has only idempotent PDUs is transmitted again.
+///
+/// With a secondary device (cable redundancy), a dummy BRD is transmitted through the secondary port with each frame.
+/// If a frame returns to the primary port, the ring is broken and the frame is forwarded through the secondary port.
+/// So the response has passed all slaves in the same order as with the closed ring.
+/// Once a frame has timed out because one side of the broken ring has no slave,
+/// later frames are completed or forwarded without waiting for the timeout.
 #[derive(Debug)]
 pub struct PduInterface<'a, D>
 where
     D: RawEthernetDevice,
 {
     ethdev: D,
+    secondary_ethdev: Option<D>,
     buffer: &'a mut [u8],
     pdus_total_size: usize,
-    pdu_count: usize,
-    tx_count: usize,
-    capacity: usize,
-    recv_data_size: usize,
+    frames: [FrameSlot; MAX_FRAMES_IN_FLIGHT],
+    num_frames: usize,
+    clock: &'a dyn Clock,
+    receive_timeout: Duration,
+    max_retransmissions: usize,
+    lost_frame_count: usize,
+    redundancy_state: Option<RedundancyState>,
+    /// The port from which no slave of the broken ring is reachable.
+    unreachable_port: Option<Port>,
 }
 
 impl<'a, D> PduInterface<'a, D>
 where
     D: RawEthernetDevice,
 {
-    pub fn new(ethdev: D, buffer: &'a mut [u8]) -> Self {
-        let capacity = buffer.len().min(MAX_ETHERCAT_DATAGRAM);
+    pub fn new(ethdev: D, buffer: &'a mut [u8], clock: &'a dyn Clock) -> Self {
         Self {
             ethdev,
+            secondary_ethdev: None,
             buffer,
             pdus_total_size: 0,
-            pdu_count: 0,
-            tx_count: 0,
-            capacity,
-            recv_data_size: 0,
+            frames: [FrameSlot::default(); MAX_FRAMES_IN_FLIGHT],
+            num_frames: 0,
+            clock,
+            receive_timeout: DEFAULT_RECEIVE_TIMEOUT,
+            max_retransmissions: 0,
+            lost_frame_count: 0,
+            redundancy_state: None,
+            unreachable_port: None,
         }
     }
 
+    /// Cable redundancy. Both devices must be connected to the ends of the same ring.
+    pub fn new_redundant(
+        ethdev: D,
+        secondary_ethdev: D,
+        buffer: &'a mut [u8],
+        clock: &'a dyn Clock,
+    ) -> Self {
+        let mut iface = Self::new(ethdev, buffer, clock);
+        iface.secondary_ethdev = Some(secondary_ethdev);
+        iface
+    }
+
+    pub fn device(&self) -> &D {
+        &self.ethdev
+    }
+
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.ethdev
+    }
+
+    pub fn secondary_device(&self) -> Option<&D> {
+        self.secondary_ethdev.as_ref()
+    }
+
+    pub fn secondary_device_mut(&mut self) -> Option<&mut D> {
+        self.secondary_ethdev.as_mut()
+    }
+
+    /// True if all devices wake the waker when a frame can be transmitted or received.
+    pub fn register_waker(&mut self, waker: &Waker) -> bool {
+        let primary = self.ethdev.register_waker(waker);
+        let secondary = self
+            .secondary_ethdev
+            .as_mut()
+            .is_none_or(|ethdev| ethdev.register_waker(waker));
+        primary && secondary
+    }
+
+    /// None without a secondary device or before the first frame returns.
+    pub fn redundancy_state(&self) -> Option<RedundancyState> {
+        self.redundancy_state
+    }
+
+    pub fn now(&self) -> EtherCatSystemTime {
+        self.clock.now()
+    }
+
+    /// Time without the response before a frame is regarded as lost.
+    pub fn set_receive_timeout(&mut self, timeout: Duration) {
+        self.receive_timeout = timeout;
+    }
+
+    /// A lost frame which has only idempotent PDUs is transmitted again up to `count` times.
+    /// Default is 0 (no retransmission).
+    pub fn set_max_retransmissions(&mut self, count: usize) {
+        self.max_retransmissions = count;
+    }
+
+    /// Number of frames whose response did not arrive, including retransmitted ones.
+    pub fn lost_frame_count(&self) -> usize {
+        self.lost_frame_count
+    }
+
     pub fn remainig_pdu_data_capacity(&self) -> usize {
-        self.capacity - self.pdus_total_size - EtherCatPdu::HEADER_SIZE - WKC_LENGTH
+        let frame_capacity = if self.num_frames < MAX_FRAMES_IN_FLIGHT {
+            MAX_ETHERCAT_DATAGRAM
+        } else {
+            match self.frames.last() {
+                Some(frame) if frame.state == FrameState::Queued => {
+                    MAX_ETHERCAT_DATAGRAM - frame.length
+                }
+                _ => 0,
+            }
+        };
+        (self.buffer.len() - self.pdus_total_size)
+            .min(frame_capacity)
+            .saturating_sub(EtherCatPdu::HEADER_SIZE + WKC_LENGTH)
     }
 
+    /// Adds a PDU. Only read commands are retransmitted.
     pub fn add_pdu<F: FnOnce(&mut [u8])>(
         &mut self,
         pdu_index: u8,
@@ -102,8 +277,31 @@ where
         data_size: usize,
         data_writer: F,
     ) -> Result<(), Command> {
-        if self.pdus_total_size + EtherCatPdu::HEADER_SIZE + data_size + WKC_LENGTH > self.capacity
-        {
+        let idempotent = is_read_command(command.c_type);
+        self.push_pdu(pdu_index, command, data_size, data_writer, idempotent)
+    }
+
+    /// Adds a PDU which is safe to be retransmitted, e.g. a write of the same data.
+    pub fn add_idempotent_pdu<F: FnOnce(&mut [u8])>(
+        &mut self,
+        pdu_index: u8,
+        command: Command,
+        data_size: usize,
+        data_writer: F,
+    ) -> Result<(), Command> {
+        self.push_pdu(pdu_index, command, data_size, data_writer, true)
+    }
+
+    fn push_pdu<F: FnOnce(&mut [u8])>(
+        &mut self,
+        pdu_index: u8,
+        command: Command,
+        data_size: usize,
+        data_writer: F,
+        idempotent: bool,
+    ) -> Result<(), Command> {
+        let pdu_size = EtherCatPdu::HEADER_SIZE + data_size + WKC_LENGTH;
+        if self.pdus_total_size + pdu_size > self.buffer.len() {
             return Err(command);
         }
 
@@ -111,6 +309,24 @@ where
             return Err(command);
         }
 
+        let frame_index = match self.frames[..self.num_frames].last() {
+            Some(frame)
+                if frame.state == FrameState::Queued
+                    && frame.length + pdu_size <= MAX_ETHERCAT_DATAGRAM =>
+            {
+                self.num_frames - 1
+            }
+            _ if self.num_frames < MAX_FRAMES_IN_FLIGHT => {
+                self.frames[self.num_frames] = FrameSlot {
+                    offset: self.pdus_total_size,
+                    ..Default::default()
+                };
+                self.num_frames += 1;
+                self.num_frames - 1
+            }
+            _ => return Err(command),
+        };
+
         let mut header = [0; EtherCatPdu::HEADER_SIZE];
         let mut pdu = EtherCatPdu(&mut header);
         pdu.set_index(pdu_index);
@@ -127,43 +343,81 @@ where
         );
 
         // Wkc field
+        self.buffer[self.pdus_total_size + EtherCatPdu::HEADER_SIZE + data_size] = 0;
         self.buffer[self.pdus_total_size + EtherCatPdu::HEADER_SIZE + data_size + 1] = 0;
-        self.buffer[self.pdus_total_size + EtherCatPdu::HEADER_SIZE + data_size + 2] = 0;
 
-        self.pdus_total_size += EtherCatPdu::HEADER_SIZE + data_size + WKC_LENGTH;
-        self.pdu_count += 1;
+        self.pdus_total_size += pdu_size;
+        let frame = &mut self.frames[frame_index];
+        frame.length += pdu_size;
+        frame.retransmittable &= idempotent;
         Ok(())
     }
 
-    pub fn consume_pdus(&mut self) -> EtherCatPdus {
-        let pdus = EtherCatPdus::new(self.buffer, self.pdus_total_size, 0);
+    /// PDUs of the lost frames. They are not returned by `consume_pdus`.
+    pub fn lost_pdus(&self) -> impl Iterator<Item = EtherCatPdu<&[u8]>> {
+        let buffer: &[u8] = self.buffer;
+        self.frames[..self.num_frames]
+            .iter()
+            .filter(|frame| frame.state == FrameState::Lost)
+            .flat_map(move |frame| {
+                EtherCatPdus::new(buffer, frame.offset + frame.length, frame.offset)
+            })
+    }
+
+    /// Returns the received PDUs and clears the frames.
+    pub fn consume_pdus(&mut self) -> impl Iterator<Item = EtherCatPdu<&[u8]>> {
+        let frames = self.frames;
+        let num_frames = self.num_frames;
         self.pdus_total_size = 0;
-        self.recv_data_size = 0;
-        pdus
+        self.num_frames = 0;
+        let buffer: &[u8] = self.buffer;
+        frames
+            .into_iter()
+            .take(num_frames)
+            .filter(|frame| frame.state == FrameState::Received)
+            .flat_map(move |frame| {
+                EtherCatPdus::new(buffer, frame.offset + frame.length, frame.offset)
+            })
     }
 
-    /// If true, all PDUs are transmitted
-    /// If None, Phy is not ready
+    /// Transmits the first queued frame.
+    /// If true, all frames are transmitted.
     pub fn transmit_one_frame(&mut self) -> Result<bool, PhyError> {
         let Self {
             ethdev,
+            secondary_ethdev,
             buffer,
-            pdus_total_size,
-            pdu_count,
-            tx_count,
+            frames,
+            num_frames,
+            clock,
             ..
         } = self;
-        if *pdu_count == 0 {
+        let frames = &mut frames[..*num_frames];
+        let (frame_number, frame) = if let Some(frame) = frames
+            .iter_mut()
+            .enumerate()
+            .find(|(_, frame)| frame.state == FrameState::Queued)
+        {
+            frame
+        } else {
             return Ok(true);
-        }
-        let buffer = &buffer[0..*pdus_total_size];
-        if let Some(tx_token) = ethdev.transmit() {
-            let len = EthernetFrame::HEADER_SIZE + EtherCatFrame::HEADER_SIZE + *pdus_total_size;
-            let tx_result = tx_token.consume(len, |tx_buffer| {
+        };
+        let pdus = &buffer[frame.offset..frame.offset + frame.length];
+
+        if frame.forward {
+            let secondary_ethdev = secondary_ethdev.as_mut().expect("secondary device");
+            transmit_frame(secondary_ethdev, pdus.len(), |tx_buffer| {
+                // The PDUs keep the data and the WKCs of the slaves in front of the break.
+                let header_size = EthernetFrame::HEADER_SIZE + EtherCatFrame::HEADER_SIZE;
+                tx_buffer[header_size..].copy_from_slice(pdus);
+                EtherCatFrame(&mut tx_buffer[EthernetFrame::HEADER_SIZE..])
+                    .set_length(pdus.len() as u16);
+                EthernetFrame(tx_buffer).set_source(SECONDARY_SRC_MAC);
+            })?;
+        } else {
+            transmit_frame(ethdev, pdus.len(), |tx_buffer| {
                 let mut ec_frame = EtherCatFrameUtil::new_unchecked(tx_buffer);
-                ec_frame.init();
-                let pdus = EtherCatPdus::new(buffer, *pdus_total_size, 0);
-                for pdu in pdus {
+                for pdu in EtherCatPdus::new(pdus, pdus.len(), 0) {
                     let index = pdu.index();
                     let command = CommandType::from(pdu.command_type());
                     let adp = pdu.adp();
@@ -172,71 +426,258 @@ where
                     if !ec_frame.add_command(command, adp, ado, data, Some(index)) {
                         panic!();
                     }
-                    *tx_count += 1;
-                    *pdu_count -= 1;
                 }
-                Ok(())
-            });
-            if tx_result.is_err() {
-                return Err(PhyError::TxError);
+            })?;
+            if let Some(secondary_ethdev) = secondary_ethdev {
+                // The dummy only detects the state of the ring. The frame is processed without it.
+                let dummy_index = DUMMY_PDU_INDEX | frame_number as u8;
+                let dummy_size = EtherCatPdu::HEADER_SIZE + 2 + WKC_LENGTH;
+                let _ = transmit_frame(secondary_ethdev, dummy_size, |tx_buffer| {
+                    EtherCatFrameUtil::new_unchecked(&mut *tx_buffer).add_command(
+                        CommandType::BRD,
+                        0,
+                        0,
+                        &[0; 2],
+                        Some(dummy_index),
+                    );
+                    EthernetFrame(tx_buffer).set_source(SECONDARY_SRC_MAC);
+                });
             }
-        } else {
-            return Err(PhyError::TxNotAvailable);
+            frame.primary_returned = false;
+            frame.secondary_reachable = false;
         }
+        frame.state = FrameState::Sent;
+        frame.sent_at = clock.now();
 
-        if *tx_count < self.pdu_count {
-            Ok(false)
-        } else {
-            Ok(true)
-        }
+        Ok(frames.iter().all(|frame| frame.state != FrameState::Queued))
     }
 
-    /// If true, all PDUs are received
-    /// If None, Phy is not ready
+    /// Receives one frame from each device and copies its PDUs to the transmitted frame which has the same PDUs.
+    /// A frame which reaches the receive timeout is lost or queued again for retransmission.
+    /// If true, all frames are received or lost.
     pub fn receive_one_frame(&mut self) -> Result<bool, PhyError> {
         let Self {
             ethdev,
+            secondary_ethdev,
             buffer,
-            tx_count,
-            recv_data_size,
+            frames,
+            num_frames,
+            clock,
+            receive_timeout,
+            max_retransmissions,
+            lost_frame_count,
+            redundancy_state,
+            unreachable_port,
             ..
         } = self;
-        if *tx_count == 0 {
-            return Ok(true);
+        let frames = &mut frames[..*num_frames];
+        let is_complete = |frames: &[FrameSlot]| {
+            frames
+                .iter()
+                .all(|frame| frame.state == FrameState::Received || frame.state == FrameState::Lost)
+        };
+        if frames.iter().all(|frame| frame.state != FrameState::Sent) {
+            return Ok(is_complete(frames));
         }
-        loop {
-            if let Some(rx_token) = ethdev.receive() {
-                let rx_result = rx_token.consume(|frame| {
-                    let eth = EthernetFrame(&frame);
-                    if eth.source() == SRC_MAC || eth.ether_type() != ETHERCAT_TYPE {
-                        return Ok(()); //continue
-                    }
-                    let ec_frame = EtherCatFrameUtil::new_unchecked(frame);
-                    for pdu in ec_frame.dlpdus() {
-                        let pdu_size =
-                            EtherCatPdu::HEADER_SIZE + pdu.length() as usize + WKC_LENGTH;
-                        buffer[*recv_data_size..*recv_data_size + pdu_size].copy_from_slice(pdu.0);
-
-                        *recv_data_size += pdu_size;
-                        *tx_count -= 1;
-                    }
-                    Ok(())
+
+        let is_redundant = secondary_ethdev.is_some();
+        let mut is_rx_available = false;
+        // A dummy BRD which has returned is processed before the frame transmitted with it.
+        let ports = [
+            (Port::Secondary, secondary_ethdev.as_mut()),
+            (Port::Primary, Some(ethdev)),
+        ];
+        for (port, device) in ports {
+            let rx_token = if let Some(rx_token) = device.and_then(|device| device.receive()) {
+                rx_token
+            } else {
+                continue;
+            };
+            is_rx_available = true;
+            let rx_result = rx_token.consume(|frame| {
+                receive_frame(
+                    frame,
+                    port,
+                    is_redundant,
+                    buffer,
+                    frames,
+                    redundancy_state,
+                    unreachable_port,
+                );
+                Ok(())
+            });
+            if rx_result.is_err() {
+                return Err(PhyError::RxError);
+            }
+        }
+
+        let now = clock.now();
+        let mut is_timed_out = false;
+        for frame in frames
+            .iter_mut()
+            .filter(|frame| frame.state == FrameState::Sent)
+        {
+            if now.elapsed_since(frame.sent_at) < *receive_timeout {
+                continue;
+            }
+            is_timed_out = true;
+            if !frame.forward && frame.primary_returned {
+                // No slave is reachable from the secondary port.
+                *redundancy_state = Some(RedundancyState::Broken {
+                    secondary_slaves: 0,
                 });
-                if rx_result.is_err() {
-                    return Err(PhyError::RxError);
-                } else {
-                    break;
-                }
+                *unreachable_port = Some(Port::Secondary);
+                frame.state = FrameState::Received;
+                continue;
+            }
+            if !frame.forward && frame.secondary_reachable {
+                // No slave is reachable from the primary port.
+                *unreachable_port = Some(Port::Primary);
+                frame.forward = true;
+                frame.state = FrameState::Queued;
+                continue;
+            }
+            *lost_frame_count += 1;
+            if frame.retransmittable && frame.retransmissions < *max_retransmissions {
+                frame.retransmissions += 1;
+                frame.forward = false;
+                frame.state = FrameState::Queued;
             } else {
-                return Err(PhyError::RxNotAvailable);
+                frame.state = FrameState::Lost;
             }
         }
-        if 0 < *tx_count {
-            Ok(false)
-        } else {
-            Ok(true)
+
+        if !(is_rx_available || is_timed_out) {
+            return Err(PhyError::RxNotAvailable);
+        }
+        Ok(is_complete(frames))
+    }
+}
+
+fn transmit_frame<D, F>(ethdev: &mut D, pdus_size: usize, pdu_writer: F) -> Result<(), PhyError>
+where
+    D: RawEthernetDevice,
+    F: FnOnce(&mut [u8]),
+{
+    let tx_token = ethdev.transmit().ok_or(PhyError::TxNotAvailable)?;
+    let len = EthernetFrame::HEADER_SIZE + EtherCatFrame::HEADER_SIZE + pdus_size;
+    tx_token
+        .consume(len, |tx_buffer| {
+            EtherCatFrameUtil::new_unchecked(&mut *tx_buffer).init();
+            pdu_writer(tx_buffer);
+            Ok(())
+        })
+        .map_err(|_| PhyError::TxError)
+}
+
+/// Copies the PDUs of a returned frame to the transmitted frame which has the same PDUs.
+fn receive_frame(
+    frame: &[u8],
+    port: Port,
+    is_redundant: bool,
+    buffer: &mut [u8],
+    frames: &mut [FrameSlot],
+    redundancy_state: &mut Option<RedundancyState>,
+    unreachable_port: &mut Option<Port>,
+) {
+    let header_size = EthernetFrame::HEADER_SIZE + EtherCatFrame::HEADER_SIZE;
+    if frame.len() < header_size {
+        return;
+    }
+    let eth = EthernetFrame(&frame);
+    if eth.source() == SRC_MAC
+        || eth.source() == SECONDARY_SRC_MAC
+        || eth.ether_type() != ETHERCAT_TYPE
+    {
+        return; //continue
+    }
+    let from_secondary = eth.source() & !LOCALLY_ADMINISTERED_BIT == SECONDARY_SRC_MAC;
+    let length = EtherCatFrame(&frame[EthernetFrame::HEADER_SIZE..]).length() as usize;
+    let frame = if let Some(frame) = frame.get(..header_size + length) {
+        frame
+    } else {
+        return;
+    };
+    let mut recv_pdus = EtherCatPdus::new(frame, frame.len(), header_size);
+
+    if from_secondary {
+        if let Some(pdu) = recv_pdus.next() {
+            if pdu.index() & DUMMY_PDU_INDEX != 0 {
+                // A dummy BRD passes the slaves only if it returns to the secondary port.
+                if port == Port::Secondary {
+                    let secondary_slaves = pdu.wkc().unwrap_or_default();
+                    *redundancy_state = Some(RedundancyState::Broken { secondary_slaves });
+                    let primary_unreachable = *unreachable_port == Some(Port::Primary);
+                    *unreachable_port = primary_unreachable.then_some(Port::Primary);
+                    let frame_number = (pdu.index() & !DUMMY_PDU_INDEX) as usize;
+                    if let Some(slot) = frames.get_mut(frame_number) {
+                        slot.secondary_reachable = true;
+                        // The frame from the primary port is not waited for if it never returns.
+                        if slot.state == FrameState::Sent
+                            && !slot.forward
+                            && (slot.primary_returned || primary_unreachable)
+                        {
+                            slot.forward = true;
+                            slot.state = FrameState::Queued;
+                        }
+                    }
+                }
+                return;
+            }
         }
     }
+
+    let slot = frames.iter_mut().find(|slot| {
+        slot.state == FrameState::Sent
+            && slot.length == length
+            && EtherCatPdus::new(buffer, slot.offset + slot.length, slot.offset)
+                .zip(EtherCatPdus::new(frame, frame.len(), header_size))
+                .all(|(sent, recv)| sent.index() == recv.index() && sent.length() == recv.length())
+    });
+    let slot = if let Some(slot) = slot {
+        slot
+    } else {
+        return;
+    };
+    buffer[slot.offset..slot.offset + slot.length].copy_from_slice(&frame[header_size..]);
+    match (port, from_secondary) {
+        (Port::Primary, false) if is_redundant => {
+            // The ring is broken. If the dummy BRD passed some slaves from the secondary port,
+            // they process the frame from the secondary port.
+            if *redundancy_state == Some(RedundancyState::Closed) || redundancy_state.is_none() {
+                *redundancy_state = Some(RedundancyState::Broken {
+                    secondary_slaves: 0,
+                });
+            }
+            slot.primary_returned = true;
+            let secondary_unreachable = *unreachable_port == Some(Port::Secondary);
+            *unreachable_port = secondary_unreachable.then_some(Port::Secondary);
+            if slot.secondary_reachable {
+                slot.forward = true;
+                slot.state = FrameState::Queued;
+            } else if secondary_unreachable {
+                // The dummy BRD is not waited for if it never returns.
+                slot.state = FrameState::Received;
+            }
+        }
+        (Port::Secondary, false) => {
+            *redundancy_state = Some(RedundancyState::Closed);
+            *unreachable_port = None;
+            slot.state = FrameState::Received;
+        }
+        _ => slot.state = FrameState::Received,
+    }
+}
+
+fn is_read_command(c_type: CommandType) -> bool {
+    matches!(
+        c_type,
+        CommandType::NOP
+            | CommandType::APRD
+            | CommandType::FPRD
+            | CommandType::BRD
+            | CommandType::LRD
+    )
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -295,3 +736,128 @@ fn get_ap_adp(slave_number: u16) -> u16 {
         0xFFFF - (slave_number - 1)
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::simulator::{SimulatedSegment, VirtualEsc};
+    use crate::interface::{RedundancyState, SlaveAddress, TargetSlave, DEFAULT_RECEIVE_TIMEOUT};
+    use crate::slave::AlState;
+    use crate::task::ManualClock;
+
+    #[test]
+    fn multiple_frames_test() {
+        let mut segment = SimulatedSegment::new();
+        segment.push_slave(VirtualEsc::new(1, 2, 3));
+        let mut buf = [0; 4000];
+        let clock = ManualClock::new();
+        let mut pdu_if = PduInterface::new(segment, &mut buf, &clock);
+
+        // Each PDU needs more than half of a frame.
+        for i in 0..3 {
+            pdu_if
+                .add_pdu(i, Command::new(CommandType::APRD, 0, 0x1000), 1000, |_| {})
+                .unwrap();
+        }
+        assert_eq!(pdu_if.transmit_one_frame(), Ok(false));
+        assert_eq!(pdu_if.transmit_one_frame(), Ok(false));
+        assert_eq!(pdu_if.transmit_one_frame(), Ok(true));
+        assert_eq!(pdu_if.receive_one_frame(), Ok(false));
+        assert_eq!(pdu_if.receive_one_frame(), Ok(false));
+        assert_eq!(pdu_if.receive_one_frame(), Ok(true));
+
+        let pdus: Vec<_> = pdu_if
+            .consume_pdus()
+            .map(|pdu| (pdu.index(), pdu.wkc()))
+            .collect();
+        assert_eq!(pdus, [(0, Some(1)), (1, Some(1)), (2, Some(1))]);
+    }
+
+    #[test]
+    fn lost_frame_test() {
+        // Frames transmitted to an empty segment are lost.
+        let mut buf = [0; 100];
+        let clock = ManualClock::new();
+        let mut pdu_if = PduInterface::new(SimulatedSegment::new(), &mut buf, &clock);
+        pdu_if.set_receive_timeout(Duration::from_millis(1));
+        pdu_if.set_max_retransmissions(1);
+
+        pdu_if
+            .add_pdu(0, Command::new(CommandType::APRD, 0, 0x1000), 2, |_| {})
+            .unwrap();
+        assert_eq!(pdu_if.transmit_one_frame(), Ok(true));
+        assert_eq!(pdu_if.receive_one_frame(), Err(PhyError::RxNotAvailable));
+        clock.advance(Duration::from_millis(1));
+        // retransmission
+        assert_eq!(pdu_if.receive_one_frame(), Ok(false));
+        assert_eq!(pdu_if.lost_frame_count(), 1);
+        assert_eq!(pdu_if.transmit_one_frame(), Ok(true));
+        assert_eq!(pdu_if.receive_one_frame(), Err(PhyError::RxNotAvailable));
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(pdu_if.receive_one_frame(), Ok(true));
+        assert_eq!(pdu_if.lost_frame_count(), 2);
+        assert_eq!(pdu_if.lost_pdus().count(), 1);
+        assert_eq!(pdu_if.consume_pdus().count(), 0);
+
+        // A write command is not retransmitted.
+        pdu_if
+            .add_pdu(0, Command::new(CommandType::APWR, 0, 0x1000), 2, |_| {})
+            .unwrap();
+        assert_eq!(pdu_if.transmit_one_frame(), Ok(true));
+        assert_eq!(pdu_if.receive_one_frame(), Err(PhyError::RxNotAvailable));
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(pdu_if.receive_one_frame(), Ok(true));
+        assert_eq!(pdu_if.lost_frame_count(), 3);
+    }
+
+    #[test]
+    fn redundancy_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.redundant_master(new_segment(3));
+        assert_eq!(master.network().num_slaves(), 3);
+        assert_eq!(master.redundancy_state(), Some(RedundancyState::Closed));
+
+        for (broken_link, secondary_slaves) in [(2, 1), (0, 3), (3, 0)] {
+            master.device().segment().set_broken_link(Some(broken_link));
+            for position in 0..3 {
+                // station address
+                let address = master
+                    .read_register(SlaveAddress::SlavePosition(position).into(), 0x0010, 2)
+                    .unwrap();
+                assert_eq!(address, (position + 1).to_le_bytes());
+            }
+            assert_eq!(
+                master.redundancy_state(),
+                Some(RedundancyState::Broken { secondary_slaves })
+            );
+        }
+
+        master.device().segment().set_broken_link(Some(1));
+        master
+            .change_al_state(TargetSlave::All(3), AlState::PreOperational)
+            .unwrap();
+        let slave = SlaveAddress::SlavePosition(2);
+        assert_eq!(master.read_sdo::<u32>(slave, 0x1018, 2).unwrap(), 0x1236);
+    }
+
+    #[test]
+    fn redundancy_latency_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.redundant_master(new_segment(3));
+
+        // No slave is reachable from one of the ports.
+        for broken_link in [0, 3] {
+            master.device().segment().set_broken_link(Some(broken_link));
+            // The first frame waits for the receive timeout to find the broken side.
+            let slave = SlaveAddress::SlavePosition(2).into();
+            master.read_register(slave, 0x0010, 2).unwrap();
+            for _ in 0..3 {
+                let start = master.now();
+                let address = master.read_register(slave, 0x0010, 2).unwrap();
+                assert_eq!(address, 3_u16.to_le_bytes());
+                assert!(master.now().elapsed_since(start) < DEFAULT_RECEIVE_TIMEOUT / 10);
+            }
+        }
+    }
+}
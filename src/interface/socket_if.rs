@@ -2,8 +2,11 @@ use super::hal::RawEthernetDevice;
 use super::Command;
 use super::PduInterface;
 use super::PhyError;
+use super::RedundancyState;
 use crate::frame::*;
+use crate::task::{EtherCatSystemTime, Timeouts};
 use crate::util::*;
+use core::task::Waker;
 
 #[derive(Debug, Clone)]
 pub struct Pdu<'a> {
@@ -29,6 +32,10 @@ pub struct PduSocket<'a> {
     wkc: u16,
     data_length: usize,
     recv_flag: bool,
+    lost_flag: bool,
+    idempotent: bool,
+    waiting: bool,
+    waker: Option<Waker>,
 }
 
 impl<'a> PduSocket<'a> {
@@ -39,6 +46,10 @@ impl<'a> PduSocket<'a> {
             wkc: 0,
             data_length: 0,
             recv_flag: false,
+            lost_flag: false,
+            idempotent: false,
+            waiting: false,
+            waker: None,
         }
     }
 
@@ -56,6 +67,31 @@ impl<'a> PduSocket<'a> {
         self.wkc = 0;
         self.data_length = 0;
         self.recv_flag = false;
+        self.lost_flag = false;
+        self.waiting = false;
+    }
+
+    /// Marks the PDUs of this socket as safe to be retransmitted, even if they are write commands.
+    pub fn set_idempotent(&mut self, idempotent: bool) {
+        self.idempotent = idempotent;
+    }
+
+    /// If true, the frame of the last PDU was lost.
+    pub fn is_lost(&self) -> bool {
+        self.lost_flag
+    }
+
+    /// If true, the last PDU has neither been received nor lost yet.
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// The waker is woken when the last PDU is received or lost.
+    pub fn register_waker(&mut self, waker: &Waker) {
+        match &mut self.waker {
+            Some(registered) if registered.will_wake(waker) => {}
+            registered => *registered = Some(waker.clone()),
+        }
     }
 
     pub fn set_pdu<F>(&mut self, command_data: F)
@@ -63,13 +99,16 @@ impl<'a> PduSocket<'a> {
         F: FnOnce(&mut [u8]) -> Option<(Command, usize)>,
     {
         self.recv_flag = false;
+        self.lost_flag = false;
         self.wkc = 0;
         if let Some((command, length)) = command_data(self.data_buf) {
             self.command = Some(command);
             self.data_length = length;
+            self.waiting = true;
         } else {
             self.command = None;
             self.data_length = 0;
+            self.waiting = false;
         }
     }
 
@@ -109,6 +148,19 @@ impl<'a> PduSocket<'a> {
             .zip(recv_data.data)
             .for_each(|(buf, d)| *buf = *d);
         self.wkc = recv_data.wkc;
+        self.wake();
+    }
+
+    fn lose(&mut self) {
+        self.lost_flag = true;
+        self.wake();
+    }
+
+    fn wake(&mut self) {
+        self.waiting = false;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
     }
 }
 
@@ -132,7 +184,8 @@ where
 {
     iface: PduInterface<'frame, D>,
     socket_set: IndexSet<SocketHandle, PduSocket<'buf>, N>,
-    pub lost_frame_count: usize,
+    timeouts: Timeouts,
+    ams_address: AmsAddress,
 }
 
 impl<'frame, 'buf, D, const N: usize> SocketInterface<'frame, 'buf, D, N>
@@ -143,10 +196,61 @@ where
         Self {
             iface,
             socket_set: IndexSet::new(),
-            lost_frame_count: 0,
+            timeouts: Timeouts::default(),
+            ams_address: AmsAddress::default(),
         }
     }
 
+    pub fn now(&self) -> EtherCatSystemTime {
+        self.iface.now()
+    }
+
+    /// Timeouts of the tasks which are processed by this interface.
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Source of AoE requests.
+    pub fn ams_address(&self) -> AmsAddress {
+        self.ams_address
+    }
+
+    pub fn set_ams_address(&mut self, ams_address: AmsAddress) {
+        self.ams_address = ams_address;
+    }
+
+    pub fn device(&self) -> &D {
+        self.iface.device()
+    }
+
+    pub fn device_mut(&mut self) -> &mut D {
+        self.iface.device_mut()
+    }
+
+    pub fn secondary_device(&self) -> Option<&D> {
+        self.iface.secondary_device()
+    }
+
+    pub fn secondary_device_mut(&mut self) -> Option<&mut D> {
+        self.iface.secondary_device_mut()
+    }
+
+    pub fn redundancy_state(&self) -> Option<RedundancyState> {
+        self.iface.redundancy_state()
+    }
+
+    pub fn lost_frame_count(&self) -> usize {
+        self.iface.lost_frame_count()
+    }
+
+    pub fn register_waker(&mut self, waker: &Waker) -> bool {
+        self.iface.register_waker(waker)
+    }
+
     pub fn add_socket(&mut self, socket: PduSocket<'buf>) -> Result<SocketHandle, PduSocket> {
         self.socket_set.add_item(socket)
     }
@@ -180,14 +284,21 @@ where
                 complete = false;
                 break;
             }
+            let idempotent = socket.idempotent;
             if let Some(command_data) = socket.take_pdu() {
-                self.iface
-                    .add_pdu(i as u8, command_data.command, len, |buf| {
-                        for (b, d) in buf.iter_mut().zip(command_data.data) {
-                            *b = *d;
-                        }
-                    })
-                    .expect("always success");
+                let data_writer = |buf: &mut [u8]| {
+                    for (b, d) in buf.iter_mut().zip(command_data.data) {
+                        *b = *d;
+                    }
+                };
+                if idempotent {
+                    self.iface
+                        .add_idempotent_pdu(i as u8, command_data.command, len, data_writer)
+                } else {
+                    self.iface
+                        .add_pdu(i as u8, command_data.command, len, data_writer)
+                }
+                .expect("always success");
             }
             //}
         }
@@ -199,10 +310,19 @@ where
         let Self {
             iface, socket_set, ..
         } = self;
-        let is_tx_ok = iface.transmit_one_frame()?;
-        let is_rx_ok = iface.receive_one_frame()?;
-        if !(is_tx_ok && is_rx_ok) {
-            return Ok(false);
+        // All frames are transmitted before receiving, so that they are in flight at the same time.
+        // Lost frames which are retransmitted are queued again by receive_one_frame.
+        loop {
+            while !iface.transmit_one_frame()? {}
+            if iface.receive_one_frame()? {
+                break;
+            }
+        }
+        for pdu in iface.lost_pdus() {
+            let index = SocketHandle(pdu.index() as usize);
+            if let Some(socket) = socket_set.get_item_mut(&index) {
+                socket.lose();
+            }
         }
         let pdus = iface.consume_pdus();
         for pdu in pdus {
@@ -222,3 +342,65 @@ where
         Ok(true)
     }
 }
+
+/// Sockets and the interface which transmits their PDUs.
+/// Acyclic operations take steps on their socket through this trait, see `AcyclicSocket`.
+pub trait SocketAccess {
+    fn now(&self) -> EtherCatSystemTime;
+
+    fn timeouts(&self) -> Timeouts;
+
+    fn ams_address(&self) -> AmsAddress;
+
+    fn poll_tx_rx(&mut self) -> Result<bool, PhyError>;
+
+    /// Panics if the socket is not found.
+    fn with_socket<R>(&mut self, handle: &SocketHandle, f: impl FnOnce(&mut PduSocket) -> R) -> R;
+}
+
+impl<'frame, 'buf, D, const N: usize> SocketAccess for SocketInterface<'frame, 'buf, D, N>
+where
+    D: RawEthernetDevice,
+{
+    fn now(&self) -> EtherCatSystemTime {
+        SocketInterface::now(self)
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        SocketInterface::timeouts(self)
+    }
+
+    fn ams_address(&self) -> AmsAddress {
+        SocketInterface::ams_address(self)
+    }
+
+    fn poll_tx_rx(&mut self) -> Result<bool, PhyError> {
+        SocketInterface::poll_tx_rx(self)
+    }
+
+    fn with_socket<R>(&mut self, handle: &SocketHandle, f: impl FnOnce(&mut PduSocket) -> R) -> R {
+        f(self.get_socket_mut(handle).expect("socket not found"))
+    }
+}
+
+impl<T: SocketAccess> SocketAccess for &mut T {
+    fn now(&self) -> EtherCatSystemTime {
+        (**self).now()
+    }
+
+    fn timeouts(&self) -> Timeouts {
+        (**self).timeouts()
+    }
+
+    fn ams_address(&self) -> AmsAddress {
+        (**self).ams_address()
+    }
+
+    fn poll_tx_rx(&mut self) -> Result<bool, PhyError> {
+        (**self).poll_tx_rx()
+    }
+
+    fn with_socket<R>(&mut self, handle: &SocketHandle, f: impl FnOnce(&mut PduSocket) -> R) -> R {
+        (**self).with_socket(handle, f)
+    }
+}
@@ -1,4 +1,4 @@
-#![cfg_attr(not(feature = "pcap"), no_std)]
+#![cfg_attr(not(feature = "std"), no_std)]
 pub mod frame;
 pub mod interface;
 pub mod master;
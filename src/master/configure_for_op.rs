@@ -14,6 +14,9 @@ use crate::{
 
 use super::*;
 
+/// Sub index of a PDO mapping object is u8.
+const MAX_PDO_ENTRIES: usize = 255;
+
 impl<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
     EtherCatMaster<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
 where
@@ -24,14 +27,14 @@ where
         if buf.len() < self.process_data_size() {
             return false;
         }
-        let process_data_handle = self.sif.add_socket(PduSocket::new(buf)).unwrap();
+        let process_data_handle = self.sif.get_mut().add_socket(PduSocket::new(buf)).unwrap();
         self.process_data_handle = Some(process_data_handle);
         true
     }
 
     /// Return process data size
     pub fn process_data_size(&self) -> usize {
-        self.process_data_task.image_size()
+        self.process_data_task.borrow().image_size()
     }
 
     /// Easy setup API. Use this in PreOperational state.
@@ -46,8 +49,10 @@ where
         self.set_pdo_config_to_od()?;
         self.set_pdo_to_sm()?;
         let (image_size, expected_wkc) = self.configure_fmmu()?;
-        self.process_data_task.set_image_size(image_size);
-        self.process_data_task.set_expected_wkc(expected_wkc);
+        self.process_data_task.get_mut().set_image_size(image_size);
+        self.process_data_task
+            .get_mut()
+            .set_expected_wkc(expected_wkc);
         self.set_logical_address_to_pdo_entry_config();
         Ok(())
     }
@@ -60,6 +65,7 @@ where
             gp_socket_handle,
             ..
         } = self;
+        let sif = sif.get_mut();
         let handle = &gp_socket_handle;
         for (slave, slave_config) in network.slaves_mut() {
             set_pdo_config_to_od_utility(slave, slave_config, sif, handle, true)?;
@@ -76,6 +82,7 @@ where
             gp_socket_handle,
             ..
         } = self;
+        let sif = sif.get_mut();
         let handle = &gp_socket_handle;
         for (slave, _) in network.slaves_mut() {
             if let Some(ram_address) = slave.info().process_data_physical_start_address() {
@@ -187,6 +194,7 @@ where
             gp_socket_handle,
             ..
         } = self;
+        let sif = sif.get_mut();
         for (slave, _) in network.slaves() {
             for (i, fmmu) in slave
                 .fmmu_config()
@@ -243,6 +251,7 @@ where
             gp_socket_handle,
             ..
         } = self;
+        let sif = sif.get_mut();
 
         for (slave, config) in network.slaves().filter(|(s, _)| s.info().support_coe()) {
             // Set Operation Mode
@@ -279,7 +288,7 @@ where
                                         error: err,
                                     }),
                                 })?;
-                        u32::from_le_bytes([time[0], time[1], time[2], time[4]])
+                        u32::from_le_bytes([time[0], time[1], time[2], time[3]])
                     }
                     CycleTime::SpecifiedValue(time) => time,
                 };
@@ -443,7 +452,7 @@ where
                                         error: err,
                                     }),
                                 })?;
-                        u32::from_le_bytes([time[0], time[1], time[2], time[4]])
+                        u32::from_le_bytes([time[0], time[1], time[2], time[3]])
                     }
                     CycleTime::SpecifiedValue(time) => time,
                 };
@@ -754,6 +763,14 @@ fn set_pdo_config_to_od_utility<'frame, 'socket, 'pdo_mapping, 'pdo_entry, D: Ra
                 }),
             })?;
 
+        if slave.info().support_sdo_complete_access() {
+            match set_pdo_config_to_od_complete(slave, pdo_mappings, sif, handle, sm_assign) {
+                // Some slaves reject complete access to the PDO objects. Write each sub index then.
+                Err(err) if is_sdo_abort(&err) => {}
+                result => return result,
+            }
+        }
+
         let mut map_index = 0;
         for pdo_map in pdo_mappings.iter() {
             if pdo_map.entries.is_empty() {
@@ -838,6 +855,75 @@ fn set_pdo_config_to_od_utility<'frame, 'socket, 'pdo_mapping, 'pdo_entry, D: Ra
     Ok(())
 }
 
+/// Set PDO map to object dictionary with SDO complete access. One SDO transfer for each object.
+/// The PDO assignment of the SM must be cleared before.
+fn set_pdo_config_to_od_complete<'frame, 'socket, D: RawEthernetDevice>(
+    slave: &Slave,
+    pdo_mappings: &[PdoMapping],
+    sif: &mut SocketInterface<'frame, 'socket, D, NUM_SOCKETS>,
+    handle: &SocketHandle,
+    sm_assign: u16,
+) -> Result<(), ConfigError> {
+    // Sub index 0 is 16-bit with complete access.
+    let mut buf = [0; 2 + OdPdoEntry::SIZE * MAX_PDO_ENTRIES];
+    let pdo_mappings = pdo_mappings
+        .iter()
+        .filter(|pdo_map| !pdo_map.entries.is_empty());
+
+    for pdo_map in pdo_mappings.clone().filter(|pdo_map| !pdo_map.is_fixed) {
+        let mut size = 2;
+        for entry in pdo_map.entries.iter() {
+            let mut od_pdo_entry = OdPdoEntry::new();
+            od_pdo_entry.set_index(entry.index());
+            od_pdo_entry.set_sub_index(entry.sub_index());
+            od_pdo_entry.set_bit_length(entry.bit_length());
+            buf[size..size + OdPdoEntry::SIZE].copy_from_slice(&od_pdo_entry.0);
+            size += OdPdoEntry::SIZE;
+        }
+        buf[0] = pdo_map.entries.len() as u8;
+        buf[1] = 0;
+        sif.write_sdo_complete(handle, slave, pdo_map.index, 0, &buf[..size])
+            .map_err(|err| ConfigError {
+                slave_address: slave.info().slave_address(),
+                kind: ConfigErrorKind::AssignPdoEntryToPdoMap(SdoError {
+                    index: pdo_map.index,
+                    sub_index: 0,
+                    error: err,
+                }),
+            })?;
+    }
+
+    let mut size = 2;
+    for pdo_map in pdo_mappings {
+        buf[size..size + 2].copy_from_slice(&pdo_map.index.to_le_bytes());
+        size += 2;
+    }
+    buf[0] = (size / 2 - 1) as u8;
+    buf[1] = 0;
+    sif.write_sdo_complete(handle, slave, sm_assign, 0, &buf[..size])
+        .map_err(|err| ConfigError {
+            slave_address: slave.info().slave_address(),
+            kind: ConfigErrorKind::AssignPdoMapToSyncManager(SdoError {
+                index: sm_assign,
+                sub_index: 0,
+                error: err,
+            }),
+        })
+}
+
+fn is_sdo_abort(err: &ConfigError) -> bool {
+    matches!(
+        err.kind,
+        ConfigErrorKind::AssignPdoEntryToPdoMap(SdoError {
+            error: TaskError::TaskSpecific(SdoErrorKind::AbortCode(_)),
+            ..
+        }) | ConfigErrorKind::AssignPdoMapToSyncManager(SdoError {
+            error: TaskError::TaskSpecific(SdoErrorKind::AbortCode(_)),
+            ..
+        })
+    )
+}
+
 /// Assign PDO map to SM.
 /// Return next pdo ram address
 /// NOTE: output = RX of slave.
@@ -0,0 +1,164 @@
+use super::{EtherCatMaster, NUM_SOCKETS};
+use crate::interface::{PduSocket, RawEthernetDevice, SocketHandle, SocketInterface};
+use crate::task::{CyclicTask, EtherCatSystemTime};
+use core::fmt;
+use core::mem;
+use core::ptr::{self, NonNull};
+
+/// Maximum number of tasks which are added by `EtherCatMaster::add_cyclic_task`.
+pub const MAX_USER_TASKS: usize = 8;
+
+/// Returned by `EtherCatMaster::add_cyclic_task`, and gives access to the task as its own type.
+/// It is valid only for the master which has returned it.
+#[derive(Debug)]
+pub struct CyclicTaskHandle<T> {
+    index: usize,
+    /// Address of the task, which identifies the entry of the master.
+    task: NonNull<T>,
+}
+
+pub(super) struct UserTaskEntry<'a> {
+    socket_handle: SocketHandle,
+    task: &'a mut (dyn CyclicTask + 'a),
+}
+
+impl<'a> UserTaskEntry<'a> {
+    /// Whether the entry holds the task of the handle.
+    fn holds<T>(&self, handle: &CyclicTaskHandle<T>) -> bool {
+        // Two tasks which are borrowed at the same time only share their address if one has no size.
+        ptr::addr_eq(&*self.task, handle.task.as_ptr())
+            && mem::size_of_val(&*self.task) == mem::size_of::<T>()
+            && mem::align_of_val(&*self.task) == mem::align_of::<T>()
+    }
+}
+
+impl<'a> fmt::Debug for UserTaskEntry<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserTaskEntry")
+            .field("socket_handle", &self.socket_handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
+    EtherCatMaster<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
+where
+    D: RawEthernetDevice,
+{
+    /// Adds a task which is processed in every cycle of `process`.
+    /// Its PDUs are transmitted in the same frames as the process data, using `buf` as the socket buffer.
+    /// Returns None if `MAX_USER_TASKS` tasks are already added.
+    pub fn add_cyclic_task<T: CyclicTask + 'socket>(
+        &mut self,
+        task: &'socket mut T,
+        buf: &'socket mut [u8],
+    ) -> Option<CyclicTaskHandle<T>> {
+        let user_tasks = self.user_tasks.get_mut();
+        let index = user_tasks.iter().position(|entry| entry.is_none())?;
+        let socket_handle = self.sif.get_mut().add_socket(PduSocket::new(buf)).ok()?;
+        let handle = CyclicTaskHandle {
+            index,
+            task: NonNull::from(&mut *task),
+        };
+        user_tasks[index] = Some(UserTaskEntry {
+            socket_handle,
+            task,
+        });
+        Some(handle)
+    }
+
+    /// Returns None if the handle is of another master.
+    pub fn cyclic_task_mut<T: CyclicTask + 'socket>(
+        &mut self,
+        handle: &CyclicTaskHandle<T>,
+    ) -> Option<&mut T> {
+        let entry = self.user_tasks.get_mut().get_mut(handle.index)?.as_mut()?;
+        if !entry.holds(handle) {
+            return None;
+        }
+        let task: *mut (dyn CyclicTask + 'socket) = &mut *entry.task;
+        // SAFETY: The entry has been added from the `&mut T` of the handle.
+        Some(unsafe { &mut *task.cast::<T>() })
+    }
+
+    /// Returns the task, which is no longer processed.
+    /// Returns None if the handle is of another master.
+    pub fn remove_cyclic_task<T: CyclicTask + 'socket>(
+        &mut self,
+        handle: CyclicTaskHandle<T>,
+    ) -> Option<&'socket mut T> {
+        let slot = self.user_tasks.get_mut().get_mut(handle.index)?;
+        if !slot.as_ref()?.holds(&handle) {
+            return None;
+        }
+        let entry = slot.take()?;
+        self.sif.get_mut().remove_socket(entry.socket_handle);
+        let task: *mut (dyn CyclicTask + 'socket) = entry.task;
+        // SAFETY: The entry has been added from the `&mut T` of the handle.
+        Some(unsafe { &mut *task.cast::<T>() })
+    }
+
+    pub(super) fn process_user_tasks(
+        &self,
+        sif: &mut SocketInterface<'frame, 'socket, D, NUM_SOCKETS>,
+        sys_time: EtherCatSystemTime,
+    ) {
+        for entry in self.user_tasks.borrow_mut().iter_mut().flatten() {
+            let socket = sif.get_socket_mut(&entry.socket_handle).unwrap();
+            entry.task.process_one_step(socket, sys_time);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::{Command, Pdu, SlaveAddress};
+    use crate::task::{CyclicTask, EtherCatSystemTime};
+
+    /// Reads the station address of each slave in turn into a borrowed array.
+    struct StationAddressReader<'a> {
+        position: u16,
+        addresses: &'a mut [u16; 2],
+    }
+
+    impl<'a> CyclicTask for StationAddressReader<'a> {
+        fn next_pdu(&mut self, buf: &mut [u8]) -> Option<(Command, usize)> {
+            buf[..2].fill(0);
+            let target = SlaveAddress::SlavePosition(self.position).into();
+            Some((Command::new_read(target, 0x0010), 2))
+        }
+
+        fn recieve_and_process(&mut self, recv_data: &Pdu, _: EtherCatSystemTime) {
+            self.addresses[self.position as usize] =
+                u16::from_le_bytes([recv_data.data[0], recv_data.data[1]]);
+            self.position = (self.position + 1) % 2;
+        }
+
+        fn is_busy(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn user_task_test() {
+        let mut addresses = [0; 2];
+        let mut task = StationAddressReader {
+            position: 0,
+            addresses: &mut addresses,
+        };
+        let mut task_buf = [0; 2];
+        let mut storage = MasterStorage::new();
+        let mut master = storage.master(new_segment(2));
+
+        let handle = master.add_cyclic_task(&mut task, &mut task_buf).unwrap();
+        for _ in 0..4 {
+            master.process(master.now()).unwrap();
+        }
+        let task = master.cyclic_task_mut(&handle).unwrap();
+        assert_eq!(task.addresses, &[1, 2]);
+        task.position = 1;
+        let task = master.remove_cyclic_task(handle).unwrap();
+        assert_eq!(task.position, 1);
+    }
+}
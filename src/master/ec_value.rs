@@ -0,0 +1,440 @@
+use core::str;
+
+use crate::{
+    interface::{RawEthernetDevice, SlaveAddress},
+    slave::PdoEntry,
+    task::{SdoErrorKind, TaskError, MAX_SM_SIZE},
+    EtherCatMaster,
+};
+
+/// Size of the largest PDO entry, whose bit length is 255.
+const MAX_PDO_ENTRY_SIZE: usize = 32;
+
+/// Value of a CoE data type, which is little-endian in an object and in a PDO entry.
+pub trait EcValue: Sized {
+    /// Bit length of the PDO entry. None for strings, whose size is that of the entry.
+    const BIT_LENGTH: Option<u8>;
+
+    /// Returns None if the size of `data` does not match the type.
+    fn decode(data: &[u8]) -> Option<Self>;
+
+    /// Passes the encoded value to `f`.
+    fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R;
+}
+
+/// BOOLEAN. It is a byte in an object, and a bit in a PDO entry.
+impl EcValue for bool {
+    const BIT_LENGTH: Option<u8> = Some(1);
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        match data {
+            [byte] => Some(byte & 1 == 1),
+            _ => None,
+        }
+    }
+
+    fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&[*self as u8])
+    }
+}
+
+macro_rules! impl_ec_value_for_number {
+    ($($ty:ty),*) => {
+        $(
+            impl EcValue for $ty {
+                const BIT_LENGTH: Option<u8> = Some(<$ty>::BITS as u8);
+
+                fn decode(data: &[u8]) -> Option<Self> {
+                    data.try_into().ok().map(<$ty>::from_le_bytes)
+                }
+
+                fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+                    f(&self.to_le_bytes())
+                }
+            }
+        )*
+    };
+}
+
+impl_ec_value_for_number!(u8, i8, u16, i16, u32, i32, u64, i64);
+
+macro_rules! impl_ec_value_for_sized_integer {
+    ($($(#[$doc:meta])* $name:ident($ty:ty, $bits:expr);)*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+            pub struct $name($ty);
+
+            impl $name {
+                pub const BITS: u32 = $bits;
+                pub const MIN: $ty = <$ty>::MIN >> (<$ty>::BITS - $bits);
+                pub const MAX: $ty = <$ty>::MAX >> (<$ty>::BITS - $bits);
+
+                /// Returns None if `value` does not fit in the bit length.
+                pub fn new(value: $ty) -> Option<Self> {
+                    (Self::MIN..=Self::MAX).contains(&value).then_some(Self(value))
+                }
+
+                pub fn get(self) -> $ty {
+                    self.0
+                }
+            }
+
+            impl EcValue for $name {
+                const BIT_LENGTH: Option<u8> = Some($bits);
+
+                fn decode(data: &[u8]) -> Option<Self> {
+                    if data.len() != $bits / 8 {
+                        return None;
+                    }
+                    let mut buf = [0; <$ty>::BITS as usize / 8];
+                    buf[..data.len()].copy_from_slice(data);
+                    // The shifts extend the sign of a signed type.
+                    let shift = <$ty>::BITS - $bits;
+                    Some(Self((<$ty>::from_le_bytes(buf) << shift) >> shift))
+                }
+
+                fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+                    f(&self.0.to_le_bytes()[..$bits / 8])
+                }
+            }
+        )*
+    };
+}
+
+impl_ec_value_for_sized_integer!(
+    /// INTEGER24
+    Integer24(i32, 24);
+    /// INTEGER40
+    Integer40(i64, 40);
+    /// INTEGER48
+    Integer48(i64, 48);
+    /// INTEGER56
+    Integer56(i64, 56);
+    /// UNSIGNED24
+    Unsigned24(u32, 24);
+    /// UNSIGNED40
+    Unsigned40(u64, 40);
+    /// UNSIGNED48
+    Unsigned48(u64, 48);
+    /// UNSIGNED56
+    Unsigned56(u64, 56);
+);
+
+/// REAL32
+impl EcValue for f32 {
+    const BIT_LENGTH: Option<u8> = Some(32);
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        data.try_into().ok().map(f32::from_le_bytes)
+    }
+
+    fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.to_le_bytes())
+    }
+}
+
+/// REAL64
+impl EcValue for f64 {
+    const BIT_LENGTH: Option<u8> = Some(64);
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        data.try_into().ok().map(f64::from_le_bytes)
+    }
+
+    fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.to_le_bytes())
+    }
+}
+
+/// OCTET_STRING of `N` bytes.
+impl<const N: usize> EcValue for [u8; N] {
+    const BIT_LENGTH: Option<u8> = None;
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        data.try_into().ok()
+    }
+
+    fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(self)
+    }
+}
+
+/// BIT1 to BIT8. The bits above `N` are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bits<const N: u8>(pub u8);
+
+impl<const N: u8> Bits<N> {
+    const MASK: u8 = 0xFF >> (8 - N);
+}
+
+impl<const N: u8> EcValue for Bits<N> {
+    const BIT_LENGTH: Option<u8> = Some(N);
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        match data {
+            [byte] => Some(Self(byte & Self::MASK)),
+            _ => None,
+        }
+    }
+
+    fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&[self.0 & Self::MASK])
+    }
+}
+
+/// VISIBLE_STRING of up to `N` bytes. The null bytes which pad it are removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisibleString<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> VisibleString<N> {
+    /// Returns None if `s` is longer than `N`.
+    pub fn new(s: &str) -> Option<Self> {
+        Self::from_bytes(s.as_bytes())
+    }
+
+    fn from_bytes(data: &[u8]) -> Option<Self> {
+        let mut buf = [0; N];
+        buf.get_mut(..data.len())?.copy_from_slice(data);
+        Some(Self {
+            buf,
+            len: data.len(),
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Returns None if it is not UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(self.as_bytes()).ok()
+    }
+}
+
+impl<const N: usize> EcValue for VisibleString<N> {
+    const BIT_LENGTH: Option<u8> = None;
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        Self::from_bytes(&data[..len])
+    }
+
+    fn encode<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(self.as_bytes())
+    }
+}
+
+/// Checks the bit length of the entry against the type. Returns the size of the entry.
+fn pdo_entry_size<T: EcValue>(pdo_entry: &PdoEntry) -> Option<usize> {
+    let bit_length = pdo_entry.bit_length();
+    match T::BIT_LENGTH {
+        Some(length) if length != bit_length => None,
+        None if bit_length % 8 != 0 => None,
+        _ => Some((bit_length as usize).div_ceil(8)),
+    }
+}
+
+impl<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
+    EtherCatMaster<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
+where
+    D: RawEthernetDevice,
+{
+    /// Reads an object which fits in the mailbox, e.g. `read_sdo::<u32>(slave, 0x1018, 1)`.
+    pub fn read_sdo<T: EcValue>(
+        &mut self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<T, TaskError<SdoErrorKind>> {
+        let data = self.read_sdo_bytes(slave_address, index, sub_index)?;
+        T::decode(data).ok_or(TaskError::TaskSpecific(SdoErrorKind::SizeUnmatch))
+    }
+
+    pub fn write_sdo<T: EcValue>(
+        &mut self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        value: T,
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        value.encode(|data| self.write_sdo_bytes(slave_address, index, sub_index, data))
+    }
+
+    pub async fn read_sdo_async<T: EcValue>(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<T, TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        let socket = self.gp_socket().await;
+        let size = socket.read_sdo(slave, index, sub_index).await?;
+        socket
+            .with_socket(|socket| T::decode(&socket.data_buf()[..size]))
+            .ok_or(TaskError::TaskSpecific(SdoErrorKind::SizeUnmatch))
+    }
+
+    /// A value which does not fit in the mailbox causes `SdoErrorKind::BufferSmall`.
+    pub async fn write_sdo_async<T: EcValue>(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        value: T,
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        // The encoded value is kept in the future while it is written.
+        let mut buf = [0; MAX_SM_SIZE as usize];
+        let size = value
+            .encode(|data| {
+                buf.get_mut(..data.len())?.copy_from_slice(data);
+                Some(data.len())
+            })
+            .ok_or(TaskError::TaskSpecific(SdoErrorKind::BufferSmall))?;
+        self.write_sdo_bytes_async(slave_address, index, sub_index, &buf[..size])
+            .await
+    }
+
+    /// Returns None if the entry does not exist, or its bit length does not match the type.
+    pub fn read_pdo<T: EcValue>(
+        &self,
+        slave_address: SlaveAddress,
+        pdo_map_index: usize,
+        pdo_entry_index: usize,
+    ) -> Option<T> {
+        let (_, config) = self.network().slave(slave_address)?;
+        let pdo_entry = config
+            .input_process_data_mappings()
+            .get(pdo_map_index)?
+            .entries
+            .get(pdo_entry_index)?;
+        let size = pdo_entry_size::<T>(pdo_entry)?;
+        let mut buf = [0; MAX_PDO_ENTRY_SIZE];
+        self.read_pdo_bytes(slave_address, pdo_map_index, pdo_entry_index, &mut buf)?;
+        T::decode(&buf[..size])
+    }
+
+    /// Returns None if the entry does not exist, or its bit length does not match the type.
+    /// A string shorter than the entry is padded with null bytes.
+    pub fn write_pdo<T: EcValue>(
+        &self,
+        slave_address: SlaveAddress,
+        pdo_map_index: usize,
+        pdo_entry_index: usize,
+        value: T,
+    ) -> Option<()> {
+        let (_, config) = self.network().slave(slave_address)?;
+        let pdo_entry = config
+            .output_process_data_mappings()
+            .get(pdo_map_index)?
+            .entries
+            .get(pdo_entry_index)?;
+        let size = pdo_entry_size::<T>(pdo_entry)?;
+        let mut buf = [0; MAX_PDO_ENTRY_SIZE];
+        value.encode(|data| {
+            buf[..size].get_mut(..data.len())?.copy_from_slice(data);
+            Some(())
+        })?;
+        self.write_pdo_bytes(slave_address, pdo_map_index, pdo_entry_index, &buf[..size])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: EcValue + PartialEq + core::fmt::Debug>(value: T, bytes: &[u8]) {
+        value.encode(|data| assert_eq!(data, bytes));
+        assert_eq!(T::decode(bytes), Some(value));
+    }
+
+    #[test]
+    fn sized_integer_test() {
+        round_trip(Integer24::new(-2).unwrap(), &[0xFE, 0xFF, 0xFF]);
+        round_trip(Integer24::new(Integer24::MAX).unwrap(), &[0xFF, 0xFF, 0x7F]);
+        round_trip(Integer24::new(Integer24::MIN).unwrap(), &[0x00, 0x00, 0x80]);
+        round_trip(Integer40::new(-1).unwrap(), &[0xFF; 5]);
+        round_trip(Integer48::new(0x1234).unwrap(), &[0x34, 0x12, 0, 0, 0, 0]);
+        round_trip(
+            Integer56::new(Integer56::MIN).unwrap(),
+            &[0, 0, 0, 0, 0, 0, 0x80],
+        );
+        round_trip(Unsigned24::new(0xABCDEF).unwrap(), &[0xEF, 0xCD, 0xAB]);
+        round_trip(Unsigned40::new(Unsigned40::MAX).unwrap(), &[0xFF; 5]);
+        round_trip(
+            Unsigned48::new(0x0102_0304_0506).unwrap(),
+            &[6, 5, 4, 3, 2, 1],
+        );
+        round_trip(Unsigned56::new(1).unwrap(), &[1, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(Integer24::MIN, -0x80_0000);
+        assert_eq!(Unsigned56::MAX, 0xFF_FFFF_FFFF_FFFF);
+        assert_eq!(Integer24::new(0x80_0000), None);
+        assert_eq!(Integer40::new(-0x80_0000_0001), None);
+        assert_eq!(Unsigned24::new(0x100_0000), None);
+        assert_eq!(Unsigned24::decode(&[0; 4]), None);
+        assert_eq!(Integer56::BIT_LENGTH, Some(56));
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod simulator_tests {
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::simulator::SdoAccess;
+    use crate::interface::SlaveAddress;
+    use crate::master::{Bits, VisibleString};
+    use crate::task::{SdoErrorKind, TaskError};
+
+    #[test]
+    fn typed_sdo_test() {
+        let mut segment = new_segment(1);
+        let od = segment.slaves_mut()[0].od_mut();
+        od.insert(0x2000, 1, SdoAccess::ReadWrite, &1.5_f32.to_le_bytes());
+        od.insert(0x2000, 2, SdoAccess::ReadWrite, &(-0.25_f64).to_le_bytes());
+        od.insert(0x2000, 3, SdoAccess::ReadWrite, &(-2_i64).to_le_bytes());
+        od.insert(0x2000, 4, SdoAccess::ReadWrite, &[1]);
+        od.insert(0x2000, 5, SdoAccess::ReadWrite, &[0b1111_0101]);
+        od.insert(0x2000, 6, SdoAccess::ReadWrite, b"abc\0\0\0\0\0");
+        od.insert(0x2000, 7, SdoAccess::ReadWrite, &[1, 2, 3]);
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(segment);
+
+        let slave = SlaveAddress::SlavePosition(0);
+        assert_eq!(master.read_sdo::<f32>(slave, 0x2000, 1).unwrap(), 1.5);
+        assert_eq!(master.read_sdo::<f64>(slave, 0x2000, 2).unwrap(), -0.25);
+        assert_eq!(master.read_sdo::<i64>(slave, 0x2000, 3).unwrap(), -2);
+        assert!(master.read_sdo::<bool>(slave, 0x2000, 4).unwrap());
+        assert_eq!(
+            master.read_sdo::<Bits<4>>(slave, 0x2000, 5).unwrap(),
+            Bits(0b0101)
+        );
+        let name = master.read_sdo::<VisibleString<8>>(slave, 0x2000, 6);
+        assert_eq!(name.unwrap().as_str(), Some("abc"));
+        assert_eq!(
+            master.read_sdo::<[u8; 3]>(slave, 0x2000, 7).unwrap(),
+            [1, 2, 3]
+        );
+
+        master.write_sdo(slave, 0x2000, 1, -3.0_f32).unwrap();
+        assert_eq!(master.read_sdo::<f32>(slave, 0x2000, 1).unwrap(), -3.0);
+        master.write_sdo(slave, 0x2000, 4, false).unwrap();
+        assert_eq!(master.read_sdo_bytes(slave, 0x2000, 4).unwrap(), &[0]);
+        let name = VisibleString::<8>::new("EtherCAT").unwrap();
+        master.write_sdo(slave, 0x2000, 6, name).unwrap();
+        let name = master.read_sdo::<VisibleString<8>>(slave, 0x2000, 6);
+        assert_eq!(name.unwrap().as_str(), Some("EtherCAT"));
+        assert!(VisibleString::<4>::new("EtherCAT").is_none());
+
+        match master.read_sdo::<u32>(slave, 0x2000, 3) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::SizeUnmatch)) => {}
+            other => panic!("{:?}", other),
+        }
+        match master.read_sdo::<VisibleString<2>>(slave, 0x2000, 6) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::SizeUnmatch)) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{interface::SlaveAddress, slave::SyncMode, task::*};
+use crate::{
+    interface::{PhyError, SlaveAddress},
+    slave::SyncMode,
+    task::*,
+};
 
 #[derive(Debug, Clone)]
 pub struct ConfigError {
@@ -47,3 +51,23 @@ pub struct RegisterError {
     pub address: u16,
     pub error: TaskError<()>,
 }
+
+#[derive(Debug, Clone)]
+pub struct FirmwareUpdateError {
+    pub slave_address: SlaveAddress,
+    pub kind: FirmwareUpdateErrorKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum FirmwareUpdateErrorKind {
+    NoMailbox,
+    BootstrapNotSupported,
+    /// The bootstrap mailbox is larger than `MAX_SM_SIZE`.
+    BootstrapMailboxTooLarge(u16),
+    GetBootstrapMailbox(TaskError<SiiTaskError>),
+    SetMailboxSyncManager(RegisterError),
+    AlStateTransition(TaskError<AlStateTransferTaskError>),
+    WriteFirmware(TaskError<FoeErrorKind>),
+    /// The interface failed while the blocking update was waiting.
+    Interface(PhyError),
+}
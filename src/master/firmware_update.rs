@@ -0,0 +1,310 @@
+use crate::{
+    interface::{RawEthernetDevice, SlaveAddress, SocketAccess, TargetSlave},
+    register::{
+        sii::{BootstrapRxMailboxOffset, BootstrapTxMailboxOffset},
+        SyncManagerActivation, SyncManagerControl,
+    },
+    slave::{AlState, Slave, SyncManager, SyncManagerBuilder},
+    task::{AcyclicSocket, MAX_SM_SIZE},
+};
+
+use super::*;
+
+impl<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
+    EtherCatMaster<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
+where
+    D: RawEthernetDevice,
+{
+    /// Writes a firmware file to a slave in the Bootstrap state.
+    /// The slave goes to Init, and its mailbox is switched to the bootstrap mailbox in the SII.
+    /// `progress` is called with the size which has been sent and the size of the firmware.
+    /// The slave ends in Init with the standard mailbox, also if the update fails.
+    pub fn update_firmware<F>(
+        &mut self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        firmware: &[u8],
+        progress: F,
+    ) -> Result<(), FirmwareUpdateError>
+    where
+        F: FnMut(usize, usize),
+    {
+        self.block_on(async {
+            Ok(self
+                .update_firmware_async(slave_address, file_name, password, firmware, progress)
+                .await)
+        })
+        .unwrap_or_else(|err| {
+            Err(FirmwareUpdateError {
+                slave_address,
+                kind: FirmwareUpdateErrorKind::Interface(err),
+            })
+        })
+    }
+
+    /// The general purpose socket is used by the update until it ends.
+    pub async fn update_firmware_async<F>(
+        &self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        firmware: &[u8],
+        mut progress: F,
+    ) -> Result<(), FirmwareUpdateError>
+    where
+        F: FnMut(usize, usize),
+    {
+        let error = |kind| FirmwareUpdateError {
+            slave_address,
+            kind,
+        };
+        let (slave, _) = self.network.slave(slave_address).expect("slave not found");
+        let (standard_rx, standard_tx) = slave
+            .info()
+            .mailbox_rx_sm()
+            .zip(slave.info().mailbox_tx_sm())
+            .ok_or(error(FirmwareUpdateErrorKind::NoMailbox))?;
+        let socket = self.gp_socket().await;
+        let target = TargetSlave::Single(slave_address);
+        socket
+            .change_al_state(target, AlState::Init)
+            .await
+            .map_err(|err| error(FirmwareUpdateErrorKind::AlStateTransition(err)))?;
+        let (bootstrap_rx, bootstrap_tx) = read_bootstrap_mailbox(
+            &socket,
+            slave_address,
+            standard_rx.number(),
+            standard_tx.number(),
+        )
+        .await
+        .map_err(error)?;
+
+        // The bootstrap mailbox is used by a copy of the slave, so that the network is not changed.
+        let mut bootstrap_slave = Slave::default();
+        *bootstrap_slave.info_mut() = slave.info().clone();
+        bootstrap_slave
+            .info_mut()
+            .set_mailbox_sm(bootstrap_rx, bootstrap_tx);
+        let _ = bootstrap_slave.set_mailbox_count(slave.mailbox_count());
+
+        let mut result = set_mailbox_sm(&socket, &bootstrap_slave).await;
+        if result.is_ok() {
+            result = write_firmware_in_bootstrap(
+                &socket,
+                &bootstrap_slave,
+                file_name,
+                password,
+                firmware,
+                &mut progress,
+            )
+            .await;
+        }
+        if result.is_err() {
+            // The first error is reported, even if the slave does not return to Init.
+            let _ = socket.change_al_state(target, AlState::Init).await;
+        }
+        let _ = slave.set_mailbox_count(bootstrap_slave.mailbox_count());
+        // The slave counts its mailboxes from the start again after Init.
+        slave.reset_received_mailbox_count();
+        let restored = set_mailbox_sm(&socket, slave).await;
+        result.and(restored).map_err(error)
+    }
+}
+
+async fn write_firmware_in_bootstrap<S: SocketAccess, F>(
+    socket: &AcyclicSocket<'_, S>,
+    slave: &Slave,
+    file_name: &[u8],
+    password: u32,
+    firmware: &[u8],
+    progress: &mut F,
+) -> Result<(), FirmwareUpdateErrorKind>
+where
+    F: FnMut(usize, usize),
+{
+    let target = TargetSlave::Single(slave.info().slave_address());
+    socket
+        .change_al_state(target, AlState::Bootstrap)
+        .await
+        .map_err(FirmwareUpdateErrorKind::AlStateTransition)?;
+    socket
+        .write_foe_with(slave, file_name, password, |offset, buf| {
+            let size = read_firmware(firmware, offset, buf);
+            progress(offset + size, firmware.len());
+            Ok(size)
+        })
+        .await
+        .map_err(FirmwareUpdateErrorKind::WriteFirmware)?;
+    socket
+        .change_al_state(target, AlState::Init)
+        .await
+        .map_err(FirmwareUpdateErrorKind::AlStateTransition)?;
+    Ok(())
+}
+
+/// Reads the offset and size of the bootstrap mailbox from the SII.
+async fn read_bootstrap_mailbox<S: SocketAccess>(
+    socket: &AcyclicSocket<'_, S>,
+    slave_address: SlaveAddress,
+    rx_number: u8,
+    tx_number: u8,
+) -> Result<(SyncManager, SyncManager), FirmwareUpdateErrorKind> {
+    let (rx, _) = socket
+        .read_sii(slave_address, BootstrapRxMailboxOffset::ADDRESS)
+        .await
+        .map_err(FirmwareUpdateErrorKind::GetBootstrapMailbox)?;
+    let (tx, _) = socket
+        .read_sii(slave_address, BootstrapTxMailboxOffset::ADDRESS)
+        .await
+        .map_err(FirmwareUpdateErrorKind::GetBootstrapMailbox)?;
+    Ok((
+        bootstrap_sm(rx_number, rx.sii_data())?,
+        bootstrap_sm(tx_number, tx.sii_data())?,
+    ))
+}
+
+/// Configures the mailbox sync managers of a slave in Init, as in the info of `slave`.
+async fn set_mailbox_sm<S: SocketAccess>(
+    socket: &AcyclicSocket<'_, S>,
+    slave: &Slave,
+) -> Result<(), FirmwareUpdateErrorKind> {
+    let info = slave.info();
+    let target = TargetSlave::Single(info.slave_address());
+    let (rx, tx) = info
+        .mailbox_rx_sm()
+        .zip(info.mailbox_tx_sm())
+        .ok_or(FirmwareUpdateErrorKind::NoMailbox)?;
+    for (sm, direction) in [(&rx, 1), (&tx, 0)] {
+        let offset = 0x08 * sm.number() as u16;
+        let control = mailbox_sm_control(sm, direction);
+        let mut activation = SyncManagerActivation::new();
+        activation.set_channel_enable(true);
+        // The sync manager is disabled while it is configured.
+        for (address, data) in [
+            (SyncManagerActivation::ADDRESS + offset, &[0][..]),
+            (SyncManagerControl::ADDRESS + offset, &control.0[..]),
+            (SyncManagerActivation::ADDRESS + offset, &activation.0[..]),
+        ] {
+            socket
+                .write_register(target, address, data)
+                .await
+                .map_err(|error| {
+                    FirmwareUpdateErrorKind::SetMailboxSyncManager(RegisterError { address, error })
+                })?;
+        }
+    }
+    Ok(())
+}
+
+/// `sii_data` holds the offset and the size words.
+fn bootstrap_sm(number: u8, sii_data: u64) -> Result<SyncManager, FirmwareUpdateErrorKind> {
+    let start_address = sii_data as u16;
+    let size = (sii_data >> 16) as u16;
+    if size == 0 {
+        return Err(FirmwareUpdateErrorKind::BootstrapNotSupported);
+    }
+    if size > MAX_SM_SIZE {
+        return Err(FirmwareUpdateErrorKind::BootstrapMailboxTooLarge(size));
+    }
+    Ok(SyncManagerBuilder {
+        number,
+        size,
+        start_address,
+    }
+    .build())
+}
+
+fn mailbox_sm_control(sm: &SyncManager, direction: u8) -> SyncManagerControl<[u8; 5]> {
+    let mut control = SyncManagerControl::new();
+    control.set_physical_start_address(sm.start_address());
+    control.set_length(sm.size());
+    control.set_buffer_type(0b10); //mailbox
+    control.set_direction(direction);
+    control.set_dls_user_event_enable(true);
+    control
+}
+
+fn read_firmware(firmware: &[u8], offset: usize, buf: &mut [u8]) -> usize {
+    let rest = firmware.get(offset..).unwrap_or_default();
+    let size = rest.len().min(buf.len());
+    buf[..size].copy_from_slice(&rest[..size]);
+    size
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::FoeErrorCode;
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::{SlaveAddress, TargetSlave};
+    use crate::master::{FirmwareUpdateError, FirmwareUpdateErrorKind};
+    use crate::slave::AlState;
+    use crate::task::{FoeErrorKind, TaskError};
+
+    #[test]
+    fn firmware_update_test() {
+        let mut segment = new_segment(1);
+        segment.slaves_mut()[0].foe_mut().set_password(0x1234);
+        let mut storage = MasterStorage::new();
+        let mut master = storage.master(segment);
+        let slave = SlaveAddress::SlavePosition(0);
+        let target = TargetSlave::Single(slave);
+        master
+            .change_al_state(target, AlState::PreOperational)
+            .unwrap();
+
+        // The packets are larger than the standard mailbox.
+        let firmware: Vec<u8> = (0..600).map(|i| (i * 13) as u8).collect();
+        let mut sent = Vec::new();
+        master
+            .update_firmware(slave, b"firmware.bin", 0x1234, &firmware, |size, entire| {
+                assert_eq!(entire, firmware.len());
+                sent.push(size);
+            })
+            .unwrap();
+        assert_eq!(sent, [244, 488, 600]);
+        let device = master.device();
+        assert_eq!(
+            device.slaves()[0].foe().file(b"firmware.bin"),
+            Some(&firmware[..])
+        );
+        drop(device);
+        assert_eq!(master.read_al_state(target).unwrap().0, AlState::Init);
+        master
+            .change_al_state(target, AlState::PreOperational)
+            .unwrap();
+        let mut data = [0; 16];
+        let size = master.read_sdo_into(slave, 0x1008, 0, &mut data).unwrap();
+        assert_eq!(&data[..size], b"Virtual ESC");
+
+        // The standard mailbox is restored after an error.
+        match master.update_firmware(slave, b"firmware.bin", 0, &firmware, |_, _| {}) {
+            Err(FirmwareUpdateError {
+                kind:
+                    FirmwareUpdateErrorKind::WriteFirmware(TaskError::TaskSpecific(
+                        FoeErrorKind::Error(FoeErrorCode::NoRights),
+                    )),
+                ..
+            }) => {}
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(master.read_al_state(target).unwrap().0, AlState::Init);
+        master
+            .change_al_state(target, AlState::PreOperational)
+            .unwrap();
+        let size = master.read_sdo_into(slave, 0x1008, 0, &mut data).unwrap();
+        assert_eq!(&data[..size], b"Virtual ESC");
+
+        // The bootstrap mailbox does not fit in the buffers of the master.
+        let address = crate::register::sii::BootstrapRxMailboxSize::ADDRESS as usize;
+        master.device_mut().slaves_mut()[0].sii_mut()[address] = 0x800;
+        match master.update_firmware(slave, b"firmware.bin", 0x1234, &firmware, |_, _| {}) {
+            Err(FirmwareUpdateError {
+                kind: FirmwareUpdateErrorKind::BootstrapMailboxTooLarge(0x800),
+                ..
+            }) => {}
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(master.read_al_state(target).unwrap().0, AlState::Init);
+    }
+}
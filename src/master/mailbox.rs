@@ -1,175 +1,1156 @@
-use crate::frame::{Mailbox, MailboxFrame};
-use crate::interface::SlaveAddress;
-use crate::interface::{PduSocket, RawEthernetDevice, SocketHandle, SocketInterface};
+use core::fmt;
+
+use crate::frame::{
+    AbortCode, CoE, Mailbox, MailboxEncodeError, MailboxFrame, MailboxType, OdListType,
+    SdoInfoOpCode, SdoRes,
+};
+use crate::interface::{RawEthernetDevice, SlaveAddress, SocketHandle, SocketInterface};
 use crate::register::SyncManagerStatus;
-use crate::slave::{Network, Slave};
-use crate::task::{CyclicTask, EtherCatSystemTime, MailboxTask, MailboxTaskError, TaskError};
+use crate::slave::{Emergency, Network, Slave};
+use crate::task::{
+    sdo_abort_code, sdo_info_response, sdo_response, set_raw_mailbox, unexpected_sdo_response,
+    CyclicTask, EtherCatSystemTime, MailboxTask, MailboxTaskError, SdoErrorKind, TaskError,
+    Timeouts, MAX_SM_SIZE,
+};
 
-use super::NUM_SOCKETS;
+/// Number of slaves whose mailboxes are serviced in the same cycle. Each has its own socket.
+pub const MAX_MAILBOX_CHANNELS: usize = 4;
+/// Number of requests which are queued or whose responses are not received yet.
+pub const MAX_MAILBOX_SESSIONS: usize = 8;
 
 #[derive(Debug)]
-pub(super) struct MailboxManager {
+struct MailboxChannel {
+    handle: SocketHandle,
     task: MailboxTask,
-    slave_with_mailbox: Option<SlaveAddress>,
+    /// Slave which the channel is servicing.
+    slave_address: Option<SlaveAddress>,
+    /// Session whose request is being written.
+    writing_session: Option<usize>,
+}
+
+/// What a channel does for a slave.
+#[derive(Debug, Clone, Copy)]
+enum Service {
+    Read { wait_full: bool },
+    Write(usize),
+}
+
+/// Mailbox communication during cyclic operation.
+/// Requests are queued per slave, and a slave has only one request whose response is not received.
+/// Slaves are serviced in turn, and a slave whose tx mailbox is full is read before a request is written.
+#[derive(Debug)]
+pub(super) struct MailboxManager {
+    channels: [MailboxChannel; MAX_MAILBOX_CHANNELS],
+    sessions: [Option<MailboxSession>; MAX_MAILBOX_SESSIONS],
+    next_sequence: u32,
+    next_position: u16,
+    timeouts: Timeouts,
 }
 
 impl MailboxManager {
-    pub fn new(task: MailboxTask) -> Self {
+    pub fn new(handles: [SocketHandle; MAX_MAILBOX_CHANNELS]) -> Self {
         Self {
-            task,
-            slave_with_mailbox: None,
+            channels: handles.map(|handle| MailboxChannel {
+                handle,
+                task: MailboxTask::new(),
+                slave_address: None,
+                writing_session: None,
+            }),
+            sessions: Default::default(),
+            next_sequence: 0,
+            next_position: 0,
+            timeouts: Timeouts::default(),
         }
     }
 
-    pub fn process_one_step<'a>(
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+        for channel in self.channels.iter_mut() {
+            channel.task.set_timeouts(timeouts);
+        }
+    }
+
+    pub fn process_one_step<D, const N: usize>(
         &mut self,
         network: &Network,
-        mb_socket: &'a mut PduSocket,
+        sif: &mut SocketInterface<'_, '_, D, N>,
         sys_time: EtherCatSystemTime,
-    ) {
-        if !self.task.is_busy() {
-            if let Some(slave_with_mailbox) = self.slave_with_mailbox {
-                let (slave, _) = network.slave(slave_with_mailbox).unwrap();
-                let tx_sm = slave.info().mailbox_tx_sm().unwrap();
-                self.task.start_to_read(slave_with_mailbox, tx_sm, false);
+    ) where
+        D: RawEthernetDevice,
+    {
+        self.expire_sessions(network, sys_time);
+        for i in 0..MAX_MAILBOX_CHANNELS {
+            if !self.channels[i].task.is_busy() {
+                self.channels[i].slave_address = None;
+                if let Some((slave, service)) = self.next_service(network) {
+                    self.start_service(i, slave, service, sif, sys_time);
+                }
+            }
+            let Self {
+                channels, sessions, ..
+            } = self;
+            let channel = &mut channels[i];
+            let socket = sif
+                .get_socket_mut(&channel.handle)
+                .expect("socket not found");
+            let was_busy = channel.task.is_busy();
+            channel.task.process_one_step(socket, sys_time);
+            if !was_busy || channel.task.is_busy() {
+                continue;
+            }
+            let result = channel.task.wait().expect("task is not complete");
+            if let Some(index) = channel.writing_session.take() {
+                // The result of an abort queued by the manager itself is not returned.
+                if sessions[index].as_ref().is_some_and(|s| s.is_automatic) {
+                    sessions[index] = None;
+                    continue;
+                }
+                if let Some(session) = sessions[index].as_mut() {
+                    session.state = match result {
+                        // The slave does not respond to an abort.
+                        Ok(()) if matches!(session.request, MailboxRequest::AbortSdo { .. }) => {
+                            SessionState::Done(Ok(()))
+                        }
+                        Ok(()) => SessionState::WaitingResponse(sys_time),
+                        Err(err) => SessionState::Done(Err(err.into())),
+                    };
+                    // The data of the request are replaced with those of the response.
+                    session.data.clear();
+                }
+                continue;
+            }
+            let slave_address = channel.task.slave_address();
+            let waiting = sessions
+                .iter_mut()
+                .filter_map(|session| session.as_mut())
+                .find(|session| session.is_waiting_response(slave_address));
+            match result {
+                Ok(()) => {
+                    let mb_frame = MailboxFrame(socket.data_buf());
+                    let slave = network.slave(slave_address).map(|(slave, _)| slave);
+                    // The slave has sent the last mailbox again. It is not the response.
+                    if slave.is_some_and(|slave| slave.is_repeated_mailbox(mb_frame.count())) {
+                        continue;
+                    }
+                    // Emergencies are stored in the slave instead of being returned by received_mailbox.
+                    // Mailboxes of other protocols, e.g. EoE or VoE, are kept in the slave for their readers.
+                    if let Ok(Mailbox::CoE((_, CoE::Emmergency(frame)))) = mb_frame.mailbox() {
+                        if let Some(slave) = slave {
+                            slave.push_emergency(Emergency::new(slave_address, sys_time, &frame));
+                        }
+                    } else if let Some(session) =
+                        waiting.filter(|session| session.accepts(&mb_frame))
+                    {
+                        session.process_response(mb_frame);
+                        let abort = session.abort_request();
+                        if let (Some(slave), Some(abort)) = (slave, abort) {
+                            self.push_abort(slave, abort);
+                        }
+                    } else if let Some(slave) = slave {
+                        slave.push_received_mailbox(mb_frame.0);
+                    }
+                }
+                // The mailbox state in the process data may be older than the last read.
+                Err(TaskError::TaskSpecific(MailboxTaskError::MailboxEmpty)) => {}
+                Err(err) => {
+                    if let Some(session) = waiting {
+                        session.state = SessionState::Done(Err(err.into()));
+                        let abort = session.abort_request();
+                        let slave = network.slave(slave_address);
+                        if let (Some((slave, _)), Some(abort)) = (slave, abort) {
+                            self.push_abort(slave, abort);
+                        }
+                    }
+                }
             }
         }
-        self.task.process_one_step(mb_socket, sys_time);
     }
 
-    pub fn received_mailbox<'a>(
-        &self,
-        mb_socket: &'a PduSocket,
-    ) -> Option<Result<(MailboxSessionId, MailboxFrame<&'a [u8]>), TaskError<MailboxTaskError>>>
-    {
-        if self.task.is_write_mode() {
-            return None;
+    /// Returns the response of the oldest session which is complete.
+    pub fn received_mailbox(
+        &mut self,
+    ) -> Option<(
+        MailboxSessionId,
+        Result<MailboxResponse, TaskError<SdoErrorKind>>,
+    )> {
+        let slot = self
+            .sessions
+            .iter_mut()
+            .filter(|session| matches!(session, Some(s) if s.is_done()))
+            .min_by_key(|session| session.as_ref().map(|s| s.sequence))?;
+        let session = slot.take()?;
+        match &session.state {
+            SessionState::Done(result) => {
+                let result = result.clone().map(|()| session.response());
+                Some((session.id, result))
+            }
+            _ => unreachable!(),
         }
-        let _ = self.task.wait()?;
-        let mb_frame = MailboxFrame(mb_socket.data_buf());
-        let session_id = MailboxSessionId {
-            slave_address: self.task.slave_address(),
-            mailbox_count: mb_frame.count(),
-        };
-        Some(Ok((session_id, mb_frame)))
     }
 
+    /// Sets whether the tx mailbox of each slave is full from the mailbox state in the process data.
     pub fn find_slave_with_mailbox_from_process_data(
         &mut self,
         network: &Network,
         logical_address_offset: u32,
         process_data_image: &[u8],
     ) {
-        for (pos, (slave, _)) in network.slaves().enumerate() {
-            if let Some(ref fmmu_config) = slave.fmmu_config()[2] {
-                let tx_sm_number = slave.info().mailbox_tx_sm().unwrap().number();
-                let mb_tx_sm_status = SyncManagerStatus::ADDRESS + 0x08 * tx_sm_number as u16;
-                assert_eq!(mb_tx_sm_status, fmmu_config.physical_address());
-
-                let mut buf = [0; SyncManagerStatus::SIZE];
-                fmmu_config
-                    .read_to_buffer(logical_address_offset, process_data_image, &mut buf)
-                    .unwrap();
+        for (slave, _) in network.slaves() {
+            let (Some(fmmu_config), Some(tx_sm)) =
+                (&slave.fmmu_config()[2], slave.info().mailbox_tx_sm())
+            else {
+                continue;
+            };
+            let mb_tx_sm_status = SyncManagerStatus::ADDRESS + 0x08 * tx_sm.number() as u16;
+            debug_assert_eq!(mb_tx_sm_status, fmmu_config.physical_address());
+            // The FMMU may have no logical address, or be mapped outside this image.
+            let mut buf = [0; SyncManagerStatus::SIZE];
+            if let Some(()) =
+                fmmu_config.read_to_buffer(logical_address_offset, process_data_image, &mut buf)
+            {
                 let sm_status = SyncManagerStatus(buf);
-                if sm_status.is_mailbox_full() {
-                    self.slave_with_mailbox = Some(SlaveAddress::SlavePosition(pos as u16));
-                }
+                slave.set_tx_mailbox_full(sm_status.is_mailbox_full());
             }
         }
-        self.slave_with_mailbox = None;
     }
 
-    pub fn try_get_mailbox_request_interface<'a>(
+    /// Returns None if there is no room for another session.
+    pub fn try_get_mailbox_request_interface<'a, 'b, 'c, 'd>(
         &'a mut self,
-    ) -> Option<MailboxRequestInterface<'a>> {
-        if self.task.is_busy() {
+        network: &'a Network<'b, 'c, 'd>,
+    ) -> Option<MailboxReqIfWrapper<'a, 'b, 'c, 'd>> {
+        if self.sessions.iter().all(|session| session.is_some()) {
+            return None;
+        }
+        Some(MailboxReqIfWrapper {
+            manager: self,
+            network,
+        })
+    }
+
+    fn push_session(
+        &mut self,
+        slave: &Slave,
+        request: MailboxRequest,
+        data: &[u8],
+    ) -> Option<MailboxSessionId> {
+        let data = MailboxData::new(data)?;
+        let slot = self.sessions.iter_mut().find(|session| session.is_none())?;
+        let id = MailboxSessionId {
+            slave_address: slave.info().slave_address(),
+            mailbox_count: slave.increment_mb_count(),
+        };
+        *slot = Some(MailboxSession {
+            id: id.clone(),
+            sequence: self.next_sequence,
+            request,
+            data,
+            fragments_left: None,
+            state: SessionState::Queued(None),
+            is_automatic: false,
+        });
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        Some(id)
+    }
+
+    /// Queues an abort which the manager sends on its own. It is skipped if there is no room for it.
+    fn push_abort(&mut self, slave: &Slave, request: MailboxRequest) {
+        if let Some(id) = self.push_session(slave, request, &[]) {
+            let session = self.sessions.iter_mut().flatten().find(|s| s.id == id);
+            if let Some(session) = session {
+                session.is_automatic = true;
+            }
+        }
+    }
+
+    /// A request times out if it is not written in `mailbox_request`, and its response in `mailbox_response`.
+    /// A queued request is timed from when it is the next one of its slave, with `mailbox_response`.
+    /// The transfer of an SDO whose request has been written, or is being written, is aborted.
+    fn expire_sessions(&mut self, network: &Network, sys_time: EtherCatSystemTime) {
+        for i in 0..MAX_MAILBOX_SESSIONS {
+            let Some(session) = self.sessions[i].as_ref() else {
+                continue;
+            };
+            let (start, timeout) = match session.state {
+                SessionState::Queued(None) => {
+                    if self.is_next_request(i) {
+                        let session = self.sessions[i].as_mut().unwrap();
+                        session.state = SessionState::Queued(Some(sys_time));
+                    }
+                    continue;
+                }
+                SessionState::Writing(start) => (start, self.timeouts.mailbox_request),
+                SessionState::Queued(Some(start)) | SessionState::WaitingResponse(start) => {
+                    (start, self.timeouts.mailbox_response)
+                }
+                SessionState::Done(_) => continue,
+            };
+            if sys_time.elapsed_since(start) <= timeout {
+                continue;
+            }
+            let is_queued = matches!(session.state, SessionState::Queued(_));
+            // The channel which is writing the request is released for other slaves.
+            for channel in self.channels.iter_mut() {
+                if channel.writing_session == Some(i) {
+                    channel.task = MailboxTask::new();
+                    channel.task.set_timeouts(self.timeouts);
+                    channel.slave_address = None;
+                    channel.writing_session = None;
+                }
+            }
+            let slot = &mut self.sessions[i];
+            if slot.as_ref().is_some_and(|s| s.is_automatic) {
+                *slot = None;
+                continue;
+            }
+            let session = slot.as_mut().unwrap();
+            session.state = SessionState::Done(Err(TaskError::Timeout));
+            let abort = session.abort_request().filter(|_| !is_queued);
+            let slave = network.slave(session.id.slave_address);
+            if let (Some((slave, _)), Some(abort)) = (slave, abort) {
+                self.push_abort(slave, abort);
+            }
+        }
+    }
+
+    /// Whether the queued session is written next to its slave.
+    fn is_next_request(&self, index: usize) -> bool {
+        let Some(session) = self.sessions[index].as_ref() else {
+            return false;
+        };
+        self.sessions
+            .iter()
+            .flatten()
+            .filter(|other| other.id.slave_address == session.id.slave_address)
+            .all(|other| match other.state {
+                SessionState::Queued(_) => other.sequence >= session.sequence,
+                SessionState::Writing(_) | SessionState::WaitingResponse(_) => false,
+                SessionState::Done(_) => true,
+            })
+    }
+
+    /// Finds the next slave which needs a service, from the slave after the last one serviced.
+    fn next_service<'n>(&mut self, network: &'n Network) -> Option<(&'n Slave, Service)> {
+        let num_slaves = network.num_slaves();
+        for i in 0..num_slaves {
+            let position = (self.next_position + i) % num_slaves;
+            let Some((slave, _)) = network.slave(SlaveAddress::SlavePosition(position)) else {
+                continue;
+            };
+            let slave_address = slave.info().slave_address();
+            if slave.info().mailbox_tx_sm().is_none()
+                || slave.info().mailbox_rx_sm().is_none()
+                || self
+                    .channels
+                    .iter()
+                    .any(|channel| channel.slave_address == Some(slave_address))
+            {
+                continue;
+            }
+            if let Some(service) = self.service_for(slave) {
+                self.next_position = (position + 1) % num_slaves;
+                return Some((slave, service));
+            }
+        }
+        None
+    }
+
+    fn service_for(&self, slave: &Slave) -> Option<Service> {
+        if slave.is_tx_mailbox_full() {
+            return Some(Service::Read { wait_full: false });
+        }
+        let slave_address = slave.info().slave_address();
+        let sessions = || {
+            self.sessions
+                .iter()
+                .filter_map(|session| session.as_ref())
+                .filter(move |session| session.id.slave_address == slave_address)
+        };
+        if sessions().any(|session| session.is_waiting_response(slave_address)) {
+            // Without the mailbox state in the process data, the channel waits for the response.
+            let has_mailbox_state = slave.fmmu_config()[2]
+                .as_ref()
+                .is_some_and(|fmmu_config| fmmu_config.logical_address().is_some());
+            return (!has_mailbox_state).then_some(Service::Read { wait_full: true });
+        }
+        if sessions().any(|session| matches!(session.state, SessionState::Writing(_))) {
             return None;
         }
+        self.sessions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, session)| Some((i, session.as_ref()?)))
+            .filter(|(_, session)| {
+                session.id.slave_address == slave_address
+                    && matches!(session.state, SessionState::Queued(_))
+            })
+            .min_by_key(|(_, session)| session.sequence)
+            .map(|(i, _)| Service::Write(i))
+    }
+
+    fn start_service<D, const N: usize>(
+        &mut self,
+        channel_index: usize,
+        slave: &Slave,
+        service: Service,
+        sif: &mut SocketInterface<'_, '_, D, N>,
+        sys_time: EtherCatSystemTime,
+    ) where
+        D: RawEthernetDevice,
+    {
+        let channel = &mut self.channels[channel_index];
+        let slave_address = slave.info().slave_address();
+        match service {
+            Service::Read { wait_full } => {
+                slave.set_tx_mailbox_full(false);
+                let tx_sm = slave.info().mailbox_tx_sm().unwrap();
+                channel.task.start_to_read(slave_address, tx_sm, wait_full);
+            }
+            Service::Write(index) => {
+                let session = self.sessions[index].as_mut().unwrap();
+                let rx_sm = slave.info().mailbox_rx_sm().unwrap();
+                let socket = sif
+                    .get_socket_mut(&channel.handle)
+                    .expect("socket not found");
+                let mut mb_frame = MailboxFrame(socket.data_buf_mut());
+                let result = session
+                    .set_request(&mut mb_frame)
+                    .map_err(MailboxTaskError::from)
+                    .and_then(|()| {
+                        let size = MailboxFrame::HEADER_SIZE + mb_frame.length() as usize;
+                        if size <= rx_sm.size() as usize {
+                            Ok(())
+                        } else {
+                            Err(MailboxTaskError::BufferSmall)
+                        }
+                    });
+                if let Err(err) = result {
+                    // An abort queued by the manager itself is dropped instead of being returned.
+                    if session.is_automatic {
+                        self.sessions[index] = None;
+                    } else {
+                        session.state = SessionState::Done(Err(SdoErrorKind::Mailbox(err).into()));
+                    }
+                    return;
+                }
+                session.state = SessionState::Writing(sys_time);
+                channel.writing_session = Some(index);
+                channel.task.start_to_write(slave_address, rx_sm, true);
+            }
+        }
+        channel.slave_address = Some(slave_address);
+    }
+}
 
-        let Self { ref mut task, .. } = self;
+/// Request of a mailbox session. The data of a download are in the session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MailboxRequest {
+    ReadSdo {
+        index: u16,
+        sub_index: u8,
+    },
+    WriteSdo {
+        index: u16,
+        sub_index: u8,
+    },
+    AbortSdo {
+        index: u16,
+        sub_index: u8,
+        abort_code: AbortCode,
+    },
+    OdList(OdListType),
+    ObjectDescription(u16),
+    EntryDescription {
+        index: u16,
+        sub_index: u8,
+        value_info: u8,
+    },
+    /// A mailbox of any protocol, whose payload is in the session.
+    Raw(MailboxType),
+}
 
-        Some(MailboxRequestInterface { task })
+impl MailboxRequest {
+    fn protocol(&self) -> MailboxType {
+        match self {
+            MailboxRequest::ReadSdo { .. }
+            | MailboxRequest::WriteSdo { .. }
+            | MailboxRequest::AbortSdo { .. }
+            | MailboxRequest::OdList(_)
+            | MailboxRequest::ObjectDescription(_)
+            | MailboxRequest::EntryDescription { .. } => MailboxType::CoE,
+            MailboxRequest::Raw(mb_type) => *mb_type,
+        }
     }
 }
 
-#[derive(Debug)]
-pub(super) struct MailboxRequestInterface<'a> {
-    task: &'a mut MailboxTask,
+#[derive(Debug, Clone)]
+enum SessionState {
+    /// The time from which the request is the next one of its slave.
+    Queued(Option<EtherCatSystemTime>),
+    /// The request has begun to be written at the time.
+    Writing(EtherCatSystemTime),
+    /// The request has been written at the time.
+    WaitingResponse(EtherCatSystemTime),
+    /// The response is decoded into the data of the session.
+    Done(Result<(), TaskError<SdoErrorKind>>),
 }
 
-impl<'a> MailboxRequestInterface<'a> {
-    pub fn request(
+#[derive(Debug, Clone)]
+struct MailboxSession {
+    id: MailboxSessionId,
+    /// Order in which the sessions are requested.
+    sequence: u32,
+    request: MailboxRequest,
+    /// The data of the request until it is written, and then the data of the response.
+    data: MailboxData,
+    /// Number expected in the next fragment of an SDO information response.
+    fragments_left: Option<u16>,
+    state: SessionState,
+    /// The session is an abort queued by the manager, whose result is not returned.
+    is_automatic: bool,
+}
+
+impl MailboxSession {
+    fn is_done(&self) -> bool {
+        matches!(self.state, SessionState::Done(_))
+    }
+
+    fn is_waiting_response(&self, slave_address: SlaveAddress) -> bool {
+        self.id.slave_address == slave_address
+            && matches!(self.state, SessionState::WaitingResponse(_))
+    }
+
+    /// Abort of the SDO transfer which the slave may still be in, after the session has failed.
+    fn abort_request(&self) -> Option<MailboxRequest> {
+        let (MailboxRequest::ReadSdo { index, sub_index }
+        | MailboxRequest::WriteSdo { index, sub_index }) = self.request
+        else {
+            return None;
+        };
+        let SessionState::Done(Err(err)) = &self.state else {
+            return None;
+        };
+        let abort_code = sdo_abort_code(err)?;
+        Some(MailboxRequest::AbortSdo {
+            index,
+            sub_index,
+            abort_code,
+        })
+    }
+
+    /// Whether the mailbox is of the protocol of the request, or an error mailbox.
+    fn accepts(&self, mb_frame: &MailboxFrame<&[u8]>) -> bool {
+        let mb_type = mb_frame.mb_type();
+        mb_type == self.request.protocol() || mb_type == MailboxType::Error
+    }
+
+    fn set_request(
+        &self,
+        mb_frame: &mut MailboxFrame<&mut [u8]>,
+    ) -> Result<(), MailboxEncodeError> {
+        let mailbox = match self.request {
+            MailboxRequest::Raw(mb_type) => {
+                let count = self.id.mailbox_count;
+                return set_raw_mailbox(mb_frame, count, mb_type, self.data.data());
+            }
+            MailboxRequest::ReadSdo { index, sub_index } => {
+                Mailbox::new_sdo_upload_request(index, sub_index)
+            }
+            MailboxRequest::WriteSdo { index, sub_index } => {
+                Mailbox::new_sdo_download_request(index, sub_index, self.data.data())
+            }
+            MailboxRequest::AbortSdo {
+                index,
+                sub_index,
+                abort_code,
+            } => Mailbox::new_sdo_abort_request(index, sub_index, abort_code),
+            MailboxRequest::OdList(list_type) => Mailbox::new_sdo_info_od_list_request(list_type),
+            MailboxRequest::ObjectDescription(index) => {
+                Mailbox::new_sdo_info_object_description_request(index)
+            }
+            MailboxRequest::EntryDescription {
+                index,
+                sub_index,
+                value_info,
+            } => Mailbox::new_sdo_info_entry_description_request(index, sub_index, value_info),
+        };
+        mb_frame.set_mailbox(&mailbox)?;
+        mb_frame.set_count(self.id.mailbox_count);
+        Ok(())
+    }
+
+    /// The session is complete unless more fragments of an SDO information follow.
+    fn process_response(&mut self, mb_frame: MailboxFrame<&[u8]>) {
+        let op_code = match self.request {
+            MailboxRequest::ReadSdo { .. }
+            | MailboxRequest::WriteSdo { .. }
+            | MailboxRequest::AbortSdo { .. } => {
+                let result =
+                    sdo_response(&mb_frame).and_then(|sdo_res| self.set_sdo_result(sdo_res));
+                self.state = SessionState::Done(result);
+                return;
+            }
+            MailboxRequest::OdList(_) => SdoInfoOpCode::GetOdListRes,
+            MailboxRequest::ObjectDescription(_) => SdoInfoOpCode::GetObjectDescriptionRes,
+            MailboxRequest::EntryDescription { .. } => SdoInfoOpCode::GetEntryDescriptionRes,
+            MailboxRequest::Raw(_) => {
+                self.state = SessionState::Done(self.set_raw_result(mb_frame));
+                return;
+            }
+        };
+        let result = sdo_info_response(mb_frame, op_code, &mut self.fragments_left).and_then(
+            |(incomplete, data)| {
+                if self.data.extend(data) {
+                    Ok(incomplete)
+                } else {
+                    Err(SdoErrorKind::BufferSmall.into())
+                }
+            },
+        );
+        match result {
+            Ok(true) => {}
+            Ok(false) => self.state = SessionState::Done(Ok(())),
+            Err(err) => self.state = SessionState::Done(Err(err)),
+        }
+    }
+
+    /// The payload of the mailbox is stored in the session. An error mailbox is an error.
+    fn set_raw_result(
         &mut self,
-        slave: &Slave,
-        mailbox: &mut Mailbox,
-        mb_buf: &mut [u8],
-    ) -> MailboxSessionId {
-        let mut mb_frame = MailboxFrame(mb_buf);
-        mb_frame.set_mailbox(mailbox).unwrap();
-        let rx_sm = slave.info().mailbox_rx_sm().unwrap();
-        let session_id = MailboxSessionId {
-            slave_address: slave.info().slave_address(),
-            mailbox_count: slave.increment_mb_count(),
+        mb_frame: MailboxFrame<&[u8]>,
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        if mb_frame.mb_type() == MailboxType::Error {
+            return match mb_frame.mailbox() {
+                Ok(Mailbox::Error(detail)) => Err(SdoErrorKind::ErrorMailbox(detail).into()),
+                _ => Err(SdoErrorKind::Mailbox(MailboxTaskError::BufferSmall).into()),
+            };
+        }
+        let data = mb_frame.without_header();
+        let payload = &data[..(mb_frame.length() as usize).min(data.len())];
+        if self.data.extend(payload) {
+            Ok(())
+        } else {
+            Err(SdoErrorKind::BufferSmall.into())
+        }
+    }
+
+    /// The uploaded data are stored in the session.
+    fn set_sdo_result(&mut self, sdo_res: SdoRes) -> Result<(), TaskError<SdoErrorKind>> {
+        match (self.request, sdo_res) {
+            (MailboxRequest::ReadSdo { .. }, SdoRes::Upload(data)) => {
+                if self.data.extend(data) {
+                    Ok(())
+                } else {
+                    Err(SdoErrorKind::BufferSmall.into())
+                }
+            }
+            // An object which needs a segmented upload is not read in a session.
+            // The upload is aborted by the manager.
+            (MailboxRequest::ReadSdo { .. }, SdoRes::SegmentedUpload { .. }) => {
+                Err(SdoErrorKind::BufferSmall.into())
+            }
+            (MailboxRequest::WriteSdo { .. }, SdoRes::DownLoad) => Ok(()),
+            (_, other) => Err(unexpected_sdo_response(other)),
+        }
+    }
+
+    fn response(&self) -> MailboxResponse {
+        match self.request {
+            MailboxRequest::ReadSdo { .. } => MailboxResponse::SdoUpload(self.data.clone()),
+            MailboxRequest::WriteSdo { .. } => MailboxResponse::SdoDownload,
+            MailboxRequest::AbortSdo { .. } => MailboxResponse::SdoAbort,
+            MailboxRequest::Raw(_) => MailboxResponse::Raw(self.data.clone()),
+            _ => MailboxResponse::SdoInfo(self.data.clone()),
+        }
+    }
+}
+
+/// Data of a mailbox session, which fit in a mailbox.
+#[derive(Clone, PartialEq)]
+pub struct MailboxData {
+    buf: [u8; MAX_SM_SIZE as usize],
+    len: usize,
+}
+
+impl MailboxData {
+    fn new(data: &[u8]) -> Option<Self> {
+        let mut mailbox_data = Self {
+            buf: [0; MAX_SM_SIZE as usize],
+            len: 0,
         };
-        mb_frame.set_count(session_id.mailbox_count);
-        self.task
-            .start_to_write(session_id.slave_address, rx_sm, false);
-        session_id
+        mailbox_data.extend(data).then_some(mailbox_data)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
     }
+
+    /// Returns false if the data do not fit.
+    fn extend(&mut self, data: &[u8]) -> bool {
+        match self.buf.get_mut(self.len..self.len + data.len()) {
+            Some(dst) => {
+                dst.copy_from_slice(data);
+                self.len += data.len();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for MailboxData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MailboxData").field(&self.data()).finish()
+    }
+}
+
+/// Response of a mailbox session, decoded according to its request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MailboxResponse {
+    /// Data of the object read by `read_sdo_request`.
+    SdoUpload(MailboxData),
+    SdoDownload,
+    /// The abort has been written. The slave does not respond to it.
+    SdoAbort,
+    /// Response of an SDO information request, joined from its fragments.
+    /// It begins with the header of the response, e.g. `ObjectDescriptionFrame`.
+    SdoInfo(MailboxData),
+    /// Payload of the mailbox which answers `raw_mailbox_request`, without the mailbox header.
+    Raw(MailboxData),
 }
 
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct MailboxSessionId {
     slave_address: SlaveAddress,
     mailbox_count: u8,
 }
 
+impl MailboxSessionId {
+    pub fn slave_address(&self) -> SlaveAddress {
+        self.slave_address
+    }
+}
+
+/// Queues requests, which are sent in `EtherCatMaster::process`.
+/// Each method returns None if there is no room for the session, the data are too large,
+/// or the slave is not found or has no mailbox.
 #[derive(Debug)]
-pub struct MailboxReqIfWrapper<'a, 'b, 'frame, 'socket, D>
-where
-    D: RawEthernetDevice,
-{
-    mif: MailboxRequestInterface<'a>,
-    sif: &'b mut SocketInterface<'frame, 'socket, D, NUM_SOCKETS>,
-    mb_handle: SocketHandle,
-}
-
-impl<'a, 'b, 'frame, 'socket, D> MailboxReqIfWrapper<'a, 'b, 'frame, 'socket, D>
-where
-    D: RawEthernetDevice,
-{
-    pub(super) fn new(
-        mif: MailboxRequestInterface<'a>,
-        sif: &'b mut SocketInterface<'frame, 'socket, D, NUM_SOCKETS>,
-        mb_handle: SocketHandle,
-    ) -> Self {
-        Self {
-            mif,
-            sif,
-            mb_handle,
+pub struct MailboxReqIfWrapper<'a, 'b, 'c, 'd> {
+    manager: &'a mut MailboxManager,
+    network: &'a Network<'b, 'c, 'd>,
+}
+
+impl<'a, 'b, 'c, 'd> MailboxReqIfWrapper<'a, 'b, 'c, 'd> {
+    fn request(
+        &mut self,
+        slave_address: SlaveAddress,
+        request: MailboxRequest,
+        data: &[u8],
+    ) -> Option<MailboxSessionId> {
+        let (slave, _) = self.network.slave(slave_address)?;
+        if slave.info().mailbox_rx_sm().is_none() || slave.info().mailbox_tx_sm().is_none() {
+            return None;
         }
+        self.manager.push_session(slave, request, data)
     }
 
     pub fn write_sdo_request(
         &mut self,
-        slave: &Slave,
+        slave_address: SlaveAddress,
         index: u16,
         sub_index: u8,
         data: &[u8],
-    ) -> MailboxSessionId {
-        let mut mailbox = Mailbox::new_sdo_download_request(index, sub_index, data);
-        let socket = self.sif.get_socket_mut(&self.mb_handle).unwrap();
-        self.mif.request(slave, &mut mailbox, socket.data_buf_mut())
+    ) -> Option<MailboxSessionId> {
+        let request = MailboxRequest::WriteSdo { index, sub_index };
+        self.request(slave_address, request, data)
     }
 
     pub fn read_sdo_request(
         &mut self,
-        slave: &Slave,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+    ) -> Option<MailboxSessionId> {
+        let request = MailboxRequest::ReadSdo { index, sub_index };
+        self.request(slave_address, request, &[])
+    }
+
+    /// Cancels a transfer of the object.
+    /// An SDO session which times out or fails after its request is written is aborted automatically.
+    pub fn abort_sdo_request(
+        &mut self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        abort_code: AbortCode,
+    ) -> Option<MailboxSessionId> {
+        let request = MailboxRequest::AbortSdo {
+            index,
+            sub_index,
+            abort_code,
+        };
+        self.request(slave_address, request, &[])
+    }
+
+    /// The response may consist of several mailboxes, which are joined. See `SdoInfo::Response`.
+    pub fn read_od_list_request(
+        &mut self,
+        slave_address: SlaveAddress,
+        list_type: OdListType,
+    ) -> Option<MailboxSessionId> {
+        self.request(slave_address, MailboxRequest::OdList(list_type), &[])
+    }
+
+    pub fn read_object_description_request(
+        &mut self,
+        slave_address: SlaveAddress,
+        index: u16,
+    ) -> Option<MailboxSessionId> {
+        let request = MailboxRequest::ObjectDescription(index);
+        self.request(slave_address, request, &[])
+    }
+
+    pub fn read_entry_description_request(
+        &mut self,
+        slave_address: SlaveAddress,
         index: u16,
         sub_index: u8,
-    ) -> MailboxSessionId {
-        let mut mailbox = Mailbox::new_sdo_upload_request(index, sub_index);
-        let socket = self.sif.get_socket_mut(&self.mb_handle).unwrap();
-        self.mif.request(slave, &mut mailbox, socket.data_buf_mut())
+        value_info: u8,
+    ) -> Option<MailboxSessionId> {
+        let request = MailboxRequest::EntryDescription {
+            index,
+            sub_index,
+            value_info,
+        };
+        self.request(slave_address, request, &[])
+    }
+
+    /// Sends `payload` in a mailbox of `mb_type`, and receives the next mailbox of the same type.
+    /// An error mailbox from the slave is returned as `SdoErrorKind::ErrorMailbox`.
+    pub fn raw_mailbox_request(
+        &mut self,
+        slave_address: SlaveAddress,
+        mb_type: MailboxType,
+        payload: &[u8],
+    ) -> Option<MailboxSessionId> {
+        self.request(slave_address, MailboxRequest::Raw(mb_type), payload)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::{AbortCode, EmmergencyFrame, MailboxType, OdListFrame, OdListType};
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::simulator::SdoAccess;
+    use crate::interface::SlaveAddress;
+    use crate::master::mailbox::{MailboxResponse, MailboxSessionId};
+    use crate::register::sii::{
+        MailboxProtocol, StandardRxMailboxOffset, StandardRxMailboxSize, StandardTxMailboxOffset,
+        StandardTxMailboxSize,
+    };
+    use crate::task::{EtherCatSystemTime, SdoErrorKind, TaskError, Timeouts};
+    use crate::EtherCatMaster;
+    use core::time::Duration;
+
+    #[test]
+    fn mailbox_session_test() {
+        let mut pdo_buffer = vec![0; 1500];
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(3));
+        master.configure_slaves_for_operation().unwrap();
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+
+        let mut emergency = EmmergencyFrame::new();
+        emergency.set_error_code(0x8130);
+        master.device_mut().slaves_mut()[2].push_emergency(&emergency);
+
+        let slave0 = SlaveAddress::SlavePosition(0);
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        let write = mif
+            .write_sdo_request(slave0, 0x7000, 1, &[0xEF, 0xBE])
+            .unwrap();
+        // It is sent after the response to the write.
+        let read = mif.read_sdo_request(slave0, 0x7000, 1).unwrap();
+        let reads = [1, 2].map(|position| {
+            mif.read_sdo_request(SlaveAddress::SlavePosition(position), 0x1018, 2)
+                .unwrap()
+        });
+        let od_list = mif.read_od_list_request(slave0, OdListType::All).unwrap();
+        let missing = mif.read_sdo_request(slave0, 0x2000, 1).unwrap();
+        assert_eq!(reads[1].slave_address(), SlaveAddress::StationAddress(3));
+
+        let mut responses = Vec::new();
+        let mut time = 0;
+        while responses.len() < 6 && time < 100_000_000 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+            while let Some(response) = master.received_mailbox() {
+                responses.push(response);
+            }
+        }
+        assert_eq!(responses.len(), 6);
+        // The first requests to the three slaves are serviced at the same time.
+        let position = |id: &MailboxSessionId| responses.iter().position(|(i, _)| i == id);
+        assert!(position(&reads[0]) < position(&read));
+        assert!(position(&reads[1]) < position(&read));
+
+        let response = |id: &MailboxSessionId| {
+            responses
+                .iter()
+                .find(|(i, _)| i == id)
+                .map(|(_, response)| response.clone())
+                .unwrap()
+        };
+        assert_eq!(response(&write).unwrap(), MailboxResponse::SdoDownload);
+        match response(&read).unwrap() {
+            MailboxResponse::SdoUpload(data) => assert_eq!(data.data(), &[0xEF, 0xBE]),
+            other => panic!("{:?}", other),
+        }
+        for id in reads.iter() {
+            match response(id).unwrap() {
+                MailboxResponse::SdoUpload(data) => assert_eq!(data.data().len(), 4),
+                other => panic!("{:?}", other),
+            }
+        }
+        match response(&od_list).unwrap() {
+            MailboxResponse::SdoInfo(data) => {
+                let od_list = OdListFrame(data.data());
+                assert_eq!(od_list.list_type(), OdListType::All);
+                assert!(od_list.indexes().any(|index| index == 0x1018));
+            }
+            other => panic!("{:?}", other),
+        }
+        match response(&missing) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::AbortCode(code))) => {
+                assert_eq!(code, AbortCode::DoesNotExistInDict)
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // The emergency is read because of the mailbox state in the process data.
+        let emergency = master.pop_emergency().unwrap();
+        assert_eq!(emergency.slave_address, SlaveAddress::StationAddress(3));
+
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        let abort = mif
+            .abort_sdo_request(slave0, 0x7000, 1, AbortCode::Timeout)
+            .unwrap();
+        let mut response = None;
+        while response.is_none() && time < 200_000_000 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+            response = master.received_mailbox();
+        }
+        let (id, response) = response.unwrap();
+        assert_eq!(id, abort);
+        assert_eq!(response.unwrap(), MailboxResponse::SdoAbort);
+        assert_eq!(
+            master.device_mut().slaves_mut()[0].sdo_aborts(),
+            [(0x7000, 1, AbortCode::Timeout)]
+        );
+    }
+
+    #[test]
+    fn mailbox_session_without_mailbox_state_test() {
+        let mut pdo_buffer = vec![0; 1500];
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+        // The FMMU of the mailbox state has no logical address before the configuration.
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        let read = mif.read_sdo_request(slave, 0x1018, 1).unwrap();
+        let mut response = None;
+        let mut time = 0;
+        while response.is_none() && time < 100_000_000 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+            response = master.received_mailbox();
+        }
+        let (id, response) = response.unwrap();
+        assert_eq!(id, read);
+        match response.unwrap() {
+            MailboxResponse::SdoUpload(data) => assert_eq!(data.data(), &0x0ABC_u32.to_le_bytes()),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn mailbox_session_abort_test() {
+        let mut segment = new_segment(1);
+        let blob: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        segment.slaves_mut()[0]
+            .od_mut()
+            .insert(0x2000, 0, SdoAccess::ReadWrite, &blob);
+        let mut pdo_buffer = vec![0; 1500];
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(segment);
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut time = 0;
+        let mut run = |master: &mut EtherCatMaster<_>| {
+            let mut responses = Vec::new();
+            for _ in 0..100 {
+                time += 1_000_000;
+                master.process(EtherCatSystemTime(time)).unwrap();
+                while let Some(response) = master.received_mailbox() {
+                    responses.push(response);
+                }
+            }
+            responses
+        };
+
+        // The segmented upload which the session does not continue is aborted.
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        let read = mif.read_sdo_request(slave, 0x2000, 0).unwrap();
+        let responses = run(&mut master);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].0, read);
+        match &responses[0].1 {
+            Err(TaskError::TaskSpecific(SdoErrorKind::BufferSmall)) => {}
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            master.device_mut().slaves_mut()[0].sdo_aborts(),
+            [(0x2000, 0, AbortCode::OutsideMemoryRange)]
+        );
+
+        // The response is not read before the timeout, and the transfer is aborted.
+        let timeouts = Timeouts {
+            mailbox_response: Duration::ZERO,
+            ..master.timeouts()
+        };
+        master.set_timeouts(timeouts);
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        let read = mif.read_sdo_request(slave, 0x1018, 1).unwrap();
+        let responses = run(&mut master);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].0, read);
+        match &responses[0].1 {
+            Err(TaskError::Timeout) => {}
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            master.device_mut().slaves_mut()[0].sdo_aborts()[1..],
+            [(0x1018, 1, AbortCode::Timeout)]
+        );
+    }
+
+    #[test]
+    fn mailbox_session_with_other_protocol_test() {
+        let mut pdo_buffer = vec![0; 1500];
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+        master.device_mut().slaves_mut()[0].set_voe_handler(|request| Some(request.to_vec()));
+
+        // The VoE response is read while the session is waiting for the SDO response.
+        let slave = SlaveAddress::SlavePosition(0);
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 1, 2, 3, 4];
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        let read = mif.read_sdo_request(slave, 0x1018, 1).unwrap();
+        let mut response = None;
+        let mut time = 0;
+        while response.is_none() && time < 100_000_000 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+            response = master.received_mailbox();
+        }
+        let (id, response) = response.unwrap();
+        assert_eq!(id, read);
+        match response.unwrap() {
+            MailboxResponse::SdoUpload(data) => assert_eq!(data.data(), &0x0ABC_u32.to_le_bytes()),
+            other => panic!("{:?}", other),
+        }
+
+        // The VoE response has been kept in the slave.
+        let mut data = [0; 128];
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &request);
+    }
+
+    #[test]
+    fn mailbox_session_without_mailbox_test() {
+        let mut segment = new_segment(2);
+        // The last slave has no mailbox, e.g. a coupler.
+        for address in [
+            StandardRxMailboxOffset::ADDRESS,
+            StandardRxMailboxSize::ADDRESS,
+            StandardTxMailboxOffset::ADDRESS,
+            StandardTxMailboxSize::ADDRESS,
+            MailboxProtocol::ADDRESS,
+        ] {
+            segment.slaves_mut()[1].sii_mut()[address as usize] = 0;
+        }
+        let mut pdo_buffer = vec![0; 1500];
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(segment);
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+
+        // Neither the slave without mailbox nor an unknown slave takes a session.
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        for position in [1, 2] {
+            let slave = SlaveAddress::SlavePosition(position);
+            assert_eq!(mif.read_sdo_request(slave, 0x1018, 1), None);
+        }
+        assert!(mif
+            .read_sdo_request(SlaveAddress::SlavePosition(0), 0x1018, 1)
+            .is_some());
+    }
+
+    #[test]
+    fn mailbox_session_expire_test() {
+        let mut pdo_buffer = vec![0; 1500];
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+        master.configure_slaves_for_operation().unwrap();
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+
+        // The request waits while the slave is read, and times out before it is written.
+        let frame: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        for _ in 0..20 {
+            master.device_mut().slaves_mut()[0].send_eoe_frame(&frame);
+        }
+        let mut time = 0;
+        for _ in 0..8 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+        }
+        let timeouts = Timeouts {
+            mailbox_response: Duration::ZERO,
+            ..master.timeouts()
+        };
+        master.set_timeouts(timeouts);
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        let read = mif.read_sdo_request(slave, 0x1018, 1).unwrap();
+        let mut response = None;
+        while response.is_none() && time < 100_000_000 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+            response = master.received_mailbox();
+        }
+        let (id, response) = response.unwrap();
+        assert_eq!(id, read);
+        match response {
+            Err(TaskError::Timeout) => {}
+            other => panic!("{:?}", other),
+        }
+        // The request which has not been written is not aborted.
+        for _ in 0..10 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+        }
+        assert!(master.device_mut().slaves_mut()[0].sdo_aborts().is_empty());
     }
 }
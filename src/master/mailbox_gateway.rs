@@ -0,0 +1,316 @@
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::frame::{EtherCatFrame, MailboxErrorDetail, MailboxFrame, MailboxType, ETHERCAT_TYPE};
+use crate::interface::{RawEthernetDevice, SlaveAddress};
+use crate::task::{MailboxTaskError, SdoErrorKind, TaskError, MAX_SM_SIZE};
+
+use super::mailbox::{MailboxResponse, MailboxSessionId};
+use super::EtherCatMaster;
+
+/// UDP port of the mailbox gateway.
+pub const MAILBOX_GATEWAY_PORT: u16 = ETHERCAT_TYPE;
+
+/// Type in the EtherCAT header of a frame which contains a mailbox.
+const ETHERCAT_TYPE_MAILBOX: u8 = 5;
+
+const BUFFER_SIZE: usize =
+    EtherCatFrame::HEADER_SIZE + MailboxFrame::HEADER_SIZE + MAX_SM_SIZE as usize;
+
+/// Mailbox gateway (ETG.8200). A request is an EtherCAT header followed by a mailbox,
+/// whose address is the station address of the slave. The request is queued as a mailbox session,
+/// and its response is returned to the client with the same header.
+/// A request which fails, e.g. to a slave which does not exist, is answered with an error mailbox.
+#[derive(Debug)]
+pub struct MailboxGateway {
+    socket: UdpSocket,
+    /// Sessions of the requests whose responses have not been returned.
+    pending: Vec<(MailboxSessionId, Reply)>,
+}
+
+/// Where and how the response to a request is returned.
+#[derive(Debug)]
+struct Reply {
+    client: SocketAddr,
+    address: u16,
+    mb_type: MailboxType,
+    count: u8,
+}
+
+impl MailboxGateway {
+    /// The socket is non-blocking. Use `MAILBOX_GATEWAY_PORT` for the standard port.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Queues a request if one has arrived. Returns false if none has.
+    /// Requests which are not mailboxes are dropped. The request is sent in `EtherCatMaster::process`,
+    /// and its response is passed from `EtherCatMaster::received_mailbox` to `answer`.
+    pub fn poll<D>(
+        &mut self,
+        master: &mut EtherCatMaster<'_, '_, '_, '_, '_, D>,
+    ) -> io::Result<bool>
+    where
+        D: RawEthernetDevice,
+    {
+        let mut buf = [0; BUFFER_SIZE];
+        let (size, client) = match self.socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        let Some(request) = mailbox_request(&buf[..size]) else {
+            return Ok(true);
+        };
+        let reply = Reply {
+            client,
+            address: request.address(),
+            mb_type: request.mb_type(),
+            count: request.count(),
+        };
+        let result = if has_mailbox(master, reply.address) {
+            queue_request(
+                master,
+                reply.address,
+                reply.mb_type,
+                request.without_header(),
+            )
+        } else {
+            Err(MailboxErrorDetail::InvalidHeader)
+        };
+        match result {
+            Ok(id) => self.pending.push((id, reply)),
+            Err(detail) => self.send(&reply, Err(detail))?,
+        }
+        Ok(true)
+    }
+
+    /// Returns the response of a session to the client of the request.
+    /// Returns false if the session is not a request of the gateway.
+    pub fn answer(
+        &mut self,
+        id: &MailboxSessionId,
+        response: &Result<MailboxResponse, TaskError<SdoErrorKind>>,
+    ) -> io::Result<bool> {
+        let Some(index) = self.pending.iter().position(|(pending, _)| pending == id) else {
+            return Ok(false);
+        };
+        let (_, reply) = self.pending.remove(index);
+        let result = match response {
+            Ok(MailboxResponse::Raw(data)) => Ok(data.data()),
+            Err(TaskError::TaskSpecific(SdoErrorKind::ErrorMailbox(detail))) => Err(*detail),
+            Err(TaskError::TaskSpecific(SdoErrorKind::Mailbox(MailboxTaskError::BufferSmall))) => {
+                Err(MailboxErrorDetail::InvalidSize)
+            }
+            // e.g. the response has not arrived within the timeout
+            _ => Err(MailboxErrorDetail::Unspecified),
+        };
+        self.send(&reply, result)?;
+        Ok(true)
+    }
+
+    /// Sends the payload of the response, or an error mailbox.
+    fn send(&self, reply: &Reply, result: Result<&[u8], MailboxErrorDetail>) -> io::Result<()> {
+        let mut response = [0; BUFFER_SIZE];
+        let mailbox_start = EtherCatFrame::HEADER_SIZE;
+        let data_start = mailbox_start + MailboxFrame::HEADER_SIZE;
+        let (mb_type, length) = match result {
+            Ok(payload) => {
+                response[data_start..data_start + payload.len()].copy_from_slice(payload);
+                (reply.mb_type, payload.len())
+            }
+            Err(detail) => (
+                MailboxType::Error,
+                set_error(&mut response[data_start..], detail),
+            ),
+        };
+
+        let mut mb_frame = MailboxFrame(&mut response[mailbox_start..]);
+        mb_frame.set_length(length as u16);
+        mb_frame.set_address(reply.address);
+        mb_frame.set_prioriry(0);
+        mb_frame.set_mb_type(mb_type);
+        mb_frame.set_count(reply.count);
+        let mut ec_frame = EtherCatFrame(&mut response[..mailbox_start]);
+        ec_frame.set_length((MailboxFrame::HEADER_SIZE + length) as u16);
+        ec_frame.set_ethercat_type(ETHERCAT_TYPE_MAILBOX);
+        self.socket
+            .send_to(&response[..data_start + length], reply.client)?;
+        Ok(())
+    }
+}
+
+/// The mailbox in a request. The size of the mailbox has to match its header.
+fn mailbox_request(frame: &[u8]) -> Option<MailboxFrame<&[u8]>> {
+    let header = EtherCatFrame(frame.get(..EtherCatFrame::HEADER_SIZE)?);
+    if header.ethercat_type() != ETHERCAT_TYPE_MAILBOX {
+        return None;
+    }
+    let mailbox = frame.get(EtherCatFrame::HEADER_SIZE..)?;
+    let mailbox = mailbox.get(..header.length() as usize)?;
+    let length = MailboxFrame(mailbox.get(..MailboxFrame::HEADER_SIZE)?).length() as usize;
+    mailbox
+        .get(..MailboxFrame::HEADER_SIZE + length)
+        .map(MailboxFrame)
+}
+
+fn has_mailbox<D>(master: &EtherCatMaster<'_, '_, '_, '_, '_, D>, address: u16) -> bool
+where
+    D: RawEthernetDevice,
+{
+    let slave_address = SlaveAddress::StationAddress(address);
+    master
+        .network()
+        .slave(slave_address)
+        .is_some_and(|(slave, _)| {
+            slave.info().slave_address() == slave_address
+                && slave.info().mailbox_rx_sm().is_some()
+                && slave.info().mailbox_tx_sm().is_some()
+        })
+}
+
+fn queue_request<D>(
+    master: &mut EtherCatMaster<'_, '_, '_, '_, '_, D>,
+    address: u16,
+    mb_type: MailboxType,
+    payload: &[u8],
+) -> Result<MailboxSessionId, MailboxErrorDetail>
+where
+    D: RawEthernetDevice,
+{
+    let slave_address = SlaveAddress::StationAddress(address);
+    let mut mif = master
+        .try_get_mailbox_request_interface()
+        .ok_or(MailboxErrorDetail::NoMoreMemory)?;
+    // The payload does not fit in a mailbox.
+    mif.raw_mailbox_request(slave_address, mb_type, payload)
+        .ok_or(MailboxErrorDetail::InvalidSize)
+}
+
+/// Returns the size of the error mailbox.
+fn set_error(buf: &mut [u8], detail: MailboxErrorDetail) -> usize {
+    // service type 0x01: mailbox command
+    buf[..2].copy_from_slice(&1_u16.to_le_bytes());
+    buf[2..4].copy_from_slice(&(detail as u16).to_le_bytes());
+    4
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::{
+        CoE, EtherCatFrame, Mailbox, MailboxErrorDetail, MailboxFrame, MailboxType, SdoRes,
+    };
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::master::MailboxGateway;
+    use crate::task::EtherCatSystemTime;
+    use core::time::Duration;
+    use std::net::UdpSocket;
+
+    #[test]
+    fn mailbox_gateway_test() {
+        let mut pdo_buffer = vec![0; 1500];
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(2));
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+
+        let mut gateway = MailboxGateway::bind("127.0.0.1:0").unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(gateway.local_addr().unwrap()).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let mut time = 0;
+        let mut request = |address: u16, mb_type: MailboxType, count: u8, mailbox: &[u8]| {
+            let mut frame = vec![0; EtherCatFrame::HEADER_SIZE + MailboxFrame::HEADER_SIZE];
+            let mut ec_frame = EtherCatFrame(&mut frame[..]);
+            ec_frame.set_length((MailboxFrame::HEADER_SIZE + mailbox.len()) as u16);
+            ec_frame.set_ethercat_type(5);
+            let mut mb_frame = MailboxFrame(&mut frame[EtherCatFrame::HEADER_SIZE..]);
+            mb_frame.set_length(mailbox.len() as u16);
+            mb_frame.set_address(address);
+            mb_frame.set_mb_type(mb_type);
+            mb_frame.set_count(count);
+            frame.extend_from_slice(mailbox);
+            client.send(&frame).unwrap();
+            while !gateway.poll(&mut master).unwrap() {}
+            // The request is serviced with the mailbox sessions of the master.
+            for _ in 0..100 {
+                time += 1_000_000;
+                master.process(EtherCatSystemTime(time)).unwrap();
+                while let Some((id, response)) = master.received_mailbox() {
+                    assert!(gateway.answer(&id, &response).unwrap());
+                }
+            }
+            let mut response = [0; 512];
+            let size = client.recv(&mut response).unwrap();
+            response[..size].to_vec()
+        };
+
+        // SDO upload request of 0x1018:02 to the second slave
+        let sdo_upload = [0x00, 0x20, 0x40, 0x18, 0x10, 0x02, 0, 0, 0, 0];
+        let response = request(2, MailboxType::CoE, 3, &sdo_upload);
+        let ec_frame = EtherCatFrame(&response[..EtherCatFrame::HEADER_SIZE]);
+        assert_eq!(ec_frame.ethercat_type(), 5);
+        assert_eq!(
+            ec_frame.length() as usize,
+            response.len() - EtherCatFrame::HEADER_SIZE
+        );
+        let mb_frame = MailboxFrame(&response[EtherCatFrame::HEADER_SIZE..]);
+        assert_eq!(mb_frame.address(), 2);
+        assert_eq!(mb_frame.count(), 3);
+        match mb_frame.mailbox() {
+            Ok(Mailbox::CoE((_, CoE::SdoRes(SdoRes::Upload(data))))) => {
+                assert_eq!(data, &0x1235_u32.to_le_bytes())
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // There is no slave at the station address 3.
+        let response = request(3, MailboxType::CoE, 4, &sdo_upload);
+        let mb_frame = MailboxFrame(&response[EtherCatFrame::HEADER_SIZE..]);
+        assert_eq!(mb_frame.address(), 3);
+        match mb_frame.mailbox() {
+            Ok(Mailbox::Error(detail)) => assert_eq!(detail, MailboxErrorDetail::InvalidHeader),
+            other => panic!("{:?}", other),
+        }
+
+        // The slave answers VoE with an error mailbox.
+        let vendor_header = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00];
+        let response = request(1, MailboxType::VoE, 5, &vendor_header);
+        let mb_frame = MailboxFrame(&response[EtherCatFrame::HEADER_SIZE..]);
+        match mb_frame.mailbox() {
+            Ok(Mailbox::Error(detail)) => {
+                assert_eq!(detail, MailboxErrorDetail::UnsupportedProtocol)
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // The request does not fit in the mailbox of the slave.
+        let response = request(1, MailboxType::VoE, 6, &[0; 200]);
+        let mb_frame = MailboxFrame(&response[EtherCatFrame::HEADER_SIZE..]);
+        match mb_frame.mailbox() {
+            Ok(Mailbox::Error(detail)) => assert_eq!(detail, MailboxErrorDetail::InvalidSize),
+            other => panic!("{:?}", other),
+        }
+
+        // The gateway keeps working after the errors.
+        let response = request(1, MailboxType::CoE, 7, &sdo_upload);
+        let mb_frame = MailboxFrame(&response[EtherCatFrame::HEADER_SIZE..]);
+        match mb_frame.mailbox() {
+            Ok(Mailbox::CoE((_, CoE::SdoRes(SdoRes::Upload(data))))) => {
+                assert_eq!(data, &0x1234_u32.to_le_bytes())
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(!gateway.poll(&mut master).unwrap());
+    }
+}
@@ -1,58 +1,112 @@
 mod configure_for_op;
+mod cyclic_task;
+mod ec_value;
 mod error;
+mod firmware_update;
 pub mod mailbox;
-mod read_write_as;
+#[cfg(feature = "std")]
+mod mailbox_gateway;
 pub use configure_for_op::*;
+pub use cyclic_task::*;
+pub use ec_value::*;
 pub use error::*;
-pub use read_write_as::*;
+#[cfg(feature = "std")]
+pub use mailbox_gateway::*;
+
+use core::cell::{Cell, Ref, RefCell};
+use core::future::{poll_fn, Future};
+use core::ops::Deref;
+use core::task::{Poll, Waker};
 
 use crate::{
-    frame::MailboxFrame,
+    frame::{
+        AbortCode, AdsIndex, AdsState, AmsAddress, EntryDescriptionFrame, EoeIpParameter,
+        FoeErrorCode, MailboxType, ObjectDescriptionFrame, OdListFrame, OdListType,
+    },
     interface::{
-        PduInterface, PduSocket, PhyError, RawEthernetDevice, SlaveAddress, SocketHandle,
-        SocketInterface, TargetSlave,
+        EoeDevice, PduInterface, PduSocket, PhyError, RawEthernetDevice, RedundancyState,
+        SlaveAddress, SocketHandle, SocketInterface, TargetSlave,
     },
     register::{AlStatusCode, RxErrorCounter, SiiData},
-    slave::{AlState, Network, Slave, SlaveConfig},
+    slave::{AlState, Emergency, Network, Slave, SlaveConfig},
     task::{
-        loop_task::*, AlStateTransferTask, AlStateTransferTaskError, CyclicTask,
-        EtherCatSystemTime, MailboxTask, MailboxTaskError, NetworkInitTaskError, SdoErrorKind,
-        SiiTaskError, TaskError, MAX_SM_SIZE,
+        self, loop_task::*, AcyclicSocket, AlStateTransferTask, AlStateTransferTaskError,
+        AoeErrorKind, CyclicTask, EoeErrorKind, EtherCatSystemTime, FoeErrorKind,
+        NetworkInitTaskError, RawMailboxErrorKind, SdoErrorKind, SiiTaskError, SoeErrorKind,
+        TaskError, Timeouts, MAX_SM_SIZE,
     },
 };
 
-use self::mailbox::{MailboxManager, MailboxReqIfWrapper, MailboxSessionId};
+use self::cyclic_task::UserTaskEntry;
+use self::mailbox::{
+    MailboxManager, MailboxReqIfWrapper, MailboxResponse, MailboxSessionId, MAX_MAILBOX_CHANNELS,
+};
 
 const LOGICAL_START_ADDRESS: u32 = 0x1000;
-const NUM_SOCKETS: usize = 7;
+const NUM_SOCKETS: usize = 6 + MAX_MAILBOX_CHANNELS + MAX_USER_TASKS;
+
+type Interface<'frame, 'socket, D> = SocketInterface<'frame, 'socket, D, NUM_SOCKETS>;
 
+/// The acyclic operations run on the general purpose socket, whose PDUs are transmitted by
+/// `process`. Their futures only borrow the master, so `process` can be called while they are
+/// pending. The master is not `Sync`, so the futures run on a single-threaded executor.
+/// The blocking operations transmit the PDUs by themselves.
 #[derive(Debug)]
 pub struct EtherCatMaster<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
 where
     D: RawEthernetDevice,
 {
-    sif: SocketInterface<'frame, 'socket, D, NUM_SOCKETS>,
+    sif: RefCell<Interface<'frame, 'socket, D>>,
     network: Network<'slave, 'pdo_mapping, 'pdo_entry>,
     gp_socket_handle: SocketHandle,
-    cycle_count: usize,
+    gp_socket_locked: Cell<bool>,
+    /// Woken when the general purpose socket is released.
+    gp_socket_waker: RefCell<Option<Waker>>,
+    cycle_count: Cell<usize>,
     //mailbox
-    mailbox_handle: SocketHandle,
-    mailbox_manager: MailboxManager,
+    mailbox_manager: RefCell<MailboxManager>,
     //process data
     process_data_handle: Option<SocketHandle>,
-    process_data_task: ProcessTask,
+    process_data_task: RefCell<ProcessTask>,
     //dc drift
     dc_handle: SocketHandle,
-    dc_task: Option<DcSyncTask>,
+    dc_task: RefCell<Option<DcSyncTask>>,
     //alstate read
     al_state_handle: SocketHandle,
-    al_state_task: AlStateReadTask,
+    al_state_task: RefCell<AlStateReadTask>,
     //rx error
     rx_error_handle: SocketHandle,
-    rx_error_task: RxErrorReadTask,
+    rx_error_task: RefCell<RxErrorReadTask>,
     //alstate transfer
     al_tf_handle: SocketHandle,
-    al_tf_task: AlStateTransferTask,
+    al_tf_task: RefCell<AlStateTransferTask>,
+    //user tasks
+    user_tasks: RefCell<[Option<UserTaskEntry<'socket>>; MAX_USER_TASKS]>,
+}
+
+/// The general purpose socket, which is used by one operation at a time.
+/// It is released when dropped.
+struct GpSocket<'a, S> {
+    socket: AcyclicSocket<'a, S>,
+    locked: &'a Cell<bool>,
+    waker: &'a RefCell<Option<Waker>>,
+}
+
+impl<'a, S> Deref for GpSocket<'a, S> {
+    type Target = AcyclicSocket<'a, S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.socket
+    }
+}
+
+impl<S> Drop for GpSocket<'_, S> {
+    fn drop(&mut self) {
+        self.locked.set(false);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
 }
 
 impl<'frame, 'socket, 'slave, 'pdo_mapping, 'pdo_entry, D>
@@ -72,7 +126,7 @@ where
             + DcSyncTask::required_buffer_size()
             + AlStateTransferTask::required_buffer_size()
             + MAX_SM_SIZE as usize
-            + MAX_SM_SIZE as usize;
+            + MAX_SM_SIZE as usize * MAX_MAILBOX_CHANNELS;
         assert!(MINIMUM_REQUIRED_BUFFER_SIZE < socket_buffer.len());
 
         let (pdu_buffer1, rest) =
@@ -80,49 +134,56 @@ where
         let (pdu_buffer2, rest) = rest.split_at_mut(RxErrorReadTask::required_buffer_size());
         let (pdu_buffer3, rest) = rest.split_at_mut(DcSyncTask::required_buffer_size());
         let (pdu_buffer4, rest) = rest.split_at_mut(MAX_SM_SIZE as usize);
-        let (pdu_buffer5, rest) = rest.split_at_mut(MAX_SM_SIZE as usize);
-        let (pdu_buffer6, _) = rest.split_at_mut(AlStateTransferTask::required_buffer_size());
+        let (pdu_buffer5, mut rest) =
+            rest.split_at_mut(AlStateTransferTask::required_buffer_size());
 
         let mut sif = SocketInterface::new(iface);
         let al_state_handle = sif.add_socket(PduSocket::new(pdu_buffer1)).unwrap();
         let rx_error_handle = sif.add_socket(PduSocket::new(pdu_buffer2)).unwrap();
         let dc_handle = sif.add_socket(PduSocket::new(pdu_buffer3)).unwrap();
         let gp_socket_handle = sif.add_socket(PduSocket::new(pdu_buffer4)).unwrap();
-        let mailbox_handle = sif.add_socket(PduSocket::new(pdu_buffer5)).unwrap();
-        let al_tf_handle = sif.add_socket(PduSocket::new(pdu_buffer6)).unwrap();
+        let al_tf_handle = sif.add_socket(PduSocket::new(pdu_buffer5)).unwrap();
+        let mailbox_handles = [(); MAX_MAILBOX_CHANNELS].map(|_| {
+            let (pdu_buffer, next) = core::mem::take(&mut rest).split_at_mut(MAX_SM_SIZE as usize);
+            rest = next;
+            sif.add_socket(PduSocket::new(pdu_buffer)).unwrap()
+        });
 
         let network = Network::new(slave_buf);
         Self {
-            sif,
+            sif: RefCell::new(sif),
             network,
             gp_socket_handle,
-            cycle_count: 0,
-            mailbox_handle,
-            mailbox_manager: MailboxManager::new(MailboxTask::new()),
+            gp_socket_locked: Cell::new(false),
+            gp_socket_waker: RefCell::new(None),
+            cycle_count: Cell::new(0),
+            mailbox_manager: RefCell::new(MailboxManager::new(mailbox_handles)),
             process_data_handle: None,
-            process_data_task: ProcessTask::new(LOGICAL_START_ADDRESS, 0, 0),
+            process_data_task: RefCell::new(ProcessTask::new(LOGICAL_START_ADDRESS, 0, 0)),
             dc_handle,
-            dc_task: None,
-            al_state_task: AlStateReadTask::new(),
+            dc_task: RefCell::new(None),
+            al_state_task: RefCell::new(AlStateReadTask::new()),
             al_state_handle,
-            rx_error_task: RxErrorReadTask::new(),
+            rx_error_task: RefCell::new(RxErrorReadTask::new()),
             rx_error_handle,
             al_tf_handle,
-            al_tf_task: AlStateTransferTask::new(),
+            al_tf_task: RefCell::new(AlStateTransferTask::new()),
+            user_tasks: Default::default(),
         }
     }
 
     pub fn init(&mut self) -> Result<(), TaskError<NetworkInitTaskError>> {
         let Self { network, sif, .. } = self;
-        sif.init(&self.gp_socket_handle, network)?;
+        sif.get_mut().init(&self.gp_socket_handle, network)?;
         self.al_state_task
+            .get_mut()
             .set_target(TargetSlave::All(network.num_slaves()));
         Ok(())
     }
 
     pub fn init_dc(&mut self) -> Result<(), TaskError<()>> {
         let Self { network, sif, .. } = self;
-        sif.init_dc(&self.gp_socket_handle, network)?;
+        sif.get_mut().init_dc(&self.gp_socket_handle, network)?;
 
         let mut firt_dc_slave = None;
         let mut dc_count = 0;
@@ -135,7 +196,7 @@ where
             }
         }
         if let Some(firt_dc_slave) = firt_dc_slave {
-            self.dc_task = Some(DcSyncTask::new(firt_dc_slave as u16, dc_count));
+            *self.dc_task.get_mut() = Some(DcSyncTask::new(firt_dc_slave as u16, dc_count));
         }
         Ok(())
     }
@@ -144,39 +205,72 @@ where
         &self.network
     }
 
+    pub fn device(&self) -> Ref<'_, D> {
+        Ref::map(self.sif.borrow(), |sif| sif.device())
+    }
+
+    pub fn device_mut(&mut self) -> &mut D {
+        self.sif.get_mut().device_mut()
+    }
+
+    pub fn secondary_device(&self) -> Option<Ref<'_, D>> {
+        Ref::filter_map(self.sif.borrow(), |sif| sif.secondary_device()).ok()
+    }
+
+    pub fn secondary_device_mut(&mut self) -> Option<&mut D> {
+        self.sif.get_mut().secondary_device_mut()
+    }
+
+    /// State of the ring with cable redundancy. See `PduInterface::new_redundant`.
+    pub fn redundancy_state(&self) -> Option<RedundancyState> {
+        self.sif.borrow().redundancy_state()
+    }
+
+    /// Current time of the clock of the interface.
+    pub fn now(&self) -> EtherCatSystemTime {
+        self.sif.borrow().now()
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.sif.borrow().timeouts()
+    }
+
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.sif.get_mut().set_timeouts(timeouts);
+        self.al_tf_task.get_mut().set_timeouts(timeouts);
+        self.mailbox_manager.get_mut().set_timeouts(timeouts);
+    }
+
+    /// Source of AoE requests.
+    pub fn ams_address(&self) -> AmsAddress {
+        self.sif.borrow().ams_address()
+    }
+
+    pub fn set_ams_address(&mut self, address: AmsAddress) {
+        self.sif.get_mut().set_ams_address(address);
+    }
+
     /// This method must be repeated until the cycle count returned is increased.
-    pub fn process(&mut self, sys_time: EtherCatSystemTime) -> Result<usize, PhyError> {
-        let is_tx_rx_ok = self.sif.poll_tx_rx()?;
+    /// It also makes progress on the pending acyclic operations.
+    pub fn process(&self, sys_time: EtherCatSystemTime) -> Result<usize, PhyError> {
+        let mut sif = self.sif.borrow_mut();
+        let is_tx_rx_ok = sif.poll_tx_rx()?;
         if !is_tx_rx_ok {
-            return Ok(self.cycle_count);
+            return Ok(self.cycle_count.get());
         }
-
-        let Self {
-            network,
-            mailbox_handle,
-            mailbox_manager,
-            process_data_handle,
-            process_data_task,
-            dc_handle,
-            dc_task,
-            al_state_handle,
-            al_state_task,
-            rx_error_handle,
-            rx_error_task,
-            al_tf_handle,
-            al_tf_task,
-            ..
-        } = self;
+        let network = &self.network;
 
         // process data + mb polling
-        if let (Some(ref handle), ref mut task) = (process_data_handle, process_data_task) {
+        if let Some(ref handle) = self.process_data_handle {
             {
-                let socket = self.sif.get_socket_mut(handle).unwrap();
-                task.process_one_step(socket, sys_time);
+                let socket = sif.get_socket_mut(handle).unwrap();
+                self.process_data_task
+                    .borrow_mut()
+                    .process_one_step(socket, sys_time);
             }
-            let mb_socket = self.sif.get_socket_mut(mailbox_handle).unwrap();
-            mailbox_manager.process_one_step(&network, mb_socket, sys_time);
-            let socket = self.sif.get_socket(handle).unwrap();
+            let mut mailbox_manager = self.mailbox_manager.borrow_mut();
+            mailbox_manager.process_one_step(network, &mut sif, sys_time);
+            let socket = sif.get_socket(handle).unwrap();
             mailbox_manager.find_slave_with_mailbox_from_process_data(
                 network,
                 LOGICAL_START_ADDRESS,
@@ -185,92 +279,200 @@ where
         }
 
         // comp dc drift
-        if let (ref handle, Some(ref mut task)) = (dc_handle, dc_task) {
-            let socket = self.sif.get_socket_mut(handle).unwrap();
+        if let Some(ref mut task) = *self.dc_task.borrow_mut() {
+            let socket = sif.get_socket_mut(&self.dc_handle).unwrap();
             task.process_one_step(socket, sys_time);
         }
 
         // check rx error
         {
-            let socket = self.sif.get_socket_mut(rx_error_handle).unwrap();
-            rx_error_task.process_one_step(socket, sys_time);
+            let socket = sif.get_socket_mut(&self.rx_error_handle).unwrap();
+            self.rx_error_task
+                .borrow_mut()
+                .process_one_step(socket, sys_time);
         }
 
         // check al state + al status code
         {
-            let socket = self.sif.get_socket_mut(al_state_handle).unwrap();
-            al_state_task.process_one_step(socket, sys_time);
+            let socket = sif.get_socket_mut(&self.al_state_handle).unwrap();
+            self.al_state_task
+                .borrow_mut()
+                .process_one_step(socket, sys_time);
         }
 
         // transfer al state
         {
-            let socket = self.sif.get_socket_mut(al_tf_handle).unwrap();
-            al_tf_task.process_one_step(socket, sys_time);
+            let socket = sif.get_socket_mut(&self.al_tf_handle).unwrap();
+            self.al_tf_task
+                .borrow_mut()
+                .process_one_step(socket, sys_time);
         }
 
-        self.cycle_count = self.cycle_count.overflowing_add(1).0;
-        Ok(self.cycle_count)
+        self.process_user_tasks(&mut sif, sys_time);
+
+        let cycle_count = self.cycle_count.get().overflowing_add(1).0;
+        self.cycle_count.set(cycle_count);
+        Ok(cycle_count)
     }
 
-    pub fn received_mailbox<'a>(
-        &'a self,
-    ) -> Option<Result<(MailboxSessionId, MailboxFrame<&'a [u8]>), TaskError<MailboxTaskError>>>
-    {
-        let mb_socket = self.sif.get_socket(&self.mailbox_handle).unwrap();
-        self.mailbox_manager.received_mailbox(mb_socket)
+    /// Returns the response of the oldest mailbox session which is complete.
+    pub fn received_mailbox(
+        &self,
+    ) -> Option<(
+        MailboxSessionId,
+        Result<MailboxResponse, TaskError<SdoErrorKind>>,
+    )> {
+        self.mailbox_manager.borrow_mut().received_mailbox()
     }
 
-    pub fn try_get_mailbox_request_interface<'a>(
-        &'a mut self,
-    ) -> Option<MailboxReqIfWrapper<'a, 'a, 'frame, 'socket, D>> {
-        let mif = self.mailbox_manager.try_get_mailbox_request_interface()?;
+    /// Pops the oldest emergency received from any slave.
+    pub fn pop_emergency(&self) -> Option<Emergency> {
+        let (slave, _) = self
+            .network
+            .slaves()
+            .filter_map(|(slave, _)| Some((slave, slave.oldest_emergency_time()?.0)))
+            .min_by_key(|(_, time)| *time)?;
+        slave.pop_emergency()
+    }
 
-        Some(MailboxReqIfWrapper::new(
-            mif,
-            &mut self.sif,
-            self.mailbox_handle.clone(),
-        ))
+    /// Requests are sent in `process`, and the responses are returned by `received_mailbox`.
+    pub fn try_get_mailbox_request_interface(
+        &mut self,
+    ) -> Option<MailboxReqIfWrapper<'_, 'slave, 'pdo_mapping, 'pdo_entry>> {
+        self.mailbox_manager
+            .get_mut()
+            .try_get_mailbox_request_interface(&self.network)
     }
 
-    pub fn rx_error_count(&self) -> &RxErrorCounter<[u8; RxErrorCounter::SIZE]> {
-        self.rx_error_task.rx_error_count()
+    pub fn rx_error_count(&self) -> RxErrorCounter<[u8; RxErrorCounter::SIZE]> {
+        self.rx_error_task.borrow().rx_error_count().clone()
     }
 
     pub fn al_state(&self) -> (Option<AlState>, Option<AlStatusCode>) {
-        self.al_state_task.last_al_state()
+        self.al_state_task.borrow().last_al_state()
     }
 
     pub fn invalid_wkc_count(&self) -> usize {
-        self.process_data_task.invalid_wkc_count
+        self.process_data_task.borrow().invalid_wkc_count
     }
 
     pub fn lost_frame_count(&self) -> usize {
-        self.sif.lost_frame_count
+        self.sif.borrow().lost_frame_count()
     }
 
     pub fn detected_slave_count(&self) -> usize {
         todo!()
     }
 
-    pub fn request_al_state(&mut self, al_state: AlState) -> bool {
-        let Self {
-            network,
-            al_tf_task,
-            ..
-        } = self;
+    pub fn request_al_state(&self, al_state: AlState) -> bool {
+        let mut al_tf_task = self.al_tf_task.borrow_mut();
         if !al_tf_task.is_busy() {
-            al_tf_task.start(TargetSlave::All(network.num_slaves()), al_state);
+            al_tf_task.start(TargetSlave::All(self.network.num_slaves()), al_state);
             true
         } else {
             false
         }
     }
 
+    /// `buf` has to be at least the bit length of the entry.
+    pub fn read_pdo_bytes(
+        &self,
+        slave_address: SlaveAddress,
+        pdo_map_index: usize,
+        pdo_entry_index: usize,
+        buf: &mut [u8],
+    ) -> Option<()> {
+        let handle = self.process_data_handle.as_ref()?;
+        let sif = self.sif.borrow();
+        let pdo_image = sif.get_socket(handle)?;
+        let (_, config) = self.network().slave(slave_address)?;
+        config
+            .input_process_data_mappings()
+            .get(pdo_map_index)
+            .map(|pdo_map| pdo_map.entries.get(pdo_entry_index))
+            .flatten()
+            .map(|pdo_entry| {
+                pdo_entry.read_to_buffer(LOGICAL_START_ADDRESS, pdo_image.data_buf(), buf)
+            })
+            .flatten()
+    }
+
+    pub fn write_pdo_bytes(
+        &self,
+        slave_address: SlaveAddress,
+        pdo_map_index: usize,
+        pdo_entry_index: usize,
+        data: &[u8],
+    ) -> Option<()> {
+        let handle = self.process_data_handle.as_ref()?;
+
+        let mut sif = self.sif.borrow_mut();
+        let pdo_image = sif.get_socket_mut(handle)?;
+
+        let (_, config) = self.network.slave(slave_address)?;
+
+        config
+            .output_process_data_mappings()
+            .get(pdo_map_index)
+            .map(|pdo_map| pdo_map.entries.get(pdo_entry_index))
+            .flatten()
+            .map(|pdo_entry| {
+                pdo_entry.write_from_buffer(LOGICAL_START_ADDRESS, pdo_image.data_buf_mut(), data)
+            })
+            .flatten()
+    }
+
+    fn slave(&self, slave_address: SlaveAddress) -> &Slave {
+        let (slave, _) = self.network.slave(slave_address).expect("slave not found");
+        slave
+    }
+
+    /// Waits until the general purpose socket is released by other operations.
+    async fn gp_socket(&self) -> GpSocket<'_, Interface<'frame, 'socket, D>> {
+        poll_fn(|cx| {
+            if self.gp_socket_locked.replace(true) {
+                let replaced = self.gp_socket_waker.replace(Some(cx.waker().clone()));
+                // Another waiting task is woken to register its waker again.
+                if let Some(waker) = replaced.filter(|waker| !waker.will_wake(cx.waker())) {
+                    waker.wake();
+                }
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await;
+        let socket = GpSocket {
+            socket: AcyclicSocket::new(&self.sif, self.gp_socket_handle.clone()),
+            locked: &self.gp_socket_locked,
+            waker: &self.gp_socket_waker,
+        };
+        // An operation which has been dropped may have left its PDU.
+        socket.reset().await;
+        socket
+    }
+
+    /// Runs an operation to completion, transmitting the PDUs without `process`.
+    fn block_on<T, E: From<PhyError>>(
+        &self,
+        future: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        task::block_on(&self.sif, future)
+    }
+
+    fn gp_socket_data(&mut self) -> &[u8] {
+        self.sif
+            .get_mut()
+            .get_socket(&self.gp_socket_handle)
+            .expect("socket not found")
+            .data_buf()
+    }
+
     pub fn read_al_state(
         &mut self,
         target_slave: TargetSlave,
     ) -> Result<(AlState, Option<AlStatusCode>), TaskError<()>> {
-        self.sif.read_al_state(&self.gp_socket_handle, target_slave)
+        self.block_on(self.read_al_state_async(target_slave))
     }
 
     pub fn change_al_state(
@@ -278,8 +480,7 @@ where
         target_slave: TargetSlave,
         al_state: AlState,
     ) -> Result<AlState, TaskError<AlStateTransferTaskError>> {
-        self.sif
-            .change_al_state(&self.gp_socket_handle, target_slave, al_state)
+        self.block_on(self.change_al_state_async(target_slave, al_state))
     }
 
     pub fn read_sii(
@@ -287,96 +488,283 @@ where
         slave_address: SlaveAddress,
         sii_address: u16,
     ) -> Result<(SiiData<[u8; SiiData::SIZE]>, usize), TaskError<SiiTaskError>> {
-        self.sif
-            .read_sii(&self.gp_socket_handle, slave_address, sii_address)
+        self.block_on(self.read_sii_async(slave_address, sii_address))
     }
 
-    pub fn read_sdo(
+    /// Returns the data of an object as it is. Use `read_sdo` for a typed value.
+    pub fn read_sdo_bytes(
         &mut self,
         slave_address: SlaveAddress,
         index: u16,
         sub_index: u8,
     ) -> Result<&[u8], TaskError<SdoErrorKind>> {
-        let (slave, _) = self.network.slave(slave_address).expect("slave not found");
-        self.sif
-            .read_sdo(&self.gp_socket_handle, slave, index, sub_index)
+        let size = self.block_on(async {
+            let slave = self.slave(slave_address);
+            self.gp_socket()
+                .await
+                .read_sdo(slave, index, sub_index)
+                .await
+        })?;
+        Ok(&self.gp_socket_data()[..size])
     }
 
-    pub fn write_sdo(
+    /// Reads an object which may be larger than the mailbox. Returns the size of the object.
+    pub fn read_sdo_into(
+        &mut self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
+        self.block_on(self.read_sdo_into_async(slave_address, index, sub_index, buf))
+    }
+
+    pub fn write_sdo_bytes(
         &mut self,
         slave_address: SlaveAddress,
         index: u16,
         sub_index: u8,
         data: &[u8],
     ) -> Result<(), TaskError<SdoErrorKind>> {
-        let (slave, _) = self.network.slave(slave_address).expect("slave not found");
-        self.sif
-            .write_sdo(&self.gp_socket_handle, slave, index, sub_index, data)
+        self.block_on(self.write_sdo_bytes_async(slave_address, index, sub_index, data))
     }
 
-    pub fn read_pdo(
-        &self,
+    /// Cancels a transfer of the object. The slave does not respond.
+    pub fn abort_sdo(
+        &mut self,
         slave_address: SlaveAddress,
-        pdo_map_index: usize,
-        pdo_entry_index: usize,
+        index: u16,
+        sub_index: u8,
+        abort_code: AbortCode,
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        self.block_on(self.abort_sdo_async(slave_address, index, sub_index, abort_code))
+    }
+
+    /// Reads all entries of an object from `sub_index` (0 or 1) with complete access.
+    /// Sub index 0 is 16-bit in `buf`. Returns the size of the data.
+    pub fn read_sdo_complete(
+        &mut self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
         buf: &mut [u8],
-    ) -> Option<()> {
-        let handle = self.process_data_handle.as_ref().map(|handle| handle)?;
-        let pdo_image = self.sif.get_socket(handle)?;
-        let (_, config) = self.network().slave(slave_address)?;
-        config
-            .input_process_data_mappings()
-            .get(pdo_map_index)
-            .map(|pdo_map| pdo_map.entries.get(pdo_entry_index))
-            .flatten()
-            .map(|pdo_entry| {
-                pdo_entry.read_to_buffer(LOGICAL_START_ADDRESS, pdo_image.data_buf(), buf)
-            })
-            .flatten()
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
+        self.block_on(self.read_sdo_complete_async(slave_address, index, sub_index, buf))
     }
 
-    pub fn write_pdo(
+    /// Writes all entries of an object from `sub_index` (0 or 1) with complete access.
+    /// Sub index 0 is 16-bit in `data`.
+    pub fn write_sdo_complete(
         &mut self,
         slave_address: SlaveAddress,
-        pdo_map_index: usize,
-        pdo_entry_index: usize,
+        index: u16,
+        sub_index: u8,
         data: &[u8],
-    ) -> Option<()> {
-        let Self {
-            sif,
-            process_data_handle,
-            ..
-        } = self;
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        self.block_on(self.write_sdo_complete_async(slave_address, index, sub_index, data))
+    }
 
-        let handle = process_data_handle.as_ref().map(|handle| handle)?;
+    /// Reads the indexes of the objects in a list of the object dictionary, with SDO information.
+    pub fn read_od_list<'b>(
+        &mut self,
+        slave_address: SlaveAddress,
+        list_type: OdListType,
+        buf: &'b mut [u8],
+    ) -> Result<OdListFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        self.block_on(self.read_od_list_async(slave_address, list_type, buf))
+    }
 
-        let pdo_image = sif.get_socket_mut(handle)?;
+    pub fn read_object_description<'b>(
+        &mut self,
+        slave_address: SlaveAddress,
+        index: u16,
+        buf: &'b mut [u8],
+    ) -> Result<ObjectDescriptionFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        self.block_on(self.read_object_description_async(slave_address, index, buf))
+    }
 
-        let (_, config) = self.network.slave(slave_address)?;
+    /// `value_info` is a combination of `ValueInfo`.
+    pub fn read_entry_description<'b>(
+        &mut self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        value_info: u8,
+        buf: &'b mut [u8],
+    ) -> Result<EntryDescriptionFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        self.block_on(self.read_entry_description_async(
+            slave_address,
+            index,
+            sub_index,
+            value_info,
+            buf,
+        ))
+    }
 
-        config
-            .output_process_data_mappings()
-            .get(pdo_map_index)
-            .map(|pdo_map| pdo_map.entries.get(pdo_entry_index))
-            .flatten()
-            .map(|pdo_entry| {
-                pdo_entry.write_from_buffer(LOGICAL_START_ADDRESS, pdo_image.data_buf_mut(), data)
-            })
-            .flatten()
+    /// Reads `elements` of an IDN of a drive with SoE. Returns the size of the data.
+    /// `elements` is a combination of `SoeElement`.
+    pub fn read_soe(
+        &mut self,
+        slave_address: SlaveAddress,
+        drive_number: u8,
+        idn: u16,
+        elements: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SoeErrorKind>> {
+        self.block_on(self.read_soe_async(slave_address, drive_number, idn, elements, buf))
     }
 
+    pub fn write_soe(
+        &mut self,
+        slave_address: SlaveAddress,
+        drive_number: u8,
+        idn: u16,
+        elements: u8,
+        data: &[u8],
+    ) -> Result<(), TaskError<SoeErrorKind>> {
+        self.block_on(self.write_soe_async(slave_address, drive_number, idn, elements, data))
+    }
+
+    /// Reads data of a device behind the slave with ADS Read. Returns the size of the data.
+    pub fn read_ads(
+        &mut self,
+        slave_address: SlaveAddress,
+        target: AmsAddress,
+        index: AdsIndex,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<AoeErrorKind>> {
+        self.block_on(self.read_ads_async(slave_address, target, index, buf))
+    }
+
+    pub fn write_ads(
+        &mut self,
+        slave_address: SlaveAddress,
+        target: AmsAddress,
+        index: AdsIndex,
+        data: &[u8],
+    ) -> Result<(), TaskError<AoeErrorKind>> {
+        self.block_on(self.write_ads_async(slave_address, target, index, data))
+    }
+
+    /// Writes `data` and reads the answer into `buf` with ADS ReadWrite.
+    /// Returns the size of the answer.
+    pub fn read_write_ads(
+        &mut self,
+        slave_address: SlaveAddress,
+        target: AmsAddress,
+        index: AdsIndex,
+        data: &[u8],
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<AoeErrorKind>> {
+        self.block_on(self.read_write_ads_async(slave_address, target, index, data, buf))
+    }
+
+    /// Returns the ADS state and the device state.
+    pub fn read_ads_state(
+        &mut self,
+        slave_address: SlaveAddress,
+        target: AmsAddress,
+    ) -> Result<(AdsState, u16), TaskError<AoeErrorKind>> {
+        self.block_on(self.read_ads_state_async(slave_address, target))
+    }
+
+    /// Sends `payload` in a mailbox of `mb_type`, e.g. a vendor specific protocol.
+    /// The response is received with `receive_raw_mailbox`.
+    pub fn send_raw_mailbox(
+        &mut self,
+        slave_address: SlaveAddress,
+        mb_type: MailboxType,
+        payload: &[u8],
+    ) -> Result<(), TaskError<RawMailboxErrorKind>> {
+        self.block_on(self.send_raw_mailbox_async(slave_address, mb_type, payload))
+    }
+
+    /// Waits for a mailbox of `mb_type` and copies its data into `buf`. Returns the size of the data.
+    pub fn receive_raw_mailbox(
+        &mut self,
+        slave_address: SlaveAddress,
+        mb_type: MailboxType,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<RawMailboxErrorKind>> {
+        self.block_on(self.receive_raw_mailbox_async(slave_address, mb_type, buf))
+    }
+
+    /// Reads a file into `buf`. Returns the size of the file.
+    pub fn read_foe(
+        &mut self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<FoeErrorKind>> {
+        self.block_on(self.read_foe_async(slave_address, file_name, password, buf))
+    }
+
+    /// Reads a file and passes the data of each packet to `writer`.
+    pub fn read_foe_with<F>(
+        &mut self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        writer: F,
+    ) -> Result<(), TaskError<FoeErrorKind>>
+    where
+        F: FnMut(&[u8]) -> Result<(), FoeErrorCode>,
+    {
+        self.block_on(self.read_foe_with_async(slave_address, file_name, password, writer))
+    }
+
+    pub fn write_foe(
+        &mut self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        data: &[u8],
+    ) -> Result<(), TaskError<FoeErrorKind>> {
+        self.block_on(self.write_foe_async(slave_address, file_name, password, data))
+    }
+
+    /// Writes a file whose data are read by `reader` from the given offset. See `AcyclicSocket::write_foe_with`.
+    pub fn write_foe_with<F>(
+        &mut self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        reader: F,
+    ) -> Result<(), TaskError<FoeErrorKind>>
+    where
+        F: FnMut(usize, &mut [u8]) -> Result<usize, FoeErrorCode>,
+    {
+        self.block_on(self.write_foe_with_async(slave_address, file_name, password, reader))
+    }
+
+    /// Exchanges the frames of `device` with its slave. Call this repeatedly.
+    pub fn poll_eoe(&mut self, device: &mut EoeDevice) -> Result<(), TaskError<EoeErrorKind>> {
+        self.block_on(self.poll_eoe_async(device))
+    }
+
+    pub fn set_eoe_ip_parameter(
+        &mut self,
+        slave_address: SlaveAddress,
+        parameter: &EoeIpParameter,
+    ) -> Result<(), TaskError<EoeErrorKind>> {
+        self.block_on(self.set_eoe_ip_parameter_async(slave_address, parameter))
+    }
+
+    /// Returns a slice of the buffer of the general purpose socket.
     pub fn read_register(
         &mut self,
         target_slave: TargetSlave,
         register_address: u16,
         data_size: usize,
     ) -> Result<&[u8], TaskError<()>> {
-        let Self {
-            sif,
-            gp_socket_handle,
-            ..
-        } = self;
-        sif.read_register(&gp_socket_handle, target_slave, register_address, data_size)
+        self.block_on(async {
+            self.gp_socket()
+                .await
+                .read_register(target_slave, register_address, data_size)
+                .await
+        })?;
+        Ok(&self.gp_socket_data()[..data_size])
     }
 
     pub fn write_register(
@@ -385,11 +773,624 @@ where
         register_address: u16,
         data: &[u8],
     ) -> Result<(), TaskError<()>> {
-        let Self {
-            sif,
-            gp_socket_handle,
-            ..
-        } = self;
-        sif.write_register(&gp_socket_handle, target_slave, register_address, data)
+        self.block_on(self.write_register_async(target_slave, register_address, data))
+    }
+
+    pub async fn read_al_state_async(
+        &self,
+        target_slave: TargetSlave,
+    ) -> Result<(AlState, Option<AlStatusCode>), TaskError<()>> {
+        self.gp_socket().await.read_al_state(target_slave).await
+    }
+
+    pub async fn change_al_state_async(
+        &self,
+        target_slave: TargetSlave,
+        al_state: AlState,
+    ) -> Result<AlState, TaskError<AlStateTransferTaskError>> {
+        let result = self
+            .gp_socket()
+            .await
+            .change_al_state(target_slave, al_state)
+            .await;
+        // The slaves count their mailboxes from the start again.
+        if al_state == AlState::Init {
+            match target_slave {
+                TargetSlave::Single(slave_address) => {
+                    if let Some((slave, _)) = self.network.slave(slave_address) {
+                        slave.reset_received_mailbox_count();
+                    }
+                }
+                TargetSlave::All(_) => {
+                    for (slave, _) in self.network.slaves() {
+                        slave.reset_received_mailbox_count();
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    pub async fn read_sii_async(
+        &self,
+        slave_address: SlaveAddress,
+        sii_address: u16,
+    ) -> Result<(SiiData<[u8; SiiData::SIZE]>, usize), TaskError<SiiTaskError>> {
+        self.gp_socket()
+            .await
+            .read_sii(slave_address, sii_address)
+            .await
+    }
+
+    pub async fn read_sdo_into_async(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_sdo_into(slave, index, sub_index, buf)
+            .await
+    }
+
+    pub async fn write_sdo_bytes_async(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .write_sdo(slave, index, sub_index, data)
+            .await
+    }
+
+    pub async fn abort_sdo_async(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        abort_code: AbortCode,
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .abort_sdo(slave, index, sub_index, abort_code)
+            .await
+    }
+
+    pub async fn read_sdo_complete_async(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_sdo_complete(slave, index, sub_index, buf)
+            .await
+    }
+
+    pub async fn write_sdo_complete_async(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .write_sdo_complete(slave, index, sub_index, data)
+            .await
+    }
+
+    pub async fn read_od_list_async<'b>(
+        &self,
+        slave_address: SlaveAddress,
+        list_type: OdListType,
+        buf: &'b mut [u8],
+    ) -> Result<OdListFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_od_list(slave, list_type, buf)
+            .await
+    }
+
+    pub async fn read_object_description_async<'b>(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        buf: &'b mut [u8],
+    ) -> Result<ObjectDescriptionFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_object_description(slave, index, buf)
+            .await
+    }
+
+    pub async fn read_entry_description_async<'b>(
+        &self,
+        slave_address: SlaveAddress,
+        index: u16,
+        sub_index: u8,
+        value_info: u8,
+        buf: &'b mut [u8],
+    ) -> Result<EntryDescriptionFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_entry_description(slave, index, sub_index, value_info, buf)
+            .await
+    }
+
+    pub async fn read_soe_async(
+        &self,
+        slave_address: SlaveAddress,
+        drive_number: u8,
+        idn: u16,
+        elements: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_soe(slave, drive_number, idn, elements, buf)
+            .await
+    }
+
+    pub async fn write_soe_async(
+        &self,
+        slave_address: SlaveAddress,
+        drive_number: u8,
+        idn: u16,
+        elements: u8,
+        data: &[u8],
+    ) -> Result<(), TaskError<SoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .write_soe(slave, drive_number, idn, elements, data)
+            .await
+    }
+
+    pub async fn read_ads_async(
+        &self,
+        slave_address: SlaveAddress,
+        target: AmsAddress,
+        index: AdsIndex,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<AoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_ads(slave, target, index, buf)
+            .await
+    }
+
+    pub async fn write_ads_async(
+        &self,
+        slave_address: SlaveAddress,
+        target: AmsAddress,
+        index: AdsIndex,
+        data: &[u8],
+    ) -> Result<(), TaskError<AoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .write_ads(slave, target, index, data)
+            .await
+    }
+
+    pub async fn read_write_ads_async(
+        &self,
+        slave_address: SlaveAddress,
+        target: AmsAddress,
+        index: AdsIndex,
+        data: &[u8],
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<AoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_write_ads(slave, target, index, data, buf)
+            .await
+    }
+
+    pub async fn read_ads_state_async(
+        &self,
+        slave_address: SlaveAddress,
+        target: AmsAddress,
+    ) -> Result<(AdsState, u16), TaskError<AoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket().await.read_ads_state(slave, target).await
+    }
+
+    pub async fn send_raw_mailbox_async(
+        &self,
+        slave_address: SlaveAddress,
+        mb_type: MailboxType,
+        payload: &[u8],
+    ) -> Result<(), TaskError<RawMailboxErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .send_raw_mailbox(slave, mb_type, payload)
+            .await
+    }
+
+    pub async fn receive_raw_mailbox_async(
+        &self,
+        slave_address: SlaveAddress,
+        mb_type: MailboxType,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<RawMailboxErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .receive_raw_mailbox(slave, mb_type, buf)
+            .await
+    }
+
+    pub async fn read_foe_async(
+        &self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<FoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_foe(slave, file_name, password, buf)
+            .await
+    }
+
+    pub async fn read_foe_with_async<F>(
+        &self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        writer: F,
+    ) -> Result<(), TaskError<FoeErrorKind>>
+    where
+        F: FnMut(&[u8]) -> Result<(), FoeErrorCode>,
+    {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .read_foe_with(slave, file_name, password, writer)
+            .await
+    }
+
+    pub async fn write_foe_async(
+        &self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        data: &[u8],
+    ) -> Result<(), TaskError<FoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .write_foe(slave, file_name, password, data)
+            .await
+    }
+
+    pub async fn write_foe_with_async<F>(
+        &self,
+        slave_address: SlaveAddress,
+        file_name: &[u8],
+        password: u32,
+        reader: F,
+    ) -> Result<(), TaskError<FoeErrorKind>>
+    where
+        F: FnMut(usize, &mut [u8]) -> Result<usize, FoeErrorCode>,
+    {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .write_foe_with(slave, file_name, password, reader)
+            .await
+    }
+
+    pub async fn poll_eoe_async(
+        &self,
+        device: &mut EoeDevice<'_>,
+    ) -> Result<(), TaskError<EoeErrorKind>> {
+        let slave = self.slave(device.slave_address());
+        self.gp_socket().await.poll_eoe(slave, device).await
+    }
+
+    pub async fn set_eoe_ip_parameter_async(
+        &self,
+        slave_address: SlaveAddress,
+        parameter: &EoeIpParameter,
+    ) -> Result<(), TaskError<EoeErrorKind>> {
+        let slave = self.slave(slave_address);
+        self.gp_socket()
+            .await
+            .set_eoe_ip_parameter(slave, parameter)
+            .await
+    }
+
+    /// Reads `buf.len()` bytes.
+    pub async fn read_register_async(
+        &self,
+        target_slave: TargetSlave,
+        register_address: u16,
+        buf: &mut [u8],
+    ) -> Result<(), TaskError<()>> {
+        let socket = self.gp_socket().await;
+        socket
+            .read_register(target_slave, register_address, buf.len())
+            .await?;
+        socket.with_socket(|socket| buf.copy_from_slice(&socket.data_buf()[..buf.len()]));
+        Ok(())
+    }
+
+    pub async fn write_register_async(
+        &self,
+        target_slave: TargetSlave,
+        register_address: u16,
+        data: &[u8],
+    ) -> Result<(), TaskError<()>> {
+        self.gp_socket()
+            .await
+            .write_register(target_slave, register_address, data)
+            .await
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::AbortCode;
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::{PduInterface, RawEthernetDevice, SlaveAddress, TargetSlave};
+    use crate::slave::{AlState, PdoEntry, PdoMapping, SlaveConfig};
+    use crate::task::{EtherCatSystemTime, ManualClock, SdoErrorKind, TaskError};
+    use crate::EtherCatMaster;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use core::time::Duration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    /// Minimal executor, which calls `process` while the future is pending.
+    fn poll_to_end<D: RawEthernetDevice, F: Future>(
+        master: &EtherCatMaster<'_, '_, '_, '_, '_, D>,
+        future: F,
+    ) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            master.process(master.now()).unwrap();
+        }
+    }
+
+    #[test]
+    fn async_test() {
+        let mut storage = MasterStorage::new();
+        let master = storage.master(new_segment(2));
+
+        poll_to_end(&master, async {
+            let mut address = [0; 2];
+            master
+                .read_register_async(SlaveAddress::SlavePosition(1).into(), 0x0010, &mut address)
+                .await
+                .unwrap();
+            assert_eq!(address, [2, 0]);
+            master
+                .change_al_state_async(TargetSlave::All(2), AlState::PreOperational)
+                .await
+                .unwrap();
+            let (al_state, _) = master
+                .read_al_state_async(TargetSlave::All(2))
+                .await
+                .unwrap();
+            assert_eq!(al_state, AlState::PreOperational);
+
+            let slave = SlaveAddress::SlavePosition(0);
+            master
+                .write_sdo_bytes_async(slave, 0x7000, 1, &[0xEF, 0xBE])
+                .await
+                .unwrap();
+            let value: u16 = master.read_sdo_async(slave, 0x7000, 1).await.unwrap();
+            assert_eq!(value, 0xBEEF);
+            master
+                .write_sdo_async(slave, 0x7000, 1, 0x1234_u16)
+                .await
+                .unwrap();
+            let value: u16 = master.read_sdo_async(slave, 0x7000, 1).await.unwrap();
+            assert_eq!(value, 0x1234);
+            match master.write_sdo_async(slave, 0x7000, 1, [0; 300]).await {
+                Err(TaskError::TaskSpecific(SdoErrorKind::BufferSmall)) => {}
+                other => panic!("{:?}", other),
+            }
+            match master.read_sdo_async::<u16>(slave, 0x2000, 1).await {
+                Err(TaskError::TaskSpecific(SdoErrorKind::AbortCode(code))) => {
+                    assert_eq!(code, AbortCode::DoesNotExistInDict)
+                }
+                other => panic!("{:?}", other),
+            }
+        });
+    }
+
+    /// Waker which counts how many times it is woken.
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn concurrent_async_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(2));
+        master
+            .write_sdo::<u16>(SlaveAddress::SlavePosition(1), 0x7000, 1, 0x1234)
+            .unwrap();
+
+        // The operations wait for each other, while the master is processed.
+        let mut read_sdo =
+            pin!(master.read_sdo_async::<u16>(SlaveAddress::SlavePosition(1), 0x7000, 1));
+        let mut read_al_state = pin!(master.read_al_state_async(TargetSlave::All(2)));
+        let mut cx = Context::from_waker(Waker::noop());
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut waiting_cx = Context::from_waker(&waker);
+        assert!(read_sdo.as_mut().poll(&mut cx).is_pending());
+        assert!(read_al_state.as_mut().poll(&mut waiting_cx).is_pending());
+
+        // The waiting operation is woken once, when the socket is released.
+        let value = loop {
+            assert_eq!(counter.0.load(Ordering::Relaxed), 0);
+            master.process(master.now()).unwrap();
+            if let Poll::Ready(result) = read_sdo.as_mut().poll(&mut cx) {
+                break result.unwrap();
+            }
+        };
+        assert_eq!(value, 0x1234);
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+
+        let mut cycle_count = 0;
+        let al_state = loop {
+            if let Poll::Ready(result) = read_al_state.as_mut().poll(&mut cx) {
+                break result.unwrap().0;
+            }
+            cycle_count = master.process(master.now()).unwrap();
+        };
+        assert_eq!(al_state, AlState::PreOperational);
+        assert!(cycle_count > 0);
+    }
+
+    #[test]
+    fn process_data_test() {
+        let mut segment = new_segment(2);
+        for slave in segment.slaves_mut() {
+            // loopback
+            slave.set_application(|_, od| {
+                let output = od.get(0x7000, 1).unwrap().to_vec();
+                od.get_mut(0x6000, 1).unwrap().copy_from_slice(&output);
+            });
+        }
+        let mut buf = [0; 1500];
+        let clock = ManualClock::with_tick(Duration::from_micros(1));
+        let iface = PduInterface::new(segment, &mut buf, &clock);
+
+        let mut output_entries = [
+            [PdoEntry::new(0x7000, 1, 16)],
+            [PdoEntry::new(0x7000, 1, 16)],
+        ];
+        let mut input_entries = [
+            [PdoEntry::new(0x6000, 1, 16)],
+            [PdoEntry::new(0x6000, 1, 16)],
+        ];
+        let [output0, output1] = &mut output_entries;
+        let [input0, input1] = &mut input_entries;
+        let mut output_maps = [
+            [PdoMapping {
+                is_fixed: false,
+                index: 0x1600,
+                entries: output0,
+            }],
+            [PdoMapping {
+                is_fixed: false,
+                index: 0x1600,
+                entries: output1,
+            }],
+        ];
+        let mut input_maps = [
+            [PdoMapping {
+                is_fixed: false,
+                index: 0x1A00,
+                entries: input0,
+            }],
+            [PdoMapping {
+                is_fixed: false,
+                index: 0x1A00,
+                entries: input1,
+            }],
+        ];
+        let mut slaves: [(_, SlaveConfig); 2] = Default::default();
+        for ((slave, output_map), input_map) in slaves
+            .iter_mut()
+            .zip(output_maps.iter_mut())
+            .zip(input_maps.iter_mut())
+        {
+            slave.1.set_output_process_data_mappings(output_map);
+            slave.1.set_input_process_data_mappings(input_map);
+        }
+
+        let mut socket_buffer = vec![0; 1500];
+        let mut pdo_buffer = vec![0; 1500];
+        let mut master = EtherCatMaster::new(&mut slaves, &mut socket_buffer, iface);
+        master.init().unwrap();
+        master.init_dc().unwrap();
+        master
+            .change_al_state(TargetSlave::All(2), AlState::PreOperational)
+            .unwrap();
+        master.configure_slaves_for_operation().unwrap();
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+        master
+            .change_al_state(TargetSlave::All(2), AlState::SafeOperational)
+            .unwrap();
+
+        master
+            .write_pdo_bytes(SlaveAddress::SlavePosition(0), 0, 0, &[0x34, 0x12])
+            .unwrap();
+        master
+            .write_pdo_bytes(SlaveAddress::SlavePosition(1), 0, 0, &[0x78, 0x56])
+            .unwrap();
+        let mut time = 0;
+        for _ in 0..100 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+            master.request_al_state(AlState::Operational);
+        }
+        assert_eq!(master.al_state().0, Some(AlState::Operational));
+
+        let mut data = [0; 2];
+        master
+            .read_pdo_bytes(SlaveAddress::SlavePosition(0), 0, 0, &mut data)
+            .unwrap();
+        assert_eq!(data, [0x34, 0x12]);
+        master
+            .read_pdo_bytes(SlaveAddress::SlavePosition(1), 0, 0, &mut data)
+            .unwrap();
+        assert_eq!(data, [0x78, 0x56]);
+
+        let slave = SlaveAddress::SlavePosition(0);
+        assert_eq!(master.read_pdo::<u16>(slave, 0, 0), Some(0x1234));
+        assert_eq!(master.read_pdo::<u32>(slave, 0, 0), None);
+        assert_eq!(master.read_pdo::<u16>(slave, 0, 1), None);
+        assert_eq!(master.write_pdo(slave, 0, 0, 0xABCD_u32), None);
+        master.write_pdo(slave, 0, 0, -2_i16).unwrap();
+        for _ in 0..2 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+        }
+        assert_eq!(master.read_pdo::<i16>(slave, 0, 0), Some(-2));
+        assert_eq!(master.read_pdo::<[u8; 2]>(slave, 0, 0), Some([0xFE, 0xFF]));
     }
 }
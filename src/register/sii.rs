@@ -123,3 +123,37 @@ impl Version {
     pub const ADDRESS: u16 = 0x3F;
     pub const SIZE: usize = 2;
 }
+
+/// Start of the categories. A category begins with its type and its data size in words.
+pub struct Categories;
+impl Categories {
+    pub const ADDRESS: u16 = 0x40;
+    pub const HEADER_SIZE: usize = 4;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum CategoryType {
+    Strings = 10,
+    DataTypes = 20,
+    General = 30,
+    Fmmu = 40,
+    SyncManager = 41,
+    TxPdo = 50,
+    RxPdo = 51,
+    Dc = 60,
+    End = 0xFFFF,
+}
+
+/// CoE details in the general category.
+pub struct CoeDetails;
+impl CoeDetails {
+    /// Offset in words from the start of the category data. The details are the high byte.
+    pub const OFFSET: u16 = 2;
+    pub const ENABLE_SDO: u8 = 0x01;
+    pub const ENABLE_SDO_INFO: u8 = 0x02;
+    pub const ENABLE_PDO_ASSIGN: u8 = 0x04;
+    pub const ENABLE_PDO_CONFIGURATION: u8 = 0x08;
+    pub const ENABLE_UPLOAD_AT_STARTUP: u8 = 0x10;
+    pub const ENABLE_SDO_COMPLETE_ACCESS: u8 = 0x20;
+}
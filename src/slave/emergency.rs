@@ -0,0 +1,106 @@
+use crate::frame::EmmergencyFrame;
+use crate::interface::SlaveAddress;
+use crate::task::EtherCatSystemTime;
+
+/// Emergencies kept for each slave. The oldest one is dropped when the queue is full.
+pub const EMERGENCY_QUEUE_SIZE: usize = 4;
+
+/// CoE emergency message received from a slave.
+#[derive(Debug, Clone)]
+pub struct Emergency {
+    pub slave_address: SlaveAddress,
+    /// Time of the mailbox read which received it.
+    pub time: EtherCatSystemTime,
+    /// Error code, error register and manufacturer specific data.
+    pub frame: EmmergencyFrame<[u8; EmmergencyFrame::SIZE]>,
+}
+
+impl Emergency {
+    pub fn new(
+        slave_address: SlaveAddress,
+        time: EtherCatSystemTime,
+        frame: &EmmergencyFrame<&[u8]>,
+    ) -> Self {
+        let mut data = EmmergencyFrame::new();
+        data.0.copy_from_slice(&frame.0[..EmmergencyFrame::SIZE]);
+        Self {
+            slave_address,
+            time,
+            frame: data,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct EmergencyQueue {
+    buf: [Option<Emergency>; EMERGENCY_QUEUE_SIZE],
+    head: usize,
+    len: usize,
+}
+
+impl EmergencyQueue {
+    pub fn push(&mut self, emergency: Emergency) {
+        let tail = (self.head + self.len) % EMERGENCY_QUEUE_SIZE;
+        self.buf[tail] = Some(emergency);
+        if self.len < EMERGENCY_QUEUE_SIZE {
+            self.len += 1;
+        } else {
+            self.head = (self.head + 1) % EMERGENCY_QUEUE_SIZE;
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Emergency> {
+        if self.len == 0 {
+            return None;
+        }
+        let emergency = self.buf[self.head].take();
+        self.head = (self.head + 1) % EMERGENCY_QUEUE_SIZE;
+        self.len -= 1;
+        emergency
+    }
+
+    pub fn front(&self) -> Option<&Emergency> {
+        if self.len == 0 {
+            return None;
+        }
+        self.buf[self.head].as_ref()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::EmmergencyFrame;
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::SlaveAddress;
+
+    #[test]
+    fn emergency_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(2));
+
+        let mut emergency = EmmergencyFrame::new();
+        emergency.set_error_code(0x8130);
+        emergency.set_error_register(0x11);
+        emergency.set_data(0x05_0403_0201);
+        master.device_mut().slaves_mut()[1].push_emergency(&emergency);
+        emergency.set_error_code(0x4210);
+        master.device_mut().slaves_mut()[0].push_emergency(&emergency);
+
+        // The emergencies arrive before the SDO responses.
+        let slave = SlaveAddress::SlavePosition(1);
+        assert_eq!(master.read_sdo::<u32>(slave, 0x1018, 2).unwrap(), 0x1235);
+        let slave = SlaveAddress::SlavePosition(0);
+        master.write_sdo::<u16>(slave, 0x7000, 1, 0xBEEF).unwrap();
+
+        let first = master.pop_emergency().unwrap();
+        assert_eq!(first.slave_address, SlaveAddress::StationAddress(2));
+        assert_eq!(first.frame.error_code(), 0x8130);
+        assert_eq!(first.frame.error_register(), 0x11);
+        assert_eq!(first.frame.data(), 0x05_0403_0201);
+        let second = master.pop_emergency().unwrap();
+        assert_eq!(second.slave_address, SlaveAddress::StationAddress(1));
+        assert_eq!(second.frame.error_code(), 0x4210);
+        assert!(second.time.0 > first.time.0);
+        assert!(master.pop_emergency().is_none());
+    }
+}
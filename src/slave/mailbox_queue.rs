@@ -0,0 +1,71 @@
+use crate::frame::{MailboxFrame, MailboxType};
+use crate::task::MAX_SM_SIZE;
+
+/// Mailboxes kept for each slave until they are taken. The oldest one is dropped when the queue is full.
+pub const MAILBOX_QUEUE_SIZE: usize = 2;
+
+/// Mailbox received from a slave, e.g. an EoE fragment or a VoE response,
+/// which did not answer the request being serviced.
+#[derive(Debug)]
+struct ReceivedMailbox {
+    data: [u8; MAX_SM_SIZE as usize],
+    len: usize,
+}
+
+impl ReceivedMailbox {
+    fn mb_frame(&self) -> MailboxFrame<&[u8]> {
+        MailboxFrame(&self.data[..self.len])
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MailboxQueue {
+    buf: [Option<ReceivedMailbox>; MAILBOX_QUEUE_SIZE],
+    len: usize,
+}
+
+impl MailboxQueue {
+    /// The mailbox is truncated to the length in its header.
+    pub fn push(&mut self, mailbox: &[u8]) {
+        if mailbox.len() < MailboxFrame::HEADER_SIZE {
+            return;
+        }
+        let len = MailboxFrame(mailbox)
+            .length()
+            .saturating_add(MailboxFrame::HEADER_SIZE as u16) as usize;
+        let len = len.min(mailbox.len()).min(MAX_SM_SIZE as usize);
+        let mut received = ReceivedMailbox {
+            data: [0; MAX_SM_SIZE as usize],
+            len,
+        };
+        received.data[..len].copy_from_slice(&mailbox[..len]);
+        if self.len == MAILBOX_QUEUE_SIZE {
+            self.remove(0);
+        }
+        self.buf[self.len] = Some(received);
+        self.len += 1;
+    }
+
+    /// Takes the oldest mailbox of `mb_type`, or an error mailbox, and passes it to `f`.
+    pub fn pop<R>(
+        &mut self,
+        mb_type: MailboxType,
+        f: impl FnOnce(MailboxFrame<&[u8]>) -> R,
+    ) -> Option<R> {
+        let index = self.buf[..self.len].iter().position(|received| {
+            received.as_ref().is_some_and(|received| {
+                let received_type = received.mb_frame().mb_type();
+                received_type == mb_type || received_type == MailboxType::Error
+            })
+        })?;
+        let received = self.remove(index)?;
+        Some(f(received.mb_frame()))
+    }
+
+    fn remove(&mut self, index: usize) -> Option<ReceivedMailbox> {
+        let received = self.buf[index].take();
+        self.buf[index..self.len].rotate_left(1);
+        self.len -= 1;
+        received
+    }
+}
@@ -1,6 +1,10 @@
 mod config;
+mod emergency;
+mod mailbox_queue;
 mod network;
 mod slave;
 pub use config::*;
+pub use emergency::{Emergency, EMERGENCY_QUEUE_SIZE};
+pub use mailbox_queue::MAILBOX_QUEUE_SIZE;
 pub use network::*;
 pub use slave::*;
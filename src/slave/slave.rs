@@ -1,5 +1,9 @@
+use super::emergency::{Emergency, EmergencyQueue};
+use super::mailbox_queue::MailboxQueue;
+use crate::frame::{MailboxFrame, MailboxType};
 use crate::interface::*;
 use crate::register::PortPhysics;
+use crate::task::EtherCatSystemTime;
 use core::{
     cell::{Cell, RefCell},
     f32::consts::E,
@@ -77,6 +81,12 @@ pub struct Slave {
 
     al_state: AlState,
     mailbox_count: Cell<u8>,
+    /// Count of the last mailbox received from the slave.
+    received_mailbox_count: Cell<u8>,
+    is_tx_mailbox_full: Cell<bool>,
+    ads_invoke_id: Cell<u32>,
+    emergencies: RefCell<EmergencyQueue>,
+    received_mailboxes: RefCell<MailboxQueue>,
 
     // for Dc init
     pub(crate) dc_context: RefCell<DcContext>,
@@ -120,6 +130,63 @@ impl Slave {
         self.mailbox_count()
     }
 
+    /// Records the count of a mailbox received from the slave.
+    /// True if it has the same non-zero count as the last one, i.e. the slave has sent it again.
+    pub(crate) fn is_repeated_mailbox(&self, count: u8) -> bool {
+        let last_count = self.received_mailbox_count.replace(count);
+        count != 0 && count == last_count
+    }
+
+    /// The slave counts its mailboxes from the start again after Init.
+    pub(crate) fn reset_received_mailbox_count(&self) {
+        self.received_mailbox_count.set(0);
+    }
+
+    /// Whether the tx mailbox is full, according to the mailbox state in the process data.
+    pub(crate) fn is_tx_mailbox_full(&self) -> bool {
+        self.is_tx_mailbox_full.get()
+    }
+
+    pub(crate) fn set_tx_mailbox_full(&self, is_full: bool) {
+        self.is_tx_mailbox_full.set(is_full);
+    }
+
+    pub(crate) fn next_ads_invoke_id(&self) -> u32 {
+        let invoke_id = self.ads_invoke_id.get().wrapping_add(1);
+        self.ads_invoke_id.set(invoke_id);
+        invoke_id
+    }
+
+    /// Pops the oldest emergency received from this slave.
+    pub fn pop_emergency(&self) -> Option<Emergency> {
+        self.emergencies.borrow_mut().pop()
+    }
+
+    pub(crate) fn push_emergency(&self, emergency: Emergency) {
+        self.emergencies.borrow_mut().push(emergency)
+    }
+
+    pub(crate) fn oldest_emergency_time(&self) -> Option<EtherCatSystemTime> {
+        self.emergencies
+            .borrow()
+            .front()
+            .map(|emergency| emergency.time)
+    }
+
+    /// Keeps a mailbox which is not the response to the request being serviced.
+    pub(crate) fn push_received_mailbox(&self, mailbox: &[u8]) {
+        self.received_mailboxes.borrow_mut().push(mailbox)
+    }
+
+    /// Takes the oldest kept mailbox of `mb_type`, or an error mailbox, and passes it to `f`.
+    pub(crate) fn pop_received_mailbox<R>(
+        &self,
+        mb_type: MailboxType,
+        f: impl FnOnce(MailboxFrame<&[u8]>) -> R,
+    ) -> Option<R> {
+        self.received_mailboxes.borrow_mut().pop(mb_type, f)
+    }
+
     pub fn fmmu_config(&self) -> &[Option<FmmuConfig>] {
         &self.fmmu
     }
@@ -160,6 +227,11 @@ pub struct SlaveInfo {
     support_fmmu_bit_operation: bool,
 
     support_coe: bool,
+    support_sdo_complete_access: bool,
+    support_eoe: bool,
+    support_foe: bool,
+    support_soe: bool,
+    support_aoe: bool,
 
     strict_al_control: bool,
 }
@@ -201,6 +273,27 @@ impl SlaveInfo {
         self.support_coe
     }
 
+    /// CoE details in the SII general category.
+    pub fn support_sdo_complete_access(&self) -> bool {
+        self.support_sdo_complete_access
+    }
+
+    pub fn support_eoe(&self) -> bool {
+        self.support_eoe
+    }
+
+    pub fn support_foe(&self) -> bool {
+        self.support_foe
+    }
+
+    pub fn support_soe(&self) -> bool {
+        self.support_soe
+    }
+
+    pub fn support_aoe(&self) -> bool {
+        self.support_aoe
+    }
+
     pub fn mailbox_rx_sm(&self) -> Option<SyncManager> {
         for sm in self.sm.iter() {
             if let Some(SyncManagerType::MailboxRx(sm)) = sm {
@@ -219,6 +312,17 @@ impl SlaveInfo {
         None
     }
 
+    /// Replaces the mailbox sync managers, e.g. with the bootstrap mailbox.
+    pub(crate) fn set_mailbox_sm(&mut self, rx: SyncManager, tx: SyncManager) {
+        for sm in self.sm.iter_mut() {
+            match sm {
+                Some(SyncManagerType::MailboxRx(mailbox)) => *mailbox = rx,
+                Some(SyncManagerType::MailboxTx(mailbox)) => *mailbox = tx,
+                _ => {}
+            }
+        }
+    }
+
     pub fn process_data_rx_sm_number(&self) -> Option<u8> {
         for (i, sm) in self.sm.iter().enumerate() {
             if let Some(SyncManagerType::ProcessDataRx) = sm {
@@ -267,6 +371,11 @@ pub(crate) struct SlaveInfoBuilder {
     pub support_fmmu_bit_operation: bool,
 
     pub support_coe: bool,
+    pub support_sdo_complete_access: bool,
+    pub support_eoe: bool,
+    pub support_foe: bool,
+    pub support_soe: bool,
+    pub support_aoe: bool,
 
     pub strict_al_control: bool,
 }
@@ -287,6 +396,11 @@ impl SlaveInfoBuilder {
             support_dc,
             support_fmmu_bit_operation,
             support_coe,
+            support_sdo_complete_access,
+            support_eoe,
+            support_foe,
+            support_soe,
+            support_aoe,
             strict_al_control,
         } = self;
         let mut sm_arr: [Option<SyncManagerType>; 4] = Default::default();
@@ -309,6 +423,11 @@ impl SlaveInfoBuilder {
             support_dc,
             support_fmmu_bit_operation,
             support_coe,
+            support_sdo_complete_access,
+            support_eoe,
+            support_foe,
+            support_soe,
+            support_aoe,
             strict_al_control,
         }
     }
@@ -476,6 +595,8 @@ impl LogicalBits {
         }
     }
 
+    /// Reads the bits into the beginning of `buf`, which has to be at least `bit_length` long.
+    /// The bits after them in the last byte are cleared.
     pub fn read_to_buffer(
         &self,
         logical_address_offset: u32,
@@ -483,20 +604,26 @@ impl LogicalBits {
         buf: &mut [u8],
     ) -> Option<()> {
         let size = self.byte_length() as usize;
-        let logical_start_address = self.logical_address?;
+        let data_size = (self.bit_length as usize).div_ceil(8);
         let start_bit = self.start_bit;
-        let pdo_offset = (logical_start_address - logical_address_offset) as usize;
-        process_data_image.get(pdo_offset + size - 1)?;
-        buf.get(size - 1)?;
-        (0..size - 1).for_each(|i| {
-            let v = process_data_image[pdo_offset + i] >> start_bit;
-            let next_v = process_data_image[pdo_offset + i + 1] << (7 - start_bit);
-            buf[i] = v | next_v;
-        });
-        buf[size - 1] = process_data_image[pdo_offset + size - 1] >> start_bit;
+        let pdo_offset = self.logical_address?.checked_sub(logical_address_offset)? as usize;
+        let image = process_data_image.get(pdo_offset..pdo_offset + size)?;
+        let buf = buf.get_mut(..data_size)?;
+        for (i, byte) in buf.iter_mut().enumerate() {
+            let mut value = (image[i] as u16) >> start_bit;
+            if let Some(next) = image.get(i + 1) {
+                value |= (*next as u16) << (8 - start_bit);
+            }
+            *byte = value as u8;
+        }
+        let rest_bits = self.bit_length % 8;
+        if rest_bits != 0 {
+            buf[data_size - 1] &= 0xFF >> (8 - rest_bits);
+        }
         Some(())
     }
 
+    /// Writes the bits from the beginning of `buf`. The other bits of the image are kept.
     pub fn write_from_buffer(
         &self,
         logical_address_offset: u32,
@@ -504,47 +631,20 @@ impl LogicalBits {
         buf: &[u8],
     ) -> Option<()> {
         let size = self.byte_length() as usize;
-        let logical_start_address = self.logical_address?;
+        let data_size = (self.bit_length as usize).div_ceil(8);
         let start_bit = self.start_bit;
-        let pdo_offset = (logical_start_address - logical_address_offset) as usize;
-        process_data_image.get(pdo_offset + size - 1)?;
-        (0..size).for_each(|i| {
-            //if i==0{
-            //    if start_bit ==0{
-            //        process_data_image[pdo_offset + i] =0;
-            //    }else{
-            //        process_data_image[pdo_offset + i] &= 0xFF >> (8 - start_bit);
-            //    }
-            //}else{
-            //    process_data_image[pdo_offset + i] =0;
-            //}
-            if i == 0 {
-                if start_bit == 0 {
-                    process_data_image[pdo_offset + i] = 0;
-                } else {
-                    process_data_image[pdo_offset + i] &= 0xFF >> (8 - start_bit);
-                }
-            } else if i == size - 1 {
-                let mod8 = ((self.bit_length + start_bit as u16) % 8) as u8;
-                if mod8 == 0 {
-                    process_data_image[pdo_offset + i] = 0;
-                } else {
-                    process_data_image[pdo_offset + i] &= 0xFF << mod8;
-                }
-            } else {
-                process_data_image[pdo_offset + i] = 0;
+        let pdo_offset = self.logical_address?.checked_sub(logical_address_offset)? as usize;
+        let image = process_data_image.get_mut(pdo_offset..pdo_offset + size)?;
+        let buf = buf.get(..data_size)?;
+        for (i, byte) in buf.iter().enumerate() {
+            let bits = (self.bit_length as usize - i * 8).min(8);
+            let mask = (0xFF_u16 >> (8 - bits)) << start_bit;
+            let value = ((*byte as u16) << start_bit) & mask;
+            image[i] = (image[i] & !(mask as u8)) | value as u8;
+            if let Some(next) = image.get_mut(i + 1) {
+                *next = (*next & !((mask >> 8) as u8)) | (value >> 8) as u8;
             }
-
-            process_data_image[pdo_offset + i] |= buf[i] << start_bit;
-            if i >= 1 && start_bit != 0 {
-                process_data_image[pdo_offset + i] |= buf[i - 1] >> (8 - start_bit);
-            }
-        });
-
-        // process_data_image[pdo_offset + size - 1] |= buf[size - 1] << start_bit;
-        // if size >= 2 && start_bit != 0 {
-        //     process_data_image[pdo_offset + size - 1] |= buf[size - 2] >> (8 - start_bit);
-        // }
+        }
         Some(())
     }
 }
@@ -734,4 +834,31 @@ mod tests {
         assert_eq!(0b0101_0101, image[1]);
         assert_eq!(0b0101_0101, image[2]);
     }
+
+    #[test]
+    fn read_logical_test() {
+        let mut image = [0; 10];
+        let logical_bits = LogicalBits {
+            logical_address: Some(100),
+            start_bit: 5,
+            bit_length: 10,
+        };
+
+        let buf = [0b1011_0110, 0b0000_0011];
+        logical_bits.write_from_buffer(100, &mut image, &buf);
+        assert_eq!(0b1100_0000, image[0]);
+        assert_eq!(0b0111_0110, image[1]);
+        assert_eq!(0, image[2]);
+
+        image[0] |= 0b0001_1111;
+        image[1] |= 0b1000_0000;
+        let mut read = [0xFF; 2];
+        logical_bits.read_to_buffer(100, &image, &mut read);
+        assert_eq!(buf, read);
+
+        let mut read = [0; 1];
+        assert!(logical_bits
+            .read_to_buffer(100, &image, &mut read)
+            .is_none());
+    }
 }
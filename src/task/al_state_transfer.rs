@@ -1,5 +1,5 @@
 use super::TaskError;
-use super::{CyclicTask, EtherCatSystemTime};
+use super::{CyclicTask, EtherCatSystemTime, Timeouts};
 use crate::interface::*;
 use crate::register::AlStatusCode;
 use crate::register::SiiAccess;
@@ -7,29 +7,7 @@ use crate::register::{AlControl, AlStatus};
 use crate::slave::AlState;
 use crate::util::const_max;
 use core::convert::TryFrom;
-
-// Timeout. Init -> PreOp or Init -> Boot
-const PREOP_TIMEOUT_DEFAULT_MS: u32 = 3000;
-// Timeout. SafeOp -> Op or PreOp -> SafeOp
-const SAFEOP_OP_TIMEOUT_DEFAULT_MS: u32 = 10000;
-// Timeout. Op/SafeOp/PreOp/Boot -> Init or SafeOp -> PreOp
-const BACK_TO_INIT_TIMEOUT_DEFAULT_MS: u32 = 5000;
-// Timeout. Op -> SafeOp
-const BACK_TO_SAFEOP_TIMEOUT_DEFAULT_MS: u32 = 200;
-
-const fn max_timeout_ms() -> u32 {
-    let mut max = PREOP_TIMEOUT_DEFAULT_MS;
-    if max < SAFEOP_OP_TIMEOUT_DEFAULT_MS {
-        max = SAFEOP_OP_TIMEOUT_DEFAULT_MS;
-    }
-    if max < BACK_TO_INIT_TIMEOUT_DEFAULT_MS {
-        max = BACK_TO_INIT_TIMEOUT_DEFAULT_MS;
-    }
-    if max < BACK_TO_SAFEOP_TIMEOUT_DEFAULT_MS {
-        max = BACK_TO_SAFEOP_TIMEOUT_DEFAULT_MS;
-    }
-    max
-}
+use core::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AlStateTransferTaskError {
@@ -63,7 +41,8 @@ pub struct AlStateTransferTask {
     target_al: AlState,
     command: Command,
     current_al_state: AlState,
-    timeout_ms: u32,
+    timeouts: Timeouts,
+    timeout: Duration,
 }
 
 impl AlStateTransferTask {
@@ -79,10 +58,15 @@ impl AlStateTransferTask {
             target_al: AlState::Init,
             command: Command::default(),
             current_al_state: AlState::Init,
-            timeout_ms: 0,
+            timeouts: Timeouts::default(),
+            timeout: Duration::ZERO,
         }
     }
 
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
     pub fn start(&mut self, slave_address: TargetSlave, target_al_state: AlState) {
         self.slave_address = slave_address;
         self.target_al = target_al_state;
@@ -151,16 +135,9 @@ impl CyclicTask for AlStateTransferTask {
                 al_control.set_state(target_al as u8);
                 self.command = Command::new_write(self.slave_address, AlControl::ADDRESS);
 
-                self.timeout_ms = match (self.current_al_state, target_al) {
-                    (AlState::PreOperational, AlState::SafeOperational)
-                    | (_, AlState::Operational) => SAFEOP_OP_TIMEOUT_DEFAULT_MS,
-                    (_, AlState::PreOperational) | (_, AlState::Bootstrap) => {
-                        PREOP_TIMEOUT_DEFAULT_MS
-                    }
-                    (_, AlState::Init) => BACK_TO_INIT_TIMEOUT_DEFAULT_MS,
-                    (_, AlState::SafeOperational) => BACK_TO_SAFEOP_TIMEOUT_DEFAULT_MS,
-                    (_, AlState::InvalidOrMixed) => max_timeout_ms(),
-                };
+                self.timeout = self
+                    .timeouts
+                    .al_state_transition(self.current_al_state, target_al);
                 Some((self.command, AlControl::SIZE))
             }
             State::Poll => {
@@ -234,9 +211,7 @@ impl CyclicTask for AlStateTransferTask {
                     self.state = State::Error(
                         AlStateTransferTaskError::AlStatusCode((al_state, al_status_code)).into(),
                     );
-                } else if self.timer_start.0 < sys_time.0
-                    && self.timeout_ms as u64 * 1000_000_000 < sys_time.0 - self.timer_start.0
-                {
+                } else if self.timeout < sys_time.elapsed_since(self.timer_start) {
                     self.state = State::Error(TaskError::Timeout);
                 }
             }
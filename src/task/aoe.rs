@@ -0,0 +1,300 @@
+use super::{AcyclicSocket, MailboxTaskError, TaskError};
+use crate::{
+    frame::{
+        AdsCommand, AdsErrorCode, AdsIndex, AdsRequest, AdsResponse, AdsState, AmsAddress,
+        AmsFrame, AmsHeader, AoE, Mailbox, MailboxErrorDetail, MailboxFrame, MailboxType,
+    },
+    interface::SocketAccess,
+    slave::{Slave, SlaveInfo},
+};
+
+/// AoE client. Requests are sent from `SocketInterface::ams_address` to `target`,
+/// and a response is matched with its request by the invoke ID.
+impl<'a, S: SocketAccess> AcyclicSocket<'a, S> {
+    /// Reads up to the size of `buf`, or of the mailbox, with ADS Read. Returns the size of the data.
+    pub async fn read_ads(
+        &self,
+        slave: &Slave,
+        target: AmsAddress,
+        index: AdsIndex,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<AoeErrorKind>> {
+        let length = ads_read_capacity(slave.info()).min(buf.len()) as u32;
+        let request = AdsRequest::Read { index, length };
+        self.request_ads(slave, target, request).await?;
+        self.with_mailbox(|mb_data| read_data(ads_response(&mb_data)?, buf))
+    }
+
+    pub async fn write_ads(
+        &self,
+        slave: &Slave,
+        target: AmsAddress,
+        index: AdsIndex,
+        data: &[u8],
+    ) -> Result<(), TaskError<AoeErrorKind>> {
+        let request = AdsRequest::Write { index, data };
+        self.request_ads(slave, target, request).await?;
+        self.with_mailbox(|mb_data| check_write(ads_response(&mb_data)?))
+    }
+
+    /// Writes `data` and reads the answer into `buf` with ADS ReadWrite.
+    /// Returns the size of the answer.
+    pub async fn read_write_ads(
+        &self,
+        slave: &Slave,
+        target: AmsAddress,
+        index: AdsIndex,
+        data: &[u8],
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<AoeErrorKind>> {
+        let request = AdsRequest::ReadWrite {
+            index,
+            read_length: ads_read_capacity(slave.info()).min(buf.len()) as u32,
+            data,
+        };
+        self.request_ads(slave, target, request).await?;
+        self.with_mailbox(|mb_data| read_data(ads_response(&mb_data)?, buf))
+    }
+
+    /// Returns the ADS state and the device state.
+    pub async fn read_ads_state(
+        &self,
+        slave: &Slave,
+        target: AmsAddress,
+    ) -> Result<(AdsState, u16), TaskError<AoeErrorKind>> {
+        self.request_ads(slave, target, AdsRequest::ReadState)
+            .await?;
+        self.with_mailbox(|mb_data| ads_state(ads_response(&mb_data)?))
+    }
+
+    /// The response is left in the socket buffer.
+    /// Responses to other requests, e.g. which have timed out, are dropped.
+    async fn request_ads(
+        &self,
+        slave: &Slave,
+        target: AmsAddress,
+        request: AdsRequest<'_>,
+    ) -> Result<(), TaskError<AoeErrorKind>> {
+        let invoke_id = slave.next_ads_invoke_id();
+        let header = AmsHeader {
+            target,
+            source: self.ams_address(),
+            error_code: AdsErrorCode::NoError,
+            invoke_id,
+        };
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(&Mailbox::AoE(AoE::Request { header, request }))
+            },
+            false,
+        )
+        .await?;
+        loop {
+            self.read_response(slave, MailboxType::AoE).await?;
+            if !self.with_mailbox(|mb_data| is_other_response(&mb_data, invoke_id)) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Data size of a Read or ReadWrite response which fits in the tx mailbox.
+fn ads_read_capacity(slave_info: &SlaveInfo) -> usize {
+    (slave_info.mailbox_tx_sm().unwrap_or_default().size() as usize)
+        .saturating_sub(MailboxFrame::HEADER_SIZE + AmsFrame::HEADER_SIZE + 8)
+}
+
+/// Whether it is an AoE response with another invoke ID.
+fn is_other_response(mb_data: &MailboxFrame<&[u8]>, invoke_id: u32) -> bool {
+    match mb_data.mailbox() {
+        Ok(Mailbox::AoE(AoE::Response { header, .. })) => header.invoke_id != invoke_id,
+        _ => false,
+    }
+}
+
+/// ADS response of the slave. An error code in the AMS header and other mailboxes are errors.
+fn ads_response<'a>(
+    mb_data: &MailboxFrame<&'a [u8]>,
+) -> Result<AdsResponse<'a>, TaskError<AoeErrorKind>> {
+    let mb = mb_data
+        .mailbox()
+        .map_err(|_| AoeErrorKind::Mailbox(MailboxTaskError::BufferSmall))?;
+    match mb {
+        Mailbox::AoE(AoE::Response { header, .. })
+            if header.error_code != AdsErrorCode::NoError =>
+        {
+            Err(AoeErrorKind::Error(header.error_code).into())
+        }
+        Mailbox::AoE(AoE::Response { response, .. }) => Ok(response),
+        Mailbox::AoE(AoE::Request { .. }) => {
+            Err(AoeErrorKind::UnexpectedMailbox(MailboxType::AoE).into())
+        }
+        Mailbox::Error(detail) => Err(AoeErrorKind::ErrorMailbox(detail).into()),
+        _ => Err(AoeErrorKind::UnexpectedMailbox(mb_data.mb_type()).into()),
+    }
+}
+
+fn read_data(response: AdsResponse, buf: &mut [u8]) -> Result<usize, TaskError<AoeErrorKind>> {
+    match response {
+        AdsResponse::Read { result, data } | AdsResponse::ReadWrite { result, data } => {
+            check_result(result)?;
+            let dst = buf.get_mut(..data.len()).ok_or(AoeErrorKind::BufferSmall)?;
+            dst.copy_from_slice(data);
+            Ok(data.len())
+        }
+        other => Err(unexpected_ads(&other).into()),
+    }
+}
+
+fn check_write(response: AdsResponse) -> Result<(), TaskError<AoeErrorKind>> {
+    match response {
+        AdsResponse::Write { result } => check_result(result),
+        other => Err(unexpected_ads(&other).into()),
+    }
+}
+
+fn ads_state(response: AdsResponse) -> Result<(AdsState, u16), TaskError<AoeErrorKind>> {
+    match response {
+        AdsResponse::ReadState {
+            result,
+            ads_state,
+            device_state,
+        } => {
+            check_result(result)?;
+            Ok((ads_state, device_state))
+        }
+        other => Err(unexpected_ads(&other).into()),
+    }
+}
+
+fn check_result(result: AdsErrorCode) -> Result<(), TaskError<AoeErrorKind>> {
+    if result == AdsErrorCode::NoError {
+        Ok(())
+    } else {
+        Err(AoeErrorKind::Error(result).into())
+    }
+}
+
+fn unexpected_ads(response: &AdsResponse) -> AoeErrorKind {
+    let command = match response {
+        AdsResponse::Read { .. } => AdsCommand::Read,
+        AdsResponse::Write { .. } => AdsCommand::Write,
+        AdsResponse::ReadWrite { .. } => AdsCommand::ReadWrite,
+        AdsResponse::ReadState { .. } => AdsCommand::ReadState,
+        AdsResponse::Other(command) => *command,
+    };
+    AoeErrorKind::UnexpectedCommand(command)
+}
+
+#[derive(Debug, Clone)]
+pub enum AoeErrorKind {
+    Mailbox(MailboxTaskError),
+    /// Error code in the AMS header, or result of the response.
+    Error(AdsErrorCode),
+    ErrorMailbox(MailboxErrorDetail),
+    UnexpectedMailbox(MailboxType),
+    UnexpectedCommand(AdsCommand),
+    /// The data do not fit in the buffer.
+    BufferSmall,
+}
+
+impl From<TaskError<MailboxTaskError>> for TaskError<AoeErrorKind> {
+    fn from(err: TaskError<MailboxTaskError>) -> Self {
+        match err {
+            TaskError::Interface(e) => TaskError::Interface(e),
+            TaskError::UnexpectedCommand => TaskError::UnexpectedCommand,
+            TaskError::UnexpectedWkc(e) => TaskError::UnexpectedWkc(e),
+            TaskError::TaskSpecific(e) => TaskError::TaskSpecific(AoeErrorKind::Mailbox(e)),
+            TaskError::Timeout => TaskError::Timeout,
+        }
+    }
+}
+
+impl From<AoeErrorKind> for TaskError<AoeErrorKind> {
+    fn from(err: AoeErrorKind) -> Self {
+        Self::TaskSpecific(err)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::{AdsErrorCode, AdsIndex, AdsState, AmsAddress};
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::SlaveAddress;
+    use crate::task::{AoeErrorKind, TaskError};
+
+    #[test]
+    fn aoe_test() {
+        let mut segment = new_segment(1);
+        let aoe = segment.slaves_mut()[0].aoe_mut();
+        let target = aoe.address();
+        let counter = AdsIndex::new(0x4020, 0);
+        let name = AdsIndex::new(0x4020, 4);
+        aoe.insert_variable(counter, &7_u32.to_le_bytes());
+        aoe.insert_variable(name, b"gateway");
+        aoe.set_ads_state(AdsState::Run, 3);
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(segment);
+        master.set_ams_address(AmsAddress::new([192, 168, 0, 10, 1, 1], 32905));
+
+        let slave = SlaveAddress::SlavePosition(0);
+        assert!(master
+            .network()
+            .slave(slave)
+            .unwrap()
+            .0
+            .info()
+            .support_aoe());
+        let mut data = [0; 64];
+        let size = master.read_ads(slave, target, counter, &mut data).unwrap();
+        assert_eq!(&data[..size], &7_u32.to_le_bytes());
+        assert_eq!(
+            master.read_ads_state(slave, target).unwrap(),
+            (AdsState::Run, 3)
+        );
+        master
+            .write_ads(slave, target, counter, &8_u32.to_le_bytes())
+            .unwrap();
+        assert_eq!(
+            master.device().slaves()[0].aoe().variable(counter),
+            Some(&8_u32.to_le_bytes()[..])
+        );
+        let size = master
+            .read_write_ads(slave, target, name, b"coupler", &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], b"coupler");
+
+        match master.read_ads(slave, target, AdsIndex::new(0x4020, 8), &mut data) {
+            Err(TaskError::TaskSpecific(AoeErrorKind::Error(code))) => {
+                assert_eq!(code, AdsErrorCode::InvalidIndexOffset)
+            }
+            other => panic!("{:?}", other),
+        }
+        match master.read_ads(slave, target, counter, &mut data[..2]) {
+            Err(TaskError::TaskSpecific(AoeErrorKind::Error(AdsErrorCode::InvalidSize))) => {}
+            other => panic!("{:?}", other),
+        }
+        let other_port = AmsAddress::new(target.net_id, target.port + 1);
+        match master.read_ads_state(slave, other_port) {
+            Err(TaskError::TaskSpecific(AoeErrorKind::Error(code))) => {
+                assert_eq!(code, AdsErrorCode::TargetPortNotFound)
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // A second copy of a response is dropped by the next request.
+        master.device_mut().slaves_mut()[0]
+            .aoe_mut()
+            .set_duplicate_response(true);
+        for value in [9_u32, 10] {
+            master
+                .write_ads(slave, target, counter, &value.to_le_bytes())
+                .unwrap();
+            let size = master.read_ads(slave, target, counter, &mut data).unwrap();
+            assert_eq!(&data[..size], &value.to_le_bytes());
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use super::EtherCatSystemTime;
+use core::cell::Cell;
+use core::fmt;
+use core::time::Duration;
+
+/// Source of the current time for timeouts.
+/// It must be monotonic, at least while the master is running.
+pub trait Clock {
+    fn now(&self) -> EtherCatSystemTime;
+}
+
+impl fmt::Debug for dyn Clock + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Clock").field(&self.now()).finish()
+    }
+}
+
+/// Monotonic clock of the operating system. The time is counted from its creation.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> EtherCatSystemTime {
+        self.start.elapsed().into()
+    }
+}
+
+/// Clock which is advanced by hand, for tests and simulations.
+/// With a tick, every read advances the clock too, so that busy loops time out.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: Cell<u64>,
+    tick: Cell<u64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tick(tick: Duration) -> Self {
+        let clock = Self::new();
+        clock.set_tick(tick);
+        clock
+    }
+
+    pub fn set(&self, time: EtherCatSystemTime) {
+        self.now.set(time.0);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration.as_nanos() as u64);
+    }
+
+    pub fn set_tick(&self, tick: Duration) {
+        self.tick.set(tick.as_nanos() as u64);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> EtherCatSystemTime {
+        let now = self.now.get();
+        self.now.set(now + self.tick.get());
+        EtherCatSystemTime(now)
+    }
+}
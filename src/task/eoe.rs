@@ -0,0 +1,377 @@
+use super::{AcyclicSocket, MailboxTaskError, TaskError};
+use crate::{
+    frame::{
+        CoE, EoE, EoeFrame, EoeIpParameter, EoeResult, Mailbox, MailboxErrorDetail, MailboxFrame,
+        MailboxType,
+    },
+    interface::{EoeDevice, FragmentError, SocketAccess},
+    slave::{Emergency, Slave, SlaveInfo},
+};
+
+/// EoE client. A frame is sent in fragments of the mailbox size.
+impl<'a, S: SocketAccess> AcyclicSocket<'a, S> {
+    /// Sends the pending frame of `device`,
+    /// and receives fragments while the slave has them and `device` has room for a frame.
+    /// Fragments which have been read by `EtherCatMaster::process`, or by another request to the slave,
+    /// are kept in the slave and taken first. Mailboxes of other protocols are kept for their readers.
+    pub async fn poll_eoe(
+        &self,
+        slave: &Slave,
+        device: &mut EoeDevice<'_>,
+    ) -> Result<(), TaskError<EoeErrorKind>> {
+        if let Some((frame_number, frame)) = device.pending_frame() {
+            self.send_eoe_frame(slave, frame_number, frame).await?;
+            device.frame_sent();
+        }
+        while device.can_receive() {
+            let received = slave
+                .pop_received_mailbox(MailboxType::EoE, |mb| receive_eoe_fragment(device, &mb));
+            if let Some(result) = received {
+                result?;
+                continue;
+            }
+            let time = self.now();
+            match self.read_mailbox(slave.info(), false).await {
+                Err(TaskError::TaskSpecific(MailboxTaskError::MailboxEmpty)) => return Ok(()),
+                result => result?,
+            };
+            if slave.is_repeated_mailbox(self.with_mailbox(|mb_data| mb_data.count())) {
+                continue;
+            }
+            self.with_mailbox(|mb_data| {
+                match mb_data.mailbox() {
+                    Ok(Mailbox::CoE((_, CoE::Emmergency(frame)))) => {
+                        let slave_address = slave.info().slave_address();
+                        slave.push_emergency(Emergency::new(slave_address, time, &frame));
+                    }
+                    _ if mb_data.mb_type() == MailboxType::EoE
+                        || mb_data.mb_type() == MailboxType::Error =>
+                    {
+                        receive_eoe_fragment(device, &mb_data)?
+                    }
+                    _ => slave.push_received_mailbox(mb_data.0),
+                }
+                Ok::<_, TaskError<EoeErrorKind>>(())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Fragments received before the response are dropped.
+    pub async fn set_eoe_ip_parameter(
+        &self,
+        slave: &Slave,
+        parameter: &EoeIpParameter,
+    ) -> Result<(), TaskError<EoeErrorKind>> {
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(&Mailbox::new_eoe_set_ip_request(*parameter))
+            },
+            false,
+        )
+        .await?;
+        loop {
+            self.read_response(slave, MailboxType::EoE).await?;
+            let done = self.with_mailbox(|mb_data| -> Result<bool, TaskError<EoeErrorKind>> {
+                match eoe_mailbox(&mb_data)? {
+                    Mailbox::EoE(EoE::Fragment { .. }) => Ok(false),
+                    Mailbox::EoE(EoE::SetIpRes(EoeResult::Success)) => Ok(true),
+                    Mailbox::EoE(EoE::SetIpRes(result)) => Err(EoeErrorKind::Result(result).into()),
+                    _ => Err(EoeErrorKind::UnexpectedMailbox(mb_data.mb_type()).into()),
+                }
+            })?;
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Each fragment waits for the slave to read the previous one.
+    async fn send_eoe_frame(
+        &self,
+        slave: &Slave,
+        frame_number: u8,
+        frame: &[u8],
+    ) -> Result<(), TaskError<EoeErrorKind>> {
+        let capacity = eoe_fragment_capacity(slave.info());
+        for (fragment_number, fragment) in frame.chunks(capacity).enumerate() {
+            let sent = fragment_number * capacity;
+            let offset = if fragment_number == 0 {
+                frame.len().div_ceil(EoeFrame::FRAGMENT_UNIT)
+            } else {
+                sent / EoeFrame::FRAGMENT_UNIT
+            };
+            let last = frame.len() <= sent + fragment.len();
+            let message = Mailbox::new_eoe_fragment(
+                frame_number,
+                fragment_number as u8,
+                offset as u8,
+                last,
+                fragment,
+            );
+            let count = slave.increment_mb_count();
+            self.write_mailbox(
+                slave.info(),
+                |mb_frame| {
+                    mb_frame.set_count(count);
+                    mb_frame.set_mailbox(&message)
+                },
+                true,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Fragments except the last one are a multiple of `EoeFrame::FRAGMENT_UNIT`.
+fn eoe_fragment_capacity(slave_info: &SlaveInfo) -> usize {
+    let mailbox_size = slave_info.mailbox_rx_sm().unwrap_or_default().size() as usize;
+    let capacity = mailbox_size.saturating_sub(MailboxFrame::HEADER_SIZE + EoeFrame::HEADER_SIZE);
+    capacity / EoeFrame::FRAGMENT_UNIT * EoeFrame::FRAGMENT_UNIT
+}
+
+fn receive_eoe_fragment(
+    device: &mut EoeDevice<'_>,
+    mb_data: &MailboxFrame<&[u8]>,
+) -> Result<(), TaskError<EoeErrorKind>> {
+    match eoe_mailbox(mb_data)? {
+        Mailbox::EoE(EoE::Fragment {
+            frame_number,
+            fragment_number,
+            offset,
+            last,
+            data,
+            ..
+        }) => device.receive_fragment(frame_number, fragment_number, offset, last, data)?,
+        _ => return Err(EoeErrorKind::UnexpectedMailbox(mb_data.mb_type()).into()),
+    }
+    Ok(())
+}
+
+fn eoe_mailbox<'a>(
+    mb_data: &MailboxFrame<&'a [u8]>,
+) -> Result<Mailbox<'a>, TaskError<EoeErrorKind>> {
+    let mb = mb_data
+        .mailbox()
+        .map_err(|_| EoeErrorKind::Mailbox(MailboxTaskError::BufferSmall))?;
+    match mb {
+        Mailbox::Error(detail) => Err(EoeErrorKind::ErrorMailbox(detail).into()),
+        mb => Ok(mb),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EoeErrorKind {
+    Mailbox(MailboxTaskError),
+    ErrorMailbox(MailboxErrorDetail),
+    UnexpectedMailbox(MailboxType),
+    /// Result of a response other than success.
+    Result(EoeResult),
+    /// A fragment does not continue the frame being received. The frame is dropped.
+    FragmentUnmatch,
+    /// A received frame does not fit in the buffer. It is dropped.
+    BufferSmall,
+}
+
+impl From<FragmentError> for EoeErrorKind {
+    fn from(err: FragmentError) -> Self {
+        match err {
+            FragmentError::Unmatch => Self::FragmentUnmatch,
+            FragmentError::BufferSmall => Self::BufferSmall,
+        }
+    }
+}
+
+impl From<FragmentError> for TaskError<EoeErrorKind> {
+    fn from(err: FragmentError) -> Self {
+        Self::TaskSpecific(err.into())
+    }
+}
+
+impl From<TaskError<MailboxTaskError>> for TaskError<EoeErrorKind> {
+    fn from(err: TaskError<MailboxTaskError>) -> Self {
+        match err {
+            TaskError::Interface(e) => TaskError::Interface(e),
+            TaskError::UnexpectedCommand => TaskError::UnexpectedCommand,
+            TaskError::UnexpectedWkc(e) => TaskError::UnexpectedWkc(e),
+            TaskError::TaskSpecific(e) => TaskError::TaskSpecific(EoeErrorKind::Mailbox(e)),
+            TaskError::Timeout => TaskError::Timeout,
+        }
+    }
+}
+
+impl From<EoeErrorKind> for TaskError<EoeErrorKind> {
+    fn from(err: EoeErrorKind) -> Self {
+        Self::TaskSpecific(err)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::{EoeIpParameter, MailboxType};
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::{EoeDevice, SlaveAddress};
+    use crate::task::EtherCatSystemTime;
+
+    #[test]
+    fn eoe_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+
+        let slave = SlaveAddress::SlavePosition(0);
+        assert!(master
+            .network()
+            .slave(slave)
+            .unwrap()
+            .0
+            .info()
+            .support_eoe());
+        let parameter = EoeIpParameter {
+            mac_address: Some([0x02, 0, 0, 0, 0, 0x02]),
+            ip_address: Some([192, 168, 1, 2]),
+            subnet_mask: Some([255, 255, 255, 0]),
+            ..Default::default()
+        };
+        master.set_eoe_ip_parameter(slave, &parameter).unwrap();
+        assert_eq!(master.device().slaves()[0].eoe().ip_parameter(), &parameter);
+
+        // Both frames are larger than the mailbox.
+        let mut tx_buf = [0; 1514];
+        let mut rx_buf = [0; 1514];
+        let mut device = EoeDevice::new(slave, &mut tx_buf, &mut rx_buf);
+        let frame: Vec<u8> = (0..1000).map(|i| (i * 3) as u8).collect();
+        assert!(device.send_frame(&frame));
+        assert!(!device.send_frame(&frame));
+        master.poll_eoe(&mut device).unwrap();
+        let eoe = master.device_mut().slaves_mut()[0].eoe_mut();
+        assert_eq!(eoe.pop_frame(), Some(frame));
+        assert_eq!(eoe.pop_frame(), None);
+
+        let frame: Vec<u8> = (0..1200).map(|i| (i * 7) as u8).collect();
+        master.device_mut().slaves_mut()[0].send_eoe_frame(&frame);
+        assert_eq!(device.receive_frame(|_| ()), None);
+        // The slave fills the mailbox after the first poll.
+        for _ in 0..2 {
+            master.poll_eoe(&mut device).unwrap();
+        }
+        assert_eq!(device.receive_frame(|data| data.to_vec()), Some(frame));
+        assert_eq!(device.receive_frame(|_| ()), None);
+
+        // The VoE response read by the poll is kept for its reader.
+        master.device_mut().slaves_mut()[0].set_voe_handler(|request| Some(request.to_vec()));
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 1, 2, 3, 4];
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        let frame: Vec<u8> = (0..60).collect();
+        master.device_mut().slaves_mut()[0].send_eoe_frame(&frame);
+        master.poll_eoe(&mut device).unwrap();
+        assert_eq!(device.receive_frame(|data| data.to_vec()), Some(frame));
+        let mut data = [0; 128];
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &request);
+
+        // The fragment read before an SDO response is received by the next poll.
+        let frame: Vec<u8> = (0..60).map(|i| i * 2).collect();
+        master.device_mut().slaves_mut()[0].send_eoe_frame(&frame);
+        assert_eq!(master.read_sdo::<u32>(slave, 0x1018, 1).unwrap(), 0x0ABC);
+        master.poll_eoe(&mut device).unwrap();
+        assert_eq!(device.receive_frame(|data| data.to_vec()), Some(frame));
+
+        // The fragment read by a mailbox session is received by the next poll.
+        let mut pdo_buffer = vec![0; 1500];
+        assert!(master.register_process_data_buffer(&mut pdo_buffer));
+        let frame: Vec<u8> = (0..60).rev().collect();
+        master.device_mut().slaves_mut()[0].send_eoe_frame(&frame);
+        let mut mif = master.try_get_mailbox_request_interface().unwrap();
+        mif.read_sdo_request(slave, 0x1018, 1).unwrap();
+        let mut response = None;
+        let mut time = 0;
+        while response.is_none() && time < 100_000_000 {
+            time += 1_000_000;
+            master.process(EtherCatSystemTime(time)).unwrap();
+            response = master.received_mailbox();
+        }
+        assert!(response.unwrap().1.is_ok());
+        master.poll_eoe(&mut device).unwrap();
+        assert_eq!(device.receive_frame(|data| data.to_vec()), Some(frame));
+    }
+
+    /// A smoltcp interface answers an ARP request of the slave.
+    #[cfg(feature = "smoltcp")]
+    #[test]
+    fn eoe_smoltcp_test() {
+        use smoltcp::iface::{InterfaceBuilder, NeighborCache, SocketStorage};
+        use smoltcp::time::Instant;
+        use smoltcp::wire::{
+            ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol,
+            EthernetRepr, IpCidr, Ipv4Address,
+        };
+
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut tx_buf = [0; 1514];
+        let mut rx_buf = [0; 1514];
+        let device = EoeDevice::new(slave, &mut tx_buf, &mut rx_buf);
+        let master_mac = EthernetAddress([0x02, 0, 0, 0, 0, 0x01]);
+        let master_ip = Ipv4Address::new(192, 168, 1, 1);
+        let mut sockets = [SocketStorage::EMPTY];
+        let mut ip_addrs = [IpCidr::new(master_ip.into(), 24)];
+        let mut neighbors = [None; 4];
+        let mut interface = InterfaceBuilder::new(device, &mut sockets[..])
+            .hardware_addr(master_mac.into())
+            .ip_addrs(&mut ip_addrs[..])
+            .neighbor_cache(NeighborCache::new(&mut neighbors[..]))
+            .finalize();
+
+        let slave_mac = EthernetAddress([0x02, 0, 0, 0, 0, 0x02]);
+        let slave_ip = Ipv4Address::new(192, 168, 1, 2);
+        let mut request = [0; 42];
+        let mut frame = EthernetFrame::new_unchecked(&mut request[..]);
+        EthernetRepr {
+            src_addr: slave_mac,
+            dst_addr: EthernetAddress::BROADCAST,
+            ethertype: EthernetProtocol::Arp,
+        }
+        .emit(&mut frame);
+        ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: slave_mac,
+            source_protocol_addr: slave_ip,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr: master_ip,
+        }
+        .emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+        master.device_mut().slaves_mut()[0].send_eoe_frame(&request);
+
+        for _ in 0..3 {
+            master.poll_eoe(interface.device_mut()).unwrap();
+            let _ = interface.poll(Instant::from_millis(0));
+        }
+        let reply = master.device_mut().slaves_mut()[0]
+            .eoe_mut()
+            .pop_frame()
+            .unwrap();
+        let frame = EthernetFrame::new_checked(&reply[..]).unwrap();
+        assert_eq!(frame.dst_addr(), slave_mac);
+        let arp = ArpRepr::parse(&ArpPacket::new_checked(frame.payload()).unwrap()).unwrap();
+        assert_eq!(
+            arp,
+            ArpRepr::EthernetIpv4 {
+                operation: ArpOperation::Reply,
+                source_hardware_addr: master_mac,
+                source_protocol_addr: master_ip,
+                target_hardware_addr: slave_mac,
+                target_protocol_addr: slave_ip,
+            }
+        );
+    }
+}
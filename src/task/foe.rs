@@ -0,0 +1,433 @@
+use super::{AcyclicSocket, EtherCatSystemTime, MailboxTaskError, TaskError};
+use crate::{
+    frame::{
+        FoE, FoeErrorCode, FoeFrame, FoeOpCode, LengthError, Mailbox, MailboxErrorDetail,
+        MailboxFrame, MailboxType,
+    },
+    interface::SocketAccess,
+    slave::{Slave, SlaveInfo},
+};
+
+/// FoE client. A file is transferred in packets of the mailbox size.
+impl<'a, S: SocketAccess> AcyclicSocket<'a, S> {
+    /// Reads a file into `buf`. Returns the size of the file.
+    pub async fn read_foe(
+        &self,
+        slave: &Slave,
+        file_name: &[u8],
+        password: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<FoeErrorKind>> {
+        let mut size = 0;
+        let result = self
+            .read_foe_with(slave, file_name, password, |data| {
+                let dst = buf
+                    .get_mut(size..size + data.len())
+                    .ok_or(FoeErrorCode::DiskFull)?;
+                dst.copy_from_slice(data);
+                size += data.len();
+                Ok(())
+            })
+            .await;
+        match result {
+            Ok(()) => Ok(size),
+            Err(TaskError::TaskSpecific(FoeErrorKind::Cancelled(FoeErrorCode::DiskFull))) => {
+                Err(FoeErrorKind::BufferSmall.into())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Reads a file and passes the data of each packet to `writer` in order.
+    /// If `writer` returns an error code, it is sent to the slave and the transfer is cancelled.
+    /// The interface is borrowed while `writer` runs.
+    pub async fn read_foe_with<F>(
+        &self,
+        slave: &Slave,
+        file_name: &[u8],
+        password: u32,
+        mut writer: F,
+    ) -> Result<(), TaskError<FoeErrorKind>>
+    where
+        F: FnMut(&[u8]) -> Result<(), FoeErrorCode>,
+    {
+        let capacity = foe_read_capacity(slave.info());
+        let mut packet_number = 0;
+        let mut busy_since = None;
+        let request = Mailbox::new_foe_read_request(password, file_name);
+        self.send_foe(slave, &request).await?;
+        loop {
+            self.read_response(slave, MailboxType::FoE).await?;
+            let step = self.with_mailbox(|mb_data| {
+                let step = match foe_response(&mb_data)? {
+                    FoE::Data {
+                        packet_number: number,
+                        data,
+                    } if number == packet_number + 1 => {
+                        packet_number = number;
+                        match writer(data) {
+                            Ok(()) => FoeStep::Next {
+                                last: data.len() < capacity,
+                            },
+                            Err(code) => FoeStep::Cancel(code, FoeErrorKind::Cancelled(code)),
+                        }
+                    }
+                    FoE::Data { .. } => FoeStep::Cancel(
+                        FoeErrorCode::PacketNumberWrong,
+                        FoeErrorKind::PacketNumberUnmatch,
+                    ),
+                    FoE::Busy { .. } => FoeStep::Busy,
+                    other => FoeStep::Cancel(FoeErrorCode::Illegal, unexpected_foe(&other)),
+                };
+                Ok::<_, TaskError<FoeErrorKind>>(step)
+            })?;
+            match step {
+                FoeStep::Next { last } => {
+                    busy_since = None;
+                    self.send_foe(slave, &Mailbox::new_foe_ack(packet_number))
+                        .await?;
+                    if last {
+                        return Ok(());
+                    }
+                }
+                // The last request is repeated.
+                FoeStep::Busy if packet_number == 0 => {
+                    self.check_foe_busy(&mut busy_since)?;
+                    self.send_foe(slave, &request).await?;
+                }
+                FoeStep::Busy => {
+                    self.check_foe_busy(&mut busy_since)?;
+                    self.send_foe(slave, &Mailbox::new_foe_ack(packet_number))
+                        .await?;
+                }
+                FoeStep::Cancel(code, kind) => {
+                    self.send_foe(slave, &Mailbox::new_foe_error(code)).await?;
+                    return Err(kind.into());
+                }
+            }
+        }
+    }
+
+    pub async fn write_foe(
+        &self,
+        slave: &Slave,
+        file_name: &[u8],
+        password: u32,
+        data: &[u8],
+    ) -> Result<(), TaskError<FoeErrorKind>> {
+        self.write_foe_with(slave, file_name, password, |offset, buf| {
+            let rest = data.get(offset..).unwrap_or_default();
+            let size = rest.len().min(buf.len());
+            buf[..size].copy_from_slice(&rest[..size]);
+            Ok(size)
+        })
+        .await
+    }
+
+    /// Writes a file whose data are read by `reader` from the given offset into the buffer.
+    /// `reader` returns the size it has read, and less than the buffer size at the end of the file.
+    /// The same offset may be read again, if the slave is busy.
+    /// If `reader` returns an error code, it is sent to the slave and the transfer is cancelled.
+    /// The interface is borrowed while `reader` runs.
+    pub async fn write_foe_with<F>(
+        &self,
+        slave: &Slave,
+        file_name: &[u8],
+        password: u32,
+        mut reader: F,
+    ) -> Result<(), TaskError<FoeErrorKind>>
+    where
+        F: FnMut(usize, &mut [u8]) -> Result<usize, FoeErrorCode>,
+    {
+        let mut packet_number = 0;
+        let mut last = false;
+        let mut busy_since = None;
+        let request = Mailbox::new_foe_write_request(password, file_name);
+        self.send_foe(slave, &request).await?;
+        loop {
+            self.read_response(slave, MailboxType::FoE).await?;
+            let step = self.with_mailbox(|mb_data| {
+                let step = match foe_response(&mb_data)? {
+                    FoE::Ack(number) if number == packet_number => FoeStep::Next { last },
+                    FoE::Ack(_) => FoeStep::Cancel(
+                        FoeErrorCode::PacketNumberWrong,
+                        FoeErrorKind::PacketNumberUnmatch,
+                    ),
+                    FoE::Busy { .. } => FoeStep::Busy,
+                    other => FoeStep::Cancel(FoeErrorCode::Illegal, unexpected_foe(&other)),
+                };
+                Ok::<_, TaskError<FoeErrorKind>>(step)
+            })?;
+            match step {
+                FoeStep::Next { last: true } => return Ok(()),
+                FoeStep::Next { last: false } => {
+                    busy_since = None;
+                    packet_number += 1;
+                    last = self
+                        .send_foe_data(slave, packet_number, &mut reader)
+                        .await?;
+                }
+                // The last request is repeated.
+                FoeStep::Busy if packet_number == 0 => {
+                    self.check_foe_busy(&mut busy_since)?;
+                    self.send_foe(slave, &request).await?;
+                }
+                FoeStep::Busy => {
+                    self.check_foe_busy(&mut busy_since)?;
+                    last = self
+                        .send_foe_data(slave, packet_number, &mut reader)
+                        .await?;
+                }
+                FoeStep::Cancel(code, kind) => {
+                    self.send_foe(slave, &Mailbox::new_foe_error(code)).await?;
+                    return Err(kind.into());
+                }
+            }
+        }
+    }
+
+    async fn send_foe(
+        &self,
+        slave: &Slave,
+        message: &Mailbox<'_>,
+    ) -> Result<(), TaskError<FoeErrorKind>> {
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(message)
+            },
+            false,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Sends a data packet which `reader` reads directly into the mailbox.
+    /// Returns whether it is the last packet.
+    async fn send_foe_data<F>(
+        &self,
+        slave: &Slave,
+        packet_number: u32,
+        reader: &mut F,
+    ) -> Result<bool, TaskError<FoeErrorKind>>
+    where
+        F: FnMut(usize, &mut [u8]) -> Result<usize, FoeErrorCode>,
+    {
+        let capacity = foe_write_capacity(slave.info());
+        let offset = (packet_number as usize - 1) * capacity;
+        let count = slave.increment_mb_count();
+        let mut result = Ok(0);
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                mb_frame.set_count(count);
+                let start = MailboxFrame::HEADER_SIZE + FoeFrame::HEADER_SIZE;
+                let buf = mb_frame
+                    .0
+                    .get_mut(start..start + capacity)
+                    .ok_or(LengthError)?;
+                result = reader(offset, buf).map(|size| size.min(capacity));
+                match result {
+                    Ok(size) => {
+                        mb_frame.set_mb_type(MailboxType::FoE);
+                        mb_frame.set_length((FoeFrame::HEADER_SIZE + size) as u16);
+                        let mut foe_frame = FoeFrame(&mut mb_frame.0[MailboxFrame::HEADER_SIZE..]);
+                        foe_frame.set_foe_op_code(FoeOpCode::Data);
+                        foe_frame.set_value(packet_number);
+                        foe_frame.0[1] = 0;
+                        Ok(())
+                    }
+                    Err(code) => mb_frame.set_mailbox(&Mailbox::new_foe_error(code)),
+                }
+            },
+            false,
+        )
+        .await?;
+        match result {
+            Ok(size) => Ok(size < capacity),
+            Err(code) => Err(FoeErrorKind::Cancelled(code).into()),
+        }
+    }
+
+    /// Fails if the slave has been busy longer than `Timeouts::foe_busy`.
+    fn check_foe_busy(
+        &self,
+        busy_since: &mut Option<EtherCatSystemTime>,
+    ) -> Result<(), TaskError<FoeErrorKind>> {
+        let now = self.now();
+        let since = *busy_since.get_or_insert(now);
+        if self.timeouts().foe_busy < now.elapsed_since(since) {
+            Err(TaskError::Timeout)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// What the client does after a response of the slave.
+enum FoeStep {
+    /// Acknowledges data or sends the next data.
+    Next {
+        last: bool,
+    },
+    Busy,
+    /// Sends an error request to the slave and returns the error.
+    Cancel(FoeErrorCode, FoeErrorKind),
+}
+
+/// Data size of a packet which the slave sends.
+fn foe_read_capacity(slave_info: &SlaveInfo) -> usize {
+    (slave_info.mailbox_tx_sm().unwrap_or_default().size() as usize)
+        .saturating_sub(MailboxFrame::HEADER_SIZE + FoeFrame::HEADER_SIZE)
+}
+
+/// Data size of a packet which fits in the rx mailbox.
+fn foe_write_capacity(slave_info: &SlaveInfo) -> usize {
+    (slave_info.mailbox_rx_sm().unwrap_or_default().size() as usize)
+        .saturating_sub(MailboxFrame::HEADER_SIZE + FoeFrame::HEADER_SIZE)
+}
+
+/// FoE response of the slave. An error request of the slave and other mailboxes are errors.
+fn foe_response<'a>(mb_data: &MailboxFrame<&'a [u8]>) -> Result<FoE<'a>, TaskError<FoeErrorKind>> {
+    let mb = mb_data
+        .mailbox()
+        .map_err(|_| FoeErrorKind::Mailbox(MailboxTaskError::BufferSmall))?;
+    match mb {
+        Mailbox::FoE(FoE::Error { code, .. }) => Err(FoeErrorKind::Error(code).into()),
+        Mailbox::FoE(foe) => Ok(foe),
+        Mailbox::Error(detail) => Err(FoeErrorKind::ErrorMailbox(detail).into()),
+        _ => Err(FoeErrorKind::UnexpectedMailbox(mb_data.mb_type()).into()),
+    }
+}
+
+fn unexpected_foe(foe: &FoE) -> FoeErrorKind {
+    let op_code = match foe {
+        FoE::ReadReq { .. } => FoeOpCode::Read,
+        FoE::WriteReq { .. } => FoeOpCode::Write,
+        FoE::Data { .. } => FoeOpCode::Data,
+        FoE::Ack(_) => FoeOpCode::Ack,
+        FoE::Error { .. } => FoeOpCode::Error,
+        FoE::Busy { .. } => FoeOpCode::Busy,
+        FoE::Other(op_code) => *op_code,
+    };
+    FoeErrorKind::UnexpectedOpCode(op_code)
+}
+
+#[derive(Debug, Clone)]
+pub enum FoeErrorKind {
+    Mailbox(MailboxTaskError),
+    /// Error request of the slave.
+    Error(FoeErrorCode),
+    ErrorMailbox(MailboxErrorDetail),
+    UnexpectedMailbox(MailboxType),
+    UnexpectedOpCode(FoeOpCode),
+    PacketNumberUnmatch,
+    /// The file does not fit in the buffer.
+    BufferSmall,
+    /// The transfer is cancelled by the reader or the writer of the file data.
+    Cancelled(FoeErrorCode),
+}
+
+impl From<TaskError<MailboxTaskError>> for TaskError<FoeErrorKind> {
+    fn from(err: TaskError<MailboxTaskError>) -> Self {
+        match err {
+            TaskError::Interface(e) => TaskError::Interface(e),
+            TaskError::UnexpectedCommand => TaskError::UnexpectedCommand,
+            TaskError::UnexpectedWkc(e) => TaskError::UnexpectedWkc(e),
+            TaskError::TaskSpecific(e) => TaskError::TaskSpecific(FoeErrorKind::Mailbox(e)),
+            TaskError::Timeout => TaskError::Timeout,
+        }
+    }
+}
+
+impl From<FoeErrorKind> for TaskError<FoeErrorKind> {
+    fn from(err: FoeErrorKind) -> Self {
+        Self::TaskSpecific(err)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::FoeErrorCode;
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::SlaveAddress;
+    use crate::task::{FoeErrorKind, TaskError};
+
+    #[test]
+    fn foe_test() {
+        let mut segment = new_segment(1);
+        let log: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let foe = segment.slaves_mut()[0].foe_mut();
+        foe.insert_file(b"log.txt", &log);
+        // A multiple of the packet size ends with an empty packet.
+        foe.insert_file(b"empty_end.bin", &[0xAA; 2 * 116]);
+        foe.set_password(0x1234);
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(segment);
+
+        let slave = SlaveAddress::SlavePosition(0);
+        assert!(master
+            .network()
+            .slave(slave)
+            .unwrap()
+            .0
+            .info()
+            .support_foe());
+        let mut data = [0; 512];
+        let size = master
+            .read_foe(slave, b"log.txt", 0x1234, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &log[..]);
+        let size = master
+            .read_foe(slave, b"empty_end.bin", 0x1234, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &[0xAA; 2 * 116]);
+        match master.read_foe(slave, b"log.txt", 0x1234, &mut data[..200]) {
+            Err(TaskError::TaskSpecific(FoeErrorKind::BufferSmall)) => {}
+            other => panic!("{:?}", other),
+        }
+        match master.read_foe(slave, b"missing.txt", 0x1234, &mut data) {
+            Err(TaskError::TaskSpecific(FoeErrorKind::Error(FoeErrorCode::NotFound))) => {}
+            other => panic!("{:?}", other),
+        }
+        match master.read_foe(slave, b"log.txt", 0, &mut data) {
+            Err(TaskError::TaskSpecific(FoeErrorKind::Error(FoeErrorCode::NoRights))) => {}
+            other => panic!("{:?}", other),
+        }
+
+        // The write request is repeated while the slave is busy.
+        master.device_mut().slaves_mut()[0].foe_mut().set_busy(2);
+        let firmware: Vec<u8> = (0..250).map(|i| (i * 7) as u8).collect();
+        master
+            .write_foe(slave, b"firmware.bin", 0x1234, &firmware)
+            .unwrap();
+        let device = master.device();
+        assert_eq!(
+            device.slaves()[0].foe().file(b"firmware.bin"),
+            Some(&firmware[..])
+        );
+        drop(device);
+
+        let mut received = Vec::new();
+        master
+            .read_foe_with(slave, b"firmware.bin", 0x1234, |packet| {
+                received.extend_from_slice(packet);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(received, firmware);
+        match master.write_foe_with(slave, b"broken.bin", 0x1234, |offset, _| {
+            if offset == 0 {
+                Ok(116)
+            } else {
+                Err(FoeErrorCode::DiskFull)
+            }
+        }) {
+            Err(TaskError::TaskSpecific(FoeErrorKind::Cancelled(FoeErrorCode::DiskFull))) => {}
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(master.device().slaves()[0].foe().file(b"broken.bin"), None);
+    }
+}
@@ -1,7 +1,8 @@
 use super::mailbox_read::MailboxReadTask;
 use super::mailbox_write::MailboxWriteTask;
 use super::TaskError;
-use super::{CyclicTask, EtherCatSystemTime};
+use super::{CyclicTask, EtherCatSystemTime, Timeouts};
+use crate::frame::MailboxEncodeError;
 use crate::interface::*;
 use crate::slave::SyncManager;
 
@@ -11,6 +12,17 @@ pub enum MailboxTaskError {
     MailboxEmpty,
     MailboxAlreadyExisted,
     BufferSmall,
+    /// The mailbox has a code whose value is not known.
+    UnknownCode,
+}
+
+impl From<MailboxEncodeError> for MailboxTaskError {
+    fn from(err: MailboxEncodeError) -> Self {
+        match err {
+            MailboxEncodeError::Length => Self::BufferSmall,
+            MailboxEncodeError::UnknownCode => Self::UnknownCode,
+        }
+    }
 }
 
 impl From<MailboxTaskError> for TaskError<MailboxTaskError> {
@@ -43,6 +55,7 @@ enum Inner {
 pub struct MailboxTask {
     state: State,
     inner: Inner,
+    timeouts: Timeouts,
 }
 
 impl MailboxTask {
@@ -50,9 +63,14 @@ impl MailboxTask {
         Self {
             state: State::Idle,
             inner: Inner::Reader(MailboxReadTask::new()),
+            timeouts: Timeouts::default(),
         }
     }
 
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
     pub fn start_to_read(
         &mut self,
         slave_address: SlaveAddress,
@@ -60,6 +78,7 @@ impl MailboxTask {
         wait_full: bool,
     ) {
         let mut reader = MailboxReadTask::new();
+        reader.set_timeouts(self.timeouts);
         reader.start(slave_address, tx_sm, wait_full);
         self.inner = Inner::Reader(reader);
         self.state = State::Processing;
@@ -72,6 +91,7 @@ impl MailboxTask {
         wait_empty: bool,
     ) {
         let mut writer = MailboxWriteTask::new();
+        writer.set_timeouts(self.timeouts);
         writer.start(slave_address, rx_sm, wait_empty);
         self.inner = Inner::Writer(writer);
         self.state = State::Processing;
@@ -107,9 +127,43 @@ impl MailboxTask {
             _ => None,
         }
     }
+
+    fn recover_lost_pdu(&mut self, sys_time: EtherCatSystemTime) {
+        if self.state != State::Processing {
+            return;
+        }
+        let result = match &mut self.inner {
+            Inner::Reader(reader) => {
+                reader.recover_lost_pdu(sys_time);
+                reader.wait()
+            }
+            Inner::Writer(writer) => {
+                writer.recover_lost_pdu(sys_time);
+                writer.wait()
+            }
+        };
+        if let Some(Err(err)) = result {
+            self.state = State::Error(err);
+        }
+    }
 }
 
+/// Mailbox resilient layer: a lost read is repeated by the slave with the repeat request
+/// of the sync manager, and a lost write is sent again.
 impl CyclicTask for MailboxTask {
+    fn process_one_step(&mut self, socket: &mut PduSocket, sys_time: EtherCatSystemTime) {
+        if socket.is_lost() {
+            self.recover_lost_pdu(sys_time);
+        } else if let Some(recv_data) = socket.get_recieved_pdu() {
+            self.recieve_and_process(&recv_data, sys_time);
+        }
+        socket.set_pdu(|buf| self.next_pdu(buf))
+    }
+
+    fn recovers_lost_pdu(&self) -> bool {
+        true
+    }
+
     fn is_busy(&self) -> bool {
         match self.state {
             State::Idle | State::Complete | State::Error(_) => false,
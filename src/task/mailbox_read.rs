@@ -1,11 +1,10 @@
 use super::mailbox::MailboxTaskError;
 use super::TaskError;
-use super::{CyclicTask, EtherCatSystemTime};
+use super::{CyclicTask, EtherCatSystemTime, Timeouts};
 use crate::interface::*;
 use crate::register::{SyncManagerActivation, SyncManagerPdiControl, SyncManagerStatus};
 use crate::slave::SyncManager;
-
-const MAILBOX_RESPONSE_RETRY_TIMEOUT_DEFAULT_MS: u32 = 2000;
+use core::time::Duration;
 
 #[derive(Debug, Clone, PartialEq)]
 enum State {
@@ -34,6 +33,7 @@ pub struct MailboxReadTask {
     sm_ado_offset: u16,
     sm_size: u16,
     sm_start_address: u16,
+    timeout: Duration,
 }
 
 impl MailboxReadTask {
@@ -47,9 +47,14 @@ impl MailboxReadTask {
             sm_ado_offset: 0,
             sm_size: 0,
             sm_start_address: 0,
+            timeout: Timeouts::default().mailbox_response,
         }
     }
 
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeout = timeouts.mailbox_response;
+    }
+
     pub fn slave_address(&self) -> SlaveAddress {
         self.slave_address
     }
@@ -72,6 +77,27 @@ impl MailboxReadTask {
             _ => None,
         }
     }
+
+    /// The frame of the last PDU was lost.
+    /// If the mailbox has been read, the slave is requested to repeat it.
+    pub fn recover_lost_pdu(&mut self, sys_time: EtherCatSystemTime) {
+        match self.state {
+            State::CheckMailboxAlreadyExisted((true, wait_full)) => {
+                self.timer_start = sys_time;
+                self.state = State::CheckMailboxAlreadyExisted((false, wait_full));
+            }
+            State::Read => self.state = State::RequestRepeat,
+            // The toggled bit is written again, because it may not have reached the slave.
+            State::RequestRepeat => {
+                self.activation_buf
+                    .set_repeat(!self.activation_buf.repeat());
+            }
+            _ => {}
+        }
+        if self.is_busy() && self.timeout < sys_time.elapsed_since(self.timer_start) {
+            self.state = State::Error(TaskError::Timeout);
+        }
+    }
 }
 
 impl CyclicTask for MailboxReadTask {
@@ -180,8 +206,7 @@ impl CyclicTask for MailboxReadTask {
         }
 
         // check timeout
-        let timeout_ns = (MAILBOX_RESPONSE_RETRY_TIMEOUT_DEFAULT_MS as u64) * 1000 * 1000;
-        if self.timer_start.0 < sys_time.0 && timeout_ns < sys_time.0 - self.timer_start.0 {
+        if self.is_busy() && self.timeout < sys_time.elapsed_since(self.timer_start) {
             self.state = State::Error(TaskError::Timeout);
         }
     }
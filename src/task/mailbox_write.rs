@@ -1,11 +1,10 @@
 use super::mailbox::MailboxTaskError;
 use super::TaskError;
-use super::{CyclicTask, EtherCatSystemTime};
+use super::{CyclicTask, EtherCatSystemTime, Timeouts};
 use crate::interface::*;
 use crate::register::{SyncManagerActivation, SyncManagerStatus};
 use crate::slave::SyncManager;
-
-const MAILBOX_REQUEST_RETRY_TIMEOUT_DEFAULT_MS: u32 = 100;
+use core::time::Duration;
 
 #[derive(Debug, Clone, PartialEq)]
 enum State {
@@ -14,6 +13,8 @@ enum State {
     Complete,
     CheckMailboxEmpty((bool, bool)),
     Write,
+    /// The frame of the write was lost. The mailbox is full if the write has reached the slave.
+    CheckWritten,
 }
 
 impl Default for State {
@@ -28,10 +29,12 @@ pub struct MailboxWriteTask {
     command: Command,
     slave_address: SlaveAddress,
     empty_check_buffer: [u8; SyncManagerStatus::SIZE + SyncManagerActivation::SIZE],
+    is_buffer_saved: bool,
     state: State,
     sm_ado_offset: u16,
     sm_size: u16,
     sm_start_address: u16,
+    timeout: Duration,
 }
 
 impl MailboxWriteTask {
@@ -41,13 +44,19 @@ impl MailboxWriteTask {
             command: Command::default(),
             slave_address: SlaveAddress::default(),
             empty_check_buffer: [0; SyncManagerStatus::SIZE + SyncManagerActivation::SIZE],
+            is_buffer_saved: false,
             state: State::Idle,
             sm_ado_offset: 0,
             sm_size: 0,
             sm_start_address: 0,
+            timeout: Timeouts::default().mailbox_request,
         }
     }
 
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeout = timeouts.mailbox_request;
+    }
+
     pub fn slave_address(&self) -> SlaveAddress {
         self.slave_address
     }
@@ -56,6 +65,7 @@ impl MailboxWriteTask {
         self.timer_start = EtherCatSystemTime(0);
         self.command = Command::default();
         self.slave_address = slave_address;
+        self.is_buffer_saved = false;
         self.state = State::CheckMailboxEmpty((true, wait_empty));
 
         self.sm_ado_offset = rx_sm.number() as u16 * 0x08;
@@ -70,6 +80,23 @@ impl MailboxWriteTask {
             _ => None,
         }
     }
+
+    /// The frame of the last PDU was lost.
+    /// A lost write is sent again with the same count unless it has reached the slave,
+    /// so the slave can discard it if it has already processed it.
+    pub fn recover_lost_pdu(&mut self, sys_time: EtherCatSystemTime) {
+        match self.state {
+            State::CheckMailboxEmpty((true, wait_empty)) => {
+                self.timer_start = sys_time;
+                self.state = State::CheckMailboxEmpty((false, wait_empty));
+            }
+            State::Write => self.state = State::CheckWritten,
+            _ => {}
+        }
+        if self.is_busy() && self.timeout < sys_time.elapsed_since(self.timer_start) {
+            self.state = State::Error(TaskError::Timeout);
+        }
+    }
 }
 
 impl CyclicTask for MailboxWriteTask {
@@ -85,24 +112,31 @@ impl CyclicTask for MailboxWriteTask {
             State::Idle => None,
             State::Error(_) => None,
             State::Complete => None,
-            State::CheckMailboxEmpty(_) => {
+            State::CheckMailboxEmpty(_) | State::CheckWritten => {
                 self.command = Command::new_read(
                     self.slave_address.into(),
                     SyncManagerStatus::ADDRESS + self.sm_ado_offset,
                 );
                 let length = SyncManagerStatus::SIZE + SyncManagerActivation::SIZE;
-                self.empty_check_buffer
-                    .iter_mut()
-                    .zip(buf.iter())
-                    .for_each(|(b, sb)| *b = *sb);
+                // The head of the mailbox is overwritten by the status until it is written.
+                if !self.is_buffer_saved {
+                    self.empty_check_buffer
+                        .iter_mut()
+                        .zip(buf.iter())
+                        .for_each(|(b, sb)| *b = *sb);
+                    self.is_buffer_saved = true;
+                }
                 buf[..length].fill(0);
                 Some((self.command, length))
             }
             State::Write => {
                 self.command = Command::new_write(self.slave_address.into(), self.sm_start_address);
-                buf.iter_mut()
-                    .zip(self.empty_check_buffer.iter())
-                    .for_each(|(sb, b)| *sb = *b);
+                if self.is_buffer_saved {
+                    buf.iter_mut()
+                        .zip(self.empty_check_buffer.iter())
+                        .for_each(|(sb, b)| *sb = *b);
+                    self.is_buffer_saved = false;
+                }
                 if buf.len() < self.sm_size as usize {
                     self.state = State::Error(MailboxTaskError::BufferSmall.into());
                     None
@@ -148,11 +182,19 @@ impl CyclicTask for MailboxWriteTask {
                     self.state = State::Complete;
                 }
             }
+            State::CheckWritten => {
+                if wkc != 1 {
+                    self.state = State::Error(MailboxTaskError::NoSlaveReaction.into());
+                } else if SyncManagerStatus(data).is_mailbox_full() {
+                    self.state = State::Complete;
+                } else {
+                    self.state = State::Write;
+                }
+            }
         }
 
         // check timeout
-        let timeout_ns = (MAILBOX_REQUEST_RETRY_TIMEOUT_DEFAULT_MS as u64) * 1000 * 1000;
-        if self.timer_start.0 < sys_time.0 && timeout_ns < sys_time.0 - self.timer_start.0 {
+        if self.is_busy() && self.timeout < sys_time.elapsed_since(self.timer_start) {
             self.state = State::Error(TaskError::Timeout);
         }
     }
@@ -1,44 +1,73 @@
 mod address_access_task;
 mod al_state_transfer;
+mod aoe;
+mod clock;
 mod dc_initilize;
+mod eoe;
 mod error;
+mod foe;
 mod mailbox;
 mod mailbox_read;
 mod mailbox_write;
 mod network_initilize;
+mod raw_mailbox;
 mod sii_read;
 mod slave_initialize;
+mod soe;
+mod timeouts;
 
 pub use address_access_task::AddressAccessTask;
 pub use al_state_transfer::{AlStateTransferTask, AlStateTransferTaskError};
+pub use aoe::AoeErrorKind;
+pub use clock::*;
 pub use dc_initilize::DcInitTask;
+pub use eoe::EoeErrorKind;
 pub use error::*;
+pub use foe::FoeErrorKind;
 pub use mailbox::{MailboxTask, MailboxTaskError};
 pub use network_initilize::{NetworkInitTask, NetworkInitTaskError};
+pub(crate) use raw_mailbox::set_raw_mailbox;
+pub use raw_mailbox::RawMailboxErrorKind;
 pub use sii_read::{SiiReader, SiiTaskError};
 pub use slave_initialize::*;
+pub use soe::SoeErrorKind;
+pub use timeouts::Timeouts;
 
 pub mod loop_task;
 use loop_task::AlStateReadTask;
 
 use crate::{
     frame::{
-        AbortCode, EmmergencyErrorCode, LengthError, Mailbox, MailboxErrorDetail, MailboxFrame,
+        AbortCode, AmsAddress, CoE, CoeFrame, CoeIndex, EmmergencyErrorCode, EntryDescriptionFrame,
+        Mailbox, MailboxEncodeError, MailboxErrorDetail, MailboxFrame, MailboxType,
+        ObjectDescriptionFrame, OdListFrame, OdListType, SdoDownloadNormalRequestFrame, SdoFrame,
+        SdoInfo, SdoInfoOpCode, SdoReq, SdoRes, SdoSegmentFrame,
     },
     interface::{
-        Command, Pdu, PduSocket, PhyError, RawEthernetDevice, SlaveAddress, SocketHandle,
-        SocketInterface, TargetSlave,
+        Command, Pdu, PduSocket, PhyError, RawEthernetDevice, SlaveAddress, SocketAccess,
+        SocketHandle, SocketInterface, TargetSlave,
     },
     register::{AlStatusCode, SiiData},
-    slave::{AlState, Network, Slave, SlaveInfo},
+    slave::{AlState, Emergency, Network, Slave, SlaveInfo},
 };
 
+use core::cell::RefCell;
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
 use core::time::Duration;
 
 /// Time elapsed since January 1, 2000 in nanoseconds. 64-bit.
 #[derive(Debug, Clone, Copy)]
 pub struct EtherCatSystemTime(pub u64);
 
+impl EtherCatSystemTime {
+    /// Zero if `start` is later than this time.
+    pub fn elapsed_since(&self, start: EtherCatSystemTime) -> Duration {
+        Duration::from_nanos(self.0.saturating_sub(start.0))
+    }
+}
+
 impl From<Duration> for EtherCatSystemTime {
     fn from(duration: Duration) -> Self {
         EtherCatSystemTime(duration.as_nanos() as u64)
@@ -59,122 +88,168 @@ pub trait CyclicTask {
     fn recieve_and_process(&mut self, recv_data: &Pdu, sys_time: EtherCatSystemTime);
 
     fn is_busy(&self) -> bool;
+
+    /// If true, `process_one_step` recovers from the loss of the last PDU by itself.
+    /// Otherwise the loss is an error.
+    fn recovers_lost_pdu(&self) -> bool {
+        false
+    }
 }
 
-impl<'frame, 'buf, D, const N: usize> SocketInterface<'frame, 'buf, D, N>
-where
-    D: RawEthernetDevice,
-{
-    fn block_on<C: CyclicTask, E>(
-        &mut self,
-        handle: &SocketHandle,
-        unit: &mut C,
-    ) -> Result<(), TaskError<E>> {
-        let mut count = 0;
-        loop {
-            match self.poll_tx_rx() {
-                Ok(is_ok) => {
-                    if !is_ok {
-                        continue;
-                    }
+/// Socket on which acyclic operations run.
+/// A task takes a step whenever the PDU of the socket has returned. The PDUs are transmitted by
+/// `SocketAccess::poll_tx_rx`, e.g. in `EtherCatMaster::process`, and the interface is only
+/// borrowed during a step. Therefore the operations do not hold the interface while they wait.
+#[derive(Debug)]
+pub struct AcyclicSocket<'a, S> {
+    sif: &'a RefCell<S>,
+    handle: SocketHandle,
+}
+
+impl<'a, S: SocketAccess> AcyclicSocket<'a, S> {
+    pub fn new(sif: &'a RefCell<S>, handle: SocketHandle) -> Self {
+        Self { sif, handle }
+    }
+
+    pub fn now(&self) -> EtherCatSystemTime {
+        self.sif.borrow().now()
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.sif.borrow().timeouts()
+    }
+
+    pub fn ams_address(&self) -> AmsAddress {
+        self.sif.borrow().ams_address()
+    }
+
+    /// The interface is borrowed while `f` runs.
+    pub fn with_socket<R>(&self, f: impl FnOnce(&mut PduSocket) -> R) -> R {
+        self.sif.borrow_mut().with_socket(&self.handle, f)
+    }
+
+    /// Passes the mailbox which the last operation has left in the socket buffer to `f`.
+    pub fn with_mailbox<R>(&self, f: impl FnOnce(MailboxFrame<&[u8]>) -> R) -> R {
+        self.with_socket(|socket| f(MailboxFrame(socket.data_buf())))
+    }
+
+    /// Waits for the PDU of an operation which has been cancelled, and clears the socket.
+    pub async fn reset(&self) {
+        poll_fn(|cx| {
+            self.with_socket(|socket| {
+                if socket.is_waiting() {
+                    socket.register_waker(cx.waker());
+                    Poll::Pending
+                } else {
+                    socket.clear();
+                    Poll::Ready(())
                 }
-                Err(PhyError::TxNotAvailable) | Err(PhyError::RxNotAvailable) => continue,
-                Err(err) => return Err(err.into()),
-            }
-            let socket = self.get_socket_mut(handle).unwrap();
-            unit.process_one_step(socket, EtherCatSystemTime(count));
-            if !unit.is_busy() {
-                break;
-            };
-            count += 1;
-            if 100000 < count {
-                return Err(TaskError::Timeout);
-            }
-        }
-        Ok(())
+            })
+        })
+        .await
     }
 
-    pub fn read_register(
-        &mut self,
-        handle: &SocketHandle,
+    /// Processes the task until it is finished.
+    async fn run<C: CyclicTask, E>(&self, unit: &mut C) -> Result<(), TaskError<E>> {
+        poll_fn(|cx| {
+            let mut sif = self.sif.borrow_mut();
+            let sys_time = sif.now();
+            sif.with_socket(&self.handle, |socket| {
+                if socket.is_waiting() {
+                    socket.register_waker(cx.waker());
+                    return Poll::Pending;
+                }
+                if socket.is_lost() && !unit.recovers_lost_pdu() {
+                    return Poll::Ready(Err(PhyError::LostFrame.into()));
+                }
+                unit.process_one_step(socket, sys_time);
+                if !unit.is_busy() {
+                    return Poll::Ready(Ok(()));
+                }
+                if socket.is_waiting() {
+                    socket.register_waker(cx.waker());
+                } else {
+                    // No PDU is queued, so nothing else wakes the task.
+                    cx.waker().wake_by_ref();
+                }
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    /// The data are left at the start of the socket buffer.
+    pub async fn read_register(
+        &self,
         target_slave: TargetSlave,
         register_address: u16,
         data_size: usize,
-    ) -> Result<&[u8], TaskError<()>> {
+    ) -> Result<(), TaskError<()>> {
         let mut unit = AddressAccessTask::new();
-
-        {
-            let socket = self.get_socket_mut(&handle).expect("socket not found");
-            assert!(data_size <= socket.data_buf().len());
-
-            unit.start_to_read(target_slave, register_address, data_size);
-        }
-
-        self.block_on(handle, &mut unit)?;
-        unit.wait().unwrap()?;
-        let socket = self.get_socket_mut(handle).expect("socket not found");
-        Ok(&socket.data_buf()[..data_size])
+        self.with_socket(|socket| assert!(data_size <= socket.data_buf().len()));
+        unit.start_to_read(target_slave, register_address, data_size);
+        self.run(&mut unit).await?;
+        unit.wait().unwrap()
     }
 
-    pub fn write_register(
-        &mut self,
-        handle: &SocketHandle,
+    pub async fn write_register(
+        &self,
         target_slave: TargetSlave,
         register_address: u16,
         data: &[u8],
     ) -> Result<(), TaskError<()>> {
         let mut unit = AddressAccessTask::new();
-
-        let socket = self.get_socket_mut(handle).expect("socket not found");
-        assert!(data.len() <= socket.data_buf().len());
-
-        unit.start_to_write(target_slave, register_address, data, socket.data_buf_mut());
-        self.block_on(handle, &mut unit)?;
+        self.with_socket(|socket| {
+            assert!(data.len() <= socket.data_buf().len());
+            unit.start_to_write(target_slave, register_address, data, socket.data_buf_mut());
+        });
+        self.run(&mut unit).await?;
         unit.wait().unwrap()
     }
 
-    pub fn init<'slave, 'pdo_mapping, 'pdo_entry>(
-        &mut self,
-        handle: &SocketHandle,
+    pub async fn init<'slave, 'pdo_mapping, 'pdo_entry>(
+        &self,
         network: &mut Network<'slave, 'pdo_mapping, 'pdo_entry>,
     ) -> Result<(), TaskError<NetworkInitTaskError>> {
+        let timeouts = self.timeouts();
         let mut unit = NetworkInitTask::new(network);
-
-        let socket = self.get_socket_mut(handle).expect("socket not found");
-        assert!(NetworkInitTask::required_buffer_size() <= socket.data_buf().len());
-
+        unit.set_timeouts(timeouts);
+        self.with_socket(|socket| {
+            assert!(NetworkInitTask::required_buffer_size() <= socket.data_buf().len())
+        });
         unit.start();
-        self.block_on::<_, NetworkInitTaskError>(handle, &mut unit)?;
+        self.run::<_, NetworkInitTaskError>(&mut unit).await?;
         unit.wait().unwrap()
     }
 
-    pub fn init_dc<'slave, 'pdo_mapping, 'pdo_entry>(
-        &mut self,
-        handle: &SocketHandle,
+    pub async fn init_dc<'slave, 'pdo_mapping, 'pdo_entry>(
+        &self,
         network: &mut Network<'slave, 'pdo_mapping, 'pdo_entry>,
     ) -> Result<(), TaskError<()>> {
         let mut unit = DcInitTask::new(network);
-
-        let socket = self.get_socket_mut(handle).expect("socket not found");
-        assert!(DcInitTask::required_buffer_size() <= socket.data_buf().len());
-
+        self.with_socket(|socket| {
+            assert!(DcInitTask::required_buffer_size() <= socket.data_buf().len())
+        });
         unit.start();
-        self.block_on(handle, &mut unit)?;
+        self.run(&mut unit).await?;
         unit.wait().unwrap()
     }
 
-    pub fn read_al_state(
-        &mut self,
-        handle: &SocketHandle,
+    pub async fn read_al_state(
+        &self,
         target_slave: TargetSlave,
     ) -> Result<(AlState, Option<AlStatusCode>), TaskError<()>> {
         let mut unit = AlStateReadTask::new();
-        {
-            let socket = self.get_socket_mut(handle).expect("socket not found");
+        self.with_socket(|socket| {
             assert!(AlStateReadTask::required_buffer_size() <= socket.data_buf().len());
-            unit.set_target(target_slave);
+            socket.clear();
+        });
+        unit.set_target(target_slave);
+        // The task is never busy. The first step only queues the PDU.
+        while unit.last_al_state().0.is_none() {
+            self.run::<_, ()>(&mut unit).await?;
         }
-        self.block_on::<_, _>(handle, &mut unit)?;
+        self.with_socket(|socket| socket.clear());
         if unit.invalid_wkc_count != 0 {
             Err(TaskError::UnexpectedWkc(
                 (unit.expected_wkc(), unit.last_wkc()).into(),
@@ -185,207 +260,860 @@ where
         }
     }
 
-    pub fn change_al_state(
-        &mut self,
-        handle: &SocketHandle,
+    pub async fn change_al_state(
+        &self,
         target_slave: TargetSlave,
         al_state: AlState,
     ) -> Result<AlState, TaskError<AlStateTransferTaskError>> {
         let mut unit = AlStateTransferTask::new();
-        {
-            let socket = self.get_socket_mut(handle).expect("socket not found");
-            assert!(AlStateTransferTask::required_buffer_size() <= socket.data_buf().len());
-            unit.start(target_slave, al_state);
-        }
-        self.block_on(handle, &mut unit)?;
+        unit.set_timeouts(self.timeouts());
+        self.with_socket(|socket| {
+            assert!(AlStateTransferTask::required_buffer_size() <= socket.data_buf().len())
+        });
+        unit.start(target_slave, al_state);
+        self.run(&mut unit).await?;
         unit.wait().unwrap()
     }
 
-    pub fn read_sii(
-        &mut self,
-        handle: &SocketHandle,
+    pub async fn read_sii(
+        &self,
         slave_address: SlaveAddress,
         sii_address: u16,
     ) -> Result<(SiiData<[u8; SiiData::SIZE]>, usize), TaskError<SiiTaskError>> {
         let mut unit = SiiReader::new();
-        {
-            let socket = self.get_socket_mut(handle).expect("socket not found");
-            assert!(SiiReader::required_buffer_size() <= socket.data_buf().len());
-            unit.start(slave_address, sii_address);
-        }
-        self.block_on::<_, _>(handle, &mut unit)?;
+        unit.set_timeouts(self.timeouts());
+        self.with_socket(|socket| {
+            assert!(SiiReader::required_buffer_size() <= socket.data_buf().len())
+        });
+        unit.start(slave_address, sii_address);
+        self.run(&mut unit).await?;
         unit.wait().unwrap()
     }
 
-    pub fn read_mailbox(
-        &mut self,
-        handle: &SocketHandle,
+    /// The mailbox is left in the socket buffer. See `with_mailbox`.
+    pub async fn read_mailbox(
+        &self,
         slave_info: &SlaveInfo,
         wait_full: bool,
-    ) -> Result<MailboxFrame<&[u8]>, TaskError<MailboxTaskError>> {
+    ) -> Result<(), TaskError<MailboxTaskError>> {
         let mut unit = MailboxTask::new();
-        {
-            let socket = self.get_socket_mut(handle).expect("socket not found");
-            assert!(
-                (slave_info.mailbox_tx_sm().unwrap_or_default().size() as usize)
-                    <= socket.data_buf().len()
-            );
-            let slave_address = slave_info.slave_address();
-            let tx_sm = slave_info.mailbox_tx_sm().unwrap_or_default();
-            unit.start_to_read(slave_address, tx_sm, wait_full);
+        unit.set_timeouts(self.timeouts());
+        let tx_sm = slave_info.mailbox_tx_sm().unwrap_or_default();
+        self.with_socket(|socket| assert!((tx_sm.size() as usize) <= socket.data_buf().len()));
+        unit.start_to_read(slave_info.slave_address(), tx_sm, wait_full);
+        self.run(&mut unit).await?;
+        unit.wait().unwrap()
+    }
+
+    /// Reads the response of a request, a mailbox of `mb_type` or an error mailbox, into the socket buffer.
+    /// Emergencies which arrive before it are stored in the slave,
+    /// and mailboxes of other types are kept in the slave for their readers.
+    async fn read_response(
+        &self,
+        slave: &Slave,
+        mb_type: MailboxType,
+    ) -> Result<(), TaskError<MailboxTaskError>> {
+        let start = self.now();
+        loop {
+            let time = self.now();
+            self.read_mailbox(slave.info(), true).await?;
+            // The slave has sent the last mailbox again, e.g. after a lost repeat request.
+            if slave.is_repeated_mailbox(self.with_mailbox(|mb_data| mb_data.count())) {
+                continue;
+            }
+            let is_response = self.with_mailbox(|mb_data| {
+                if let Ok(Mailbox::CoE((_, CoE::Emmergency(frame)))) = mb_data.mailbox() {
+                    let address = slave.info().slave_address();
+                    slave.push_emergency(Emergency::new(address, time, &frame));
+                    false
+                } else if mb_data.mb_type() == mb_type || mb_data.mb_type() == MailboxType::Error {
+                    true
+                } else {
+                    slave.push_received_mailbox(mb_data.0);
+                    false
+                }
+            });
+            if is_response {
+                return Ok(());
+            }
+            if self.timeouts().mailbox_response < self.now().elapsed_since(start) {
+                return Err(TaskError::Timeout);
+            }
         }
-        self.block_on(handle, &mut unit)?;
-        unit.wait().unwrap()?;
-        let socket = self.get_socket_mut(handle).expect("socket not found");
-        Ok(MailboxFrame(socket.data_buf()))
     }
 
-    pub fn write_mailbox<F: FnOnce(&mut MailboxFrame<&mut [u8]>) -> Result<(), LengthError>>(
-        &mut self,
-        handle: &SocketHandle,
+    pub async fn write_mailbox<
+        F: FnOnce(&mut MailboxFrame<&mut [u8]>) -> Result<(), MailboxEncodeError>,
+    >(
+        &self,
         slave_info: &SlaveInfo,
         mb_frame_writer: F,
         wait_empty: bool,
     ) -> Result<(), TaskError<MailboxTaskError>> {
         let mut unit = MailboxTask::new();
-        {
-            let socket = self.get_socket_mut(handle).expect("socket not found");
-            assert!(
-                (slave_info.mailbox_rx_sm().unwrap_or_default().size() as usize)
-                    <= socket.data_buf().len()
-            );
-            let slave_address = slave_info.slave_address();
-            let rx_sm = slave_info.mailbox_rx_sm().unwrap_or_default();
+        unit.set_timeouts(self.timeouts());
+        let rx_sm = slave_info.mailbox_rx_sm().unwrap_or_default();
+        self.with_socket(|socket| {
+            assert!((rx_sm.size() as usize) <= socket.data_buf().len());
             mb_frame_writer(&mut MailboxFrame(socket.data_buf_mut()))
-                .map_err(|_| TaskError::TaskSpecific(MailboxTaskError::BufferSmall))?;
-            unit.start_to_write(slave_address, rx_sm, wait_empty);
-        }
-        self.block_on(handle, &mut unit)?;
+        })
+        .map_err(|err| TaskError::TaskSpecific(err.into()))?;
+        unit.start_to_write(slave_info.slave_address(), rx_sm, wait_empty);
+        self.run(&mut unit).await?;
         unit.wait().unwrap()
     }
 
-    pub fn write_sdo(
-        &mut self,
-        handle: &SocketHandle,
+    /// Objects which do not fit in the mailbox are written with a segmented download.
+    pub async fn write_sdo(
+        &self,
         slave: &Slave,
         index: u16,
         sub_index: u8,
         data: &[u8],
     ) -> Result<(), TaskError<SdoErrorKind>> {
-        let count = slave.increment_mb_count();
+        self.download_sdo(slave, CoeIndex::new(index, sub_index), data)
+            .await
+    }
+
+    /// Writes all entries of an object from `sub_index` (0 or 1) in one transfer.
+    /// Sub index 0 is 16-bit in `data`.
+    pub async fn write_sdo_complete(
+        &self,
+        slave: &Slave,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        self.download_sdo(slave, CoeIndex::complete(index, sub_index), data)
+            .await
+    }
+
+    async fn download_sdo(
+        &self,
+        slave: &Slave,
+        coe_index: CoeIndex,
+        data: &[u8],
+    ) -> Result<(), TaskError<SdoErrorKind>> {
         let slave_info = slave.info();
+        if sdo_download_capacity(slave_info) < data.len() {
+            return self.write_sdo_segmented(slave, coe_index, data).await;
+        }
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave_info,
+            |mb_frame| {
+                let message = Mailbox::CoE((coe_index, CoE::SdoReq(SdoReq::DownLoad(data))));
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(&message)
+            },
+            false,
+        )
+        .await?;
+        self.read_response(slave, MailboxType::CoE).await?;
+        self.with_mailbox(sdo_download_response)
+    }
 
+    async fn write_sdo_segmented(
+        &self,
+        slave: &Slave,
+        coe_index: CoeIndex,
+        data: &[u8],
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        let slave_info = slave.info();
+        let (first, mut rest) = data.split_at(sdo_download_capacity(slave_info));
+        let count = slave.increment_mb_count();
         self.write_mailbox(
-            handle,
             slave_info,
             |mb_frame| {
-                let message = Mailbox::new_sdo_download_request(index, sub_index, data);
+                let sdo_req = SdoReq::SegmentedDownLoad {
+                    complete_size: data.len() as u32,
+                    data: first,
+                };
+                let message = Mailbox::CoE((coe_index, CoE::SdoReq(sdo_req)));
                 mb_frame.set_count(count);
                 mb_frame.set_mailbox(&message)
             },
             false,
         )
-        .unwrap();
-        let mb_data = self.read_mailbox(handle, slave_info, true).unwrap();
-        let mb = mb_data
-            .mailbox()
-            .map_err(|_| SdoErrorKind::Mailbox(MailboxTaskError::BufferSmall))?;
-
-        match mb {
-            Mailbox::Error(err) => Err(SdoErrorKind::ErrorMailbox(err.clone()).into()),
-            Mailbox::UnsupportedProtocol(_) => Err(SdoErrorKind::UnsupportedMailboxProtocol.into()),
-            Mailbox::CoE((_, coe)) => match coe {
-                crate::frame::CoE::Emmergency(emm_f) => {
-                    Err(SdoErrorKind::Emmergency(emm_f.emmergency_error_code()).into())
-                }
-                crate::frame::CoE::SdoReq(sdo_req) => match sdo_req {
-                    crate::frame::SdoReq::Abort(abort_code) => {
-                        Err(SdoErrorKind::AbortCode(abort_code).into())
-                    }
-                    _ => Err(SdoErrorKind::UnexpectedSdoType(SdoType::Req).into()),
-                },
-                crate::frame::CoE::SdoRes(sdo_res) => match sdo_res {
-                    crate::frame::SdoRes::DownLoad => {
-                        if mb_data.count() != count {
-                            Err(SdoErrorKind::CountUnmatch.into())
-                        } else {
-                            Ok(())
-                        }
-                    }
-                    crate::frame::SdoRes::Upload(_) => {
-                        Err(SdoErrorKind::UnexpectedSdoType(SdoType::UploadRes).into())
-                    }
-                    crate::frame::SdoRes::Other(_) => {
-                        Err(SdoErrorKind::UnexpectedSdoType(SdoType::OtherRes).into())
-                    }
-                },
-                crate::frame::CoE::UnsupportedType(_) => {
-                    Err(SdoErrorKind::UnexpectedSdoType(SdoType::UnsupportedType).into())
-                }
+        .await?;
+        self.read_response(slave, MailboxType::CoE).await?;
+        self.with_mailbox(sdo_download_response)?;
+
+        let mut toggle = false;
+        while !rest.is_empty() {
+            let (segment, next) = rest.split_at(rest.len().min(sdo_segment_capacity(slave_info)));
+            let response = self
+                .download_sdo_segment(slave, toggle, next.is_empty(), segment)
+                .await;
+            if let Err(err) = response {
+                return Err(self.abort_sdo_on_error(slave, coe_index, err).await);
+            }
+            toggle = !toggle;
+            rest = next;
+        }
+        Ok(())
+    }
+
+    async fn download_sdo_segment(
+        &self,
+        slave: &Slave,
+        toggle: bool,
+        is_last: bool,
+        segment: &[u8],
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                let message = Mailbox::new_sdo_download_segment_request(toggle, is_last, segment);
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(&message)
+            },
+            false,
+        )
+        .await?;
+        self.read_response(slave, MailboxType::CoE).await?;
+        self.with_mailbox(|mb_data| sdo_download_segment_response(mb_data, toggle))
+    }
+
+    /// Reads an object which fits in the mailbox. The data are moved to the start of the socket buffer.
+    /// Returns the size of the data.
+    /// Objects which do not fit in the mailbox cause `SdoErrorKind::BufferSmall`. Use `read_sdo_into` for them.
+    pub async fn read_sdo(
+        &self,
+        slave: &Slave,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                let message = Mailbox::new_sdo_upload_request(index, sub_index);
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(&message)
             },
+            false,
+        )
+        .await?;
+        self.read_response(slave, MailboxType::CoE).await?;
+        let (size, complete_size) = self.with_socket(|socket| {
+            let buf = socket.data_buf_mut();
+            let (data, complete_size) = sdo_upload_response(MailboxFrame(&*buf))?;
+            let start = data.as_ptr() as usize - buf.as_ptr() as usize;
+            let size = data.len();
+            buf.copy_within(start..start + size, 0);
+            Ok::<_, TaskError<SdoErrorKind>>((size, complete_size))
+        })?;
+        if size < complete_size {
+            let err = SdoErrorKind::BufferSmall.into();
+            let coe_index = CoeIndex::new(index, sub_index);
+            return Err(self.abort_sdo_on_error(slave, coe_index, err).await);
         }
+        Ok(size)
     }
 
-    pub fn read_sdo(
-        &mut self,
-        handle: &SocketHandle,
+    /// Reads an object into `buf`, with a segmented upload if it does not fit in the mailbox.
+    /// Returns the size of the object.
+    pub async fn read_sdo_into(
+        &self,
         slave: &Slave,
         index: u16,
         sub_index: u8,
-    ) -> Result<&[u8], TaskError<SdoErrorKind>> {
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
+        self.upload_sdo_into(slave, CoeIndex::new(index, sub_index), buf)
+            .await
+    }
+
+    /// Reads all entries of an object from `sub_index` (0 or 1) in one transfer.
+    /// Sub index 0 is 16-bit in `buf`. Returns the size of the data.
+    pub async fn read_sdo_complete(
+        &self,
+        slave: &Slave,
+        index: u16,
+        sub_index: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
+        self.upload_sdo_into(slave, CoeIndex::complete(index, sub_index), buf)
+            .await
+    }
+
+    async fn upload_sdo_into(
+        &self,
+        slave: &Slave,
+        coe_index: CoeIndex,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
         let count = slave.increment_mb_count();
         let slave_info = slave.info();
         self.write_mailbox(
-            handle,
             slave_info,
             |mb_frame| {
-                let message = Mailbox::new_sdo_upload_request(index, sub_index);
+                let message = Mailbox::CoE((coe_index, CoE::SdoReq(SdoReq::Upload)));
                 mb_frame.set_count(count);
                 mb_frame.set_mailbox(&message)
             },
             false,
         )
-        .unwrap();
-        let mb_data = self.read_mailbox(handle, slave_info, true).unwrap();
-        let mb = mb_data
-            .mailbox()
-            .map_err(|_| SdoErrorKind::Mailbox(MailboxTaskError::BufferSmall))?;
-        match mb {
-            Mailbox::Error(err) => Err(SdoErrorKind::ErrorMailbox(err.clone()).into()),
-            Mailbox::UnsupportedProtocol(_) => Err(SdoErrorKind::UnsupportedMailboxProtocol.into()),
-            Mailbox::CoE((_, coe)) => match coe {
-                crate::frame::CoE::Emmergency(emm_f) => {
-                    Err(SdoErrorKind::Emmergency(emm_f.emmergency_error_code()).into())
-                }
-                crate::frame::CoE::SdoReq(sdo_req) => match sdo_req {
-                    crate::frame::SdoReq::Abort(abort_code) => {
-                        Err(SdoErrorKind::AbortCode(abort_code).into())
-                    }
-                    _ => Err(SdoErrorKind::UnexpectedSdoType(SdoType::Req).into()),
-                },
-                crate::frame::CoE::SdoRes(sdo_res) => match sdo_res {
-                    crate::frame::SdoRes::DownLoad => {
-                        Err(SdoErrorKind::UnexpectedSdoType(SdoType::DownLoadRes).into())
-                    }
-                    crate::frame::SdoRes::Upload(res) => {
-                        if mb_data.count() != count {
-                            Err(SdoErrorKind::CountUnmatch.into())
-                        } else {
-                            Ok(res)
-                        }
-                    }
-                    crate::frame::SdoRes::Other(_) => {
-                        Err(SdoErrorKind::UnexpectedSdoType(SdoType::OtherRes).into())
+        .await?;
+        self.read_response(slave, MailboxType::CoE).await?;
+        let (mut received, complete_size) = self.with_mailbox(|mb_data| {
+            let (data, complete_size) = sdo_upload_response(mb_data)?;
+            if let Some(buf) = buf.get_mut(..data.len()) {
+                buf.copy_from_slice(data);
+            }
+            Ok::<_, TaskError<SdoErrorKind>>((data.len(), complete_size))
+        })?;
+        if buf.len() < complete_size {
+            let err = SdoErrorKind::BufferSmall.into();
+            if received < complete_size {
+                return Err(self.abort_sdo_on_error(slave, coe_index, err).await);
+            }
+            return Err(err);
+        }
+
+        let mut toggle = false;
+        while received < complete_size {
+            let response = self
+                .upload_sdo_segment(slave, toggle, &mut buf[received..])
+                .await;
+            match response {
+                Ok((size, last)) => {
+                    received += size;
+                    if last {
+                        break;
                     }
-                },
-                crate::frame::CoE::UnsupportedType(_) => {
-                    Err(SdoErrorKind::UnexpectedSdoType(SdoType::UnsupportedType).into())
                 }
+                Err(err) => return Err(self.abort_sdo_on_error(slave, coe_index, err).await),
+            }
+            toggle = !toggle;
+        }
+        Ok(received)
+    }
+
+    /// Returns the size of the segment and whether it is the last one.
+    async fn upload_sdo_segment(
+        &self,
+        slave: &Slave,
+        toggle: bool,
+        buf: &mut [u8],
+    ) -> Result<(usize, bool), TaskError<SdoErrorKind>> {
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                let message = Mailbox::new_sdo_upload_segment_request(toggle);
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(&message)
+            },
+            false,
+        )
+        .await?;
+        self.read_response(slave, MailboxType::CoE).await?;
+        self.with_mailbox(|mb_data| sdo_upload_segment_response(mb_data, toggle, buf))
+    }
+
+    /// Aborts a segmented transfer. The slave does not respond.
+    pub async fn abort_sdo(
+        &self,
+        slave: &Slave,
+        index: u16,
+        sub_index: u8,
+        abort_code: AbortCode,
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                let message = Mailbox::new_sdo_abort_request(index, sub_index, abort_code);
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(&message)
+            },
+            false,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn abort_sdo_on_error(
+        &self,
+        slave: &Slave,
+        coe_index: CoeIndex,
+        err: TaskError<SdoErrorKind>,
+    ) -> TaskError<SdoErrorKind> {
+        if let Some(abort_code) = sdo_abort_code(&err) {
+            let CoeIndex {
+                index, sub_index, ..
+            } = coe_index;
+            // The original error is more useful than an error of the abort.
+            let _ = self.abort_sdo(slave, index, sub_index, abort_code).await;
+        }
+        err
+    }
+
+    /// Reads the indexes of the objects in a list of the object dictionary.
+    pub async fn read_od_list<'b>(
+        &self,
+        slave: &Slave,
+        list_type: OdListType,
+        buf: &'b mut [u8],
+    ) -> Result<OdListFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        let request = SdoInfo::GetOdListReq(list_type);
+        let size = self.read_sdo_info(slave, request, buf).await?;
+        sdo_info_data(buf, size, OdListFrame::HEADER_SIZE).map(OdListFrame)
+    }
+
+    pub async fn read_object_description<'b>(
+        &self,
+        slave: &Slave,
+        index: u16,
+        buf: &'b mut [u8],
+    ) -> Result<ObjectDescriptionFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        let request = SdoInfo::GetObjectDescriptionReq(index);
+        let size = self.read_sdo_info(slave, request, buf).await?;
+        sdo_info_data(buf, size, ObjectDescriptionFrame::HEADER_SIZE).map(ObjectDescriptionFrame)
+    }
+
+    /// `value_info` is a combination of `ValueInfo`.
+    pub async fn read_entry_description<'b>(
+        &self,
+        slave: &Slave,
+        index: u16,
+        sub_index: u8,
+        value_info: u8,
+        buf: &'b mut [u8],
+    ) -> Result<EntryDescriptionFrame<&'b [u8]>, TaskError<SdoErrorKind>> {
+        let request = SdoInfo::GetEntryDescriptionReq {
+            index,
+            sub_index,
+            value_info,
+        };
+        let size = self.read_sdo_info(slave, request, buf).await?;
+        sdo_info_data(buf, size, EntryDescriptionFrame::HEADER_SIZE).map(EntryDescriptionFrame)
+    }
+
+    /// Joins the fragments of the response into `buf`. Returns the size of the response.
+    async fn read_sdo_info(
+        &self,
+        slave: &Slave,
+        request: SdoInfo<'_>,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SdoErrorKind>> {
+        let op_code = sdo_info_response_op_code(&request);
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                let message = Mailbox::CoE((CoeIndex::default(), CoE::SdoInfo(request)));
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(&message)
+            },
+            false,
+        )
+        .await?;
+        let mut size = 0;
+        let mut fragments_left = None;
+        loop {
+            self.read_response(slave, MailboxType::CoE).await?;
+            let incomplete = self.with_mailbox(|mb_data| {
+                let (incomplete, data) = sdo_info_response(mb_data, op_code, &mut fragments_left)?;
+                // The rest of the fragments are read even if the buffer is small.
+                if let Some(buf) = buf.get_mut(size..size + data.len()) {
+                    buf.copy_from_slice(data);
+                }
+                size += data.len();
+                Ok::<_, TaskError<SdoErrorKind>>(incomplete)
+            })?;
+            if !incomplete {
+                break;
+            }
+        }
+        if buf.len() < size {
+            return Err(SdoErrorKind::BufferSmall.into());
+        }
+        Ok(size)
+    }
+}
+
+/// Runs an acyclic operation on a socket of `sif` to completion.
+/// The PDUs of the interface are transmitted while the operation is pending.
+pub fn block_on<S: SocketAccess, T, E: From<PhyError>>(
+    sif: &RefCell<S>,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+            return result;
+        }
+        match sif.borrow_mut().poll_tx_rx() {
+            Ok(_) | Err(PhyError::TxNotAvailable) | Err(PhyError::RxNotAvailable) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Blocking operations, which are thin wrappers of the `AcyclicSocket` operations.
+impl<'frame, 'buf, D, const N: usize> SocketInterface<'frame, 'buf, D, N>
+where
+    D: RawEthernetDevice,
+{
+    /// Runs an operation on the socket of `handle`.
+    fn block_on_socket<T, E>(
+        &mut self,
+        handle: &SocketHandle,
+        operation: impl AsyncFnOnce(&AcyclicSocket<'_, &mut Self>) -> Result<T, TaskError<E>>,
+    ) -> Result<T, TaskError<E>> {
+        let sif = RefCell::new(self);
+        let socket = AcyclicSocket::new(&sif, handle.clone());
+        block_on(&sif, operation(&socket))
+    }
+
+    /// Returns a slice of the socket buffer.
+    pub fn read_register(
+        &mut self,
+        handle: &SocketHandle,
+        target_slave: TargetSlave,
+        register_address: u16,
+        data_size: usize,
+    ) -> Result<&[u8], TaskError<()>> {
+        self.block_on_socket(handle, async |socket| {
+            socket
+                .read_register(target_slave, register_address, data_size)
+                .await
+        })?;
+        let socket = self.get_socket(handle).expect("socket not found");
+        Ok(&socket.data_buf()[..data_size])
+    }
+
+    pub fn write_register(
+        &mut self,
+        handle: &SocketHandle,
+        target_slave: TargetSlave,
+        register_address: u16,
+        data: &[u8],
+    ) -> Result<(), TaskError<()>> {
+        self.block_on_socket(handle, async |socket| {
+            socket
+                .write_register(target_slave, register_address, data)
+                .await
+        })
+    }
+
+    pub fn init<'slave, 'pdo_mapping, 'pdo_entry>(
+        &mut self,
+        handle: &SocketHandle,
+        network: &mut Network<'slave, 'pdo_mapping, 'pdo_entry>,
+    ) -> Result<(), TaskError<NetworkInitTaskError>> {
+        self.block_on_socket(handle, async |socket| socket.init(network).await)
+    }
+
+    pub fn init_dc<'slave, 'pdo_mapping, 'pdo_entry>(
+        &mut self,
+        handle: &SocketHandle,
+        network: &mut Network<'slave, 'pdo_mapping, 'pdo_entry>,
+    ) -> Result<(), TaskError<()>> {
+        self.block_on_socket(handle, async |socket| socket.init_dc(network).await)
+    }
+
+    pub fn read_al_state(
+        &mut self,
+        handle: &SocketHandle,
+        target_slave: TargetSlave,
+    ) -> Result<(AlState, Option<AlStatusCode>), TaskError<()>> {
+        self.block_on_socket(handle, async |socket| {
+            socket.read_al_state(target_slave).await
+        })
+    }
+
+    pub fn change_al_state(
+        &mut self,
+        handle: &SocketHandle,
+        target_slave: TargetSlave,
+        al_state: AlState,
+    ) -> Result<AlState, TaskError<AlStateTransferTaskError>> {
+        self.block_on_socket(handle, async |socket| {
+            socket.change_al_state(target_slave, al_state).await
+        })
+    }
+
+    pub fn read_sii(
+        &mut self,
+        handle: &SocketHandle,
+        slave_address: SlaveAddress,
+        sii_address: u16,
+    ) -> Result<(SiiData<[u8; SiiData::SIZE]>, usize), TaskError<SiiTaskError>> {
+        self.block_on_socket(handle, async |socket| {
+            socket.read_sii(slave_address, sii_address).await
+        })
+    }
+
+    pub fn read_mailbox(
+        &mut self,
+        handle: &SocketHandle,
+        slave_info: &SlaveInfo,
+        wait_full: bool,
+    ) -> Result<MailboxFrame<&[u8]>, TaskError<MailboxTaskError>> {
+        self.block_on_socket(handle, async |socket| {
+            socket.read_mailbox(slave_info, wait_full).await
+        })?;
+        let socket = self.get_socket(handle).expect("socket not found");
+        Ok(MailboxFrame(socket.data_buf()))
+    }
+
+    pub fn write_mailbox<
+        F: FnOnce(&mut MailboxFrame<&mut [u8]>) -> Result<(), MailboxEncodeError>,
+    >(
+        &mut self,
+        handle: &SocketHandle,
+        slave_info: &SlaveInfo,
+        mb_frame_writer: F,
+        wait_empty: bool,
+    ) -> Result<(), TaskError<MailboxTaskError>> {
+        self.block_on_socket(handle, async |socket| {
+            socket
+                .write_mailbox(slave_info, mb_frame_writer, wait_empty)
+                .await
+        })
+    }
+
+    /// Objects which do not fit in the mailbox are written with a segmented download.
+    pub fn write_sdo(
+        &mut self,
+        handle: &SocketHandle,
+        slave: &Slave,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        self.block_on_socket(handle, async |socket| {
+            socket.write_sdo(slave, index, sub_index, data).await
+        })
+    }
+
+    /// Writes all entries of an object from `sub_index` (0 or 1) in one transfer.
+    /// Sub index 0 is 16-bit in `data`.
+    pub fn write_sdo_complete(
+        &mut self,
+        handle: &SocketHandle,
+        slave: &Slave,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+    ) -> Result<(), TaskError<SdoErrorKind>> {
+        self.block_on_socket(handle, async |socket| {
+            socket
+                .write_sdo_complete(slave, index, sub_index, data)
+                .await
+        })
+    }
+
+    /// Returns a slice of the socket buffer.
+    /// Objects which do not fit in the mailbox cause `SdoErrorKind::BufferSmall`.
+    pub fn read_sdo(
+        &mut self,
+        handle: &SocketHandle,
+        slave: &Slave,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<&[u8], TaskError<SdoErrorKind>> {
+        let size = self.block_on_socket(handle, async |socket| {
+            socket.read_sdo(slave, index, sub_index).await
+        })?;
+        let socket = self.get_socket(handle).expect("socket not found");
+        Ok(&socket.data_buf()[..size])
+    }
+}
+
+/// Data size of a download request which fits in the rx mailbox.
+fn sdo_download_capacity(slave_info: &SlaveInfo) -> usize {
+    (slave_info.mailbox_rx_sm().unwrap_or_default().size() as usize).saturating_sub(
+        MailboxFrame::HEADER_SIZE
+            + CoeFrame::HEADER_SIZE
+            + SdoFrame::HEADER_SIZE
+            + SdoDownloadNormalRequestFrame::HEADER_SIZE,
+    )
+}
+
+/// Data size of a download segment which fits in the rx mailbox.
+fn sdo_segment_capacity(slave_info: &SlaveInfo) -> usize {
+    (slave_info.mailbox_rx_sm().unwrap_or_default().size() as usize)
+        .saturating_sub(
+            MailboxFrame::HEADER_SIZE + CoeFrame::HEADER_SIZE + SdoSegmentFrame::HEADER_SIZE,
+        )
+        .max(SdoSegmentFrame::MIN_DATA_SIZE)
+}
+
+/// Abort code which the master sends to the slave, when a segmented transfer fails.
+/// Returns None if the slave has aborted the transfer itself.
+pub(crate) fn sdo_abort_code(err: &TaskError<SdoErrorKind>) -> Option<AbortCode> {
+    match err {
+        TaskError::TaskSpecific(SdoErrorKind::AbortCode(_)) => None,
+        TaskError::TaskSpecific(SdoErrorKind::ToggleBitUnmatch) => {
+            Some(AbortCode::NoToggleBitChange)
+        }
+        TaskError::TaskSpecific(SdoErrorKind::BufferSmall) => Some(AbortCode::OutsideMemoryRange),
+        TaskError::Timeout => Some(AbortCode::Timeout),
+        _ => Some(AbortCode::GeneralError),
+    }
+}
+
+/// SDO response of the slave. An abort of the slave and other mailboxes are errors.
+pub(crate) fn sdo_response<'a>(
+    mb_data: &MailboxFrame<&'a [u8]>,
+) -> Result<SdoRes<'a>, TaskError<SdoErrorKind>> {
+    let mb = mb_data
+        .mailbox()
+        .map_err(|_| SdoErrorKind::Mailbox(MailboxTaskError::BufferSmall))?;
+
+    match mb {
+        Mailbox::Error(err) => Err(SdoErrorKind::ErrorMailbox(err).into()),
+        Mailbox::AoE(_)
+        | Mailbox::EoE(_)
+        | Mailbox::FoE(_)
+        | Mailbox::SoE(_)
+        | Mailbox::VoE(_)
+        | Mailbox::UnsupportedProtocol(_) => Err(SdoErrorKind::UnsupportedMailboxProtocol.into()),
+        Mailbox::CoE((_, coe)) => match coe {
+            CoE::Emmergency(emm_f) => {
+                Err(SdoErrorKind::Emmergency(emm_f.emmergency_error_code()).into())
+            }
+            CoE::SdoReq(sdo_req) => match sdo_req {
+                SdoReq::Abort(abort_code) => Err(SdoErrorKind::AbortCode(abort_code).into()),
+                _ => Err(SdoErrorKind::UnexpectedSdoType(SdoType::Req).into()),
             },
+            CoE::SdoRes(sdo_res) => Ok(sdo_res),
+            CoE::SdoInfo(_) => Err(SdoErrorKind::UnexpectedSdoType(SdoType::Info).into()),
+            CoE::UnsupportedType(_) => {
+                Err(SdoErrorKind::UnexpectedSdoType(SdoType::UnsupportedType).into())
+            }
+        },
+    }
+}
+
+pub(crate) fn unexpected_sdo_response(sdo_res: SdoRes) -> TaskError<SdoErrorKind> {
+    let sdo_type = match sdo_res {
+        SdoRes::DownLoad => SdoType::DownLoadRes,
+        SdoRes::Upload(_) | SdoRes::SegmentedUpload { .. } => SdoType::UploadRes,
+        SdoRes::DownLoadSegment { .. } => SdoType::DownLoadSegmentRes,
+        SdoRes::UploadSegment { .. } => SdoType::UploadSegmentRes,
+        SdoRes::Other(_) => SdoType::OtherRes,
+    };
+    SdoErrorKind::UnexpectedSdoType(sdo_type).into()
+}
+
+fn sdo_download_response(mb_data: MailboxFrame<&[u8]>) -> Result<(), TaskError<SdoErrorKind>> {
+    match sdo_response(&mb_data)? {
+        SdoRes::DownLoad => Ok(()),
+        other => Err(unexpected_sdo_response(other)),
+    }
+}
+
+/// Returns the data in the mailbox and the complete size of the object.
+/// The data are shorter than the complete size if the rest follows in segments.
+fn sdo_upload_response(
+    mb_data: MailboxFrame<&[u8]>,
+) -> Result<(&[u8], usize), TaskError<SdoErrorKind>> {
+    match sdo_response(&mb_data)? {
+        SdoRes::Upload(data) => Ok((data, data.len())),
+        SdoRes::SegmentedUpload {
+            complete_size,
+            data,
+        } => Ok((data, complete_size as usize)),
+        other => Err(unexpected_sdo_response(other)),
+    }
+}
+
+fn sdo_download_segment_response(
+    mb_data: MailboxFrame<&[u8]>,
+    toggle: bool,
+) -> Result<(), TaskError<SdoErrorKind>> {
+    match sdo_response(&mb_data)? {
+        SdoRes::DownLoadSegment { toggle: t } if t == toggle => Ok(()),
+        SdoRes::DownLoadSegment { .. } => Err(SdoErrorKind::ToggleBitUnmatch.into()),
+        other => Err(unexpected_sdo_response(other)),
+    }
+}
+
+/// Copies the segment data to `buf`. Returns the data size and whether it is the last segment.
+fn sdo_upload_segment_response(
+    mb_data: MailboxFrame<&[u8]>,
+    toggle: bool,
+    buf: &mut [u8],
+) -> Result<(usize, bool), TaskError<SdoErrorKind>> {
+    match sdo_response(&mb_data)? {
+        SdoRes::UploadSegment { toggle: t, .. } if t != toggle => {
+            Err(SdoErrorKind::ToggleBitUnmatch.into())
         }
+        SdoRes::UploadSegment { last, data, .. } => {
+            buf.get_mut(..data.len())
+                .ok_or(SdoErrorKind::BufferSmall)?
+                .copy_from_slice(data);
+            Ok((data.len(), last))
+        }
+        other => Err(unexpected_sdo_response(other)),
+    }
+}
+
+fn sdo_info_response_op_code(request: &SdoInfo) -> SdoInfoOpCode {
+    match request {
+        SdoInfo::GetOdListReq(_) => SdoInfoOpCode::GetOdListRes,
+        SdoInfo::GetObjectDescriptionReq(_) => SdoInfoOpCode::GetObjectDescriptionRes,
+        SdoInfo::GetEntryDescriptionReq { .. } => SdoInfoOpCode::GetEntryDescriptionRes,
+        _ => unreachable!("not an SDO information request"),
     }
 }
 
+/// Returns whether more fragments follow, and the data of the fragment.
+/// The counter is not checked, because a response may consist of several mailboxes.
+/// `fragments_left` is the number expected in the fragment, or None for the first fragment.
+/// It is updated for the next fragment.
+pub(crate) fn sdo_info_response<'a>(
+    mb_data: MailboxFrame<&'a [u8]>,
+    op_code: SdoInfoOpCode,
+    fragments_left: &mut Option<u16>,
+) -> Result<(bool, &'a [u8]), TaskError<SdoErrorKind>> {
+    let mb = mb_data
+        .mailbox()
+        .map_err(|_| SdoErrorKind::Mailbox(MailboxTaskError::BufferSmall))?;
+    match mb {
+        Mailbox::CoE((_, CoE::SdoInfo(sdo_info))) => match sdo_info {
+            SdoInfo::Response {
+                op_code: res_op_code,
+                incomplete,
+                fragments_left: left,
+                data,
+            } if res_op_code == op_code => {
+                if fragments_left.is_some_and(|expected| expected != left)
+                    || incomplete != (0 < left)
+                {
+                    return Err(SdoErrorKind::FragmentsLeftUnmatch.into());
+                }
+                *fragments_left = left.checked_sub(1);
+                Ok((incomplete, data))
+            }
+            SdoInfo::Error(abort_code) => Err(SdoErrorKind::AbortCode(abort_code).into()),
+            _ => Err(SdoErrorKind::UnexpectedSdoType(SdoType::Info).into()),
+        },
+        // errors, emergencies and aborts
+        _ => Err(unexpected_sdo_response(sdo_response(&mb_data)?)),
+    }
+}
+
+/// The response has to contain at least its header.
+fn sdo_info_data(
+    buf: &[u8],
+    size: usize,
+    header_size: usize,
+) -> Result<&[u8], TaskError<SdoErrorKind>> {
+    if size < header_size {
+        return Err(SdoErrorKind::Mailbox(MailboxTaskError::BufferSmall).into());
+    }
+    Ok(&buf[..size])
+}
+
 #[derive(Debug, Clone)]
 pub enum SdoErrorKind {
     Mailbox(MailboxTaskError),
@@ -393,8 +1121,15 @@ pub enum SdoErrorKind {
     ErrorMailbox(MailboxErrorDetail),
     Emmergency(EmmergencyErrorCode),
     UnexpectedSdoType(SdoType),
-    CountUnmatch,
     UnsupportedMailboxProtocol,
+    /// The toggle bit of a segment response is not changed.
+    ToggleBitUnmatch,
+    /// The object does not fit in the buffer.
+    BufferSmall,
+    /// The size of the object does not match the type of the value.
+    SizeUnmatch,
+    /// A fragment of an SDO information response is missing or repeated.
+    FragmentsLeftUnmatch,
 }
 
 #[derive(Debug, Clone)]
@@ -402,7 +1137,10 @@ pub enum SdoType {
     Req,
     UploadRes,
     DownLoadRes,
+    UploadSegmentRes,
+    DownLoadSegmentRes,
     OtherRes,
+    Info,
     UnsupportedType,
 }
 
@@ -418,8 +1156,261 @@ impl From<TaskError<()>> for TaskError<SdoErrorKind> {
     }
 }
 
+impl From<TaskError<MailboxTaskError>> for TaskError<SdoErrorKind> {
+    fn from(err: TaskError<MailboxTaskError>) -> Self {
+        match err {
+            TaskError::Interface(e) => TaskError::Interface(e),
+            TaskError::UnexpectedCommand => TaskError::UnexpectedCommand,
+            TaskError::UnexpectedWkc(e) => TaskError::UnexpectedWkc(e),
+            TaskError::TaskSpecific(e) => TaskError::TaskSpecific(SdoErrorKind::Mailbox(e)),
+            TaskError::Timeout => TaskError::Timeout,
+        }
+    }
+}
+
 impl From<SdoErrorKind> for TaskError<SdoErrorKind> {
     fn from(err: SdoErrorKind) -> Self {
         Self::TaskSpecific(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        sdo_info_response, CoE, CoeIndex, Mailbox, MailboxFrame, SdoErrorKind, SdoInfo,
+        SdoInfoOpCode, TaskError,
+    };
+
+    fn od_list_fragment(buf: &mut [u8], incomplete: bool, fragments_left: u16) {
+        let sdo_info = SdoInfo::Response {
+            op_code: SdoInfoOpCode::GetOdListRes,
+            incomplete,
+            fragments_left,
+            data: &[1, 2],
+        };
+        let message = Mailbox::CoE((CoeIndex::default(), CoE::SdoInfo(sdo_info)));
+        MailboxFrame(buf).set_mailbox(&message).unwrap();
+    }
+
+    #[test]
+    fn sdo_info_fragments_test() {
+        let mut buf = [0; 64];
+        let op_code = SdoInfoOpCode::GetOdListRes;
+        let mut fragments_left = None;
+        for left in [2, 1] {
+            od_list_fragment(&mut buf, true, left);
+            let (incomplete, data) =
+                sdo_info_response(MailboxFrame(&buf), op_code, &mut fragments_left).unwrap();
+            assert!(incomplete);
+            assert_eq!(data, [1, 2]);
+        }
+        od_list_fragment(&mut buf, false, 0);
+        let (incomplete, _) =
+            sdo_info_response(MailboxFrame(&buf), op_code, &mut fragments_left).unwrap();
+        assert!(!incomplete);
+
+        // A fragment is skipped.
+        let mut fragments_left = None;
+        od_list_fragment(&mut buf, true, 2);
+        sdo_info_response(MailboxFrame(&buf), op_code, &mut fragments_left).unwrap();
+        od_list_fragment(&mut buf, false, 0);
+        assert!(matches!(
+            sdo_info_response(MailboxFrame(&buf), op_code, &mut fragments_left),
+            Err(TaskError::TaskSpecific(SdoErrorKind::FragmentsLeftUnmatch))
+        ));
+
+        // The last fragment has fragments left.
+        od_list_fragment(&mut buf, false, 1);
+        assert!(matches!(
+            sdo_info_response(MailboxFrame(&buf), op_code, &mut None),
+            Err(TaskError::TaskSpecific(SdoErrorKind::FragmentsLeftUnmatch))
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod simulator_tests {
+    use crate::frame::{AbortCode, ObjectAccess, ObjectCode, OdListType};
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::simulator::SdoAccess;
+    use crate::interface::{SlaveAddress, TargetSlave};
+    use crate::slave::AlState;
+    use crate::task::{SdoErrorKind, TaskError};
+
+    #[test]
+    fn sdo_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.master(new_segment(2));
+        assert_eq!(master.network().num_slaves(), 2);
+        master.init_dc().unwrap();
+        let delta = master
+            .read_register(SlaveAddress::SlavePosition(1).into(), 0x092C, 4)
+            .unwrap();
+        assert_eq!(delta, [0; 4]);
+        master
+            .change_al_state(TargetSlave::All(2), AlState::PreOperational)
+            .unwrap();
+
+        let slave = SlaveAddress::SlavePosition(1);
+        assert_eq!(master.read_sdo::<u32>(slave, 0x1018, 1).unwrap(), 0x0ABC);
+        assert_eq!(master.read_sdo::<u32>(slave, 0x1018, 2).unwrap(), 0x1235);
+        master.write_sdo::<u16>(slave, 0x7000, 1, 0xBEEF).unwrap();
+        assert_eq!(master.read_sdo::<u16>(slave, 0x7000, 1).unwrap(), 0xBEEF);
+        assert_eq!(
+            master.read_sdo_bytes(slave, 0x1008, 0).unwrap(),
+            b"Virtual ESC"
+        );
+
+        match master.read_sdo_bytes(slave, 0x2000, 1) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::AbortCode(code))) => {
+                assert_eq!(code, AbortCode::DoesNotExistInDict)
+            }
+            other => panic!("{:?}", other),
+        }
+        match master.write_sdo::<u16>(slave, 0x6000, 1, 1) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::AbortCode(code))) => {
+                assert_eq!(code, AbortCode::ReadOnly)
+            }
+            other => panic!("{:?}", other),
+        }
+
+        master
+            .abort_sdo(slave, 0x7000, 1, AbortCode::GeneralError)
+            .unwrap();
+        assert_eq!(
+            master.device_mut().slaves_mut()[1].sdo_aborts(),
+            [(0x7000, 1, AbortCode::GeneralError)]
+        );
+        assert_eq!(master.read_sdo::<u16>(slave, 0x7000, 1).unwrap(), 0xBEEF);
+    }
+
+    #[test]
+    fn segmented_sdo_test() {
+        let mut segment = new_segment(1);
+        // Larger than the mailbox (128 bytes)
+        let blob: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        segment.slaves_mut()[0]
+            .od_mut()
+            .insert(0x2000, 0, SdoAccess::ReadWrite, &blob);
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(segment);
+
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut data = [0; 512];
+        let size = master.read_sdo_into(slave, 0x2000, 0, &mut data).unwrap();
+        assert_eq!(&data[..size], blob.as_slice());
+
+        match master.read_sdo_bytes(slave, 0x2000, 0) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::BufferSmall)) => {}
+            other => panic!("{:?}", other),
+        }
+        match master.read_sdo_into(slave, 0x2000, 0, &mut data[..100]) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::BufferSmall)) => {}
+            other => panic!("{:?}", other),
+        }
+
+        let reversed: Vec<u8> = blob.iter().rev().copied().collect();
+        master.write_sdo_bytes(slave, 0x2000, 0, &reversed).unwrap();
+        let size = master.read_sdo_into(slave, 0x2000, 0, &mut data).unwrap();
+        assert_eq!(&data[..size], reversed.as_slice());
+        let size = master.read_sdo_into(slave, 0x1008, 0, &mut data).unwrap();
+        assert_eq!(&data[..size], b"Virtual ESC");
+    }
+
+    #[test]
+    fn complete_sdo_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+
+        let slave = SlaveAddress::SlavePosition(0);
+        let (slave_info, _) = master.network().slave(slave).unwrap();
+        assert!(slave_info.info().support_sdo_complete_access());
+
+        let mut data = [0; 64];
+        let size = master
+            .read_sdo_complete(slave, 0x1018, 0, &mut data)
+            .unwrap();
+        assert_eq!(size, 2 + 4 * 4);
+        assert_eq!(&data[..2], &[4, 0]);
+        assert_eq!(&data[2..6], &0x0000_0ABC_u32.to_le_bytes());
+        let size = master
+            .read_sdo_complete(slave, 0x1018, 1, &mut data)
+            .unwrap();
+        assert_eq!(&data[..4], &0x0000_0ABC_u32.to_le_bytes());
+        assert_eq!(size, 4 * 4);
+
+        let mapping = [2, 0, 0x10, 1, 0, 0x70, 0x10, 2, 0, 0x70];
+        master
+            .write_sdo_complete(slave, 0x1600, 0, &mapping)
+            .unwrap();
+        assert_eq!(master.read_sdo_bytes(slave, 0x1600, 0).unwrap(), &[2]);
+        let size = master
+            .read_sdo_complete(slave, 0x1600, 0, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &mapping);
+
+        match master.write_sdo_complete(slave, 0x1600, 0, &mapping[..6]) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::AbortCode(
+                AbortCode::ParameterLengthTooShort,
+            ))) => {}
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(master.read_sdo_bytes(slave, 0x1600, 0).unwrap(), &[2]);
+        match master.read_sdo_complete(slave, 0x1008, 0, &mut data) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::AbortCode(
+                AbortCode::NotSupportedAccess,
+            ))) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn sdo_info_test() {
+        let mut segment = new_segment(1);
+        let od = segment.slaves_mut()[0].od_mut();
+        let num_objects = od.indexes().count();
+        // The list does not fit in a mailbox.
+        for index in 0x2000..0x2040 {
+            od.insert(index, 0, SdoAccess::ReadWrite, &[0; 2]);
+        }
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(segment);
+
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut data = [0; 512];
+        let od_list = master
+            .read_od_list(slave, OdListType::All, &mut data)
+            .unwrap();
+        assert_eq!(od_list.list_type(), OdListType::All);
+        assert_eq!(od_list.indexes().count(), num_objects + 0x40);
+        assert_eq!(od_list.indexes().next(), Some(0x1000));
+        assert!(od_list.indexes().any(|index| index == 0x203F));
+        match master.read_od_list(slave, OdListType::All, &mut data[..64]) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::BufferSmall)) => {}
+            other => panic!("{:?}", other),
+        }
+        let od_list = master
+            .read_od_list(slave, OdListType::Length, &mut data)
+            .unwrap();
+        assert_eq!(od_list.indexes().next(), Some(num_objects as u16 + 0x40));
+
+        let description = master
+            .read_object_description(slave, 0x1018, &mut data)
+            .unwrap();
+        assert_eq!(description.index(), 0x1018);
+        assert_eq!(description.max_sub_index(), 4);
+        assert_eq!(description.object_code(), ObjectCode::Record as u8);
+        assert_eq!(description.name(), b"Identity");
+        let description = master
+            .read_entry_description(slave, 0x1018, 1, 0, &mut data)
+            .unwrap();
+        assert_eq!(description.bit_length(), 32);
+        assert_eq!(description.object_access() & ObjectAccess::WRITE_PRE_OP, 0);
+        match master.read_object_description(slave, 0x1234, &mut data) {
+            Err(TaskError::TaskSpecific(SdoErrorKind::AbortCode(
+                AbortCode::DoesNotExistInDict,
+            ))) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
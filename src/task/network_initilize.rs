@@ -1,7 +1,7 @@
 use super::slave_initialize::SlaveInitTaskError;
 use super::SlaveInitTask;
 use super::TaskError;
-use super::{CyclicTask, EtherCatSystemTime};
+use super::{CyclicTask, EtherCatSystemTime, Timeouts};
 use crate::frame::CommandType;
 use crate::interface::{Command, Pdu};
 use crate::register::{DlControl, SyncManagerStatus};
@@ -71,6 +71,10 @@ impl<'a, 'b, 'c, 'd> NetworkInitTask<'a, 'b, 'c, 'd> {
         }
     }
 
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.initilizer.set_timeouts(timeouts);
+    }
+
     pub fn take(self) -> &'d mut Network<'a, 'b, 'c> {
         self.network
     }
@@ -0,0 +1,297 @@
+use super::{AcyclicSocket, MailboxTaskError, TaskError};
+use crate::{
+    frame::{
+        LengthError, Mailbox, MailboxEncodeError, MailboxErrorDetail, MailboxFrame, MailboxType,
+    },
+    interface::SocketAccess,
+    slave::Slave,
+};
+
+/// Mailboxes of any protocol, e.g. VoE, whose data are passed through as they are.
+/// The mailbox header, its counter and the sync manager handshakes are handled here.
+impl<'a, S: SocketAccess> AcyclicSocket<'a, S> {
+    /// Sends `payload` in a mailbox of `mb_type`. `payload` does not include the mailbox header.
+    pub async fn send_raw_mailbox(
+        &self,
+        slave: &Slave,
+        mb_type: MailboxType,
+        payload: &[u8],
+    ) -> Result<(), TaskError<RawMailboxErrorKind>> {
+        check_payload_size(slave, payload)?;
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| set_raw_mailbox(mb_frame, count, mb_type, payload),
+            false,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Waits for a mailbox of `mb_type` and copies its data into `buf`.
+    /// Returns the size of the data. Emergencies which arrive before it are stored in the slave,
+    /// and mailboxes of other types are kept in the slave for their readers.
+    /// A mailbox of `mb_type` which has been kept in the slave, e.g. during a mailbox session, is taken first.
+    pub async fn receive_raw_mailbox(
+        &self,
+        slave: &Slave,
+        mb_type: MailboxType,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<RawMailboxErrorKind>> {
+        if let Some(result) =
+            slave.pop_received_mailbox(mb_type, |mb_data| copy_raw_payload(&mb_data, mb_type, buf))
+        {
+            return result;
+        }
+        self.read_response(slave, mb_type).await?;
+        self.with_mailbox(|mb_data| copy_raw_payload(&mb_data, mb_type, buf))
+    }
+}
+
+fn check_payload_size(slave: &Slave, payload: &[u8]) -> Result<(), RawMailboxErrorKind> {
+    let capacity = (slave.info().mailbox_rx_sm().unwrap_or_default().size() as usize)
+        .saturating_sub(MailboxFrame::HEADER_SIZE);
+    if capacity < payload.len() {
+        Err(RawMailboxErrorKind::PayloadTooLarge)
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) fn set_raw_mailbox(
+    mb_frame: &mut MailboxFrame<&mut [u8]>,
+    count: u8,
+    mb_type: MailboxType,
+    payload: &[u8],
+) -> Result<(), MailboxEncodeError> {
+    let data = mb_frame
+        .0
+        .get_mut(MailboxFrame::HEADER_SIZE..MailboxFrame::HEADER_SIZE + payload.len())
+        .ok_or(LengthError)?;
+    data.copy_from_slice(payload);
+    mb_frame.set_length(payload.len() as u16);
+    mb_frame.set_address(0);
+    mb_frame.set_prioriry(0);
+    mb_frame.set_mb_type(mb_type);
+    mb_frame.set_count(count);
+    Ok(())
+}
+
+/// The mailbox is of `mb_type`, or an error mailbox.
+fn copy_raw_payload(
+    mb_data: &MailboxFrame<&[u8]>,
+    mb_type: MailboxType,
+    buf: &mut [u8],
+) -> Result<usize, TaskError<RawMailboxErrorKind>> {
+    let received_type = mb_data.mb_type();
+    if received_type == MailboxType::Error && mb_type != MailboxType::Error {
+        return match mb_data.mailbox() {
+            Ok(Mailbox::Error(detail)) => Err(RawMailboxErrorKind::ErrorMailbox(detail).into()),
+            _ => Err(RawMailboxErrorKind::Mailbox(MailboxTaskError::BufferSmall).into()),
+        };
+    }
+    let data = mb_data.without_header();
+    let payload = &data[..(mb_data.length() as usize).min(data.len())];
+    buf.get_mut(..payload.len())
+        .ok_or(RawMailboxErrorKind::BufferSmall)?
+        .copy_from_slice(payload);
+    Ok(payload.len())
+}
+
+#[derive(Debug, Clone)]
+pub enum RawMailboxErrorKind {
+    Mailbox(MailboxTaskError),
+    ErrorMailbox(MailboxErrorDetail),
+    /// The payload does not fit in the rx mailbox of the slave.
+    PayloadTooLarge,
+    /// The received data do not fit in the buffer.
+    BufferSmall,
+}
+
+impl From<TaskError<MailboxTaskError>> for TaskError<RawMailboxErrorKind> {
+    fn from(err: TaskError<MailboxTaskError>) -> Self {
+        match err {
+            TaskError::Interface(e) => TaskError::Interface(e),
+            TaskError::UnexpectedCommand => TaskError::UnexpectedCommand,
+            TaskError::UnexpectedWkc(e) => TaskError::UnexpectedWkc(e),
+            TaskError::TaskSpecific(e) => TaskError::TaskSpecific(RawMailboxErrorKind::Mailbox(e)),
+            TaskError::Timeout => TaskError::Timeout,
+        }
+    }
+}
+
+impl From<RawMailboxErrorKind> for TaskError<RawMailboxErrorKind> {
+    fn from(err: RawMailboxErrorKind) -> Self {
+        Self::TaskSpecific(err)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::{MailboxErrorDetail, MailboxType};
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::{EoeDevice, SlaveAddress};
+    use crate::task::{RawMailboxErrorKind, TaskError};
+    use core::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn raw_mailbox_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+
+        // vendor ID, vendor type and data
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 1, 2, 3, 4];
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut data = [0; 128];
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        match master.receive_raw_mailbox(slave, MailboxType::VoE, &mut data) {
+            Err(TaskError::TaskSpecific(RawMailboxErrorKind::ErrorMailbox(
+                MailboxErrorDetail::UnsupportedProtocol,
+            ))) => {}
+            other => panic!("{:?}", other),
+        }
+
+        // The data after the vendor header are returned in reverse order.
+        master.device_mut().slaves_mut()[0].set_voe_handler(|request| {
+            let mut response = request.to_vec();
+            response[6..].reverse();
+            Some(response)
+        });
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(
+            &data[..size],
+            &[0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 4, 3, 2, 1]
+        );
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        match master.receive_raw_mailbox(slave, MailboxType::VoE, &mut data[..4]) {
+            Err(TaskError::TaskSpecific(RawMailboxErrorKind::BufferSmall)) => {}
+            other => panic!("{:?}", other),
+        }
+        match master.send_raw_mailbox(slave, MailboxType::VoE, &[0; 512]) {
+            Err(TaskError::TaskSpecific(RawMailboxErrorKind::PayloadTooLarge)) => {}
+            other => panic!("{:?}", other),
+        }
+
+        // A raw CoE SDO upload request of 0x1018:01
+        let request = [0x00, 0x20, 0x40, 0x18, 0x10, 0x01, 0, 0, 0, 0];
+        master
+            .send_raw_mailbox(slave, MailboxType::CoE, &request)
+            .unwrap();
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::CoE, &mut data)
+            .unwrap();
+        assert_eq!(size, 10);
+        assert_eq!(&data[6..10], &0x0000_0ABC_u32.to_le_bytes());
+
+        // The EoE fragment which arrives before the VoE response is kept for the EoE device.
+        let frame: Vec<u8> = (0..60).collect();
+        master.device_mut().slaves_mut()[0].send_eoe_frame(&frame);
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 5, 6, 7, 8];
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(&data[6..size], &[8, 7, 6, 5]);
+        let mut tx_buf = [0; 1514];
+        let mut rx_buf = [0; 1514];
+        let mut device = EoeDevice::new(slave, &mut tx_buf, &mut rx_buf);
+        master.poll_eoe(&mut device).unwrap();
+        assert_eq!(device.receive_frame(|data| data.to_vec()), Some(frame));
+    }
+
+    #[test]
+    fn mailbox_resilient_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+
+        let num_requests = Rc::new(Cell::new(0));
+        let counter = num_requests.clone();
+        master.device_mut().slaves_mut()[0].set_voe_handler(move |request| {
+            counter.set(counter.get() + 1);
+            Some(request.to_vec())
+        });
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut data = [0; 128];
+
+        // The response which is read by the lost frame is requested again.
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 1, 2, 3, 4];
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        master.device_mut().lose_frames_accessing(0x1080, 1);
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &request);
+        assert_eq!(num_requests.get(), 1);
+
+        // The request which is written by the lost frame is sent again,
+        // and the slave discards it if it has already received it.
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 5, 6, 7, 8];
+        master.device_mut().lose_frames_accessing(0x1000, 1);
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &request);
+        assert_eq!(num_requests.get(), 2);
+
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 9, 10, 11, 12];
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &request);
+        assert_eq!(num_requests.get(), 3);
+    }
+
+    #[test]
+    fn repeated_mailbox_test() {
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(new_segment(1));
+        master.device_mut().slaves_mut()[0].set_voe_handler(|request| Some(request.to_vec()));
+        let slave = SlaveAddress::SlavePosition(0);
+        let mut data = [0; 128];
+
+        // The response which is read by the lost frame is repeated, and is received once.
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 1, 2, 3, 4];
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        master.device_mut().lose_frames_accessing(0x1080, 1);
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &request);
+
+        // Another repeat request puts the same response in the tx mailbox again.
+        let activation = master.read_register(slave.into(), 0x080E, 1).unwrap()[0];
+        master
+            .write_register(slave.into(), 0x080E, &[activation ^ 0x02])
+            .unwrap();
+        let request = [0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 5, 6, 7, 8];
+        master
+            .send_raw_mailbox(slave, MailboxType::VoE, &request)
+            .unwrap();
+        let size = master
+            .receive_raw_mailbox(slave, MailboxType::VoE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &request);
+    }
+}
@@ -1,10 +1,9 @@
 use super::TaskError;
-use super::{CyclicTask, EtherCatSystemTime};
+use super::{CyclicTask, EtherCatSystemTime, Timeouts};
 use crate::interface::*;
 use crate::register::{SiiAccess, SiiAddress, SiiControl, SiiData};
 use crate::util::const_max;
-
-const TIMEOUT_MS: u32 = 100;
+use core::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SiiTaskError {
@@ -51,6 +50,7 @@ pub struct SiiReader {
     sii_address: u16,
     read_size: usize,
     sii_data: SiiData<[u8; SiiData::SIZE]>,
+    timeout: Duration,
 }
 
 impl SiiReader {
@@ -67,9 +67,14 @@ impl SiiReader {
             command: Command::default(),
             read_size: 0,
             sii_data: SiiData::new(),
+            timeout: Timeouts::default().sii,
         }
     }
 
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeout = timeouts.sii;
+    }
+
     pub fn start(&mut self, slave_address: SlaveAddress, sii_address: u16) {
         self.slave_address = slave_address;
         self.sii_address = sii_address;
@@ -212,9 +217,7 @@ impl CyclicTask for SiiReader {
                     self.state = State::Error(SiiTaskError::CommandError.into());
                 } else if !sii_control.busy() && !sii_control.read_operation() {
                     self.state = State::Read;
-                } else if self.timer_start.0 < sys_time.0
-                    && TIMEOUT_MS as u64 * 1000 < sys_time.0 - self.timer_start.0
-                {
+                } else if self.timeout < sys_time.elapsed_since(self.timer_start) {
                     self.state = State::Error(TaskError::Timeout)
                 }
             }
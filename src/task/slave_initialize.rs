@@ -1,14 +1,15 @@
 use super::{
     al_state_transfer::AlStateTransferTask,
     sii_read::{SiiReader, SiiTaskError},
-    AlStateTransferTaskError, CyclicTask, EtherCatSystemTime, TaskError,
+    AlStateTransferTaskError, CyclicTask, EtherCatSystemTime, TaskError, Timeouts,
 };
 use crate::{
     interface::{Command, Pdu, SlaveAddress},
     register::{
         sii::{
-            MailboxProtocol, ProductCode, RevisionNumber, StandardRxMailboxOffset,
-            StandardRxMailboxSize, StandardTxMailboxOffset, StandardTxMailboxSize, VenderId,
+            Categories, CategoryType, CoeDetails, MailboxProtocol, ProductCode, RevisionNumber,
+            StandardRxMailboxOffset, StandardRxMailboxSize, StandardTxMailboxOffset,
+            StandardTxMailboxSize, VenderId,
         },
         CyclicOperationStartTime, DcActivation, DlControl, DlInformation, DlStatus, DlUserWatchDog,
         FixedStationAddress, FmmuRegister, Latch0NegativeEdgeValue, Latch0PositiveEdgeValue,
@@ -86,6 +87,8 @@ enum State {
     GetRxMailboxOffset(bool),
     GetTxMailboxSize(bool),
     GetTxMailboxOffset(bool),
+    GetCategoryHeader(u16, bool),
+    GetCoeDetails(u16, bool),
     SetSmControl(usize),
     SetSmActivation(usize),
     SetStationAddress,
@@ -104,6 +107,7 @@ pub struct SlaveInitTask {
     state: State,
     command: Command,
     slave_info: Option<SlaveInfoBuilder>,
+    timeouts: Timeouts,
 }
 
 impl SlaveInitTask {
@@ -118,9 +122,14 @@ impl SlaveInitTask {
             state: State::Idle,
             command: Command::default(),
             slave_info: None,
+            timeouts: Timeouts::default(),
         }
     }
 
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
     pub fn start(&mut self, slave_position: u16) {
         self.slave_address = SlaveAddress::SlavePosition(slave_position);
         self.state = State::SetLoopPort;
@@ -169,7 +178,7 @@ impl CyclicTask for SlaveInitTask {
                 Some((command, DlControl::SIZE))
             }
             State::RequestInitState(is_first) => {
-                self.inner.into_al_state_transfer();
+                self.inner.into_al_state_transfer(self.timeouts);
                 let al_transfer = self.inner.al_state_transfer().unwrap();
                 if is_first {
                     al_transfer.start(self.slave_address.into(), AlState::Init);
@@ -238,7 +247,7 @@ impl CyclicTask for SlaveInitTask {
                 Some((command, length))
             }
             State::GetVenderID(is_first) => {
-                self.inner.into_sii();
+                self.inner.into_sii(self.timeouts);
                 let sii_reader = self.inner.sii().unwrap();
                 if is_first {
                     sii_reader.start(self.slave_address, VenderId::ADDRESS);
@@ -294,6 +303,14 @@ impl CyclicTask for SlaveInitTask {
                 }
                 sii_reader.next_pdu(buf)
             }
+            State::GetCategoryHeader(sii_address, is_first)
+            | State::GetCoeDetails(sii_address, is_first) => {
+                let sii_reader = self.inner.sii().unwrap();
+                if is_first {
+                    sii_reader.start(self.slave_address, sii_address);
+                }
+                sii_reader.next_pdu(buf)
+            }
             State::SetSmControl(num) => {
                 let command = Command::new_write(
                     self.slave_address.into(),
@@ -524,6 +541,10 @@ impl CyclicTask for SlaveInitTask {
                 match sii_reader.wait() {
                     Some(Ok((data, _size))) => {
                         self.slave_info.as_mut().unwrap().support_coe = data.0[0].get_bit(2);
+                        self.slave_info.as_mut().unwrap().support_eoe = data.0[0].get_bit(1);
+                        self.slave_info.as_mut().unwrap().support_foe = data.0[0].get_bit(3);
+                        self.slave_info.as_mut().unwrap().support_aoe = data.0[0].get_bit(0);
+                        self.slave_info.as_mut().unwrap().support_soe = data.0[0].get_bit(4);
                         self.state = State::GetRxMailboxSize(true)
                     }
                     None => self.state = State::GetProtocol(false),
@@ -622,7 +643,11 @@ impl CyclicTask for SlaveInitTask {
                         }
                         set_process_data_sm_size_offset(self.slave_info.as_mut().unwrap());
 
-                        self.state = State::SetSmControl(0);
+                        if self.slave_info.as_ref().unwrap().support_coe {
+                            self.state = State::GetCategoryHeader(Categories::ADDRESS, true);
+                        } else {
+                            self.state = State::SetSmControl(0);
+                        }
                     }
                     None => self.state = State::GetTxMailboxOffset(false),
                     Some(Err(err)) => {
@@ -630,6 +655,51 @@ impl CyclicTask for SlaveInitTask {
                     }
                 }
             }
+            State::GetCategoryHeader(sii_address, _) => {
+                let sii_reader = self.inner.sii().unwrap();
+                sii_reader.recieve_and_process(recv_data, sys_time);
+                match sii_reader.wait() {
+                    Some(Ok((data, _size))) => {
+                        let category_type = u16::from_le_bytes([data.0[0], data.0[1]]);
+                        let word_size = u16::from_le_bytes([data.0[2], data.0[3]]);
+                        let data_address = sii_address + (Categories::HEADER_SIZE / 2) as u16;
+                        let next_address = data_address.checked_add(word_size);
+                        self.state = match next_address {
+                            _ if category_type == CategoryType::General as u16 => {
+                                State::GetCoeDetails(data_address + CoeDetails::OFFSET, true)
+                            }
+                            Some(next_address) if category_type != CategoryType::End as u16 => {
+                                State::GetCategoryHeader(next_address, true)
+                            }
+                            // No general category
+                            _ => State::SetSmControl(0),
+                        };
+                    }
+                    None => self.state = State::GetCategoryHeader(sii_address, false),
+                    Some(Err(err)) => {
+                        self.state = State::Error(err.into());
+                    }
+                }
+            }
+            State::GetCoeDetails(sii_address, _) => {
+                let sii_reader = self.inner.sii().unwrap();
+                sii_reader.recieve_and_process(recv_data, sys_time);
+                match sii_reader.wait() {
+                    Some(Ok((data, _size))) => {
+                        let coe_details = data.0[1];
+                        self.slave_info
+                            .as_mut()
+                            .unwrap()
+                            .support_sdo_complete_access =
+                            coe_details & CoeDetails::ENABLE_SDO_COMPLETE_ACCESS != 0;
+                        self.state = State::SetSmControl(0);
+                    }
+                    None => self.state = State::GetCoeDetails(sii_address, false),
+                    Some(Err(err)) => {
+                        self.state = State::Error(err.into());
+                    }
+                }
+            }
             State::SetSmControl(num) => self.state = State::SetSmActivation(num),
             State::SetSmActivation(num) => {
                 if 3 <= num {
@@ -739,18 +809,22 @@ impl Default for InnerFunction {
 }
 
 impl InnerFunction {
-    fn into_sii(&mut self) {
+    fn into_sii(&mut self, timeouts: Timeouts) {
         if let Self::Sii(_) = &self {
             return;
         }
-        *self = Self::Sii(SiiReader::new());
+        let mut sii = SiiReader::new();
+        sii.set_timeouts(timeouts);
+        *self = Self::Sii(sii);
     }
 
-    fn into_al_state_transfer(&mut self) {
+    fn into_al_state_transfer(&mut self, timeouts: Timeouts) {
         if let Self::AlStateTransferTask(_) = &self {
             return;
         }
-        *self = Self::AlStateTransferTask(AlStateTransferTask::new());
+        let mut al = AlStateTransferTask::new();
+        al.set_timeouts(timeouts);
+        *self = Self::AlStateTransferTask(al);
     }
 
     fn sii(&mut self) -> Option<&mut SiiReader> {
@@ -0,0 +1,312 @@
+use super::{AcyclicSocket, MailboxTaskError, TaskError};
+use crate::{
+    frame::{
+        Mailbox, MailboxErrorDetail, MailboxFrame, MailboxType, SoE, SoeErrorCode, SoeFrame,
+        SoeOpCode,
+    },
+    interface::SocketAccess,
+    slave::{Slave, SlaveInfo},
+};
+
+/// SoE client. Data which do not fit in the mailbox are transferred in fragments.
+impl<'a, S: SocketAccess> AcyclicSocket<'a, S> {
+    /// Reads `elements` of an IDN into `buf`. Returns the size of the data.
+    /// `elements` is a combination of `SoeElement`.
+    pub async fn read_soe(
+        &self,
+        slave: &Slave,
+        drive_number: u8,
+        idn: u16,
+        elements: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, TaskError<SoeErrorKind>> {
+        let request = Mailbox::new_soe_read_request(drive_number, idn, elements);
+        self.send_soe(slave, &request, false).await?;
+        let mut size = 0;
+        let mut buffer_small = false;
+        loop {
+            self.read_response(slave, MailboxType::SoE).await?;
+            let last = self.with_mailbox(|mb_data| {
+                let (last, data) = read_soe_fragment(&mb_data, drive_number, idn)?;
+                // The rest of the fragments are read even if they do not fit.
+                match buf.get_mut(size..size + data.len()) {
+                    Some(dst) if !buffer_small => dst.copy_from_slice(data),
+                    _ => buffer_small = true,
+                }
+                size += data.len();
+                Ok::<_, TaskError<SoeErrorKind>>(last)
+            })?;
+            if last {
+                break;
+            }
+        }
+        if buffer_small {
+            Err(SoeErrorKind::BufferSmall.into())
+        } else {
+            Ok(size)
+        }
+    }
+
+    /// Writes `elements` of an IDN. `elements` is a combination of `SoeElement`.
+    pub async fn write_soe(
+        &self,
+        slave: &Slave,
+        drive_number: u8,
+        idn: u16,
+        elements: u8,
+        data: &[u8],
+    ) -> Result<(), TaskError<SoeErrorKind>> {
+        let capacity = soe_write_capacity(slave.info());
+        let num_fragments = data.len().div_ceil(capacity).max(1);
+        for i in 0..num_fragments {
+            let fragment = &data[i * capacity..data.len().min((i + 1) * capacity)];
+            let fragments_left = (num_fragments - i - 1) as u16;
+            let request = if fragments_left == 0 {
+                Mailbox::new_soe_write_request(drive_number, idn, elements, fragment)
+            } else {
+                Mailbox::new_soe_write_fragment(drive_number, elements, fragments_left, fragment)
+            };
+            // The slave responds only to the last fragment.
+            self.send_soe(slave, &request, 0 < i).await?;
+        }
+        self.read_response(slave, MailboxType::SoE).await?;
+        self.with_mailbox(|mb_data| check_soe_write_response(&mb_data, drive_number, idn))
+    }
+
+    async fn send_soe(
+        &self,
+        slave: &Slave,
+        message: &Mailbox<'_>,
+        wait_empty: bool,
+    ) -> Result<(), TaskError<SoeErrorKind>> {
+        let count = slave.increment_mb_count();
+        self.write_mailbox(
+            slave.info(),
+            |mb_frame| {
+                mb_frame.set_count(count);
+                mb_frame.set_mailbox(message)
+            },
+            wait_empty,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Data size of a write fragment which fits in the rx mailbox.
+fn soe_write_capacity(slave_info: &SlaveInfo) -> usize {
+    (slave_info.mailbox_rx_sm().unwrap_or_default().size() as usize)
+        .saturating_sub(MailboxFrame::HEADER_SIZE + SoeFrame::HEADER_SIZE)
+        .max(1)
+}
+
+/// Returns whether it is the last fragment of a read response, and its data.
+fn read_soe_fragment<'a>(
+    mb_data: &MailboxFrame<&'a [u8]>,
+    drive_number: u8,
+    idn: u16,
+) -> Result<(bool, &'a [u8]), TaskError<SoeErrorKind>> {
+    match soe_response(mb_data)? {
+        SoE::ReadRes {
+            drive_number: number,
+            incomplete,
+            idn: res_idn,
+            data,
+            ..
+        } if number == drive_number => {
+            if !incomplete && res_idn != idn {
+                Err(SoeErrorKind::ResponseUnmatch.into())
+            } else {
+                Ok((!incomplete, data))
+            }
+        }
+        SoE::ReadRes { .. } => Err(SoeErrorKind::ResponseUnmatch.into()),
+        other => Err(unexpected_soe(&other).into()),
+    }
+}
+
+fn check_soe_write_response(
+    mb_data: &MailboxFrame<&[u8]>,
+    drive_number: u8,
+    idn: u16,
+) -> Result<(), TaskError<SoeErrorKind>> {
+    match soe_response(mb_data)? {
+        SoE::WriteRes {
+            drive_number: number,
+            idn: res_idn,
+            ..
+        } if number == drive_number && res_idn == idn => Ok(()),
+        SoE::WriteRes { .. } => Err(SoeErrorKind::ResponseUnmatch.into()),
+        other => Err(unexpected_soe(&other).into()),
+    }
+}
+
+/// SoE response of the slave. A response with the error flag and other mailboxes are errors.
+fn soe_response<'a>(mb_data: &MailboxFrame<&'a [u8]>) -> Result<SoE<'a>, TaskError<SoeErrorKind>> {
+    let mb = mb_data
+        .mailbox()
+        .map_err(|_| SoeErrorKind::Mailbox(MailboxTaskError::BufferSmall))?;
+    match mb {
+        Mailbox::SoE(SoE::Error { code, .. }) => Err(SoeErrorKind::Error(code).into()),
+        Mailbox::SoE(soe) => Ok(soe),
+        Mailbox::Error(detail) => Err(SoeErrorKind::ErrorMailbox(detail).into()),
+        _ => Err(SoeErrorKind::UnexpectedMailbox(mb_data.mb_type()).into()),
+    }
+}
+
+fn unexpected_soe(soe: &SoE) -> SoeErrorKind {
+    let op_code = match soe {
+        SoE::ReadReq { .. } => SoeOpCode::ReadReq,
+        SoE::ReadRes { .. } => SoeOpCode::ReadRes,
+        SoE::WriteReq { .. } => SoeOpCode::WriteReq,
+        SoE::WriteRes { .. } => SoeOpCode::WriteRes,
+        SoE::Error { op_code, .. } | SoE::Other(op_code) => *op_code,
+    };
+    SoeErrorKind::UnexpectedOpCode(op_code)
+}
+
+#[derive(Debug, Clone)]
+pub enum SoeErrorKind {
+    Mailbox(MailboxTaskError),
+    /// Response of the slave with the error flag.
+    Error(SoeErrorCode),
+    ErrorMailbox(MailboxErrorDetail),
+    UnexpectedMailbox(MailboxType),
+    UnexpectedOpCode(SoeOpCode),
+    /// The response is for another drive or IDN.
+    ResponseUnmatch,
+    /// The data do not fit in the buffer.
+    BufferSmall,
+}
+
+impl From<TaskError<MailboxTaskError>> for TaskError<SoeErrorKind> {
+    fn from(err: TaskError<MailboxTaskError>) -> Self {
+        match err {
+            TaskError::Interface(e) => TaskError::Interface(e),
+            TaskError::UnexpectedCommand => TaskError::UnexpectedCommand,
+            TaskError::UnexpectedWkc(e) => TaskError::UnexpectedWkc(e),
+            TaskError::TaskSpecific(e) => TaskError::TaskSpecific(SoeErrorKind::Mailbox(e)),
+            TaskError::Timeout => TaskError::Timeout,
+        }
+    }
+}
+
+impl From<SoeErrorKind> for TaskError<SoeErrorKind> {
+    fn from(err: SoeErrorKind) -> Self {
+        Self::TaskSpecific(err)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::frame::{SoeElement, SoeErrorCode};
+    use crate::interface::simulator::test_util::{new_segment, MasterStorage};
+    use crate::interface::SlaveAddress;
+    use crate::task::{SoeErrorKind, TaskError};
+
+    #[test]
+    fn soe_test() {
+        let mut segment = new_segment(1);
+        let soe = segment.slaves_mut()[0].soe_mut();
+        // S-0-0036: velocity command value, on two drives
+        soe.insert_element(0, 36, SoeElement::VALUE, &100_i32.to_le_bytes());
+        soe.insert_element(1, 36, SoeElement::VALUE, &200_i32.to_le_bytes());
+        soe.insert_element(0, 36, SoeElement::ATTRIBUTE, &0x0022_0002_u32.to_le_bytes());
+        let mut name = vec![];
+        for length in [16_u16, 16] {
+            name.extend_from_slice(&length.to_le_bytes());
+        }
+        name.extend_from_slice(b"Velocity command");
+        soe.insert_element(0, 36, SoeElement::NAME, &name);
+        // P-0-0001: a list longer than the mailbox
+        let list: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        soe.insert_element(0, 0x8001, SoeElement::VALUE, &list);
+        let mut storage = MasterStorage::new();
+        let mut master = storage.pre_op_master(segment);
+
+        let slave = SlaveAddress::SlavePosition(0);
+        assert!(master
+            .network()
+            .slave(slave)
+            .unwrap()
+            .0
+            .info()
+            .support_soe());
+        let mut data = [0; 512];
+        let size = master
+            .read_soe(slave, 0, 36, SoeElement::VALUE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &100_i32.to_le_bytes());
+        let size = master
+            .read_soe(slave, 1, 36, SoeElement::VALUE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &200_i32.to_le_bytes());
+        let size = master
+            .read_soe(slave, 0, 36, SoeElement::NAME, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &name[..]);
+        // The elements follow in the order of the bits.
+        let size = master
+            .read_soe(
+                slave,
+                0,
+                36,
+                SoeElement::ATTRIBUTE | SoeElement::VALUE,
+                &mut data,
+            )
+            .unwrap();
+        assert_eq!(&data[..4], &0x0022_0002_u32.to_le_bytes());
+        assert_eq!(&data[4..size], &100_i32.to_le_bytes());
+        let size = master
+            .read_soe(slave, 0, 0x8001, SoeElement::VALUE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &list[..]);
+        match master.read_soe(slave, 0, 0x8001, SoeElement::VALUE, &mut data[..200]) {
+            Err(TaskError::TaskSpecific(SoeErrorKind::BufferSmall)) => {}
+            other => panic!("{:?}", other),
+        }
+        match master.read_soe(slave, 0, 37, SoeElement::VALUE, &mut data) {
+            Err(TaskError::TaskSpecific(SoeErrorKind::Error(SoeErrorCode::NoIdn))) => {}
+            other => panic!("{:?}", other),
+        }
+        match master.read_soe(slave, 0, 36, SoeElement::UNIT, &mut data) {
+            Err(TaskError::TaskSpecific(SoeErrorKind::Error(SoeErrorCode::NoUnit))) => {}
+            other => panic!("{:?}", other),
+        }
+
+        master
+            .write_soe(slave, 1, 36, SoeElement::VALUE, &(-50_i32).to_le_bytes())
+            .unwrap();
+        let device = master.device();
+        let soe = device.slaves()[0].soe();
+        assert_eq!(
+            soe.element(1, 36, SoeElement::VALUE),
+            Some(&(-50_i32).to_le_bytes()[..])
+        );
+        assert_eq!(
+            soe.element(0, 36, SoeElement::VALUE),
+            Some(&100_i32.to_le_bytes()[..])
+        );
+        drop(device);
+        let reversed: Vec<u8> = list.iter().rev().copied().collect();
+        master
+            .write_soe(slave, 0, 0x8001, SoeElement::VALUE, &reversed)
+            .unwrap();
+        let size = master
+            .read_soe(slave, 0, 0x8001, SoeElement::VALUE, &mut data)
+            .unwrap();
+        assert_eq!(&data[..size], &reversed[..]);
+        match master.write_soe(slave, 0, 36, SoeElement::NAME, b"Velocity") {
+            Err(TaskError::TaskSpecific(SoeErrorKind::Error(
+                SoeErrorCode::NameCannotBeChanged,
+            ))) => {}
+            other => panic!("{:?}", other),
+        }
+        match master.write_soe(slave, 0, 36, SoeElement::VALUE, &[0; 8]) {
+            Err(TaskError::TaskSpecific(SoeErrorKind::Error(
+                SoeErrorCode::DataTransmissionTooLong,
+            ))) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
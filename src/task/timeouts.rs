@@ -0,0 +1,56 @@
+use crate::slave::AlState;
+use core::time::Duration;
+
+/// Timeouts of the tasks which wait for slaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Init -> PreOp or Init -> Boot
+    pub pre_op: Duration,
+    /// PreOp -> SafeOp or SafeOp -> Op
+    pub safe_op_op: Duration,
+    /// Op/SafeOp/PreOp/Boot -> Init or SafeOp -> PreOp
+    pub back_to_init: Duration,
+    /// Op -> SafeOp
+    pub back_to_safe_op: Duration,
+    /// Until the EEPROM interface finishes a read operation.
+    pub sii: Duration,
+    /// Until the rx mailbox is empty.
+    pub mailbox_request: Duration,
+    /// Until the tx mailbox is full.
+    pub mailbox_response: Duration,
+    /// While a slave answers a file access with busy.
+    pub foe_busy: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            pre_op: Duration::from_millis(3000),
+            safe_op_op: Duration::from_millis(10000),
+            back_to_init: Duration::from_millis(5000),
+            back_to_safe_op: Duration::from_millis(200),
+            sii: Duration::from_millis(100),
+            mailbox_request: Duration::from_millis(100),
+            mailbox_response: Duration::from_millis(2000),
+            foe_busy: Duration::from_millis(10000),
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn al_state_transition(&self, from: AlState, to: AlState) -> Duration {
+        match (from, to) {
+            (AlState::PreOperational, AlState::SafeOperational) | (_, AlState::Operational) => {
+                self.safe_op_op
+            }
+            (_, AlState::PreOperational) | (_, AlState::Bootstrap) => self.pre_op,
+            (_, AlState::Init) => self.back_to_init,
+            (_, AlState::SafeOperational) => self.back_to_safe_op,
+            (_, AlState::InvalidOrMixed) => self
+                .pre_op
+                .max(self.safe_op_op)
+                .max(self.back_to_init)
+                .max(self.back_to_safe_op),
+        }
+    }
+}